@@ -0,0 +1,286 @@
+// Deserialize bencode into any `Deserialize` type.
+//
+// The input is parsed into `BencodeRef` first, so strings and byte strings
+// can be borrowed from the input, and strings are never normalized.
+
+use super::*;
+use error::*;
+use serde::de::{self, Deserialize, DeserializeSeed, IntoDeserializer, Visitor};
+use std::borrow::Cow;
+use std::fmt::Display;
+use std::vec;
+
+/// Deserialize an instance of `T` from `bytes`.
+///
+/// `bytes` must contain exactly one bencode element. See [`to_bytes()`] for how
+/// `serde`'s data model is mapped to bencode. In addition, strings can be
+/// deserialized as byte strings, and missing dictionary entries
+/// are deserialized as `None`.
+///
+/// [`to_bytes()`]: fn.to_bytes.html
+pub fn from_bytes<'de, T>(bytes: &'de [u8]) -> Result<T>
+where
+    T: Deserialize<'de>,
+{
+    let mut elements = BencodeRef::from_bytes(bytes)?;
+    if elements.len() != 1 {
+        bail!(ErrorKind::SerdeFailure(Cow::Owned(format!(
+            "Expected exactly 1 bencode element, found {}.",
+            elements.len()
+        ))));
+    }
+
+    T::deserialize(elements.remove(0))
+}
+
+impl de::Error for Error {
+    fn custom<T: Display>(msg: T) -> Error {
+        ErrorKind::SerdeFailure(Cow::Owned(msg.to_string())).into()
+    }
+}
+
+impl<'de> BencodeRef<'de> {
+    fn unexpected(&self) -> de::Unexpected<'_> {
+        match *self {
+            BencodeRef::String(string) => de::Unexpected::Str(string),
+            BencodeRef::Bytes(bytes) => de::Unexpected::Bytes(bytes),
+            BencodeRef::Integer(int) => de::Unexpected::Signed(int),
+            BencodeRef::List(_) => de::Unexpected::Seq,
+            BencodeRef::Dictionary(_) => de::Unexpected::Map,
+        }
+    }
+}
+
+// Dictionary keys are presented as strings if possible.
+fn key_to_ref(key: &[u8]) -> BencodeRef<'_> {
+    match ::std::str::from_utf8(key) {
+        Ok(string) => BencodeRef::String(string),
+        Err(_) => BencodeRef::Bytes(key),
+    }
+}
+
+impl<'de> de::Deserializer<'de> for BencodeRef<'de> {
+    type Error = Error;
+
+    fn is_human_readable(&self) -> bool {
+        false
+    }
+
+    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        match self {
+            BencodeRef::String(string) => visitor.visit_borrowed_str(string),
+            BencodeRef::Bytes(bytes) => visitor.visit_borrowed_bytes(bytes),
+            BencodeRef::Integer(int) => visitor.visit_i64(int),
+            BencodeRef::List(list) => visitor.visit_seq(SeqAccess {
+                iter: list.into_iter(),
+            }),
+            BencodeRef::Dictionary(entries) => visitor.visit_map(MapAccess {
+                iter: entries.into_iter(),
+                value: None,
+            }),
+        }
+    }
+
+    fn deserialize_bool<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        match self {
+            BencodeRef::Integer(0) => visitor.visit_bool(false),
+            BencodeRef::Integer(1) => visitor.visit_bool(true),
+            other => Err(de::Error::invalid_type(other.unexpected(), &visitor)),
+        }
+    }
+
+    fn deserialize_bytes<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        match self {
+            BencodeRef::String(string) => visitor.visit_borrowed_bytes(string.as_bytes()),
+            other => other.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_byte_buf<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_bytes(visitor)
+    }
+
+    fn deserialize_option<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        // `None` is never serialized, so anything present is `Some`
+        visitor.visit_some(self)
+    }
+
+    fn deserialize_unit<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_unit()
+    }
+
+    fn deserialize_unit_struct<V>(self, _name: &'static str, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_unit()
+    }
+
+    fn deserialize_newtype_struct<V>(self, _name: &'static str, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        match self {
+            BencodeRef::String(variant) => visitor.visit_enum(variant.into_deserializer()),
+            BencodeRef::Dictionary(mut entries) => {
+                if entries.len() != 1 {
+                    bail!(ErrorKind::SerdeFailure(Cow::Borrowed(
+                        "An enum must be a dictionary with exactly 1 entry."
+                    )));
+                }
+                let (variant, value) = entries.remove(0);
+                visitor.visit_enum(EnumAccess {
+                    variant: key_to_ref(variant),
+                    value,
+                })
+            }
+            other => Err(de::Error::invalid_type(other.unexpected(), &visitor)),
+        }
+    }
+
+    fn deserialize_ignored_any<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_unit()
+    }
+
+    forward_to_deserialize_any! {
+        i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        seq tuple tuple_struct map struct identifier
+    }
+}
+
+struct SeqAccess<'de> {
+    iter: vec::IntoIter<BencodeRef<'de>>,
+}
+
+impl<'de> de::SeqAccess<'de> for SeqAccess<'de> {
+    type Error = Error;
+
+    fn next_element_seed<T>(&mut self, seed: T) -> Result<Option<T::Value>>
+    where
+        T: DeserializeSeed<'de>,
+    {
+        match self.iter.next() {
+            Some(value) => seed.deserialize(value).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.iter.len())
+    }
+}
+
+struct MapAccess<'de> {
+    iter: vec::IntoIter<(&'de [u8], BencodeRef<'de>)>,
+    value: Option<BencodeRef<'de>>, // value of the last key
+}
+
+impl<'de> de::MapAccess<'de> for MapAccess<'de> {
+    type Error = Error;
+
+    fn next_key_seed<K>(&mut self, seed: K) -> Result<Option<K::Value>>
+    where
+        K: DeserializeSeed<'de>,
+    {
+        match self.iter.next() {
+            Some((key, value)) => {
+                self.value = Some(value);
+                seed.deserialize(key_to_ref(key)).map(Some)
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn next_value_seed<V>(&mut self, seed: V) -> Result<V::Value>
+    where
+        V: DeserializeSeed<'de>,
+    {
+        match self.value.take() {
+            Some(value) => seed.deserialize(value),
+            None => panic!("next_value_seed() is called before next_key_seed()."),
+        }
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.iter.len())
+    }
+}
+
+struct EnumAccess<'de> {
+    variant: BencodeRef<'de>,
+    value: BencodeRef<'de>,
+}
+
+impl<'de> de::EnumAccess<'de> for EnumAccess<'de> {
+    type Error = Error;
+    type Variant = BencodeRef<'de>;
+
+    fn variant_seed<V>(self, seed: V) -> Result<(V::Value, BencodeRef<'de>)>
+    where
+        V: DeserializeSeed<'de>,
+    {
+        Ok((seed.deserialize(self.variant)?, self.value))
+    }
+}
+
+impl<'de> de::VariantAccess<'de> for BencodeRef<'de> {
+    type Error = Error;
+
+    fn unit_variant(self) -> Result<()> {
+        Ok(())
+    }
+
+    fn newtype_variant_seed<T>(self, seed: T) -> Result<T::Value>
+    where
+        T: DeserializeSeed<'de>,
+    {
+        seed.deserialize(self)
+    }
+
+    fn tuple_variant<V>(self, _len: usize, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        de::Deserializer::deserialize_seq(self, visitor)
+    }
+
+    fn struct_variant<V>(self, _fields: &'static [&'static str], visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        de::Deserializer::deserialize_map(self, visitor)
+    }
+}
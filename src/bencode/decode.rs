@@ -0,0 +1,339 @@
+//! Module for incremental bencode decoding.
+//!
+//! [`Decoder`] is useful when the input arrives in fragments (e.g. from a
+//! socket), or when the input is too large to be read into memory up front.
+//!
+//! [`Decoder`]: struct.Decoder.html
+
+use super::*;
+use error::*;
+use std::borrow::Cow;
+use std::io::Read;
+
+// size of the chunks read by `Decoder::read_from()`
+const READ_CHUNK_SIZE: usize = 8192;
+
+/// The result of [`Decoder::decode()`].
+///
+/// [`Decoder::decode()`]: struct.Decoder.html#method.decode
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Decoded {
+    /// A complete element has been decoded.
+    Element(BencodeElem),
+    /// The buffered bytes do not contain a complete element (yet).
+    NeedMoreData,
+}
+
+/// A pull-based decoder that decodes bencode incrementally.
+///
+/// Bytes can be passed in with [`feed()`] as they arrive, and complete elements
+/// can be pulled out with [`decode()`]. If the buffered bytes are truncated,
+/// then `Decoded::NeedMoreData` is returned instead of `MalformedBencode`.
+/// Alternatively, [`read_from()`] can be used to decode elements from
+/// any `Read`.
+///
+/// Each element is parsed in the same way as [`BencodeElem::from_bytes_with()`],
+/// so the same checks (e.g. dictionaries must be sorted) apply.
+///
+/// After an error has been returned, the decoder should not be used anymore.
+///
+/// [`feed()`]: #method.feed
+/// [`decode()`]: #method.decode
+/// [`read_from()`]: #method.read_from
+/// [`BencodeElem::from_bytes_with()`]: ../enum.BencodeElem.html#method.from_bytes_with
+#[derive(Clone, Debug, Default)]
+pub struct Decoder {
+    buffer: Vec<u8>,
+    options: ParseOptions,
+    scan_pos: usize, // how far the buffer has been scanned
+    depth: usize,    // number of open lists/dictionaries at `scan_pos`
+}
+
+impl Decoder {
+    /// Create a new `Decoder` with default `ParseOptions`.
+    pub fn new() -> Decoder {
+        Default::default()
+    }
+
+    /// Create a new `Decoder` that parses elements with `options`.
+    pub fn with_options(options: ParseOptions) -> Decoder {
+        Decoder {
+            options,
+            ..Default::default()
+        }
+    }
+
+    /// Append `chunk` to the internal buffer.
+    pub fn feed<B>(&mut self, chunk: B)
+    where
+        B: AsRef<[u8]>,
+    {
+        self.buffer.extend_from_slice(chunk.as_ref());
+    }
+
+    /// Return the number of bytes that have been fed but not decoded yet.
+    pub fn buffered_len(&self) -> usize {
+        self.buffer.len()
+    }
+
+    /// Try to decode the next element from the buffered bytes.
+    ///
+    /// If the buffered bytes contain a complete element, then it is removed from
+    /// the buffer and returned. Otherwise `Decoded::NeedMoreData` is returned,
+    /// and nothing is consumed.
+    ///
+    /// If the buffered bytes are malformed (in a way that can't be fixed by
+    /// feeding more bytes), then `Err(error)` will be returned.
+    pub fn decode(&mut self) -> Result<Decoded> {
+        match self.scan()? {
+            Some(end) => {
+                let mut elements =
+                    BencodeElem::from_bytes_with(&self.buffer[..end], &self.options)?;
+                self.buffer.drain(..end);
+                self.scan_pos = 0;
+                self.depth = 0;
+
+                match elements.pop() {
+                    Some(element) => Ok(Decoded::Element(element)),
+                    None => panic!("scan() returned an empty element."),
+                }
+            }
+            None => Ok(Decoded::NeedMoreData),
+        }
+    }
+
+    /// Decode the next element, reading more bytes from `reader` as needed.
+    ///
+    /// `Ok(None)` is returned if `reader` reaches EOF with no bytes left in the buffer.
+    /// If `reader` reaches EOF in the middle of an element, then
+    /// `MalformedBencode` will be returned.
+    pub fn read_from<R>(&mut self, reader: &mut R) -> Result<Option<BencodeElem>>
+    where
+        R: Read,
+    {
+        let mut chunk = [0; READ_CHUNK_SIZE];
+
+        loop {
+            if let Decoded::Element(element) = self.decode()? {
+                return Ok(Some(element));
+            }
+
+            let read = reader.read(&mut chunk)?;
+            if read == 0 {
+                if self.buffer.is_empty() {
+                    return Ok(None);
+                } else {
+                    bail!(ErrorKind::MalformedBencode(Cow::Borrowed(
+                        "Expected more bytes, but none found."
+                    )));
+                }
+            }
+            self.feed(&chunk[..read]);
+        }
+    }
+
+    // Scan the buffer (starting from where the last call left off) to find
+    // the end of the first element. `None` is returned if the element is incomplete.
+    //
+    // Only the structure is checked here. Everything else is
+    // checked when the element is actually parsed.
+    fn scan(&mut self) -> Result<Option<usize>> {
+        while let Some(&byte) = self.buffer.get(self.scan_pos) {
+            match byte {
+                DICTIONARY_PREFIX | LIST_PREFIX => {
+                    self.depth += 1;
+                    self.scan_pos += 1;
+                }
+                DICTIONARY_POSTFIX if self.depth > 0 => {
+                    self.depth -= 1;
+                    self.scan_pos += 1;
+                }
+                INTEGER_PREFIX => {
+                    let remaining = &self.buffer[(self.scan_pos + 1)..];
+                    match remaining.iter().position(|&b| b == INTEGER_POSTFIX) {
+                        Some(len) => self.scan_pos += len + 2,
+                        None if is_integer_prefix(remaining) => return Ok(None),
+                        None => return self.malformed(),
+                    }
+                }
+                b'0'..=b'9' => {
+                    let remaining = &self.buffer[self.scan_pos..];
+                    match remaining.iter().position(|&b| b == STRING_DELIMITER) {
+                        Some(len) => match parse_length(&remaining[..len]) {
+                            // `string_len` is untrusted, so the end could overflow
+                            Some(string_len) => {
+                                match (self.scan_pos + len + 1).checked_add(string_len) {
+                                    Some(end) if end > self.buffer.len() => return Ok(None),
+                                    Some(end) => self.scan_pos = end,
+                                    None => return self.malformed(),
+                                }
+                            }
+                            None => return self.malformed(),
+                        },
+                        None if is_integer_prefix(remaining) => return Ok(None),
+                        None => return self.malformed(),
+                    }
+                }
+                _ => return self.malformed(),
+            }
+
+            if self.depth == 0 {
+                return Ok(Some(self.scan_pos));
+            }
+        }
+        Ok(None)
+    }
+
+    // Parse the buffer to get the same error that `BencodeElem::from_bytes_with()`
+    // would return.
+    fn malformed(&self) -> Result<Option<usize>> {
+        BencodeElem::from_bytes_with(&self.buffer, &self.options)?;
+        bail!(ErrorKind::MalformedBencode(Cow::Owned(format!(
+            "Unexpected byte {:#04x} found.",
+            self.buffer[self.scan_pos]
+        ))))
+    }
+}
+
+fn is_integer_prefix(bytes: &[u8]) -> bool {
+    bytes.iter().all(|&b| b == b'-' || b.is_ascii_digit())
+}
+
+fn parse_length(bytes: &[u8]) -> Option<usize> {
+    ::std::str::from_utf8(bytes).ok()?.parse().ok()
+}
+
+#[cfg(test)]
+mod decoder_tests {
+    use super::*;
+    use std::io::Cursor;
+    use std::iter::FromIterator;
+
+    #[test]
+    fn decode_ok() {
+        let mut decoder = Decoder::new();
+        decoder.feed("d3:cow3:moo4:spaml4:eggsi-1eee");
+        assert_eq!(
+            decoder.decode().unwrap(),
+            Decoded::Element(bencode_elem!({ ("cow", "moo"), ("spam", ["eggs", (-1)]) })),
+        );
+        assert_eq!(decoder.decode().unwrap(), Decoded::NeedMoreData);
+        assert_eq!(decoder.buffered_len(), 0);
+    }
+
+    #[test]
+    fn decode_multiple_elements() {
+        let mut decoder = Decoder::new();
+        decoder.feed("i1e4:spam");
+        assert_eq!(
+            decoder.decode().unwrap(),
+            Decoded::Element(bencode_elem!(1))
+        );
+        assert_eq!(
+            decoder.decode().unwrap(),
+            Decoded::Element(bencode_elem!("spam"))
+        );
+        assert_eq!(decoder.decode().unwrap(), Decoded::NeedMoreData);
+    }
+
+    #[test]
+    fn decode_byte_by_byte() {
+        let bytes = "d3:cow3:moo4:spaml4:eggsi-10eee".as_bytes();
+        let mut decoder = Decoder::new();
+
+        for byte in &bytes[..(bytes.len() - 1)] {
+            decoder.feed([*byte]);
+            assert_eq!(decoder.decode().unwrap(), Decoded::NeedMoreData);
+        }
+        decoder.feed([bytes[bytes.len() - 1]]);
+        assert_eq!(
+            decoder.decode().unwrap(),
+            Decoded::Element(bencode_elem!({ ("cow", "moo"), ("spam", ["eggs", (-10)]) })),
+        );
+    }
+
+    #[test]
+    fn decode_with_options() {
+        let mut decoder = Decoder::with_options(ParseOptions::new().set_normalize_nfc(false));
+        decoder.feed("3:e\u{301}");
+        assert_eq!(
+            decoder.decode().unwrap(),
+            Decoded::Element(bencode_elem!("e\u{301}"))
+        );
+    }
+
+    #[test]
+    fn decode_not_sorted() {
+        let mut decoder = Decoder::new();
+        decoder.feed("d4:spam4:eggs3:cow3:mooe");
+        match decoder.decode() {
+            Err(Error(ErrorKind::MalformedBencode(m), _)) => {
+                assert_eq!(m, "A dictionary is not properly sorted.");
+            }
+            _ => assert!(false),
+        }
+    }
+
+    #[test]
+    fn decode_invalid_integer() {
+        let mut decoder = Decoder::new();
+        decoder.feed("i1x");
+        match decoder.decode() {
+            Err(Error(ErrorKind::MalformedBencode(m), _)) => {
+                assert_eq!(m, "Integer delimiter not found.");
+            }
+            _ => assert!(false),
+        }
+    }
+
+    #[test]
+    fn decode_unexpected_postfix() {
+        let mut decoder = Decoder::new();
+        decoder.feed("e");
+        match decoder.decode() {
+            Err(Error(ErrorKind::MalformedBencode(m), _)) => {
+                assert_eq!(m, "Integer delimiter not found.");
+            }
+            _ => assert!(false),
+        }
+    }
+
+    #[test]
+    fn decode_string_length_overflow() {
+        let mut decoder = Decoder::new();
+        decoder.feed("18446744073709551615:abc");
+        match decoder.decode() {
+            Err(Error(ErrorKind::MalformedBencode(m), _)) => {
+                assert_eq!(m, "Input contains invalid integer: 18446744073709551615.");
+            }
+            _ => assert!(false),
+        }
+    }
+
+    #[test]
+    fn read_from_ok() {
+        let mut reader = Cursor::new("l4:spame4:eggs".as_bytes());
+        let mut decoder = Decoder::new();
+        assert_eq!(
+            decoder.read_from(&mut reader).unwrap(),
+            Some(bencode_elem!(["spam"]))
+        );
+        assert_eq!(
+            decoder.read_from(&mut reader).unwrap(),
+            Some(bencode_elem!("eggs"))
+        );
+        assert_eq!(decoder.read_from(&mut reader).unwrap(), None);
+    }
+
+    #[test]
+    fn read_from_truncated() {
+        let mut reader = Cursor::new("l4:spam4:eg".as_bytes());
+        let mut decoder = Decoder::new();
+        match decoder.read_from(&mut reader) {
+            Err(Error(ErrorKind::MalformedBencode(m), _)) => {
+                assert_eq!(m, "Expected more bytes, but none found.");
+            }
+            _ => assert!(false),
+        }
+    }
+}
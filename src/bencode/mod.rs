@@ -2,6 +2,13 @@
 //!
 //! Most of methods are associated methods of `BencodeElem`. Some general methods
 //! are placed at the module level, and they can be found in [`write`](write/index.html).
+//! Incremental decoding is supported by [`decode`](decode/index.html).
+//!
+//! With the `serde` feature enabled, [`to_bytes()`] and [`from_bytes()`] can be used
+//! to convert between bencode and any type that implements `Serialize`/`Deserialize`.
+//!
+//! [`to_bytes()`]: fn.to_bytes.html
+//! [`from_bytes()`]: fn.from_bytes.html
 
 use itertools;
 use itertools::Itertools;
@@ -12,9 +19,19 @@ use std::fmt;
 #[cfg(test)]
 #[macro_use]
 mod macros;
+#[cfg(feature = "serde")]
+mod de;
+pub mod decode;
 mod read;
+#[cfg(feature = "serde")]
+mod ser;
 pub mod write;
 
+#[cfg(feature = "serde")]
+pub use self::de::from_bytes;
+#[cfg(feature = "serde")]
+pub use self::ser::to_bytes;
+
 const DICTIONARY_PREFIX: u8 = b'd';
 const DICTIONARY_POSTFIX: u8 = b'e';
 const LIST_PREFIX: u8 = b'l';
@@ -40,13 +57,73 @@ const STRING_DELIMITER: u8 = b':';
 /// [`Integer`]: ../torrent/v1/type.Integer.html
 /// [spec]: http://bittorrent.org/beps/bep_0003.html
 #[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum BencodeElem {
     String(String),
-    Bytes(Vec<u8>),
+    Bytes(#[cfg_attr(feature = "serde", serde(with = "::serde_util::bytes"))] Vec<u8>),
     Integer(i64),
     List(Vec<BencodeElem>),
     Dictionary(HashMap<String, BencodeElem>),
-    RawDictionary(HashMap<Vec<u8>, BencodeElem>),
+    RawDictionary(
+        #[cfg_attr(feature = "serde", serde(with = "::serde_util::bytes_map"))]
+        HashMap<Vec<u8>, BencodeElem>,
+    ),
+}
+
+/// Represent a single bencode element that borrows from the input buffer.
+///
+/// This is the zero-copy counterpart of [`BencodeElem`]: strings and byte sequences
+/// are slices into the parsed bytes, and dictionary entries are kept in the order
+/// they appear in the input (keys are always raw bytes). No allocation is made
+/// except for the `Vec`s that hold list items and dictionary entries.
+///
+/// Unlike `BencodeElem`, strings are not normalized (they can't be, without
+/// copying). Use [`to_owned()`] to convert a `BencodeRef` into a `BencodeElem`.
+///
+/// [`BencodeElem`]: enum.BencodeElem.html
+/// [`to_owned()`]: #method.to_owned
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum BencodeRef<'a> {
+    String(&'a str),
+    Bytes(&'a [u8]),
+    Integer(i64),
+    List(Vec<BencodeRef<'a>>),
+    Dictionary(Vec<(&'a [u8], BencodeRef<'a>)>),
+}
+
+/// Options for parsing bencode.
+///
+/// By default, valid UTF-8 strings and dictionary keys are normalized
+/// to NFC forms when parsed. This means that parsing some bytes and then
+/// encoding the result might not reproduce the original bytes. To keep
+/// strings exactly as given, call [`set_normalize_nfc(false)`].
+///
+/// [`set_normalize_nfc(false)`]: #method.set_normalize_nfc
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ParseOptions {
+    pub(crate) normalize_nfc: bool,
+}
+
+impl ParseOptions {
+    /// Create a new `ParseOptions` with default settings.
+    pub fn new() -> ParseOptions {
+        Default::default()
+    }
+
+    /// Enable (or disable) NFC normalization of strings and dictionary keys.
+    ///
+    /// Calling this method multiple times will simply override previous settings.
+    pub fn set_normalize_nfc(self, normalize_nfc: bool) -> ParseOptions {
+        ParseOptions { normalize_nfc }
+    }
+}
+
+impl Default for ParseOptions {
+    fn default() -> ParseOptions {
+        ParseOptions {
+            normalize_nfc: true,
+        }
+    }
 }
 
 impl From<u8> for BencodeElem {
@@ -1,11 +1,13 @@
 use super::*;
 use error::*;
 use std::borrow::Cow;
+use std::cmp;
 use std::collections::HashMap;
 use std::fs::File;
 use std::io::{BufReader, Read};
 use std::iter::FromIterator;
 use std::path::Path;
+use std::str;
 use unicode_normalization::UnicodeNormalization;
 use util;
 use util::ByteBuffer;
@@ -20,6 +22,19 @@ impl BencodeElem {
     /// error is encountered (e.g. `IOError`), then `Err(error)`
     /// will be returned.
     pub fn from_bytes<B>(bytes: B) -> Result<Vec<BencodeElem>>
+    where
+        B: AsRef<[u8]>,
+    {
+        Self::from_bytes_with(bytes, &ParseOptions::default())
+    }
+
+    /// Parse `bytes` with `options` and return all `BencodeElem` found.
+    ///
+    /// This is the same as [`from_bytes()`], except that the parsing
+    /// behavior (e.g. whether strings are normalized) is determined by `options`.
+    ///
+    /// [`from_bytes()`]: #method.from_bytes
+    pub fn from_bytes_with<B>(bytes: B, options: &ParseOptions) -> Result<Vec<BencodeElem>>
     where
         B: AsRef<[u8]>,
     {
@@ -27,7 +42,7 @@ impl BencodeElem {
         let mut elements = Vec::new();
 
         while !bytes.is_empty() {
-            let element = BencodeElem::parse(&mut bytes)?;
+            let element = BencodeElem::parse(&mut bytes, options)?;
             elements.push(element);
         }
 
@@ -43,6 +58,20 @@ impl BencodeElem {
     /// error is encountered (e.g. `IOError`), then `Err(error)`
     /// will be returned.
     pub fn from_file<P>(path: P) -> Result<Vec<BencodeElem>>
+    where
+        P: AsRef<Path>,
+    {
+        Self::from_file_with(path, &ParseOptions::default())
+    }
+
+    /// Parse the content of the file at `path` with `options`
+    /// and return all `BencodeElem` found.
+    ///
+    /// This is the same as [`from_file()`], except that the parsing
+    /// behavior (e.g. whether strings are normalized) is determined by `options`.
+    ///
+    /// [`from_file()`]: #method.from_file
+    pub fn from_file_with<P>(path: P, options: &ParseOptions) -> Result<Vec<BencodeElem>>
     where
         P: AsRef<Path>,
     {
@@ -50,7 +79,57 @@ impl BencodeElem {
         let mut bytes = Vec::new();
 
         BufReader::new(file).read_to_end(&mut bytes)?;
-        Self::from_bytes(bytes)
+        Self::from_bytes_with(bytes, options)
+    }
+
+    // Find the value of `key` in the top-level dictionary of `bytes`, and
+    // return its position in `bytes` as `(start, end)` (end exclusive).
+    //
+    // `None` is returned if `bytes` does not start with a dictionary,
+    // or if `key` is not found in that dictionary.
+    pub(crate) fn find_value_span(bytes: &[u8], key: &[u8]) -> Result<Option<(usize, usize)>> {
+        let mut bytes = ByteBuffer::new(bytes);
+        if bytes.peek() != Some(&DICTIONARY_PREFIX) {
+            return Ok(None);
+        }
+        bytes.advance(1);
+
+        while Self::peek_byte(&mut bytes)? != DICTIONARY_POSTFIX {
+            let current = match Self::decode_bytes(&mut bytes)? {
+                BencodeElem::Bytes(current) => current,
+                _ => panic!("decode_bytes() did not return bytes."),
+            };
+            let start = bytes.pos();
+            Self::skip(&mut bytes)?;
+
+            if current == key {
+                return Ok(Some((start, bytes.pos())));
+            }
+        }
+        Ok(None)
+    }
+
+    // Advance `bytes` past the next element without decoding it.
+    fn skip(bytes: &mut ByteBuffer) -> Result<()> {
+        match Self::peek_byte(bytes)? {
+            DICTIONARY_PREFIX | LIST_PREFIX => {
+                bytes.advance(1);
+                // dictionary keys are skipped as strings
+                while Self::peek_byte(bytes)? != LIST_POSTFIX {
+                    Self::skip(bytes)?;
+                }
+                bytes.advance(1); // consume the postfix
+            }
+            INTEGER_PREFIX => {
+                bytes.advance(1);
+                Self::decode_integer(bytes, INTEGER_POSTFIX)?;
+            }
+            _ => match Self::decode_integer(bytes, STRING_DELIMITER)? {
+                BencodeElem::Integer(len) => bytes.advance(util::i64_to_usize(len)?),
+                _ => panic!("decode_integer() did not return an integer."),
+            },
+        }
+        Ok(())
     }
 
     fn peek_byte(bytes: &mut ByteBuffer) -> Result<u8> {
@@ -62,31 +141,31 @@ impl BencodeElem {
         }
     }
 
-    fn parse(bytes: &mut ByteBuffer) -> Result<BencodeElem> {
+    fn parse(bytes: &mut ByteBuffer, options: &ParseOptions) -> Result<BencodeElem> {
         match Self::peek_byte(bytes)? {
             DICTIONARY_PREFIX => {
                 bytes.advance(1);
-                Ok(Self::decode_dictionary(bytes)?)
+                Ok(Self::decode_dictionary(bytes, options)?)
             }
             LIST_PREFIX => {
                 bytes.advance(1);
-                Ok(Self::decode_list(bytes)?)
+                Ok(Self::decode_list(bytes, options)?)
             }
             INTEGER_PREFIX => {
                 bytes.advance(1);
                 Ok(Self::decode_integer(bytes, INTEGER_POSTFIX)?)
             }
-            _ => Ok(Self::decode_string(bytes)?),
+            _ => Ok(Self::decode_string(bytes, options)?),
         }
     }
 
-    fn decode_dictionary(bytes: &mut ByteBuffer) -> Result<BencodeElem> {
+    fn decode_dictionary(bytes: &mut ByteBuffer, options: &ParseOptions) -> Result<BencodeElem> {
         let mut entries = Vec::new();
 
         while Self::peek_byte(bytes)? != DICTIONARY_POSTFIX {
             // more to parse
             match Self::decode_bytes(bytes) {
-                Ok(BencodeElem::Bytes(key)) => entries.push((key, Self::parse(bytes)?)),
+                Ok(BencodeElem::Bytes(key)) => entries.push((key, Self::parse(bytes, options)?)),
                 Ok(_) => bail!(ErrorKind::MalformedBencode(Cow::Borrowed(
                     "Non-string dictionary key."
                 ))),
@@ -97,7 +176,7 @@ impl BencodeElem {
 
         // check that the dictionary is sorted
         for (i, j) in (1..entries.len()).enumerate() {
-            let (&(ref k1, _), &(ref k2, _)) = (&entries[i], &entries[j]);
+            let ((k1, _), (k2, _)) = (&entries[i], &entries[j]);
             // "sorted as raw strings, not alphanumerics"
             if k1 > k2 {
                 bail!(ErrorKind::MalformedBencode(Cow::Borrowed(
@@ -107,29 +186,25 @@ impl BencodeElem {
         }
 
         // convert to Dictionary if possible
-        // in which case keys are normalized to NFC forms
+        // in which case keys are normalized to NFC forms (unless disabled)
         let mut entries2 = Vec::new();
         for (k, v) in &entries {
             match String::from_utf8(k.to_owned()) {
-                Ok(s) => entries2.push((s.chars().nfc().collect(), v.to_owned())),
+                Ok(s) => entries2.push((Self::normalize(s, options), v.to_owned())),
                 Err(_) => {
-                    return Ok(BencodeElem::RawDictionary(HashMap::from_iter(
-                        entries.into_iter(),
-                    )));
+                    return Ok(BencodeElem::RawDictionary(HashMap::from_iter(entries)));
                 }
             }
         }
-        Ok(BencodeElem::Dictionary(HashMap::from_iter(
-            entries2.into_iter(),
-        )))
+        Ok(BencodeElem::Dictionary(HashMap::from_iter(entries2)))
     }
 
-    fn decode_list(bytes: &mut ByteBuffer) -> Result<BencodeElem> {
+    fn decode_list(bytes: &mut ByteBuffer, options: &ParseOptions) -> Result<BencodeElem> {
         let mut list = Vec::new();
 
         while Self::peek_byte(bytes)? != LIST_POSTFIX {
             // more to parse
-            list.push(Self::parse(bytes)?);
+            list.push(Self::parse(bytes, options)?);
         }
         bytes.advance(1); //consume the postfix
 
@@ -137,46 +212,50 @@ impl BencodeElem {
     }
 
     fn decode_integer(bytes: &mut ByteBuffer, delimiter: u8) -> Result<BencodeElem> {
-        let old_pos = bytes.pos();
-        let read: Vec<u8> = bytes.take_while(|&&b| b != delimiter).cloned().collect();
-        let bytes_read = bytes.pos() - old_pos;
-
-        if read.len() == bytes_read {
-            bail!(ErrorKind::MalformedBencode(Cow::Borrowed(
-                "Integer delimiter not found."
-            )));
-        } else {
-            match String::from_utf8(read) {
-                Ok(int_string) => {
-                    if int_string.starts_with("-0") {
-                        bail!(ErrorKind::MalformedBencode(Cow::Borrowed("-0 found.")));
-                    } else if (int_string.starts_with('0')) && (int_string.len() != 1) {
-                        bail!(ErrorKind::MalformedBencode(Cow::Borrowed(
-                            "Integer with leading zero(s) found."
-                        )));
-                    } else {
-                        match int_string.parse() {
-                            Ok(int) => Ok(BencodeElem::Integer(int)),
-                            Err(_) => bail!(ErrorKind::MalformedBencode(Cow::Owned(format!(
-                                "Input contains invalid integer: {}.",
-                                int_string
-                            )))),
-                        }
+        let remaining = bytes.remaining();
+        let read = match remaining.iter().position(|&b| b == delimiter) {
+            Some(len) => {
+                bytes.advance(len + 1); // consume the delimiter as well
+                &remaining[..len]
+            }
+            None => {
+                bytes.advance(remaining.len());
+                bail!(ErrorKind::MalformedBencode(Cow::Borrowed(
+                    "Integer delimiter not found."
+                )));
+            }
+        };
+
+        match str::from_utf8(read) {
+            Ok(int_string) => {
+                if int_string.starts_with("-0") {
+                    bail!(ErrorKind::MalformedBencode(Cow::Borrowed("-0 found.")));
+                } else if (int_string.starts_with('0')) && (int_string.len() != 1) {
+                    bail!(ErrorKind::MalformedBencode(Cow::Borrowed(
+                        "Integer with leading zero(s) found."
+                    )));
+                } else {
+                    match int_string.parse() {
+                        Ok(int) => Ok(BencodeElem::Integer(int)),
+                        Err(_) => bail!(ErrorKind::MalformedBencode(Cow::Owned(format!(
+                            "Input contains invalid integer: {}.",
+                            int_string
+                        )))),
                     }
                 }
-                Err(_) => bail!(ErrorKind::MalformedBencode(Cow::Borrowed(
-                    "Input contains invalid UTF-8."
-                ))),
             }
+            Err(_) => bail!(ErrorKind::MalformedBencode(Cow::Borrowed(
+                "Input contains invalid UTF-8."
+            ))),
         }
     }
 
-    fn decode_string(bytes: &mut ByteBuffer) -> Result<BencodeElem> {
+    fn decode_string(bytes: &mut ByteBuffer, options: &ParseOptions) -> Result<BencodeElem> {
         match Self::decode_bytes(bytes) {
             Ok(BencodeElem::Bytes(string_bytes)) => {
-                // Valid UTF8 strings are normalizd to NFC forms.
+                // Valid UTF8 strings are normalizd to NFC forms (unless disabled).
                 match String::from_utf8(string_bytes) {
-                    Ok(string) => Ok(BencodeElem::String(string.chars().nfc().collect())),
+                    Ok(string) => Ok(BencodeElem::String(Self::normalize(string, options))),
                     Err(e) => Ok(BencodeElem::Bytes(e.into_bytes())),
                 }
             }
@@ -185,11 +264,26 @@ impl BencodeElem {
         }
     }
 
+    fn normalize(string: String, options: &ParseOptions) -> String {
+        if options.normalize_nfc {
+            string.chars().nfc().collect()
+        } else {
+            string
+        }
+    }
+
     fn decode_bytes(bytes: &mut ByteBuffer) -> Result<BencodeElem> {
+        Ok(BencodeElem::Bytes(Self::decode_slice(bytes)?.to_vec()))
+    }
+
+    fn decode_slice<'a>(bytes: &mut ByteBuffer<'a>) -> Result<&'a [u8]> {
         match Self::decode_integer(bytes, STRING_DELIMITER) {
             Ok(BencodeElem::Integer(len)) => {
                 if let Ok(len) = util::i64_to_usize(len) {
-                    Ok(BencodeElem::Bytes(bytes.take(len).cloned().collect()))
+                    let remaining = bytes.remaining();
+                    let len = cmp::min(len, remaining.len());
+                    bytes.advance(len);
+                    Ok(&remaining[..len])
                 } else {
                     bail!(ErrorKind::MalformedBencode(Cow::Borrowed(
                         "A string's length does not fit into `usize`."
@@ -202,6 +296,142 @@ impl BencodeElem {
     }
 }
 
+impl<'a> BencodeRef<'a> {
+    /// Parse `bytes` and return all `BencodeRef` found.
+    ///
+    /// The grammar (and thus the errors returned) is the same as
+    /// [`BencodeElem::from_bytes()`], except that strings are not
+    /// normalized and dictionaries are never converted to `HashMap`s.
+    ///
+    /// If `bytes` is empty, then `Ok(vec)` will be returned, but
+    /// `vec` would be empty as well.
+    ///
+    /// [`BencodeElem::from_bytes()`]: enum.BencodeElem.html#method.from_bytes
+    pub fn from_bytes(bytes: &'a [u8]) -> Result<Vec<BencodeRef<'a>>> {
+        let mut bytes = ByteBuffer::new(bytes);
+        let mut elements = Vec::new();
+
+        while !bytes.is_empty() {
+            let element = BencodeRef::parse(&mut bytes)?;
+            elements.push(element);
+        }
+
+        Ok(elements)
+    }
+
+    /// Convert this `BencodeRef` into a `BencodeElem`.
+    ///
+    /// The result is the same as what [`BencodeElem::from_bytes()`] would
+    /// return for the same input (i.e. strings and dictionary keys are
+    /// normalized to NFC forms). Use [`to_owned_with()`] to change that.
+    ///
+    /// [`BencodeElem::from_bytes()`]: enum.BencodeElem.html#method.from_bytes
+    /// [`to_owned_with()`]: #method.to_owned_with
+    pub fn to_owned(&self) -> BencodeElem {
+        self.to_owned_with(&ParseOptions::default())
+    }
+
+    /// Convert this `BencodeRef` into a `BencodeElem` according to `options`.
+    pub fn to_owned_with(&self, options: &ParseOptions) -> BencodeElem {
+        match *self {
+            BencodeRef::String(string) => {
+                BencodeElem::String(BencodeElem::normalize(string.to_owned(), options))
+            }
+            BencodeRef::Bytes(bytes) => BencodeElem::Bytes(bytes.to_vec()),
+            BencodeRef::Integer(int) => BencodeElem::Integer(int),
+            BencodeRef::List(ref list) => {
+                BencodeElem::List(list.iter().map(|e| e.to_owned_with(options)).collect())
+            }
+            BencodeRef::Dictionary(ref entries) => {
+                // convert to Dictionary if possible, same as `BencodeElem::decode_dictionary()`
+                if entries.iter().all(|(k, _)| str::from_utf8(k).is_ok()) {
+                    BencodeElem::Dictionary(
+                        entries
+                            .iter()
+                            .map(|(k, v)| {
+                                (
+                                    BencodeElem::normalize(
+                                        String::from_utf8_lossy(k).into_owned(),
+                                        options,
+                                    ),
+                                    v.to_owned_with(options),
+                                )
+                            })
+                            .collect(),
+                    )
+                } else {
+                    BencodeElem::RawDictionary(
+                        entries
+                            .iter()
+                            .map(|(k, v)| (k.to_vec(), v.to_owned_with(options)))
+                            .collect(),
+                    )
+                }
+            }
+        }
+    }
+
+    fn parse(bytes: &mut ByteBuffer<'a>) -> Result<BencodeRef<'a>> {
+        match BencodeElem::peek_byte(bytes)? {
+            DICTIONARY_PREFIX => {
+                bytes.advance(1);
+                Ok(Self::decode_dictionary(bytes)?)
+            }
+            LIST_PREFIX => {
+                bytes.advance(1);
+                Ok(Self::decode_list(bytes)?)
+            }
+            INTEGER_PREFIX => {
+                bytes.advance(1);
+                match BencodeElem::decode_integer(bytes, INTEGER_POSTFIX)? {
+                    BencodeElem::Integer(int) => Ok(BencodeRef::Integer(int)),
+                    _ => panic!("decode_integer() did not return an integer."),
+                }
+            }
+            _ => {
+                let string_bytes = BencodeElem::decode_slice(bytes)?;
+                match str::from_utf8(string_bytes) {
+                    Ok(string) => Ok(BencodeRef::String(string)),
+                    Err(_) => Ok(BencodeRef::Bytes(string_bytes)),
+                }
+            }
+        }
+    }
+
+    fn decode_dictionary(bytes: &mut ByteBuffer<'a>) -> Result<BencodeRef<'a>> {
+        let mut entries: Vec<(&'a [u8], BencodeRef<'a>)> = Vec::new();
+
+        while BencodeElem::peek_byte(bytes)? != DICTIONARY_POSTFIX {
+            // more to parse
+            let key = BencodeElem::decode_slice(bytes)?;
+            // "sorted as raw strings, not alphanumerics"
+            if let Some(&(prev, _)) = entries.last() {
+                if prev > key {
+                    bail!(ErrorKind::MalformedBencode(Cow::Borrowed(
+                        "A dictionary is not properly sorted."
+                    )));
+                }
+            }
+            entries.push((key, Self::parse(bytes)?));
+        }
+        bytes.advance(1); // consume the postfix
+
+        Ok(BencodeRef::Dictionary(entries))
+    }
+
+    fn decode_list(bytes: &mut ByteBuffer<'a>) -> Result<BencodeRef<'a>> {
+        let mut list = Vec::new();
+
+        while BencodeElem::peek_byte(bytes)? != LIST_POSTFIX {
+            // more to parse
+            list.push(Self::parse(bytes)?);
+        }
+        bytes.advance(1); //consume the postfix
+
+        Ok(BencodeRef::List(list))
+    }
+}
+
 #[cfg(test)]
 mod bencode_elem_read_tests {
     // @note: `from_bytes()` and `from_file()` are not tested
@@ -210,6 +440,41 @@ mod bencode_elem_read_tests {
     // and `Torrent::read_from_file()`).
     use super::*;
 
+    #[test]
+    fn find_value_span_ok() {
+        let bytes = "d1:ai1e4:infod1:bl1:ci-2eee1:zdee".as_bytes();
+        let (start, end) = BencodeElem::find_value_span(bytes, b"info")
+            .unwrap()
+            .unwrap();
+        assert_eq!(&bytes[start..end], "d1:bl1:ci-2eee".as_bytes());
+
+        let (start, end) = BencodeElem::find_value_span(bytes, b"z").unwrap().unwrap();
+        assert_eq!(&bytes[start..end], "de".as_bytes());
+    }
+
+    #[test]
+    fn find_value_span_not_found() {
+        let bytes = "d1:ai1ee".as_bytes();
+        assert_eq!(BencodeElem::find_value_span(bytes, b"info").unwrap(), None);
+    }
+
+    #[test]
+    fn find_value_span_not_dictionary() {
+        let bytes = "l4:infoe".as_bytes();
+        assert_eq!(BencodeElem::find_value_span(bytes, b"info").unwrap(), None);
+    }
+
+    #[test]
+    fn find_value_span_malformed() {
+        let bytes = "d4:infol".as_bytes();
+        match BencodeElem::find_value_span(bytes, b"info") {
+            Err(Error(ErrorKind::MalformedBencode(m), _)) => {
+                assert_eq!(m, "Expected more bytes, but none found.");
+            }
+            _ => assert!(false),
+        }
+    }
+
     #[test]
     fn peek_byte_ok() {
         let bytes = "a".as_bytes();
@@ -336,7 +601,8 @@ mod bencode_elem_read_tests {
     fn decode_string_ok() {
         let bytes = "4:spam".as_bytes();
         assert_eq!(
-            BencodeElem::decode_string(&mut ByteBuffer::new(bytes)).unwrap(),
+            BencodeElem::decode_string(&mut ByteBuffer::new(bytes), &ParseOptions::default())
+                .unwrap(),
             bencode_elem!("spam")
         );
     }
@@ -344,7 +610,7 @@ mod bencode_elem_read_tests {
     #[test]
     fn decode_string_invalid_len() {
         let bytes = "a:spam".as_bytes();
-        match BencodeElem::decode_string(&mut ByteBuffer::new(bytes)) {
+        match BencodeElem::decode_string(&mut ByteBuffer::new(bytes), &ParseOptions::default()) {
             Err(Error(ErrorKind::MalformedBencode(m), _)) => {
                 assert_eq!(m, "Input contains invalid integer: a.");
             }
@@ -355,7 +621,7 @@ mod bencode_elem_read_tests {
     #[test]
     fn decode_string_no_len() {
         let bytes = ":spam".as_bytes();
-        match BencodeElem::decode_string(&mut ByteBuffer::new(bytes)) {
+        match BencodeElem::decode_string(&mut ByteBuffer::new(bytes), &ParseOptions::default()) {
             Err(Error(ErrorKind::MalformedBencode(m), _)) => {
                 assert_eq!(m, "Input contains invalid integer: .");
             }
@@ -366,7 +632,7 @@ mod bencode_elem_read_tests {
     #[test]
     fn decode_string_negative_len() {
         let bytes = "-1:spam".as_bytes();
-        match BencodeElem::decode_string(&mut ByteBuffer::new(bytes)) {
+        match BencodeElem::decode_string(&mut ByteBuffer::new(bytes), &ParseOptions::default()) {
             Err(Error(ErrorKind::MalformedBencode(m), _)) => {
                 assert_eq!(m, "A string's length does not fit into `usize`.");
             }
@@ -377,7 +643,7 @@ mod bencode_elem_read_tests {
     #[test]
     fn decode_string_no_delimiter() {
         let bytes = "4spam".as_bytes();
-        match BencodeElem::decode_string(&mut ByteBuffer::new(bytes)) {
+        match BencodeElem::decode_string(&mut ByteBuffer::new(bytes), &ParseOptions::default()) {
             Err(Error(ErrorKind::MalformedBencode(m), _)) => {
                 assert_eq!(m, "Integer delimiter not found.");
             }
@@ -388,7 +654,7 @@ mod bencode_elem_read_tests {
     #[test]
     fn decode_string_no_delimiter_2() {
         let bytes = "456".as_bytes();
-        match BencodeElem::decode_string(&mut ByteBuffer::new(bytes)) {
+        match BencodeElem::decode_string(&mut ByteBuffer::new(bytes), &ParseOptions::default()) {
             Err(Error(ErrorKind::MalformedBencode(m), _)) => {
                 assert_eq!(m, "Integer delimiter not found.");
             }
@@ -400,16 +666,38 @@ mod bencode_elem_read_tests {
     fn decode_string_as_bytes() {
         let bytes = vec![b'4', b':', 0xff, 0xf8, 0xff, 0xee]; // bad UTF8 gives bytes
         assert_eq!(
-            BencodeElem::decode_string(&mut ByteBuffer::new(&bytes)).unwrap(),
+            BencodeElem::decode_string(&mut ByteBuffer::new(&bytes), &ParseOptions::default())
+                .unwrap(),
             bencode_elem!((0xff, 0xf8, 0xff, 0xee))
         );
     }
 
+    #[test]
+    fn decode_string_normalized() {
+        let bytes = "3:e\u{301}".as_bytes();
+        assert_eq!(
+            BencodeElem::decode_string(&mut ByteBuffer::new(bytes), &ParseOptions::default())
+                .unwrap(),
+            bencode_elem!("\u{e9}")
+        );
+    }
+
+    #[test]
+    fn decode_string_not_normalized() {
+        let bytes = "3:e\u{301}".as_bytes();
+        let options = ParseOptions::new().set_normalize_nfc(false);
+        assert_eq!(
+            BencodeElem::decode_string(&mut ByteBuffer::new(bytes), &options).unwrap(),
+            bencode_elem!("e\u{301}")
+        );
+    }
+
     #[test]
     fn decode_list_ok() {
         let bytes = "4:spam4:eggse".as_bytes();
         assert_eq!(
-            BencodeElem::decode_list(&mut ByteBuffer::new(bytes)).unwrap(),
+            BencodeElem::decode_list(&mut ByteBuffer::new(bytes), &ParseOptions::default())
+                .unwrap(),
             bencode_elem!(["spam", "eggs"])
         );
     }
@@ -418,7 +706,8 @@ mod bencode_elem_read_tests {
     fn decode_list_nested() {
         let bytes = "4:spaml6:cheesee4:eggse".as_bytes();
         assert_eq!(
-            BencodeElem::decode_list(&mut ByteBuffer::new(bytes)).unwrap(),
+            BencodeElem::decode_list(&mut ByteBuffer::new(bytes), &ParseOptions::default())
+                .unwrap(),
             bencode_elem!(["spam", ["cheese"], "eggs"])
         );
     }
@@ -427,7 +716,8 @@ mod bencode_elem_read_tests {
     fn decode_list_empty() {
         let bytes = "e".as_bytes();
         assert_eq!(
-            BencodeElem::decode_list(&mut ByteBuffer::new(bytes)).unwrap(),
+            BencodeElem::decode_list(&mut ByteBuffer::new(bytes), &ParseOptions::default())
+                .unwrap(),
             bencode_elem!([])
         );
     }
@@ -435,7 +725,7 @@ mod bencode_elem_read_tests {
     #[test]
     fn decode_list_bad_structure() {
         let bytes = "4:spaml6:cheese4:eggse".as_bytes();
-        match BencodeElem::decode_list(&mut ByteBuffer::new(bytes)) {
+        match BencodeElem::decode_list(&mut ByteBuffer::new(bytes), &ParseOptions::default()) {
             Err(Error(ErrorKind::MalformedBencode(m), _)) => {
                 assert_eq!(m, "Expected more bytes, but none found.");
             }
@@ -447,7 +737,8 @@ mod bencode_elem_read_tests {
     fn decode_dictionary_ok() {
         let bytes = "3:cow3:moo4:spam4:eggse".as_bytes();
         assert_eq!(
-            BencodeElem::decode_dictionary(&mut ByteBuffer::new(bytes)).unwrap(),
+            BencodeElem::decode_dictionary(&mut ByteBuffer::new(bytes), &ParseOptions::default())
+                .unwrap(),
             bencode_elem!({ ("cow", "moo"), ("spam", "eggs") })
         );
     }
@@ -456,7 +747,8 @@ mod bencode_elem_read_tests {
     fn decode_dictionary_nested() {
         let bytes = "3:cowd3:mooi4ee4:spam4:eggse".as_bytes();
         assert_eq!(
-            BencodeElem::decode_dictionary(&mut ByteBuffer::new(bytes)).unwrap(),
+            BencodeElem::decode_dictionary(&mut ByteBuffer::new(bytes), &ParseOptions::default())
+                .unwrap(),
             bencode_elem!({ ("cow", { ("moo", 4_i64) }), ("spam", "eggs") })
         );
     }
@@ -465,7 +757,8 @@ mod bencode_elem_read_tests {
     fn decode_dictionary_empty() {
         let bytes = "e".as_bytes();
         assert_eq!(
-            BencodeElem::decode_dictionary(&mut ByteBuffer::new(bytes)).unwrap(),
+            BencodeElem::decode_dictionary(&mut ByteBuffer::new(bytes), &ParseOptions::default())
+                .unwrap(),
             bencode_elem!({})
         );
     }
@@ -473,7 +766,8 @@ mod bencode_elem_read_tests {
     #[test]
     fn decode_dictionary_bad_structure() {
         let bytes = "3:cow3:moo4:spame".as_bytes();
-        match BencodeElem::decode_dictionary(&mut ByteBuffer::new(bytes)) {
+        match BencodeElem::decode_dictionary(&mut ByteBuffer::new(bytes), &ParseOptions::default())
+        {
             Err(Error(ErrorKind::MalformedBencode(m), _)) => {
                 assert_eq!(m, "Integer delimiter not found.");
             }
@@ -484,7 +778,8 @@ mod bencode_elem_read_tests {
     #[test]
     fn decode_dictionary_non_string_key_1() {
         let bytes = "i4e3:moo4:spam4:eggse".as_bytes();
-        match BencodeElem::decode_dictionary(&mut ByteBuffer::new(bytes)) {
+        match BencodeElem::decode_dictionary(&mut ByteBuffer::new(bytes), &ParseOptions::default())
+        {
             Err(Error(ErrorKind::MalformedBencode(m), _)) => {
                 assert_eq!(m, "Input contains invalid integer: i4e3.");
             }
@@ -495,7 +790,8 @@ mod bencode_elem_read_tests {
     #[test]
     fn decode_dictionary_not_sorted() {
         let bytes = "3:zoo3:moo4:spam4:eggse".as_bytes();
-        match BencodeElem::decode_dictionary(&mut ByteBuffer::new(bytes)) {
+        match BencodeElem::decode_dictionary(&mut ByteBuffer::new(bytes), &ParseOptions::default())
+        {
             Err(Error(ErrorKind::MalformedBencode(m), _)) => {
                 assert_eq!(m, "A dictionary is not properly sorted.");
             }
@@ -509,7 +805,8 @@ mod bencode_elem_read_tests {
         bytes.extend("3:mooe".as_bytes());
 
         assert_eq!(
-            BencodeElem::decode_dictionary(&mut ByteBuffer::new(&bytes)).unwrap(),
+            BencodeElem::decode_dictionary(&mut ByteBuffer::new(&bytes), &ParseOptions::default())
+                .unwrap(),
             bencode_elem!(r{ ([0xff, 0xf8, 0xff, 0xee], "moo") })
         );
     }
@@ -522,11 +819,41 @@ mod bencode_elem_read_tests {
         bytes.extend("4:eggse".as_bytes());
 
         assert_eq!(
-            BencodeElem::decode_dictionary(&mut ByteBuffer::new(&bytes)).unwrap(),
+            BencodeElem::decode_dictionary(&mut ByteBuffer::new(&bytes), &ParseOptions::default())
+                .unwrap(),
             bencode_elem!(r{ ([b'z', b'o', b'o'], "moo"), ([0xff, 0xf8, 0xff, 0xee], "eggs") })
         );
     }
 
+    #[test]
+    fn decode_dictionary_normalized() {
+        let bytes = "3:e\u{301}3:e\u{301}e".as_bytes();
+        assert_eq!(
+            BencodeElem::decode_dictionary(&mut ByteBuffer::new(bytes), &ParseOptions::default())
+                .unwrap(),
+            bencode_elem!({ ("\u{e9}", "\u{e9}") })
+        );
+    }
+
+    #[test]
+    fn decode_dictionary_not_normalized() {
+        let bytes = "3:e\u{301}3:e\u{301}e".as_bytes();
+        let options = ParseOptions::new().set_normalize_nfc(false);
+        assert_eq!(
+            BencodeElem::decode_dictionary(&mut ByteBuffer::new(bytes), &options).unwrap(),
+            bencode_elem!({ ("e\u{301}", "e\u{301}") })
+        );
+    }
+
+    #[test]
+    fn from_bytes_with_round_trip() {
+        let bytes = "d3:e\u{301}l3:e\u{301}i1eee".as_bytes();
+        let options = ParseOptions::new().set_normalize_nfc(false);
+        let parsed = BencodeElem::from_bytes_with(bytes, &options).unwrap();
+        assert_eq!(parsed.len(), 1);
+        assert_eq!(parsed[0].encode(), bytes);
+    }
+
     // @note: `parse()` is called by other `decode_*()` methods, so
     // it is implicitly tested by other tests. Still, the following tests
     // are provided. Though these tests are not as comprehensive.
@@ -534,7 +861,7 @@ mod bencode_elem_read_tests {
     fn parse_integer_ok() {
         let bytes = "i0e".as_bytes();
         assert_eq!(
-            BencodeElem::parse(&mut ByteBuffer::new(bytes)).unwrap(),
+            BencodeElem::parse(&mut ByteBuffer::new(bytes), &ParseOptions::default()).unwrap(),
             bencode_elem!(0_i64)
         );
     }
@@ -543,7 +870,7 @@ mod bencode_elem_read_tests {
     fn parse_string_ok() {
         let bytes = "4:spam".as_bytes();
         assert_eq!(
-            BencodeElem::parse(&mut ByteBuffer::new(bytes)).unwrap(),
+            BencodeElem::parse(&mut ByteBuffer::new(bytes), &ParseOptions::default()).unwrap(),
             bencode_elem!("spam")
         );
     }
@@ -552,7 +879,7 @@ mod bencode_elem_read_tests {
     fn parse_bytes_ok() {
         let bytes = vec![b'4', b':', 0xff, 0xf8, 0xff, 0xee]; // bad UTF8 gives bytes
         assert_eq!(
-            BencodeElem::parse(&mut ByteBuffer::new(&bytes)).unwrap(),
+            BencodeElem::parse(&mut ByteBuffer::new(&bytes), &ParseOptions::default()).unwrap(),
             bencode_elem!((0xff, 0xf8, 0xff, 0xee))
         );
     }
@@ -561,7 +888,7 @@ mod bencode_elem_read_tests {
     fn parse_list_ok() {
         let bytes = "l4:spam4:eggse".as_bytes();
         assert_eq!(
-            BencodeElem::parse(&mut ByteBuffer::new(bytes)).unwrap(),
+            BencodeElem::parse(&mut ByteBuffer::new(bytes), &ParseOptions::default()).unwrap(),
             bencode_elem!(["spam", "eggs"])
         );
     }
@@ -570,8 +897,106 @@ mod bencode_elem_read_tests {
     fn parse_dictionary_ok() {
         let bytes = "d3:cow3:moo4:spam4:eggse".as_bytes();
         assert_eq!(
-            BencodeElem::parse(&mut ByteBuffer::new(bytes)).unwrap(),
+            BencodeElem::parse(&mut ByteBuffer::new(bytes), &ParseOptions::default()).unwrap(),
             bencode_elem!({ ("cow", "moo"), ("spam", "eggs") })
         );
     }
 }
+
+#[cfg(test)]
+mod bencode_ref_read_tests {
+    use super::*;
+
+    #[test]
+    fn from_bytes_ok() {
+        let bytes = "i-2e4:spaml1:ai1ee".as_bytes();
+        assert_eq!(
+            BencodeRef::from_bytes(bytes).unwrap(),
+            vec![
+                BencodeRef::Integer(-2),
+                BencodeRef::String("spam"),
+                BencodeRef::List(vec![BencodeRef::String("a"), BencodeRef::Integer(1)]),
+            ]
+        );
+    }
+
+    #[test]
+    fn from_bytes_dictionary_ordered() {
+        let mut bytes = "d3:cow3:moo4:spam".as_bytes().to_owned();
+        bytes.extend(vec![b'4', b':', 0xff, 0xf8, 0xff, 0xee]);
+        bytes.extend("e".as_bytes());
+
+        assert_eq!(
+            BencodeRef::from_bytes(&bytes).unwrap(),
+            vec![BencodeRef::Dictionary(vec![
+                (&b"cow"[..], BencodeRef::String("moo")),
+                (&b"spam"[..], BencodeRef::Bytes(&[0xff, 0xf8, 0xff, 0xee])),
+            ])]
+        );
+    }
+
+    #[test]
+    fn from_bytes_not_normalized() {
+        let bytes = "3:e\u{301}".as_bytes();
+        assert_eq!(
+            BencodeRef::from_bytes(bytes).unwrap(),
+            vec![BencodeRef::String("e\u{301}")]
+        );
+    }
+
+    #[test]
+    fn from_bytes_not_sorted() {
+        let bytes = "d4:spam4:eggs3:cow3:mooe".as_bytes();
+        match BencodeRef::from_bytes(bytes) {
+            Err(Error(ErrorKind::MalformedBencode(m), _)) => {
+                assert_eq!(m, "A dictionary is not properly sorted.");
+            }
+            _ => assert!(false),
+        }
+    }
+
+    #[test]
+    fn from_bytes_bad_structure() {
+        let bytes = "l4:spam4:eggs".as_bytes();
+        match BencodeRef::from_bytes(bytes) {
+            Err(Error(ErrorKind::MalformedBencode(m), _)) => {
+                assert_eq!(m, "Expected more bytes, but none found.");
+            }
+            _ => assert!(false),
+        }
+    }
+
+    #[test]
+    fn to_owned_ok() {
+        let bytes = "d3:cow3:e\u{301}4:spaml4:eggsi1eee".as_bytes();
+        let elements = BencodeRef::from_bytes(bytes).unwrap();
+        assert_eq!(
+            elements[0].to_owned(),
+            bencode_elem!({ ("cow", "\u{e9}"), ("spam", ["eggs", 1]) })
+        );
+    }
+
+    #[test]
+    fn to_owned_with_not_normalized() {
+        let bytes = "d3:cow3:e\u{301}e".as_bytes();
+        let elements = BencodeRef::from_bytes(bytes).unwrap();
+        let options = ParseOptions::new().set_normalize_nfc(false);
+        assert_eq!(
+            elements[0].to_owned_with(&options),
+            bencode_elem!({ ("cow", "e\u{301}") })
+        );
+    }
+
+    #[test]
+    fn to_owned_raw_dictionary() {
+        let mut bytes = "d".as_bytes().to_owned();
+        bytes.extend(vec![b'4', b':', 0xff, 0xf8, 0xff, 0xee]);
+        bytes.extend("3:mooe".as_bytes());
+
+        let elements = BencodeRef::from_bytes(&bytes).unwrap();
+        assert_eq!(
+            elements[0].to_owned(),
+            bencode_elem!(r{ ([0xff, 0xf8, 0xff, 0xee], "moo") })
+        );
+    }
+}
@@ -0,0 +1,442 @@
+// Serialize any `Serialize` type into bencode.
+//
+// Values are first serialized into `BencodeElem`, which is then encoded.
+// As `BencodeElem::encode()` always sorts dictionary keys, the output
+// is canonical.
+
+use super::*;
+use error::*;
+use serde::ser::{self, Serialize};
+use std::borrow::Cow;
+use std::fmt::Display;
+use util;
+
+/// Serialize `value` into canonical bencode (i.e. with sorted dictionary keys).
+///
+/// Since bencode has fewer types than `serde`, values are mapped as follows:
+/// - `bool` becomes an integer (`0` or `1`).
+/// - `char` and `&str` become strings; `&[u8]` (with [`serde_bytes`])
+///   becomes a byte string.
+/// - `None` and `()` are omitted when they are dictionary values. They can't be
+///   list items or the top-level value.
+/// - Sequences and tuples become lists; maps and structs become dictionaries.
+///   Map keys must be strings or byte strings.
+/// - Unit variants become strings. Other enum variants become dictionaries
+///   with a single entry, where the key is the variant's name.
+/// - Floating-point numbers are not supported.
+///
+/// [`serde_bytes`]: https://docs.rs/serde_bytes
+pub fn to_bytes<T>(value: &T) -> Result<Vec<u8>>
+where
+    T: Serialize + ?Sized,
+{
+    match value.serialize(Serializer)? {
+        Some(elem) => Ok(elem.encode()),
+        None => bail!(ErrorKind::SerdeFailure(Cow::Borrowed(
+            "The top-level value must not be `None` or `()`."
+        ))),
+    }
+}
+
+impl ser::Error for Error {
+    fn custom<T: Display>(msg: T) -> Error {
+        ErrorKind::SerdeFailure(Cow::Owned(msg.to_string())).into()
+    }
+}
+
+// `None` means the value should be omitted.
+type Output = Option<BencodeElem>;
+
+struct Serializer;
+
+// Build a dictionary from `entries`, using `Dictionary` if possible.
+fn into_dictionary(entries: Vec<(Vec<u8>, BencodeElem)>) -> BencodeElem {
+    if entries
+        .iter()
+        .all(|(k, _)| ::std::str::from_utf8(k).is_ok())
+    {
+        BencodeElem::Dictionary(
+            entries
+                .into_iter()
+                .map(|(k, v)| (String::from_utf8(k).expect("Checked above."), v))
+                .collect(),
+        )
+    } else {
+        BencodeElem::RawDictionary(entries.into_iter().collect())
+    }
+}
+
+fn with_variant(variant: &str, value: BencodeElem) -> BencodeElem {
+    BencodeElem::Dictionary(Some((variant.to_owned(), value)).into_iter().collect())
+}
+
+fn unsupported(what: &'static str) -> Error {
+    ErrorKind::SerdeFailure(Cow::Owned(format!(
+        "{} can't be serialized into bencode.",
+        what
+    )))
+    .into()
+}
+
+impl ser::Serializer for Serializer {
+    type Ok = Output;
+    type Error = Error;
+
+    type SerializeSeq = SerializeList;
+    type SerializeTuple = SerializeList;
+    type SerializeTupleStruct = SerializeList;
+    type SerializeTupleVariant = SerializeList;
+    type SerializeMap = SerializeDictionary;
+    type SerializeStruct = SerializeDictionary;
+    type SerializeStructVariant = SerializeDictionary;
+
+    fn is_human_readable(&self) -> bool {
+        false
+    }
+
+    fn serialize_bool(self, v: bool) -> Result<Output> {
+        Ok(Some(BencodeElem::Integer(i64::from(v))))
+    }
+
+    fn serialize_i8(self, v: i8) -> Result<Output> {
+        Ok(Some(BencodeElem::from(v)))
+    }
+
+    fn serialize_i16(self, v: i16) -> Result<Output> {
+        Ok(Some(BencodeElem::from(v)))
+    }
+
+    fn serialize_i32(self, v: i32) -> Result<Output> {
+        Ok(Some(BencodeElem::from(v)))
+    }
+
+    fn serialize_i64(self, v: i64) -> Result<Output> {
+        Ok(Some(BencodeElem::from(v)))
+    }
+
+    fn serialize_u8(self, v: u8) -> Result<Output> {
+        Ok(Some(BencodeElem::from(v)))
+    }
+
+    fn serialize_u16(self, v: u16) -> Result<Output> {
+        Ok(Some(BencodeElem::from(v)))
+    }
+
+    fn serialize_u32(self, v: u32) -> Result<Output> {
+        Ok(Some(BencodeElem::from(v)))
+    }
+
+    fn serialize_u64(self, v: u64) -> Result<Output> {
+        Ok(Some(BencodeElem::Integer(util::u64_to_i64(v)?)))
+    }
+
+    fn serialize_f32(self, _v: f32) -> Result<Output> {
+        Err(unsupported("`f32`"))
+    }
+
+    fn serialize_f64(self, _v: f64) -> Result<Output> {
+        Err(unsupported("`f64`"))
+    }
+
+    fn serialize_char(self, v: char) -> Result<Output> {
+        Ok(Some(BencodeElem::String(v.to_string())))
+    }
+
+    fn serialize_str(self, v: &str) -> Result<Output> {
+        Ok(Some(BencodeElem::from(v)))
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> Result<Output> {
+        Ok(Some(BencodeElem::from(v)))
+    }
+
+    fn serialize_none(self) -> Result<Output> {
+        Ok(None)
+    }
+
+    fn serialize_some<T>(self, value: &T) -> Result<Output>
+    where
+        T: Serialize + ?Sized,
+    {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<Output> {
+        Ok(None)
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<Output> {
+        Ok(None)
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+    ) -> Result<Output> {
+        Ok(Some(BencodeElem::from(variant)))
+    }
+
+    fn serialize_newtype_struct<T>(self, _name: &'static str, value: &T) -> Result<Output>
+    where
+        T: Serialize + ?Sized,
+    {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T>(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> Result<Output>
+    where
+        T: Serialize + ?Sized,
+    {
+        match value.serialize(self)? {
+            Some(value) => Ok(Some(with_variant(variant, value))),
+            None => Ok(Some(BencodeElem::from(variant))),
+        }
+    }
+
+    fn serialize_seq(self, len: Option<usize>) -> Result<SerializeList> {
+        Ok(SerializeList {
+            variant: None,
+            list: Vec::with_capacity(len.unwrap_or(0)),
+        })
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<SerializeList> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_struct(self, _name: &'static str, len: usize) -> Result<SerializeList> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<SerializeList> {
+        Ok(SerializeList {
+            variant: Some(variant),
+            list: Vec::with_capacity(len),
+        })
+    }
+
+    fn serialize_map(self, len: Option<usize>) -> Result<SerializeDictionary> {
+        Ok(SerializeDictionary {
+            variant: None,
+            entries: Vec::with_capacity(len.unwrap_or(0)),
+            key: None,
+        })
+    }
+
+    fn serialize_struct(self, _name: &'static str, len: usize) -> Result<SerializeDictionary> {
+        self.serialize_map(Some(len))
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<SerializeDictionary> {
+        Ok(SerializeDictionary {
+            variant: Some(variant),
+            entries: Vec::with_capacity(len),
+            key: None,
+        })
+    }
+}
+
+struct SerializeList {
+    variant: Option<&'static str>,
+    list: Vec<BencodeElem>,
+}
+
+impl SerializeList {
+    fn push<T>(&mut self, value: &T) -> Result<()>
+    where
+        T: Serialize + ?Sized,
+    {
+        match value.serialize(Serializer)? {
+            Some(value) => {
+                self.list.push(value);
+                Ok(())
+            }
+            None => bail!(ErrorKind::SerdeFailure(Cow::Borrowed(
+                "A list item must not be `None` or `()`."
+            ))),
+        }
+    }
+
+    fn finish(self) -> Result<Output> {
+        let list = BencodeElem::List(self.list);
+        match self.variant {
+            Some(variant) => Ok(Some(with_variant(variant, list))),
+            None => Ok(Some(list)),
+        }
+    }
+}
+
+impl ser::SerializeSeq for SerializeList {
+    type Ok = Output;
+    type Error = Error;
+
+    fn serialize_element<T>(&mut self, value: &T) -> Result<()>
+    where
+        T: Serialize + ?Sized,
+    {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<Output> {
+        self.finish()
+    }
+}
+
+impl ser::SerializeTuple for SerializeList {
+    type Ok = Output;
+    type Error = Error;
+
+    fn serialize_element<T>(&mut self, value: &T) -> Result<()>
+    where
+        T: Serialize + ?Sized,
+    {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<Output> {
+        self.finish()
+    }
+}
+
+impl ser::SerializeTupleStruct for SerializeList {
+    type Ok = Output;
+    type Error = Error;
+
+    fn serialize_field<T>(&mut self, value: &T) -> Result<()>
+    where
+        T: Serialize + ?Sized,
+    {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<Output> {
+        self.finish()
+    }
+}
+
+impl ser::SerializeTupleVariant for SerializeList {
+    type Ok = Output;
+    type Error = Error;
+
+    fn serialize_field<T>(&mut self, value: &T) -> Result<()>
+    where
+        T: Serialize + ?Sized,
+    {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<Output> {
+        self.finish()
+    }
+}
+
+struct SerializeDictionary {
+    variant: Option<&'static str>,
+    entries: Vec<(Vec<u8>, BencodeElem)>,
+    key: Option<Vec<u8>>, // key waiting for its value
+}
+
+impl SerializeDictionary {
+    fn insert<T>(&mut self, key: Vec<u8>, value: &T) -> Result<()>
+    where
+        T: Serialize + ?Sized,
+    {
+        if let Some(value) = value.serialize(Serializer)? {
+            self.entries.push((key, value));
+        }
+        Ok(())
+    }
+
+    fn finish(self) -> Result<Output> {
+        let dict = into_dictionary(self.entries);
+        match self.variant {
+            Some(variant) => Ok(Some(with_variant(variant, dict))),
+            None => Ok(Some(dict)),
+        }
+    }
+}
+
+impl ser::SerializeMap for SerializeDictionary {
+    type Ok = Output;
+    type Error = Error;
+
+    fn serialize_key<T>(&mut self, key: &T) -> Result<()>
+    where
+        T: Serialize + ?Sized,
+    {
+        match key.serialize(Serializer)? {
+            Some(BencodeElem::String(key)) => self.key = Some(key.into_bytes()),
+            Some(BencodeElem::Bytes(key)) => self.key = Some(key),
+            _ => bail!(ErrorKind::SerdeFailure(Cow::Borrowed(
+                "A dictionary key must be a string or a byte string."
+            ))),
+        }
+        Ok(())
+    }
+
+    fn serialize_value<T>(&mut self, value: &T) -> Result<()>
+    where
+        T: Serialize + ?Sized,
+    {
+        match self.key.take() {
+            Some(key) => self.insert(key, value),
+            None => panic!("serialize_value() is called before serialize_key()."),
+        }
+    }
+
+    fn end(self) -> Result<Output> {
+        self.finish()
+    }
+}
+
+impl ser::SerializeStruct for SerializeDictionary {
+    type Ok = Output;
+    type Error = Error;
+
+    fn serialize_field<T>(&mut self, key: &'static str, value: &T) -> Result<()>
+    where
+        T: Serialize + ?Sized,
+    {
+        self.insert(key.as_bytes().to_vec(), value)
+    }
+
+    fn end(self) -> Result<Output> {
+        self.finish()
+    }
+}
+
+impl ser::SerializeStructVariant for SerializeDictionary {
+    type Ok = Output;
+    type Error = Error;
+
+    fn serialize_field<T>(&mut self, key: &'static str, value: &T) -> Result<()>
+    where
+        T: Serialize + ?Sized,
+    {
+        self.insert(key.as_bytes().to_vec(), value)
+    }
+
+    fn end(self) -> Result<Output> {
+        self.finish()
+    }
+}
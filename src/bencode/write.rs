@@ -102,6 +102,41 @@ where
     Ok(())
 }
 
+/// Encode `dict` and write the result to `dst`, with `raw` written
+/// verbatim as the value of `key`.
+///
+/// `raw` must be valid bencode (e.g. the original bytes of a parsed
+/// dictionary). Any value `dict` has for `key` is ignored.
+pub(crate) fn write_dictionary_with_raw_value<W, S>(
+    dict: &HashMap<String, BencodeElem, S>,
+    key: &str,
+    raw: &[u8],
+    dst: &mut W,
+) -> Result<()>
+where
+    W: Write,
+    S: BuildHasher,
+{
+    let mut sorted = dict
+        .iter()
+        .filter(|&(k, _)| k != key)
+        .map(|(k, v)| (k.as_str(), Some(v)))
+        .collect::<Vec<(&str, Option<&BencodeElem>)>>();
+    sorted.push((key, None));
+    sorted.sort_by_key(|&(key, _)| key.as_bytes());
+
+    dst.write_all(&[DICTIONARY_PREFIX])?;
+    for (key, val) in sorted {
+        write_string(key, dst)?;
+        match val {
+            Some(val) => val.write_into(dst)?,
+            None => dst.write_all(raw)?,
+        }
+    }
+    dst.write_all(&[DICTIONARY_POSTFIX])?;
+    Ok(())
+}
+
 /// Encode `string` and return the result in a `Vec`.
 pub fn encode_string<S>(string: S) -> Vec<u8>
 where
@@ -221,14 +256,14 @@ mod bencode_elem_write_tests {
     #[test]
     fn write_string_ok() {
         let mut vec = Vec::new();
-        write_string(&"spam".to_owned(), &mut vec).unwrap();
+        write_string("spam", &mut vec).unwrap();
         assert_eq!(vec, "4:spam".as_bytes().to_vec());
     }
 
     #[test]
     fn write_bytes_ok() {
         let mut vec = Vec::new();
-        write_bytes(&[0x01, 0x02, 0x03, 0x04], &mut vec).unwrap();
+        write_bytes([0x01, 0x02, 0x03, 0x04], &mut vec).unwrap();
         assert_eq!(vec, vec![b'4', b':', 0x01, 0x02, 0x03, 0x04]);
     }
 
@@ -242,7 +277,7 @@ mod bencode_elem_write_tests {
     #[test]
     fn write_list_ok() {
         let mut vec = Vec::new();
-        write_list(&vec![bencode_elem!(42), bencode_elem!("spam")], &mut vec).unwrap();
+        write_list(vec![bencode_elem!(42), bencode_elem!("spam")], &mut vec).unwrap();
         assert_eq!(
             vec,
             vec![b'l', b'i', b'4', b'2', b'e', b'4', b':', b's', b'p', b'a', b'm', b'e',]
@@ -253,13 +288,10 @@ mod bencode_elem_write_tests {
     fn write_dictionary_ok() {
         let mut vec = Vec::new();
         write_dictionary::<_, RandomState>(
-            &HashMap::from_iter(
-                vec![
-                    ("spam".to_owned(), bencode_elem!(42)),
-                    ("cow".to_owned(), bencode_elem!("moo")),
-                ]
-                .into_iter(),
-            ),
+            &HashMap::from_iter(vec![
+                ("spam".to_owned(), bencode_elem!(42)),
+                ("cow".to_owned(), bencode_elem!("moo")),
+            ]),
             &mut vec,
         )
         .unwrap();
@@ -273,17 +305,31 @@ mod bencode_elem_write_tests {
     }
 
     #[test]
-    fn encode_string_ok() {
-        assert_eq!(
-            encode_string(&"spam".to_owned()),
-            "4:spam".as_bytes().to_vec(),
+    fn write_dictionary_with_raw_value_ok() {
+        let mut vec = Vec::new();
+        write_dictionary_with_raw_value::<_, RandomState>(
+            &HashMap::from_iter(vec![
+                ("spam".to_owned(), bencode_elem!(42)),
+                ("cow".to_owned(), bencode_elem!("moo")),
+                ("info".to_owned(), bencode_elem!("ignored")),
+            ]),
+            "info",
+            b"de",
+            &mut vec,
         )
+        .unwrap();
+        assert_eq!(vec, b"d3:cow3:moo4:infode4:spami42ee".to_vec());
+    }
+
+    #[test]
+    fn encode_string_ok() {
+        assert_eq!(encode_string("spam"), "4:spam".as_bytes().to_vec(),)
     }
 
     #[test]
     fn encode_bytes_ok() {
         assert_eq!(
-            encode_bytes(&[0x01, 0x02, 0x03, 0x04]),
+            encode_bytes([0x01, 0x02, 0x03, 0x04]),
             vec![b'4', b':', 0x01, 0x02, 0x03, 0x04],
         )
     }
@@ -296,7 +342,7 @@ mod bencode_elem_write_tests {
     #[test]
     fn encode_list_ok() {
         assert_eq!(
-            encode_list(&vec![bencode_elem!(42), bencode_elem!("spam")]),
+            encode_list(vec![bencode_elem!(42), bencode_elem!("spam")]),
             vec![b'l', b'i', b'4', b'2', b'e', b'4', b':', b's', b'p', b'a', b'm', b'e',],
         )
     }
@@ -23,17 +23,24 @@
 //!
 //! # *Overview*
 //! - **It is not recommended to use [`lava_torrent`] in any critical system at this point.**
-//! - Currently, only [v1] torrents are supported. [Merkle tree torrents] can be supported
-//! if there's enough demand. [v2] torrents might be supported once it's stabilized.
+//! - Both [v1] and [v2] torrents are supported. [v1] torrents live in [`torrent::v1`] while
+//!   [v2] torrents live in [`torrent::v2`]. [Merkle tree torrents] can be supported
+//!   if there's enough demand.
 //! - Methods for parsing and encoding are generally bound to structs (i.e. they are
-//! "associated methods"). Methods that are general enough are placed at the module-level (e.g.
-//! [`lava_torrent::bencode::write::encode_bytes()`]).
+//!   "associated methods"). Methods that are general enough are placed at the module-level (e.g.
+//!   [`lava_torrent::bencode::write::encode_bytes()`]).
 //!
 //! ## Functionality
 //! - bencode parsing/encoding (i.e. "bencoding/bdecoding") => [`BencodeElem`]
+//! - bencode (de)serialization with `serde` (requires the `serde` feature)
+//!   => [`bencode::to_bytes()`], [`bencode::from_bytes()`]
 //! - torrent parsing/encoding (based on [`BencodeElem`]) => [`Torrent`]
-//! - torrent creation => [`TorrentBuilder`]
-//! - tracker response parsing => [`tracker`] [experimental]
+//! - v2 torrent parsing/encoding (based on [`BencodeElem`]) => [`torrent::v2::Torrent`]
+//! - torrent creation (v1 and hybrid v1 + v2) => [`TorrentBuilder`]
+//! - verification of downloaded content against a torrent => [`Torrent::verify()`]
+//! - magnet link parsing/encoding => [`MagnetLink`]
+//! - tracker announce/scrape URL building and response parsing/encoding => [`tracker`] [experimental]
+//! - UDP tracker message encoding/decoding => [`tracker::udp`] [experimental]
 //!
 //! # *Correctness*
 //! [`lava_torrent`] is written without using any existing parser or parser generator.
@@ -45,36 +52,44 @@
 //!
 //! ## Known Issues
 //! 1. [BEP 3] specifies that a bencode integer has no
-//! size limit. This is a reasonable choice as it allows the protocol to be used
-//! in the future when file sizes grow significantly. However, using a 64-bit signed
-//! integer to represent a bencode integer should be more-than sufficient even in 2018.
-//! Therefore, while technically we should use something like
-//! [`bigint`] to represent bencode integers,
-//! `i64` is used in the current implementation. If a bencode integer larger than
-//! [`i64::max_value()`]
-//! is found, an `Error` will be returned.
+//!    size limit. This is a reasonable choice as it allows the protocol to be used
+//!    in the future when file sizes grow significantly. However, using a 64-bit signed
+//!    integer to represent a bencode integer should be more-than sufficient even in 2018.
+//!    Therefore, while technically we should use something like
+//!    [`bigint`] to represent bencode integers,
+//!    `i64` is used in the current implementation. If a bencode integer larger than
+//!    [`i64::max_value()`]
+//!    is found, an `Error` will be returned.
 //!
 //! 2. Several private methods will panic if something that "just won't happen"
-//! happens. For the purpose of full disclosure this behavior is mentioned here,
-//! but in reality panic should never be triggered. If you want to locate these
-//! private methods try searching for "panic", "unwrap", and "expect" in `*.rs` files.
+//!    happens. For the purpose of full disclosure this behavior is mentioned here,
+//!    but in reality panic should never be triggered. If you want to locate these
+//!    private methods try searching for "panic", "unwrap", and "expect" in `*.rs` files.
 //!
 //! # *Implemented BEPs*
 //! NOTE: Only the parsing/encoding aspects are implemented.
 //! - [BEP 3]
+//! - [BEP 5] \(partial, only implemented `nodes` in torrents)
 //! - [BEP 9] \(partial, only implemented magnet url v1)
 //! - [BEP 12]
+//! - [BEP 15] \(partial, only implemented message encoding/decoding)
+//! - [BEP 17]
+//! - [BEP 19]
 //! - [BEP 27]
+//! - [BEP 41] \(partial, only implemented URL data)
+//! - [BEP 47]
+//! - [BEP 52] \(partial, only implemented torrent parsing/encoding and hybrid torrent creation)
+//! - [BEP 53]
 //!
 //! # *Other Stuff*
 //! - Feature Request: To request a feature please open a GitHub issue (please
-//! try to request only 1 feature per issue).
+//!   try to request only 1 feature per issue).
 //! - Contribution: PR is always welcome.
 //! - What's with "lava": Originally I intended to start a project for downloading/crawling
-//! stuff. When downloading files, a stream of bits will be moving around--like lava.
+//!   stuff. When downloading files, a stream of bits will be moving around--like lava.
 //! - Other "lava" crates: The landscape for downloading/crawling stuff is fairly mature
-//! at this point, which means reinventing the wheels now is rather pointless... So this
-//! might be the only crate published under the "lava" name.
+//!   at this point, which means reinventing the wheels now is rather pointless... So this
+//!   might be the only crate published under the "lava" name.
 //! - Similar crates: [bip-rs]
 //!
 //! [`lava_torrent`]: index.html
@@ -88,26 +103,52 @@
 //! [`BencodeElem`]: bencode/enum.BencodeElem.html
 //! [`Torrent`]: torrent/v1/struct.Torrent.html
 //! [`TorrentBuilder`]: torrent/v1/struct.TorrentBuilder.html
+//! [`Torrent::verify()`]: torrent/v1/struct.Torrent.html#method.verify
 //! [`tracker`]: tracker/index.html
+//! [`tracker::udp`]: tracker/udp/index.html
+//! [`MagnetLink`]: magnet/struct.MagnetLink.html
+//! [`torrent::v1`]: torrent/v1/index.html
+//! [`torrent::v2`]: torrent/v2/index.html
+//! [`bencode::to_bytes()`]: bencode/fn.to_bytes.html
+//! [`bencode::from_bytes()`]: bencode/fn.from_bytes.html
+//! [`torrent::v2::Torrent`]: torrent/v2/struct.Torrent.html
 //! [BitTorrent specification]: http://bittorrent.org/beps/bep_0003.html
 //! [BEP 3]: http://bittorrent.org/beps/bep_0003.html
 //! [`bigint`]: https://github.com/rust-num/num-bigint
 //! [`i64::max_value()`]: https://doc.rust-lang.org/stable/std/primitive.i64.html#method.max_value
+//! [BEP 5]: http://bittorrent.org/beps/bep_0005.html
 //! [BEP 9]: http://bittorrent.org/beps/bep_0009.html
 //! [BEP 12]: http://bittorrent.org/beps/bep_0012.html
+//! [BEP 15]: http://bittorrent.org/beps/bep_0015.html
+//! [BEP 17]: http://bittorrent.org/beps/bep_0017.html
+//! [BEP 19]: http://bittorrent.org/beps/bep_0019.html
 //! [BEP 27]: http://bittorrent.org/beps/bep_0027.html
+//! [BEP 41]: http://bittorrent.org/beps/bep_0041.html
+//! [BEP 47]: http://bittorrent.org/beps/bep_0047.html
+//! [BEP 52]: http://bittorrent.org/beps/bep_0052.html
+//! [BEP 53]: http://bittorrent.org/beps/bep_0053.html
 //! [bip-rs]: https://github.com/GGist/bip-rs
 
+#![cfg_attr(test, allow(clippy::assertions_on_constants))]
+
 extern crate conv;
+extern crate glob;
 extern crate itertools;
 extern crate sha1;
+extern crate sha2;
 extern crate unicode_normalization;
 #[macro_use]
 extern crate error_chain;
+#[cfg(feature = "serde")]
+#[macro_use]
+extern crate serde;
 
+#[cfg(feature = "serde")]
+mod serde_util;
 pub(crate) mod util;
 #[macro_use]
 pub mod bencode;
+pub mod magnet;
 pub mod torrent;
 pub mod tracker;
 
@@ -145,6 +186,20 @@ pub mod error {
                 display("malformed response: {}", reason)
             }
 
+            #[doc = "Data sent to a tracker is gibberish, so we \
+             can't extract a request from it."]
+            MalformedRequest(reason: ::std::borrow::Cow<'static, str>) {
+                description("malformed request")
+                display("malformed request: {}", reason)
+            }
+
+            #[doc = "The magnet link is malformed, so we \
+             can't extract a `MagnetLink` from it."]
+            MalformedMagnetLink(reason: ::std::borrow::Cow<'static, str>) {
+                description("malformed magnet link")
+                display("malformed magnet link: {}", reason)
+            }
+
             #[doc = "Response from the tracker indicates an error"]
             TrackerErrorResponse(reason: ::std::borrow::Cow<'static, str>) {
                 description("error response")
@@ -159,12 +214,26 @@ pub mod error {
                 display("failed to build torrent: {}", reason)
             }
 
+            #[doc = "`TorrentBuilder` is cancelled via its `CancellationToken` \
+             before the `Torrent` is built."]
+            TorrentBuilderCancelled {
+                description("torrent building cancelled")
+                display("torrent building cancelled")
+            }
+
             #[doc = "An invalid argument is passed to a function."]
             InvalidArgument(reason: ::std::borrow::Cow<'static, str>) {
                 description("invalid argument:")
                 display("invalid argument: {}", reason)
             }
 
+            #[doc = "(De)serialization with `serde` has failed. For instance, \
+             the bencode does not match the target type."]
+            SerdeFailure(reason: ::std::borrow::Cow<'static, str>) {
+                description("serde failed")
+                display("serde failed: {}", reason)
+            }
+
             #[doc = "Conversion between numeric types (e.g. `i64 -> u64`) has failed."]
             FailedNumericConv(msg: ::std::borrow::Cow<'static, str>) {
                 description("numeric conversion failed:")
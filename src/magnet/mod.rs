@@ -0,0 +1,678 @@
+//! Module for magnet link parsing/encoding.
+//!
+//! Supported parameters are those defined in
+//! [BEP 9](http://bittorrent.org/beps/bep_0009.html) (`xt`, `dn`, `tr`, `x.pe`),
+//! [BEP 19](http://bittorrent.org/beps/bep_0019.html) (`ws`),
+//! [BEP 52](http://bittorrent.org/beps/bep_0052.html) (`xt=urn:btmh:`),
+//! and [BEP 53](http://bittorrent.org/beps/bep_0053.html) (`so`),
+//! as well as the commonly used `xl` (exact length).
+
+use error::*;
+use itertools::Itertools;
+use std::borrow::Cow;
+use std::fmt;
+use std::ops::RangeInclusive;
+use torrent::v1::Integer;
+use util;
+
+const PREFIX: &str = "magnet:?";
+const BTIH_PREFIX: &str = "urn:btih:";
+const BTMH_PREFIX: &str = "urn:btmh:";
+// multihash prefix of a SHA256 digest (code 0x12, length 0x20)
+const SHA256_MULTIHASH_PREFIX: [u8; 2] = [0x12, 0x20];
+const BASE32_ALPHABET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ234567";
+const V1_HASH_LENGTH: usize = 20;
+const V2_HASH_LENGTH: usize = 32;
+
+/// Everything found in a magnet link.
+///
+/// All values are stored decoded (i.e. percent-encoding is removed).
+/// Unknown parameters are kept in `extra_params` so
+/// that they are not lost when the link is re-encoded.
+///
+/// A magnet link can be encoded by calling `to_string()`, in which case
+/// all values are percent-encoded (characters that are safe in a query,
+/// such as `:` and `/`, are left as is).
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct MagnetLink {
+    /// The [v1](http://bittorrent.org/beps/bep_0003.html) info hash
+    /// (`xt=urn:btih:`). It always contains 20 bytes.
+    pub info_hash: Option<Vec<u8>>,
+    /// The [v2](http://bittorrent.org/beps/bep_0052.html) info hash
+    /// (`xt=urn:btmh:`). It always contains 32 bytes (i.e.
+    /// the SHA256 digest without the multihash prefix).
+    pub info_hash_v2: Option<Vec<u8>>,
+    /// Display name (`dn`).
+    pub name: Option<String>,
+    /// Exact length in bytes (`xl`).
+    pub length: Option<Integer>,
+    /// Tracker URLs (`tr`).
+    pub trackers: Vec<String>,
+    /// Web seed URLs (`ws`).
+    pub web_seeds: Vec<String>,
+    /// Peer addresses (`x.pe`), in the form of `hostname:port`,
+    /// `ipv4:port`, or `[ipv6]:port`.
+    pub peers: Vec<String>,
+    /// Indices of the files to download (`so`) as defined in
+    /// [BEP 53](http://bittorrent.org/beps/bep_0053.html).
+    /// A single index `i` is represented as `i..=i`.
+    pub select_only: Vec<RangeInclusive<usize>>,
+    /// Parameters not listed above, in the order they appear in the link.
+    pub extra_params: Vec<(String, String)>,
+}
+
+/// Options for generating magnet links from torrents.
+///
+/// By default, a generated magnet link contains only the
+/// v1 info hash, the display name, and the trackers. Other
+/// parameters can be included by calling the corresponding methods
+/// (e.g. [`set_length()`]).
+///
+/// [`set_length()`]: #method.set_length
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct MagnetLinkOptions {
+    pub(crate) include_length: bool,
+    pub(crate) include_web_seeds: bool,
+    pub(crate) include_info_hash_v2: bool,
+    pub(crate) peers: Vec<String>,
+    pub(crate) select_only: Vec<RangeInclusive<usize>>,
+}
+
+impl MagnetLinkOptions {
+    /// Create a new `MagnetLinkOptions` with default settings.
+    pub fn new() -> MagnetLinkOptions {
+        Default::default()
+    }
+
+    /// Include (or exclude) the torrent's total length as `xl`.
+    ///
+    /// Calling this method multiple times will simply override previous settings.
+    pub fn set_length(self, include_length: bool) -> MagnetLinkOptions {
+        MagnetLinkOptions {
+            include_length,
+            ..self
+        }
+    }
+
+    /// Include (or exclude) the torrent's web seeds (`url-list`) as `ws`.
+    ///
+    /// Calling this method multiple times will simply override previous settings.
+    pub fn set_web_seeds(self, include_web_seeds: bool) -> MagnetLinkOptions {
+        MagnetLinkOptions {
+            include_web_seeds,
+            ..self
+        }
+    }
+
+    /// Include (or exclude) the v2 info hash as `xt=urn:btmh:`.
+    ///
+    /// This setting has no effect on torrents that are not hybrid
+    /// (i.e. torrents without `meta version` 2 in the `info` dictionary).
+    ///
+    /// Calling this method multiple times will simply override previous settings.
+    pub fn set_info_hash_v2(self, include_info_hash_v2: bool) -> MagnetLinkOptions {
+        MagnetLinkOptions {
+            include_info_hash_v2,
+            ..self
+        }
+    }
+
+    /// Add a peer address as `x.pe`.
+    ///
+    /// `peer` should be in the form of `hostname:port`,
+    /// `ipv4:port`, or `[ipv6]:port`.
+    pub fn add_peer(self, peer: String) -> MagnetLinkOptions {
+        let mut peers = self.peers;
+        peers.push(peer);
+
+        MagnetLinkOptions { peers, ..self }
+    }
+
+    /// Set the indices of the files to download as `so`, as defined in
+    /// [BEP 53](http://bittorrent.org/beps/bep_0053.html).
+    ///
+    /// Calling this method multiple times will simply override previous settings.
+    pub fn set_select_only(self, select_only: Vec<RangeInclusive<usize>>) -> MagnetLinkOptions {
+        MagnetLinkOptions {
+            select_only,
+            ..self
+        }
+    }
+}
+
+impl MagnetLink {
+    /// Parse `link` and return the extracted `MagnetLink`.
+    ///
+    /// `link` must contain at least one `xt` parameter with a
+    /// v1 (`urn:btih:`, hex or base32) or v2 (`urn:btmh:`) info hash.
+    /// Other `xt` parameters are placed in `extra_params`.
+    ///
+    /// Parameters with an index suffix (e.g. `tr.1`) are treated
+    /// the same as those without. In `dn`, `+` is decoded as a space.
+    pub fn parse(link: &str) -> Result<MagnetLink> {
+        if !link.starts_with(PREFIX) {
+            bail!(ErrorKind::MalformedMagnetLink(Cow::Borrowed(
+                r#"Magnet link does not start with "magnet:?"."#
+            )));
+        }
+
+        let mut magnet = MagnetLink::default();
+        for param in link[PREFIX.len()..].split('&').filter(|p| !p.is_empty()) {
+            let (key, value) = match param.find('=') {
+                Some(i) => (&param[..i], &param[(i + 1)..]),
+                None => bail!(ErrorKind::MalformedMagnetLink(Cow::Owned(format!(
+                    "Parameter [{}] has no value.",
+                    param
+                )))),
+            };
+
+            match Self::strip_index(key) {
+                "xt" => magnet.parse_exact_topic(key, &Self::decode(value, false)?)?,
+                "dn" => magnet.name = Some(Self::decode(value, true)?),
+                "xl" => magnet.length = Some(Self::parse_length(&Self::decode(value, false)?)?),
+                "tr" => magnet.trackers.push(Self::decode(value, false)?),
+                "ws" => magnet.web_seeds.push(Self::decode(value, false)?),
+                "x.pe" => magnet.peers.push(Self::decode(value, false)?),
+                "so" => {
+                    let ranges = Self::parse_select_only(&Self::decode(value, false)?)?;
+                    magnet.select_only.extend(ranges);
+                }
+                _ => magnet
+                    .extra_params
+                    .push((key.to_owned(), Self::decode(value, false)?)),
+            }
+        }
+
+        if magnet.info_hash.is_none() && magnet.info_hash_v2.is_none() {
+            bail!(ErrorKind::MalformedMagnetLink(Cow::Borrowed(
+                "Magnet link does not contain any info hash."
+            )))
+        } else {
+            Ok(magnet)
+        }
+    }
+
+    /// Return the v1 info hash as a lowercase hex string (if it exists).
+    pub fn info_hash_hex(&self) -> Option<String> {
+        self.info_hash.as_ref().map(|hash| util::encode_hex(hash))
+    }
+
+    /// Return the v2 info hash as a lowercase hex string (if it exists).
+    ///
+    /// The multihash prefix is not included.
+    pub fn info_hash_v2_hex(&self) -> Option<String> {
+        self.info_hash_v2
+            .as_ref()
+            .map(|hash| util::encode_hex(hash))
+    }
+
+    // "tr.1" -> "tr", but "x.pe" -> "x.pe"
+    fn strip_index(key: &str) -> &str {
+        match key.rfind('.') {
+            Some(i)
+                if (i + 1 < key.len()) && key[(i + 1)..].bytes().all(|b| b.is_ascii_digit()) =>
+            {
+                &key[..i]
+            }
+            _ => key,
+        }
+    }
+
+    fn encode(value: &str) -> String {
+        util::percent_encode(value, util::QUERY_VALUE_SAFE)
+    }
+
+    fn decode(value: &str, plus_as_space: bool) -> Result<String> {
+        match util::percent_decode(value, plus_as_space) {
+            Ok(bytes) => match String::from_utf8(bytes) {
+                Ok(string) => Ok(string),
+                Err(_) => bail!(ErrorKind::MalformedMagnetLink(Cow::Owned(format!(
+                    "[{}] is not valid UTF8 after decoding.",
+                    value
+                )))),
+            },
+            Err(_) => bail!(ErrorKind::MalformedMagnetLink(Cow::Owned(format!(
+                "[{}] is not properly percent-encoded.",
+                value
+            )))),
+        }
+    }
+
+    fn parse_exact_topic(&mut self, key: &str, topic: &str) -> Result<()> {
+        if let Some(hash) = Self::strip_prefix_ignore_case(topic, BTIH_PREFIX) {
+            if self.info_hash.is_some() {
+                bail!(ErrorKind::MalformedMagnetLink(Cow::Borrowed(
+                    "Magnet link contains multiple v1 info hashes."
+                )));
+            }
+            self.info_hash = Some(Self::parse_btih(hash)?);
+        } else if let Some(hash) = Self::strip_prefix_ignore_case(topic, BTMH_PREFIX) {
+            if self.info_hash_v2.is_some() {
+                bail!(ErrorKind::MalformedMagnetLink(Cow::Borrowed(
+                    "Magnet link contains multiple v2 info hashes."
+                )));
+            }
+            self.info_hash_v2 = Some(Self::parse_btmh(hash)?);
+        } else {
+            self.extra_params.push((key.to_owned(), topic.to_owned()));
+        }
+        Ok(())
+    }
+
+    fn strip_prefix_ignore_case<'a>(src: &'a str, prefix: &str) -> Option<&'a str> {
+        match src.get(..prefix.len()) {
+            Some(head) if head.eq_ignore_ascii_case(prefix) => Some(&src[prefix.len()..]),
+            _ => None,
+        }
+    }
+
+    // a v1 info hash is either 40 hex chars or 32 base32 chars
+    fn parse_btih(hash: &str) -> Result<Vec<u8>> {
+        let decoded = match hash.len() {
+            40 => util::decode_hex(hash),
+            32 => Self::decode_base32(hash),
+            _ => None,
+        };
+
+        match decoded {
+            Some(ref bytes) if bytes.len() == V1_HASH_LENGTH => Ok(bytes.clone()),
+            _ => bail!(ErrorKind::MalformedMagnetLink(Cow::Owned(format!(
+                "[{}] is not a valid v1 info hash.",
+                hash
+            )))),
+        }
+    }
+
+    // a v2 info hash is a hex-encoded multihash
+    // (only SHA256 is supported as per BEP 52)
+    fn parse_btmh(hash: &str) -> Result<Vec<u8>> {
+        match util::decode_hex(hash) {
+            Some(ref bytes)
+                if (bytes.len() == SHA256_MULTIHASH_PREFIX.len() + V2_HASH_LENGTH)
+                    && bytes.starts_with(&SHA256_MULTIHASH_PREFIX) =>
+            {
+                Ok(bytes[SHA256_MULTIHASH_PREFIX.len()..].to_vec())
+            }
+            _ => bail!(ErrorKind::MalformedMagnetLink(Cow::Owned(format!(
+                "[{}] is not a valid v2 info hash.",
+                hash
+            )))),
+        }
+    }
+
+    fn parse_length(length: &str) -> Result<Integer> {
+        match length.parse::<Integer>() {
+            Ok(length) if length >= 0 => Ok(length),
+            _ => bail!(ErrorKind::MalformedMagnetLink(Cow::Owned(format!(
+                r#"[{}] is not a valid "xl"."#,
+                length
+            )))),
+        }
+    }
+
+    // e.g. "0,2,4,6-8"
+    fn parse_select_only(value: &str) -> Result<Vec<RangeInclusive<usize>>> {
+        let mut ranges = Vec::new();
+
+        for item in value.split(',') {
+            let range = match item.find('-') {
+                Some(i) => (item[..i].parse::<usize>(), item[(i + 1)..].parse::<usize>()),
+                None => (item.parse::<usize>(), item.parse::<usize>()),
+            };
+
+            match range {
+                (Ok(start), Ok(end)) if start <= end => ranges.push(start..=end),
+                _ => bail!(ErrorKind::MalformedMagnetLink(Cow::Owned(format!(
+                    r#"[{}] is not a valid "so"."#,
+                    value
+                )))),
+            }
+        }
+        Ok(ranges)
+    }
+
+    // RFC 4648 base32 without padding (case-insensitive)
+    fn decode_base32(src: &str) -> Option<Vec<u8>> {
+        let mut result = Vec::with_capacity(src.len() * 5 / 8);
+        let mut buffer: u32 = 0;
+        let mut bits = 0;
+
+        for byte in src.bytes() {
+            let value = BASE32_ALPHABET
+                .iter()
+                .position(|&c| c == byte.to_ascii_uppercase())?;
+            buffer = (buffer << 5) | (value as u32);
+            bits += 5;
+
+            if bits >= 8 {
+                bits -= 8;
+                result.push((buffer >> bits) as u8);
+                buffer &= (1 << bits) - 1;
+            }
+        }
+        Some(result)
+    }
+}
+
+impl fmt::Display for MagnetLink {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut params = Vec::new();
+
+        if let Some(ref hash) = self.info_hash {
+            params.push(format!("xt={}{}", BTIH_PREFIX, util::encode_hex(hash)));
+        }
+        if let Some(ref hash) = self.info_hash_v2 {
+            params.push(format!(
+                "xt={}{}{}",
+                BTMH_PREFIX,
+                util::encode_hex(&SHA256_MULTIHASH_PREFIX),
+                util::encode_hex(hash)
+            ));
+        }
+        if let Some(ref name) = self.name {
+            params.push(format!("dn={}", Self::encode(name)));
+        }
+        if let Some(length) = self.length {
+            params.push(format!("xl={}", length));
+        }
+        params.extend(
+            self.trackers
+                .iter()
+                .map(|url| format!("tr={}", Self::encode(url))),
+        );
+        params.extend(
+            self.web_seeds
+                .iter()
+                .map(|url| format!("ws={}", Self::encode(url))),
+        );
+        params.extend(
+            self.peers
+                .iter()
+                .map(|peer| format!("x.pe={}", Self::encode(peer))),
+        );
+        if !self.select_only.is_empty() {
+            params.push(format!(
+                "so={}",
+                self.select_only.iter().format_with(",", |range, f| {
+                    if range.start() == range.end() {
+                        f(range.start())
+                    } else {
+                        f(&format_args!("{}-{}", range.start(), range.end()))
+                    }
+                })
+            ));
+        }
+        params.extend(
+            self.extra_params
+                .iter()
+                .map(|(key, value)| format!("{}={}", key, Self::encode(value))),
+        );
+
+        write!(f, "{}{}", PREFIX, params.iter().format("&"))
+    }
+}
+
+#[cfg(test)]
+mod magnet_link_options_tests {
+    use super::*;
+
+    #[test]
+    fn new_ok() {
+        assert_eq!(
+            MagnetLinkOptions::new(),
+            MagnetLinkOptions {
+                include_length: false,
+                include_web_seeds: false,
+                include_info_hash_v2: false,
+                peers: vec![],
+                select_only: vec![],
+            }
+        );
+    }
+
+    #[test]
+    fn setters_ok() {
+        let options = MagnetLinkOptions::new()
+            .set_length(true)
+            .set_web_seeds(true)
+            .set_info_hash_v2(true)
+            .add_peer("10.0.0.1:6881".to_owned())
+            .add_peer("[::1]:6881".to_owned())
+            .set_select_only(vec![0..=0, 2..=3]);
+
+        assert_eq!(
+            options,
+            MagnetLinkOptions {
+                include_length: true,
+                include_web_seeds: true,
+                include_info_hash_v2: true,
+                peers: vec!["10.0.0.1:6881".to_owned(), "[::1]:6881".to_owned()],
+                select_only: vec![0..=0, 2..=3],
+            }
+        );
+
+        let options = options.set_length(false).set_select_only(vec![]);
+        assert!(!options.include_length);
+        assert!(options.select_only.is_empty());
+    }
+}
+
+#[cfg(test)]
+mod magnet_link_tests {
+    use super::*;
+
+    const V1_HEX: &str = "778ce280b595e57780ff083f2eb6f897dfa4a4ee";
+    const V1_BASE32: &str = "O6GOFAFVSXSXPAH7BA7S5NXYS7P2JJHO";
+    const V2_HEX: &str = "1d931870066e47f5200a224df330a79b78053f9ba383250708cfab0f3753ebf1";
+
+    fn v1_hash() -> Vec<u8> {
+        util::decode_hex(V1_HEX).unwrap()
+    }
+
+    fn v2_hash() -> Vec<u8> {
+        util::decode_hex(V2_HEX).unwrap()
+    }
+
+    #[test]
+    fn parse_ok() {
+        let link = format!(
+            "magnet:?xt=urn:btih:{}&xt=urn:btmh:1220{}\
+             &dn=Ubuntu+16.04%20%26%20more&xl=1624211456\
+             &tr=http%3A%2F%2Ftorrent.ubuntu.com%3A6969%2Fannounce\
+             &tr.1=udp://tracker.example.com:80\
+             &ws=http%3A%2F%2Fexample.com%2Fubuntu.iso\
+             &x.pe=10.0.0.1:6881&x.pe=%5B%3A%3A1%5D%3A6881\
+             &so=0,2,4-6&foo=bar",
+            V1_HEX, V2_HEX
+        );
+
+        assert_eq!(
+            MagnetLink::parse(&link).unwrap(),
+            MagnetLink {
+                info_hash: Some(v1_hash()),
+                info_hash_v2: Some(v2_hash()),
+                name: Some("Ubuntu 16.04 & more".to_owned()),
+                length: Some(1_624_211_456),
+                trackers: vec![
+                    "http://torrent.ubuntu.com:6969/announce".to_owned(),
+                    "udp://tracker.example.com:80".to_owned(),
+                ],
+                web_seeds: vec!["http://example.com/ubuntu.iso".to_owned()],
+                peers: vec!["10.0.0.1:6881".to_owned(), "[::1]:6881".to_owned()],
+                select_only: vec![0..=0, 2..=2, 4..=6],
+                extra_params: vec![("foo".to_owned(), "bar".to_owned())],
+            }
+        );
+    }
+
+    #[test]
+    fn parse_base32() {
+        let magnet = MagnetLink::parse(&format!("magnet:?xt=urn:btih:{}", V1_BASE32)).unwrap();
+        assert_eq!(magnet.info_hash, Some(v1_hash()));
+        assert_eq!(magnet.info_hash_hex(), Some(V1_HEX.to_owned()));
+        assert_eq!(magnet.info_hash_v2, None);
+
+        let magnet =
+            MagnetLink::parse(&format!("magnet:?xt=urn:btih:{}", V1_BASE32.to_lowercase()))
+                .unwrap();
+        assert_eq!(magnet.info_hash, Some(v1_hash()));
+    }
+
+    #[test]
+    fn parse_v2_only() {
+        let magnet = MagnetLink::parse(&format!("magnet:?xt=urn:btmh:1220{}", V2_HEX)).unwrap();
+        assert_eq!(magnet.info_hash, None);
+        assert_eq!(magnet.info_hash_v2_hex(), Some(V2_HEX.to_owned()));
+    }
+
+    #[test]
+    fn parse_other_topic() {
+        let magnet =
+            MagnetLink::parse(&format!("magnet:?xt=urn:sha1:ABCD&xt=urn:btih:{}", V1_HEX)).unwrap();
+        assert_eq!(magnet.info_hash, Some(v1_hash()));
+        assert_eq!(
+            magnet.extra_params,
+            vec![("xt".to_owned(), "urn:sha1:ABCD".to_owned())]
+        );
+    }
+
+    #[test]
+    fn parse_invalid_prefix() {
+        match MagnetLink::parse("http://example.com/?xt=urn:btih:") {
+            Err(Error(ErrorKind::MalformedMagnetLink(m), _)) => {
+                assert_eq!(m, r#"Magnet link does not start with "magnet:?"."#);
+            }
+            _ => assert!(false),
+        }
+    }
+
+    #[test]
+    fn parse_no_info_hash() {
+        match MagnetLink::parse("magnet:?dn=sample&tr=url") {
+            Err(Error(ErrorKind::MalformedMagnetLink(m), _)) => {
+                assert_eq!(m, "Magnet link does not contain any info hash.");
+            }
+            _ => assert!(false),
+        }
+    }
+
+    #[test]
+    fn parse_multiple_v1_info_hashes() {
+        let link = format!("magnet:?xt=urn:btih:{}&xt=urn:btih:{}", V1_HEX, V1_BASE32);
+
+        match MagnetLink::parse(&link) {
+            Err(Error(ErrorKind::MalformedMagnetLink(m), _)) => {
+                assert_eq!(m, "Magnet link contains multiple v1 info hashes.");
+            }
+            _ => assert!(false),
+        }
+    }
+
+    #[test]
+    fn parse_invalid_btih() {
+        match MagnetLink::parse("magnet:?xt=urn:btih:abcd") {
+            Err(Error(ErrorKind::MalformedMagnetLink(m), _)) => {
+                assert_eq!(m, "[abcd] is not a valid v1 info hash.");
+            }
+            _ => assert!(false),
+        }
+    }
+
+    #[test]
+    fn parse_invalid_btmh() {
+        // multihash code 0x11 is SHA1, which is not allowed
+        let hash = format!("1114{}", V1_HEX);
+
+        match MagnetLink::parse(&format!("magnet:?xt=urn:btmh:{}", hash)) {
+            Err(Error(ErrorKind::MalformedMagnetLink(m), _)) => {
+                assert_eq!(m, format!("[{}] is not a valid v2 info hash.", hash));
+            }
+            _ => assert!(false),
+        }
+    }
+
+    #[test]
+    fn parse_param_without_value() {
+        match MagnetLink::parse(&format!("magnet:?xt=urn:btih:{}&dn", V1_HEX)) {
+            Err(Error(ErrorKind::MalformedMagnetLink(m), _)) => {
+                assert_eq!(m, "Parameter [dn] has no value.");
+            }
+            _ => assert!(false),
+        }
+    }
+
+    #[test]
+    fn parse_invalid_percent_encoding() {
+        match MagnetLink::parse(&format!("magnet:?xt=urn:btih:{}&dn=%ZZ", V1_HEX)) {
+            Err(Error(ErrorKind::MalformedMagnetLink(m), _)) => {
+                assert_eq!(m, "[%ZZ] is not properly percent-encoded.");
+            }
+            _ => assert!(false),
+        }
+    }
+
+    #[test]
+    fn parse_invalid_length() {
+        match MagnetLink::parse(&format!("magnet:?xt=urn:btih:{}&xl=-1", V1_HEX)) {
+            Err(Error(ErrorKind::MalformedMagnetLink(m), _)) => {
+                assert_eq!(m, r#"[-1] is not a valid "xl"."#);
+            }
+            _ => assert!(false),
+        }
+    }
+
+    #[test]
+    fn parse_invalid_select_only() {
+        match MagnetLink::parse(&format!("magnet:?xt=urn:btih:{}&so=1,4-2", V1_HEX)) {
+            Err(Error(ErrorKind::MalformedMagnetLink(m), _)) => {
+                assert_eq!(m, r#"[1,4-2] is not a valid "so"."#);
+            }
+            _ => assert!(false),
+        }
+    }
+
+    #[test]
+    fn strip_index_ok() {
+        assert_eq!(MagnetLink::strip_index("tr"), "tr");
+        assert_eq!(MagnetLink::strip_index("tr.12"), "tr");
+        assert_eq!(MagnetLink::strip_index("x.pe"), "x.pe");
+        assert_eq!(MagnetLink::strip_index("tr."), "tr.");
+    }
+
+    #[test]
+    fn decode_base32_ok() {
+        assert_eq!(
+            MagnetLink::decode_base32("MZXW6YTBOI").unwrap(),
+            b"foobar".to_vec()
+        );
+        assert_eq!(MagnetLink::decode_base32("MZXW1"), None);
+    }
+
+    #[test]
+    fn to_string_ok() {
+        let magnet = MagnetLink {
+            info_hash: Some(v1_hash()),
+            info_hash_v2: Some(v2_hash()),
+            name: Some("Ubuntu 16.04 & more".to_owned()),
+            length: Some(42),
+            trackers: vec!["http://t.com/announce".to_owned()],
+            web_seeds: vec!["http://w.com/".to_owned()],
+            peers: vec!["[::1]:6881".to_owned()],
+            select_only: vec![0..=0, 4..=6],
+            extra_params: vec![("foo".to_owned(), "b r".to_owned())],
+        };
+
+        assert_eq!(
+            magnet.to_string(),
+            format!(
+                "magnet:?xt=urn:btih:{}&xt=urn:btmh:1220{}\
+                 &dn=Ubuntu%2016.04%20%26%20more&xl=42\
+                 &tr=http://t.com/announce&ws=http://w.com/\
+                 &x.pe=%5B::1%5D:6881&so=0,4-6&foo=b%20r",
+                V1_HEX, V2_HEX
+            )
+        );
+        // round trip
+        assert_eq!(MagnetLink::parse(&magnet.to_string()).unwrap(), magnet);
+    }
+}
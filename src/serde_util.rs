@@ -0,0 +1,214 @@
+// Helpers for (de)serializing byte sequences with `serde`.
+//
+// Byte sequences (e.g. hashes) are represented as lowercase hex strings
+// in human-readable formats (e.g. JSON), and as byte strings otherwise.
+// They are used with `#[serde(with = "...")]`.
+
+use serde::de::{self, Deserialize, Deserializer, SeqAccess, Visitor};
+use serde::ser::{Serialize, Serializer};
+use std::collections::HashMap;
+use std::fmt;
+use std::hash::BuildHasher;
+use util;
+
+struct BytesRef<'a>(&'a [u8]);
+
+impl<'a> Serialize for BytesRef<'a> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        if serializer.is_human_readable() {
+            serializer.serialize_str(&util::encode_hex(self.0))
+        } else {
+            serializer.serialize_bytes(self.0)
+        }
+    }
+}
+
+#[derive(Eq, Hash, PartialEq)]
+struct ByteBuf(Vec<u8>);
+
+struct ByteBufVisitor;
+
+impl<'de> Visitor<'de> for ByteBufVisitor {
+    type Value = ByteBuf;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "a byte string")
+    }
+
+    fn visit_bytes<E>(self, v: &[u8]) -> Result<ByteBuf, E>
+    where
+        E: de::Error,
+    {
+        Ok(ByteBuf(v.to_vec()))
+    }
+
+    fn visit_byte_buf<E>(self, v: Vec<u8>) -> Result<ByteBuf, E>
+    where
+        E: de::Error,
+    {
+        Ok(ByteBuf(v))
+    }
+
+    fn visit_str<E>(self, v: &str) -> Result<ByteBuf, E>
+    where
+        E: de::Error,
+    {
+        Ok(ByteBuf(v.as_bytes().to_vec()))
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> Result<ByteBuf, A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        let mut bytes = Vec::with_capacity(seq.size_hint().unwrap_or(0));
+        while let Some(byte) = seq.next_element()? {
+            bytes.push(byte);
+        }
+        Ok(ByteBuf(bytes))
+    }
+}
+
+impl<'de> Deserialize<'de> for ByteBuf {
+    fn deserialize<D>(deserializer: D) -> Result<ByteBuf, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        if deserializer.is_human_readable() {
+            let hex = String::deserialize(deserializer)?;
+            match util::decode_hex(&hex) {
+                Some(bytes) => Ok(ByteBuf(bytes)),
+                None => Err(de::Error::invalid_value(
+                    de::Unexpected::Str(&hex),
+                    &"a hex string",
+                )),
+            }
+        } else {
+            deserializer.deserialize_byte_buf(ByteBufVisitor)
+        }
+    }
+}
+
+struct PiecesRef<'a>(&'a [Vec<u8>]);
+
+impl<'a> Serialize for PiecesRef<'a> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.collect_seq(self.0.iter().map(|piece| BytesRef(piece)))
+    }
+}
+
+// `Vec<u8>`
+pub(crate) mod bytes {
+    use super::*;
+
+    pub(crate) fn serialize<S>(bytes: &[u8], serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        BytesRef(bytes).serialize(serializer)
+    }
+
+    pub(crate) fn deserialize<'de, D>(deserializer: D) -> Result<Vec<u8>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        Ok(ByteBuf::deserialize(deserializer)?.0)
+    }
+}
+
+// `Option<Vec<u8>>`, should be used with `#[serde(default)]`
+pub(crate) mod option_bytes {
+    use super::*;
+
+    pub(crate) fn serialize<S>(bytes: &Option<Vec<u8>>, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        bytes.as_ref().map(|b| BytesRef(b)).serialize(serializer)
+    }
+
+    pub(crate) fn deserialize<'de, D>(deserializer: D) -> Result<Option<Vec<u8>>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        Ok(Option::<ByteBuf>::deserialize(deserializer)?.map(|b| b.0))
+    }
+}
+
+// `Vec<Piece>`
+pub(crate) mod pieces {
+    use super::*;
+
+    pub(crate) fn serialize<S>(pieces: &[Vec<u8>], serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        PiecesRef(pieces).serialize(serializer)
+    }
+
+    pub(crate) fn deserialize<'de, D>(deserializer: D) -> Result<Vec<Vec<u8>>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        Ok(Vec::<ByteBuf>::deserialize(deserializer)?
+            .into_iter()
+            .map(|b| b.0)
+            .collect())
+    }
+}
+
+// `HashMap<Vec<u8>, V>`
+pub(crate) mod bytes_map {
+    use super::*;
+
+    pub(crate) fn serialize<V, H, S>(
+        map: &HashMap<Vec<u8>, V, H>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error>
+    where
+        V: Serialize,
+        H: BuildHasher,
+        S: Serializer,
+    {
+        serializer.collect_map(map.iter().map(|(k, v)| (BytesRef(k), v)))
+    }
+
+    pub(crate) fn deserialize<'de, V, D>(deserializer: D) -> Result<HashMap<Vec<u8>, V>, D::Error>
+    where
+        V: Deserialize<'de>,
+        D: Deserializer<'de>,
+    {
+        Ok(HashMap::<ByteBuf, V>::deserialize(deserializer)?
+            .into_iter()
+            .map(|(k, v)| (k.0, v))
+            .collect())
+    }
+}
+
+// v2 `PieceLayers`
+pub(crate) mod piece_layers {
+    use super::*;
+    use torrent::v2::PieceLayers;
+
+    pub(crate) fn serialize<S>(map: &PieceLayers, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.collect_map(map.iter().map(|(k, v)| (BytesRef(k), PiecesRef(v))))
+    }
+
+    pub(crate) fn deserialize<'de, D>(deserializer: D) -> Result<PieceLayers, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        Ok(HashMap::<ByteBuf, Vec<ByteBuf>>::deserialize(deserializer)?
+            .into_iter()
+            .map(|(k, v)| (k.0, v.into_iter().map(|b| b.0).collect()))
+            .collect())
+    }
+}
@@ -1,3 +1,38 @@
 //! Module for `.torrent` files related parsing/encoding/creation.
 
+use bencode::BencodeElem;
+
 pub mod v1;
+pub mod v2;
+
+/// Determine which bytes are hashed when calculating a torrent's info hash.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum InfoHashSource {
+    /// Hash the original bytes of the `info` dict (i.e. `raw_info`),
+    /// which is what other clients do. If the original bytes are not
+    /// available (e.g. the torrent is created by `TorrentBuilder`), or if
+    /// they no longer match the fields in `info` (i.e. some of them have
+    /// been modified), then this is the same as `Reencoded`.
+    Original,
+    /// Hash the re-encoded `info` dict (i.e. `construct_info().encode()`).
+    ///
+    /// Note that strings are normalized to NFC forms when parsed,
+    /// so the re-encoded `info` dict might differ from the original one.
+    Reencoded,
+}
+
+// Check if `raw_info` (the original bytes of `info`) still matches
+// `reencoded` (`info` encoded from the fields of a torrent), i.e.
+// no field in `info` has been modified since the torrent is parsed.
+//
+// Strings might have been normalized to NFC when parsed, so `raw_info`
+// is also compared after being parsed and re-encoded in the same way.
+pub(crate) fn is_raw_info_current(raw_info: &[u8], reencoded: &[u8]) -> bool {
+    if raw_info == reencoded {
+        return true;
+    }
+    match BencodeElem::from_bytes(raw_info) {
+        Ok(ref parsed) if parsed.len() == 1 => parsed[0].encode() == reencoded,
+        _ => false,
+    }
+}
@@ -1,9 +1,141 @@
 use super::*;
+use glob::Pattern;
 use sha1::{Digest, Sha1};
-use std::io::{BufReader, Read};
+use sha2::Sha256;
+use std::cmp;
+use std::collections::BTreeMap;
+use std::io::{Read, Seek, SeekFrom};
+use std::mem;
 use std::path::Component;
+use std::sync::mpsc;
+use std::sync::Mutex;
+use std::thread;
+use std::time::{SystemTime, UNIX_EPOCH};
+use torrent::v2::{self, merkle};
 use util;
 
+// `piece_length` chosen by `set_auto_piece_length()` is the smallest
+// power of 2 (between the min and the max) that results in at most this # of pieces
+const AUTO_MAX_PIECES: u64 = 2048;
+// "It must be a power of two and at least 16KiB." (BEP 52)
+const AUTO_MIN_PIECE_LENGTH: u64 = 16 * 1024;
+
+// content read from disk when building a hybrid torrent
+struct HybridContent {
+    length: Integer,
+    files: Option<Vec<File>>,
+    pieces: Vec<Piece>,
+    file_tree: v2::FileTree,
+    piece_layers: v2::PieceLayers,
+}
+
+// a piece to be read and hashed by `hash_pieces()`
+struct PieceJob {
+    // the (path, offset, length) ranges of the files that make up the piece
+    ranges: Vec<(PathBuf, u64, u64)>,
+    // # of zeros appended to the piece (i.e. padding)
+    padding: u64,
+    // for hybrid torrents, the # of leaves in the piece's merkle tree
+    merkle_width: Option<usize>,
+}
+
+impl PieceJob {
+    // # of bytes in the piece that are not padding
+    fn content_length(&self) -> u64 {
+        self.ranges.iter().map(|&(_, _, length)| length).sum()
+    }
+
+    // the file that the piece ends in
+    fn path(&self) -> &Path {
+        self.ranges
+            .last()
+            .map(|(path, _, _)| path.as_path())
+            .unwrap_or_else(|| Path::new(""))
+    }
+
+    // Read the piece from disk and hash it.
+    //
+    // `file` keeps the last opened file, so that consecutive
+    // pieces of the same file are read without reopening it.
+    fn hash(&self, file: &mut Option<(PathBuf, ::std::fs::File)>) -> Result<PieceHashes> {
+        let mut piece =
+            Vec::with_capacity(util::u64_to_usize(self.content_length() + self.padding)?);
+        for (path, offset, length) in &self.ranges {
+            if file.as_ref().map_or(true, |(opened, _)| opened != path) {
+                *file = Some((path.clone(), ::std::fs::File::open(path)?));
+            }
+            let (_, ref mut opened) = *file.as_mut().expect("`file` is opened above.");
+            opened.seek(SeekFrom::Start(*offset))?;
+
+            let start = piece.len();
+            piece.resize(start + util::u64_to_usize(*length)?, 0);
+            opened.read_exact(&mut piece[start..])?;
+        }
+
+        // padding is not part of the v2 merkle tree
+        let merkle_root = self.merkle_width.map(|width| {
+            let leaves = piece
+                .chunks(merkle::BLOCK_SIZE)
+                .map(|block| Sha256::digest(block).to_vec())
+                .collect::<Vec<Vec<u8>>>();
+            merkle::root(leaves, width, &[0; merkle::HASH_LENGTH])
+        });
+
+        piece.resize(piece.len() + util::u64_to_usize(self.padding)?, 0);
+        Ok(PieceHashes {
+            sha1: Sha1::digest(&piece).to_vec(),
+            merkle_root,
+        })
+    }
+}
+
+// hashes of a piece read by `PieceJob::hash()`
+#[derive(Default)]
+struct PieceHashes {
+    sha1: Piece,
+    // for hybrid torrents, the root of the piece's merkle tree
+    merkle_root: Option<Piece>,
+}
+
+// tracks the progress of `build_with_progress()`, reports it to the callback,
+// and checks the cancellation token
+struct ProgressTracker<'a> {
+    progress: BuildProgress,
+    callback: &'a mut dyn FnMut(&BuildProgress),
+    cancellation_token: Option<&'a CancellationToken>,
+}
+
+impl<'a> ProgressTracker<'a> {
+    // set the totals and report the initial progress
+    fn start(&mut self, bytes_total: u64, pieces_total: u64) -> Result<()> {
+        self.progress.bytes_total = bytes_total;
+        self.progress.pieces_total = util::u64_to_usize(pieces_total)?;
+        (self.callback)(&self.progress);
+        self.check_cancelled()
+    }
+
+    fn set_current_file(&mut self, path: &Path) {
+        if self.progress.current_file != path {
+            self.progress.current_file = path.to_path_buf();
+        }
+    }
+
+    // record a hashed piece containing `length` bytes (excluding padding)
+    fn piece_hashed(&mut self, length: u64) -> Result<()> {
+        self.progress.bytes_hashed += length;
+        self.progress.pieces_done += 1;
+        (self.callback)(&self.progress);
+        self.check_cancelled()
+    }
+
+    fn check_cancelled(&self) -> Result<()> {
+        match self.cancellation_token {
+            Some(token) if token.is_cancelled() => bail!(ErrorKind::TorrentBuilderCancelled),
+            _ => Ok(()),
+        }
+    }
+}
+
 impl TorrentBuilder {
     /// Create a new `TorrentBuilder` with required fields set.
     ///
@@ -17,7 +149,7 @@ impl TorrentBuilder {
     /// - A valid `piece_length` is larger than `0` AND is a power of `2`.
     ///
     /// - Using a `path` containing hidden components will make `build()` return `Err`.
-    /// This is \*nix-specific. Hidden components are those that start with `.`.
+    ///   This is \*nix-specific. Hidden components are those that start with `.`.
     ///
     /// - Paths with components exactly matching `..` are invalid.
     ///
@@ -48,11 +180,56 @@ impl TorrentBuilder {
     ///
     /// [last component]: https://doc.rust-lang.org/std/path/struct.Path.html#method.file_name
     pub fn build(self) -> Result<Torrent> {
+        self.build_with_progress(|_| ())
+    }
+
+    /// Same as [`build()`], but `callback` is called with the
+    /// [`BuildProgress`] each time a piece is hashed (and once before
+    /// any piece is hashed).
+    ///
+    /// `callback` is always called from the thread calling this method,
+    /// even if multiple threads are used for hashing (see [`set_num_threads()`]).
+    ///
+    /// If a [`CancellationToken`] has been set with [`set_cancellation_token()`]
+    /// and it is cancelled, then `Err(ErrorKind::TorrentBuilderCancelled)`
+    /// will be returned.
+    ///
+    /// [`build()`]: #method.build
+    /// [`BuildProgress`]: struct.BuildProgress.html
+    /// [`set_num_threads()`]: #method.set_num_threads
+    /// [`CancellationToken`]: struct.CancellationToken.html
+    /// [`set_cancellation_token()`]: #method.set_cancellation_token
+    pub fn build_with_progress<F>(mut self, mut callback: F) -> Result<Torrent>
+    where
+        F: FnMut(&BuildProgress),
+    {
         // delegate validation to other methods
         self.validate_announce()?;
         self.validate_announce_list()?;
+        self.validate_web_seeds()?;
+        self.validate_metainfo_fields()?;
         self.validate_name()?;
         self.validate_path()?;
+        self.validate_globs()?;
+        self.validate_max_auto_piece_length()?;
+
+        // list the files first, so that the filtered list
+        // is used everywhere
+        let canonicalized_path = self.path.canonicalize()?;
+        let entries = if canonicalized_path.is_dir() {
+            Some(self.list_entries(&canonicalized_path)?)
+        } else {
+            None
+        };
+
+        if let Some(max_piece_length) = self.max_auto_piece_length {
+            let total_length = match entries {
+                Some(ref entries) => entries.iter().map(|entry| entry.length).sum(),
+                None => canonicalized_path.metadata()?.len(),
+            };
+            self.piece_length =
+                Self::choose_piece_length(total_length, util::i64_to_u64(max_piece_length)?)?;
+        }
         self.validate_piece_length()?;
         self.validate_extra_fields()?;
         self.validate_extra_info_fields()?;
@@ -72,14 +249,86 @@ impl TorrentBuilder {
                 .insert("private".to_owned(), BencodeElem::Integer(1));
         }
 
+        let mut tracker = ProgressTracker {
+            progress: Default::default(),
+            callback: &mut callback,
+            cancellation_token: self.cancellation_token.as_ref(),
+        };
+        tracker.check_cancelled()?;
+
         // delegate the actual file reading to other methods
-        let canonicalized_path = self.path.canonicalize()?;
-        if self.path.metadata()?.is_dir() {
-            let (length, files, pieces) = Self::read_dir(canonicalized_path, self.piece_length)?;
+        if self.is_hybrid {
+            let content = Self::read_hybrid(
+                canonicalized_path,
+                entries,
+                &name,
+                self.piece_length,
+                self.num_threads,
+                &mut tracker,
+            )?;
+
+            // v2 fields are stored as extra fields of the v1 torrent
+            let mut extra_fields = self.extra_fields;
+            extra_fields.get_or_insert_with(HashMap::new).insert(
+                "piece layers".to_owned(),
+                BencodeElem::RawDictionary(
+                    content
+                        .piece_layers
+                        .into_iter()
+                        .map(|(root, layer)| (root, BencodeElem::Bytes(layer.concat())))
+                        .collect(),
+                ),
+            );
+            let info = extra_info_fields.get_or_insert_with(HashMap::new);
+            info.insert(
+                "file tree".to_owned(),
+                content.file_tree.into_bencode_elem(),
+            );
+            info.insert("meta version".to_owned(), BencodeElem::Integer(2));
 
             Ok(Torrent {
                 announce: self.announce,
                 announce_list: self.announce_list,
+                url_list: self.url_list,
+                http_seeds: self.http_seeds,
+                creation_date: self.creation_date,
+                comment: self.comment,
+                created_by: self.created_by,
+                encoding: self.encoding,
+                nodes: self.nodes,
+                source: self.source,
+                length: content.length,
+                files: content.files,
+                name,
+                piece_length: self.piece_length,
+                pieces: content.pieces,
+                extra_fields,
+                extra_info_fields,
+                raw_info: None,
+                url_list_is_string: false,
+                http_seeds_is_string: false,
+            })
+        } else if let Some(entries) = entries {
+            let (length, files, pieces) = Self::read_dir(
+                canonicalized_path,
+                entries,
+                self.piece_length,
+                self.pad_files,
+                self.num_threads,
+                &mut tracker,
+            )?;
+
+            Ok(Torrent {
+                announce: self.announce,
+                announce_list: self.announce_list,
+                url_list: self.url_list,
+                http_seeds: self.http_seeds,
+                creation_date: self.creation_date,
+                comment: self.comment,
+                created_by: self.created_by,
+                encoding: self.encoding,
+                nodes: self.nodes,
+                source: self.source,
                 length,
                 files: Some(files),
                 name,
@@ -87,13 +336,29 @@ impl TorrentBuilder {
                 pieces,
                 extra_fields: self.extra_fields,
                 extra_info_fields,
+                raw_info: None,
+                url_list_is_string: false,
+                http_seeds_is_string: false,
             })
         } else {
-            let (length, pieces) = Self::read_file(canonicalized_path, self.piece_length)?;
+            let (length, pieces) = Self::read_file(
+                canonicalized_path,
+                self.piece_length,
+                self.num_threads,
+                &mut tracker,
+            )?;
 
             Ok(Torrent {
                 announce: self.announce,
                 announce_list: self.announce_list,
+                url_list: self.url_list,
+                http_seeds: self.http_seeds,
+                creation_date: self.creation_date,
+                comment: self.comment,
+                created_by: self.created_by,
+                encoding: self.encoding,
+                nodes: self.nodes,
+                source: self.source,
                 length,
                 files: None,
                 name,
@@ -101,6 +366,9 @@ impl TorrentBuilder {
                 pieces,
                 extra_fields: self.extra_fields,
                 extra_info_fields,
+                raw_info: None,
+                url_list_is_string: false,
+                http_seeds_is_string: false,
             })
         }
     }
@@ -134,6 +402,150 @@ impl TorrentBuilder {
         }
     }
 
+    /// Set the `url_list` field (web seeds) of the `Torrent` to be built,
+    /// as defined in [BEP 19].
+    ///
+    /// Calling this method multiple times will simply override previous settings.
+    ///
+    /// The caller has to ensure that `url_list` is valid, as
+    /// this method does not validate its value. If `url_list`
+    /// turns out to be invalid, calling [`build()`] later will fail.
+    ///
+    /// [BEP 19]: http://bittorrent.org/beps/bep_0019.html
+    /// [`build()`]: #method.build
+    pub fn set_url_list(self, url_list: Vec<String>) -> TorrentBuilder {
+        TorrentBuilder {
+            url_list: Some(url_list),
+            ..self
+        }
+    }
+
+    /// Set the `http_seeds` field of the `Torrent` to be built,
+    /// as defined in [BEP 17].
+    ///
+    /// Calling this method multiple times will simply override previous settings.
+    ///
+    /// The caller has to ensure that `http_seeds` is valid, as
+    /// this method does not validate its value. If `http_seeds`
+    /// turns out to be invalid, calling [`build()`] later will fail.
+    ///
+    /// [BEP 17]: http://bittorrent.org/beps/bep_0017.html
+    /// [`build()`]: #method.build
+    pub fn set_http_seeds(self, http_seeds: Vec<String>) -> TorrentBuilder {
+        TorrentBuilder {
+            http_seeds: Some(http_seeds),
+            ..self
+        }
+    }
+
+    /// Set the `creation_date` field of the `Torrent` to be built,
+    /// in standard UNIX epoch format.
+    ///
+    /// Calling this method multiple times will simply override previous settings.
+    ///
+    /// The caller has to ensure that `creation_date` is valid, as
+    /// this method does not validate its value. If `creation_date`
+    /// turns out to be invalid, calling [`build()`] later will fail.
+    ///
+    /// [`build()`]: #method.build
+    pub fn set_creation_date(self, creation_date: Option<Integer>) -> TorrentBuilder {
+        TorrentBuilder {
+            creation_date,
+            ..self
+        }
+    }
+
+    /// Set the `creation_date` field of the `Torrent` to be built
+    /// to the current time.
+    ///
+    /// If the system clock is set before the UNIX epoch then
+    /// `creation_date` will be unset.
+    ///
+    /// Calling this method multiple times will simply override previous settings.
+    pub fn set_creation_date_now(self) -> TorrentBuilder {
+        let creation_date = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .ok()
+            .and_then(|elapsed| util::u64_to_i64(elapsed.as_secs()).ok());
+        self.set_creation_date(creation_date)
+    }
+
+    /// Set the `comment` field of the `Torrent` to be built.
+    ///
+    /// Calling this method multiple times will simply override previous settings.
+    ///
+    /// The caller has to ensure that `comment` is valid, as
+    /// this method does not validate its value. If `comment`
+    /// turns out to be invalid, calling [`build()`] later will fail.
+    ///
+    /// [`build()`]: #method.build
+    pub fn set_comment(self, comment: Option<String>) -> TorrentBuilder {
+        TorrentBuilder { comment, ..self }
+    }
+
+    /// Set the `created_by` field of the `Torrent` to be built.
+    ///
+    /// Calling this method multiple times will simply override previous settings.
+    ///
+    /// The caller has to ensure that `created_by` is valid, as
+    /// this method does not validate its value. If `created_by`
+    /// turns out to be invalid, calling [`build()`] later will fail.
+    ///
+    /// [`build()`]: #method.build
+    pub fn set_created_by(self, created_by: Option<String>) -> TorrentBuilder {
+        TorrentBuilder { created_by, ..self }
+    }
+
+    /// Set the `encoding` field of the `Torrent` to be built.
+    ///
+    /// Note that this only sets the field. Strings are always
+    /// encoded as UTF-8 by this crate.
+    ///
+    /// Calling this method multiple times will simply override previous settings.
+    ///
+    /// The caller has to ensure that `encoding` is valid, as
+    /// this method does not validate its value. If `encoding`
+    /// turns out to be invalid, calling [`build()`] later will fail.
+    ///
+    /// [`build()`]: #method.build
+    pub fn set_encoding(self, encoding: Option<String>) -> TorrentBuilder {
+        TorrentBuilder { encoding, ..self }
+    }
+
+    /// Set the `nodes` field (DHT bootstrap nodes) of the `Torrent` to be built,
+    /// as defined in [BEP 5].
+    ///
+    /// Calling this method multiple times will simply override previous settings.
+    ///
+    /// The caller has to ensure that `nodes` is valid, as
+    /// this method does not validate its value. If `nodes`
+    /// turns out to be invalid, calling [`build()`] later will fail.
+    ///
+    /// [BEP 5]: http://bittorrent.org/beps/bep_0005.html
+    /// [`build()`]: #method.build
+    pub fn set_nodes(self, nodes: Vec<Node>) -> TorrentBuilder {
+        TorrentBuilder {
+            nodes: Some(nodes),
+            ..self
+        }
+    }
+
+    /// Set the `source` field of the `Torrent` to be built.
+    ///
+    /// `source` is placed in the `info` dictionary, so it changes the
+    /// info hash.
+    ///
+    /// Calling this method multiple times will simply override previous settings.
+    ///
+    /// The caller has to ensure that `source` is valid, as
+    /// this method does not validate its value. If `source`
+    /// turns out to be invalid, calling [`build()`] later will fail.
+    ///
+    /// [`build()`]: #method.build
+    pub fn set_source(self, source: Option<String>) -> TorrentBuilder {
+        TorrentBuilder { source, ..self }
+    }
+
     /// Set the `name` field of the `Torrent` to be built.
     ///
     /// Calling this method multiple times will simply override previous settings.
@@ -162,7 +574,7 @@ impl TorrentBuilder {
     /// - `path` must be absolute.
     ///
     /// - Using a `path` containing hidden components will make `build()` return `Err`.
-    /// This is \*nix-specific. Hidden components are those that start with `.`.
+    ///   This is \*nix-specific. Hidden components are those that start with `.`.
     ///
     /// - Paths with components exactly matching `..` are invalid.
     ///
@@ -195,6 +607,102 @@ impl TorrentBuilder {
         }
     }
 
+    /// Choose `piece_length` automatically, based on the total size of the file(s).
+    ///
+    /// If `max_piece_length` is `Some`, then the `piece_length` given to
+    /// [`new()`] or [`set_piece_length()`] is ignored. Instead, the smallest
+    /// power of 2 that results in at most `2048` pieces is used. It is
+    /// never smaller than `16384` (16 KiB), and never larger than `max_piece_length`
+    /// (e.g. [`DEFAULT_MAX_PIECE_LENGTH`]). Passing `None` disables
+    /// this behavior (the default).
+    ///
+    /// Calling this method multiple times will simply override previous settings.
+    ///
+    /// NOTE: **A valid `max_piece_length` is at least `16384` AND is a power of `2`.**
+    ///
+    /// [`new()`]: #method.new
+    /// [`set_piece_length()`]: #method.set_piece_length
+    /// [`DEFAULT_MAX_PIECE_LENGTH`]: constant.DEFAULT_MAX_PIECE_LENGTH.html
+    pub fn set_auto_piece_length(self, max_piece_length: Option<Integer>) -> TorrentBuilder {
+        TorrentBuilder {
+            max_auto_piece_length: max_piece_length,
+            ..self
+        }
+    }
+
+    /// Include or ignore \*nix hidden files/dirs (those that start with `.`).
+    ///
+    /// By default they are ignored. This setting also applies to
+    /// the components of `path`.
+    ///
+    /// Calling this method multiple times will simply override previous settings.
+    pub fn set_include_hidden(self, include_hidden: bool) -> TorrentBuilder {
+        TorrentBuilder {
+            include_hidden,
+            ..self
+        }
+    }
+
+    /// Add a glob pattern (e.g. `*.mkv`) for files to be included.
+    ///
+    /// If any include pattern is added, then only files matching at least
+    /// one of them are included. A pattern matches a file if it matches
+    /// either the file's path (relative to `path`) or its name.
+    /// Directories are not matched against include patterns.
+    ///
+    /// Calling this method multiple times will add multiple patterns.
+    ///
+    /// The caller has to ensure that `pattern` is valid, as
+    /// this method does not validate its value. If `pattern`
+    /// turns out to be invalid, calling [`build()`] later will fail.
+    ///
+    /// [`build()`]: #method.build
+    pub fn add_include_glob(self, pattern: String) -> TorrentBuilder {
+        let mut include_globs = self.include_globs;
+        include_globs.push(pattern);
+
+        TorrentBuilder {
+            include_globs,
+            ..self
+        }
+    }
+
+    /// Add a glob pattern (e.g. `*.part` or `Thumbs.db`) for files/dirs to be excluded.
+    ///
+    /// A pattern matches a file/dir if it matches either its path
+    /// (relative to `path`) or its name. Excluded dirs are skipped entirely.
+    /// Exclude patterns take precedence over include patterns.
+    ///
+    /// Calling this method multiple times will add multiple patterns.
+    ///
+    /// The caller has to ensure that `pattern` is valid, as
+    /// this method does not validate its value. If `pattern`
+    /// turns out to be invalid, calling [`build()`] later will fail.
+    ///
+    /// [`build()`]: #method.build
+    pub fn add_exclude_glob(self, pattern: String) -> TorrentBuilder {
+        let mut exclude_globs = self.exclude_globs;
+        exclude_globs.push(pattern);
+
+        TorrentBuilder {
+            exclude_globs,
+            ..self
+        }
+    }
+
+    /// Set how symbolic links found in `path` are handled.
+    ///
+    /// By default symbolic links are followed. If `path` itself is
+    /// a symbolic link, then it is always followed.
+    ///
+    /// Calling this method multiple times will simply override previous settings.
+    pub fn set_symlink_policy(self, symlink_policy: SymlinkPolicy) -> TorrentBuilder {
+        TorrentBuilder {
+            symlink_policy,
+            ..self
+        }
+    }
+
     /// Add an extra field to `Torrent` (i.e. to the root dictionary).
     ///
     /// Calling this method multiple times with the same key will
@@ -248,6 +756,83 @@ impl TorrentBuilder {
         TorrentBuilder { is_private, ..self }
     }
 
+    /// Make the `Torrent` a hybrid ([v1] + [v2]) torrent, as defined in [BEP 52].
+    ///
+    /// Besides the SHA1 `pieces`, a hybrid torrent contains
+    /// the v2 `file tree` and `piece layers`, so that it can be used by
+    /// both v1-only and v2-capable clients. The v2 fields are placed in
+    /// `extra_info_fields` (`file tree` and `meta version`) and
+    /// `extra_fields` (`piece layers`) of the built `Torrent`, which
+    /// can also be parsed as a [v2 `Torrent`] once encoded.
+    ///
+    /// To align v1 pieces to file boundaries, [BEP 47] padding files
+    /// are inserted after each file (except the last one) whose length
+    /// is not a multiple of `piece_length`.
+    ///
+    /// Calling this method multiple times will simply override previous settings.
+    ///
+    /// NOTE: **A hybrid torrent's `piece_length` must be at least `16384` (16 KiB).**
+    ///
+    /// [v1]: http://bittorrent.org/beps/bep_0003.html
+    /// [v2]: http://bittorrent.org/beps/bep_0052.html
+    /// [BEP 52]: http://bittorrent.org/beps/bep_0052.html
+    /// [BEP 47]: http://bittorrent.org/beps/bep_0047.html
+    /// [v2 `Torrent`]: ../v2/struct.Torrent.html
+    pub fn set_hybrid(self, is_hybrid: bool) -> TorrentBuilder {
+        TorrentBuilder { is_hybrid, ..self }
+    }
+
+    /// Pad files to piece boundaries with [BEP 47] padding files.
+    ///
+    /// If `pad_files` is `true`, then a padding file (named `.pad/<length>`,
+    /// with `attr` set to `p`) is inserted after each file (except the last one)
+    /// whose length is not a multiple of `piece_length`, so that every file
+    /// starts at a piece boundary. This setting has no effect on single-file
+    /// torrents, and hybrid torrents are always padded.
+    ///
+    /// Calling this method multiple times will simply override previous settings.
+    ///
+    /// [BEP 47]: http://bittorrent.org/beps/bep_0047.html
+    pub fn set_pad_files(self, pad_files: bool) -> TorrentBuilder {
+        TorrentBuilder { pad_files, ..self }
+    }
+
+    /// Read and hash pieces with `num_threads` worker threads.
+    ///
+    /// Each worker reads a piece from disk and hashes it (including the v2
+    /// hashes of [hybrid] torrents), so pieces are read and hashed concurrently.
+    /// The built `Torrent` is identical regardless of the number of threads used.
+    ///
+    /// `0` and `1` (the default) both mean that pieces are read and hashed
+    /// by the thread calling [`build()`], without spawning any threads.
+    ///
+    /// Calling this method multiple times will simply override previous settings.
+    ///
+    /// [hybrid]: #method.set_hybrid
+    /// [`build()`]: #method.build
+    pub fn set_num_threads(self, num_threads: usize) -> TorrentBuilder {
+        TorrentBuilder {
+            num_threads,
+            ..self
+        }
+    }
+
+    /// Set a [`CancellationToken`] for cancelling the building of the `Torrent`.
+    ///
+    /// The token is checked between pieces. Once it is cancelled, [`build()`]
+    /// returns `Err(ErrorKind::TorrentBuilderCancelled)`.
+    ///
+    /// Calling this method multiple times will simply override previous settings.
+    ///
+    /// [`CancellationToken`]: struct.CancellationToken.html
+    /// [`build()`]: #method.build
+    pub fn set_cancellation_token(self, token: CancellationToken) -> TorrentBuilder {
+        TorrentBuilder {
+            cancellation_token: Some(token),
+            ..self
+        }
+    }
+
     fn validate_announce(&self) -> Result<()> {
         match self.announce {
             Some(ref announce) => {
@@ -294,6 +879,65 @@ impl TorrentBuilder {
         }
     }
 
+    fn validate_web_seeds(&self) -> Result<()> {
+        for (field, urls) in &[
+            ("url_list", &self.url_list),
+            ("http_seeds", &self.http_seeds),
+        ] {
+            if let Some(ref urls) = urls {
+                if urls.is_empty() {
+                    bail!(ErrorKind::TorrentBuilderFailure(Cow::Owned(format!(
+                        "TorrentBuilder has `{}` but it's empty.",
+                        field
+                    ))));
+                } else if urls.iter().any(|url| url.is_empty()) {
+                    bail!(ErrorKind::TorrentBuilderFailure(Cow::Owned(format!(
+                        "TorrentBuilder has `{}` but it contains a 0-length url.",
+                        field
+                    ))));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn validate_metainfo_fields(&self) -> Result<()> {
+        if let Some(creation_date) = self.creation_date {
+            if creation_date < 0 {
+                bail!(ErrorKind::TorrentBuilderFailure(Cow::Borrowed(
+                    "TorrentBuilder has `creation_date` but it's negative."
+                )));
+            }
+        }
+
+        for (field, value) in &[
+            ("comment", &self.comment),
+            ("created_by", &self.created_by),
+            ("encoding", &self.encoding),
+            ("source", &self.source),
+        ] {
+            if value.as_ref().is_some_and(|value| value.is_empty()) {
+                bail!(ErrorKind::TorrentBuilderFailure(Cow::Owned(format!(
+                    "TorrentBuilder has `{}` but its length is 0.",
+                    field
+                ))));
+            }
+        }
+
+        if let Some(ref nodes) = self.nodes {
+            if nodes.is_empty() {
+                bail!(ErrorKind::TorrentBuilderFailure(Cow::Borrowed(
+                    "TorrentBuilder has `nodes` but it's empty."
+                )));
+            } else if nodes.iter().any(|(host, _)| host.is_empty()) {
+                bail!(ErrorKind::TorrentBuilderFailure(Cow::Borrowed(
+                    "TorrentBuilder has `nodes` but it contains a 0-length host."
+                )));
+            }
+        }
+        Ok(())
+    }
+
     fn validate_name(&self) -> Result<()> {
         if let Some(ref name) = self.name {
             if name.is_empty() {
@@ -318,12 +962,12 @@ impl TorrentBuilder {
                         r#"Root path contains components exactly matching ".."."#
                     )));
                 }
-                Component::Normal(s) => {
-                    if s.to_string_lossy().starts_with('.') {
-                        bail!(ErrorKind::TorrentBuilderFailure(Cow::Borrowed(
-                            "Root path contains hidden components."
-                        )));
-                    }
+                Component::Normal(s)
+                    if !self.include_hidden && s.to_string_lossy().starts_with('.') =>
+                {
+                    bail!(ErrorKind::TorrentBuilderFailure(Cow::Borrowed(
+                        "Root path contains hidden components."
+                    )));
                 }
                 _ => (),
             }
@@ -355,18 +999,47 @@ impl TorrentBuilder {
             bail!(ErrorKind::TorrentBuilderFailure(Cow::Borrowed(
                 "TorrentBuilder has `piece_length` that is not a power of 2."
             )))
+        } else if self.is_hybrid
+            && (self.piece_length < util::u64_to_i64(util::usize_to_u64(merkle::BLOCK_SIZE)?)?)
+        {
+            // "It must be a power of two and at least 16KiB."
+            bail!(ErrorKind::TorrentBuilderFailure(Cow::Borrowed(
+                "TorrentBuilder is hybrid but has `piece_length` < 16384."
+            )))
         } else {
             Ok(())
         }
     }
 
-    fn validate_extra_fields(&self) -> Result<()> {
-        if let Some(ref extra_fields) = self.extra_fields {
-            if extra_fields.is_empty() {
-                panic!("TorrentBuilder has `extra_fields` but it's empty.")
-            } else {
-                for key in extra_fields.keys() {
-                    if key.is_empty() {
+    fn validate_globs(&self) -> Result<()> {
+        Self::compile_globs(&self.include_globs)?;
+        Self::compile_globs(&self.exclude_globs)?;
+        Ok(())
+    }
+
+    fn validate_max_auto_piece_length(&self) -> Result<()> {
+        match self.max_auto_piece_length {
+            Some(max) if max < util::u64_to_i64(AUTO_MIN_PIECE_LENGTH)? => {
+                bail!(ErrorKind::TorrentBuilderFailure(Cow::Borrowed(
+                    "TorrentBuilder has `max_auto_piece_length` < 16384."
+                )))
+            }
+            Some(max) if (max & (max - 1)) != 0 => {
+                bail!(ErrorKind::TorrentBuilderFailure(Cow::Borrowed(
+                    "TorrentBuilder has `max_auto_piece_length` that is not a power of 2."
+                )))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn validate_extra_fields(&self) -> Result<()> {
+        if let Some(ref extra_fields) = self.extra_fields {
+            if extra_fields.is_empty() {
+                panic!("TorrentBuilder has `extra_fields` but it's empty.")
+            } else {
+                for key in extra_fields.keys() {
+                    if key.is_empty() {
                         bail!(ErrorKind::TorrentBuilderFailure(Cow::Borrowed(
                             "TorrentBuilder has `extra_fields` but it contains a 0-length key.",
                         )));
@@ -398,84 +1071,568 @@ impl TorrentBuilder {
         }
     }
 
-    fn read_file<P>(path: P, piece_length: Integer) -> Result<(Integer, Vec<Piece>)>
+    fn compile_globs(patterns: &[String]) -> Result<Vec<Pattern>> {
+        patterns
+            .iter()
+            .map(|pattern| {
+                Pattern::new(pattern).chain_err(|| {
+                    ErrorKind::TorrentBuilderFailure(Cow::Owned(format!(
+                        "TorrentBuilder has an invalid glob pattern [{}].",
+                        pattern
+                    )))
+                })
+            })
+            .collect()
+    }
+
+    // list the files in `root`, taking hidden files, globs,
+    // and the symlink policy into account
+    fn list_entries(&self, root: &Path) -> Result<Vec<util::DirEntry>> {
+        let include = Self::compile_globs(&self.include_globs)?;
+        let exclude = Self::compile_globs(&self.exclude_globs)?;
+        let filter = |relative: &Path, is_dir: bool| {
+            let matches = |pattern: &Pattern| {
+                pattern.matches_path(relative)
+                    || relative
+                        .file_name()
+                        .is_some_and(|name| pattern.matches(&name.to_string_lossy()))
+            };
+
+            if exclude.iter().any(matches) {
+                false
+            } else {
+                is_dir || include.is_empty() || include.iter().any(matches)
+            }
+        };
+
+        let entries = util::list_dir(
+            root,
+            &util::ListDirOptions {
+                include_hidden: self.include_hidden,
+                symlink_policy: self.symlink_policy,
+                filter: &filter,
+            },
+        )?;
+        if entries.is_empty() {
+            bail!(ErrorKind::TorrentBuilderFailure(Cow::Borrowed(
+                "TorrentBuilder has `path` but it contains no file to include."
+            )));
+        }
+        Ok(entries)
+    }
+
+    // `symlink path` (BEP 47) of a symbolic link whose (normalized)
+    // target is `target`, i.e. `target` relative to `root`
+    fn symlink_path(root: &Path, link: &Path, target: &Path) -> Result<PathBuf> {
+        match target.strip_prefix(root) {
+            Ok(relative) => Ok(relative.to_path_buf()),
+            Err(_) => bail!(ErrorKind::TorrentBuilderFailure(Cow::Owned(format!(
+                "Symbolic link [{}] points outside of the root.",
+                link.display()
+            )))),
+        }
+    }
+
+    // `attr` and `symlink path` of a symbolic link, as extra fields of a v2 file
+    fn symlink_fields(symlink_path: &Path) -> Dictionary {
+        vec![
+            ("attr".to_owned(), BencodeElem::String("l".to_owned())),
+            (
+                "symlink path".to_owned(),
+                BencodeElem::List(
+                    symlink_path
+                        .components()
+                        .map(|c| BencodeElem::String(c.as_os_str().to_string_lossy().into()))
+                        .collect(),
+                ),
+            ),
+        ]
+        .into_iter()
+        .collect()
+    }
+
+    // the padding file that fills the rest of a piece, as in BEP 47
+    fn padding_file(padding: u64) -> Result<File> {
+        Ok(File {
+            length: util::u64_to_i64(padding)?,
+            path: PathBuf::from(".pad").join(padding.to_string()),
+            attr: Some(FileAttributes {
+                padding: true,
+                ..Default::default()
+            }),
+            sha1: None,
+            symlink_path: None,
+            extra_fields: None,
+        })
+    }
+
+    fn choose_piece_length(total_length: u64, max_piece_length: u64) -> Result<Integer> {
+        let mut piece_length = AUTO_MIN_PIECE_LENGTH;
+        while (piece_length < max_piece_length)
+            && (total_length.div_ceil(piece_length) > AUTO_MAX_PIECES)
+        {
+            piece_length *= 2;
+        }
+        util::u64_to_i64(piece_length)
+    }
+
+    fn read_file<P>(
+        path: P,
+        piece_length: Integer,
+        num_threads: usize,
+        tracker: &mut ProgressTracker,
+    ) -> Result<(Integer, Vec<Piece>)>
     where
         P: AsRef<Path>,
     {
         let path = path.as_ref();
         let length = path.metadata()?.len();
         let piece_length = util::i64_to_u64(piece_length)?;
+        tracker.start(length, length.div_ceil(piece_length))?;
+
+        // split the file into pieces + calculate pieces/hashs
+        let pieces = Self::hash_pieces(num_threads, tracker, |hash| {
+            let mut offset = 0;
+            while offset < length {
+                let to_read = cmp::min(piece_length, length - offset);
+                hash(PieceJob {
+                    ranges: vec![(path.to_path_buf(), offset, to_read)],
+                    padding: 0,
+                    merkle_width: None,
+                })?;
+                offset += to_read;
+            }
+            Ok(())
+        })?;
+
+        Ok((
+            util::u64_to_i64(length)?,
+            pieces.into_iter().map(|piece| piece.sha1).collect(),
+        ))
+    }
+
+    // If `pad` is `true`, then all files except the last one
+    // are padded to piece boundaries with padding files.
+    fn read_dir<P>(
+        path: P,
+        entries: Vec<util::DirEntry>,
+        piece_length: Integer,
+        pad: bool,
+        num_threads: usize,
+        tracker: &mut ProgressTracker,
+    ) -> Result<(Integer, Vec<File>, Vec<Piece>)>
+    where
+        P: AsRef<Path>,
+    {
+        let path = path.as_ref();
+        let piece_length = util::i64_to_u64(piece_length)?;
+        let content_length = entries.iter().fold(0, |acc, entry| acc + entry.length);
+        let mut total_length = content_length;
+        let mut files = Vec::with_capacity(entries.len());
+        if pad {
+            // each file starts at a piece boundary
+            tracker.start(
+                content_length,
+                entries
+                    .iter()
+                    .map(|entry| entry.length.div_ceil(piece_length))
+                    .sum(),
+            )?;
+        } else {
+            tracker.start(content_length, content_length.div_ceil(piece_length))?;
+        }
+
+        let pieces = Self::hash_pieces(num_threads, tracker, |hash| {
+            // the file ranges that make up the current piece
+            let mut ranges = Vec::new();
+            let mut piece_filled = 0;
+            for (i, entry) in entries.iter().enumerate() {
+                let relative = entry
+                    .path
+                    .strip_prefix(path)
+                    .expect("list_dir() returned an entry outside of the root.")
+                    .to_path_buf();
+                if let Some(ref target) = entry.symlink_target {
+                    // a recorded symlink has no content
+                    files.push(File {
+                        length: 0,
+                        path: relative,
+                        attr: Some(FileAttributes {
+                            symlink: true,
+                            ..Default::default()
+                        }),
+                        sha1: None,
+                        symlink_path: Some(Self::symlink_path(path, &entry.path, target)?),
+                        extra_fields: None,
+                    });
+                    continue;
+                }
+
+                let mut offset = 0;
+                while offset < entry.length {
+                    // calculate the # of bytes to read in this iteration
+                    let to_read = cmp::min(entry.length - offset, piece_length - piece_filled);
+                    ranges.push((entry.path.clone(), offset, to_read));
+                    offset += to_read;
+                    piece_filled += to_read;
+
+                    // if piece is completely filled, hash it
+                    if piece_filled == piece_length {
+                        hash(PieceJob {
+                            ranges: mem::take(&mut ranges),
+                            padding: 0,
+                            merkle_width: None,
+                        })?;
+                        piece_filled = 0;
+                    }
+                }
+
+                files.push(File {
+                    length: util::u64_to_i64(entry.length)?,
+                    // path relative to the root, e.g. `dir1/dir2/file`
+                    path: relative,
+                    attr: None,
+                    sha1: None,
+                    symlink_path: None,
+                    extra_fields: None,
+                });
+
+                // fill the rest of the piece with zeros
+                if pad && (i != entries.len() - 1) && (piece_filled != 0) {
+                    let padding = piece_length - piece_filled;
+                    hash(PieceJob {
+                        ranges: mem::take(&mut ranges),
+                        padding,
+                        merkle_width: None,
+                    })?;
+                    piece_filled = 0;
+
+                    total_length += padding;
+                    files.push(Self::padding_file(padding)?);
+                }
+            }
 
-        // read file content + calculate pieces/hashs
-        let mut file = BufReader::new(::std::fs::File::open(&path)?);
-        let mut piece = Vec::with_capacity(util::u64_to_usize(piece_length)?);
-        let mut pieces = Vec::with_capacity(util::u64_to_usize(length / piece_length + 1)?);
-        let mut total_read = 0;
+            // if piece is empty then the total file size is divisible by the piece length
+            // otherwise the last piece is partially filled and we have to hash it
+            if !ranges.is_empty() {
+                hash(PieceJob {
+                    ranges,
+                    padding: 0,
+                    merkle_width: None,
+                })?;
+            }
+            Ok(())
+        })?;
 
-        while total_read < length {
-            let read = file.by_ref().take(piece_length).read_to_end(&mut piece)?;
-            total_read += util::usize_to_u64(read)?;
+        Ok((
+            util::u64_to_i64(total_length)?,
+            files,
+            pieces.into_iter().map(|piece| piece.sha1).collect(),
+        ))
+    }
 
-            pieces.push(Sha1::digest(&piece).to_vec());
-            piece.clear();
+    // Read and hash the pieces passed to `hash` by `list_pieces`,
+    // returning their hashes in the order they are passed.
+    //
+    // If `num_threads > 1`, then pieces are both read and hashed by
+    // `num_threads` worker threads, while the calling thread keeps
+    // listing pieces and collects the hashes. Otherwise, everything
+    // is done by the calling thread.
+    fn hash_pieces<F>(
+        num_threads: usize,
+        tracker: &mut ProgressTracker,
+        list_pieces: F,
+    ) -> Result<Vec<PieceHashes>>
+    where
+        F: FnOnce(&mut dyn FnMut(PieceJob) -> Result<()>) -> Result<()>,
+    {
+        if num_threads <= 1 {
+            let mut pieces = Vec::new();
+            let mut file = None;
+            list_pieces(&mut |job| {
+                tracker.set_current_file(job.path());
+                pieces.push(job.hash(&mut file)?);
+                tracker.piece_hashed(job.content_length())
+            })?;
+            return Ok(pieces);
         }
 
-        Ok((util::u64_to_i64(length)?, pieces))
+        // bounded, so that at most a few pieces per worker are queued
+        let (job_tx, job_rx) = mpsc::sync_channel::<(usize, PieceJob)>(num_threads * 2);
+        let (hash_tx, hash_rx) = mpsc::channel();
+        let job_rx = Mutex::new(job_rx);
+
+        thread::scope(|scope| {
+            for _ in 0..num_threads {
+                let job_rx = &job_rx;
+                let hash_tx = hash_tx.clone();
+                scope.spawn(move || {
+                    let mut file = None;
+                    loop {
+                        let received = job_rx.lock().expect("A hashing thread panicked.").recv();
+                        match received {
+                            Ok((index, job)) => {
+                                let hashed = job.hash(&mut file);
+                                if hash_tx.send((index, hashed, job.content_length())).is_err() {
+                                    break;
+                                }
+                            }
+                            // all pieces have been listed
+                            Err(_) => break,
+                        }
+                    }
+                });
+            }
+            drop(hash_tx);
+
+            // hashes can arrive in any order
+            let mut pieces = Vec::new();
+            let mut n_pieces = 0;
+            let result = list_pieces(&mut |job| {
+                tracker.set_current_file(job.path());
+                job_tx
+                    .send((n_pieces, job))
+                    .expect("`job_rx` is dropped before `job_tx`.");
+                n_pieces += 1;
+
+                while let Ok(hashed) = hash_rx.try_recv() {
+                    Self::collect_hash(&mut pieces, tracker, hashed)?;
+                }
+                tracker.check_cancelled()
+            });
+            // let the workers exit once all pieces are hashed
+            drop(job_tx);
+            result?;
+
+            for hashed in hash_rx {
+                Self::collect_hash(&mut pieces, tracker, hashed)?;
+            }
+            Ok(pieces)
+        })
     }
 
-    fn read_dir<P>(path: P, piece_length: Integer) -> Result<(Integer, Vec<File>, Vec<Piece>)>
+    fn collect_hash(
+        pieces: &mut Vec<PieceHashes>,
+        tracker: &mut ProgressTracker,
+        (index, hashed, length): (usize, Result<PieceHashes>, u64),
+    ) -> Result<()> {
+        if index >= pieces.len() {
+            pieces.resize_with(index + 1, Default::default);
+        }
+        pieces[index] = hashed?;
+        tracker.piece_hashed(length)
+    }
+
+    fn read_hybrid<P>(
+        path: P,
+        entries: Option<Vec<util::DirEntry>>,
+        name: &str,
+        piece_length: Integer,
+        num_threads: usize,
+        tracker: &mut ProgressTracker,
+    ) -> Result<HybridContent>
     where
         P: AsRef<Path>,
     {
+        let path = path.as_ref();
+        let is_dir = entries.is_some();
         let piece_length = util::i64_to_u64(piece_length)?;
-        let entries = util::list_dir(path)?;
-        let total_length = entries.iter().fold(0, |acc, &(_, len)| acc + len);
-        let mut files = Vec::with_capacity(entries.len());
-        let mut pieces = Vec::with_capacity(util::u64_to_usize(total_length / piece_length + 1)?);
-
-        let mut piece = Vec::new();
-        let mut bytes = Vec::with_capacity(util::u64_to_usize(piece_length)?);
-        for (path, length) in entries {
-            let mut file = BufReader::new(::std::fs::File::open(&path)?);
-            let mut file_remaining = length;
-
-            while file_remaining > 0 {
-                // calculate the # of bytes to read in this iteration
-                let piece_filled = util::usize_to_u64(piece.len())?;
-                let piece_reamining = piece_length - piece_filled;
-                let to_read = if file_remaining < piece_reamining {
-                    file_remaining
+        let blocks_per_piece = util::u64_to_usize(piece_length)? / merkle::BLOCK_SIZE;
+
+        // for a single file torrent, `name` is used as the
+        // file's path in `file tree`
+        let entries: Vec<(util::DirEntry, PathBuf)> = match entries {
+            Some(entries) => entries
+                .into_iter()
+                .map(|entry| {
+                    let relative = entry
+                        .path
+                        .strip_prefix(path)
+                        .expect("list_dir() returned an entry outside of the root.")
+                        .to_path_buf();
+                    (entry, relative)
+                })
+                .collect(),
+            None => vec![(
+                util::DirEntry {
+                    path: path.to_path_buf(),
+                    length: path.metadata()?.len(),
+                    symlink_target: None,
+                },
+                PathBuf::from(name),
+            )],
+        };
+
+        // pad all files except the last one to piece boundaries
+        let n_entries = entries.len();
+        let paddings = entries
+            .iter()
+            .enumerate()
+            .map(|(i, (entry, _))| {
+                if is_dir && (i != n_entries - 1) && (entry.length % piece_length != 0) {
+                    piece_length - entry.length % piece_length
                 } else {
-                    piece_reamining
-                };
+                    0
+                }
+            })
+            .collect::<Vec<u64>>();
+
+        // each file starts at a piece boundary
+        tracker.start(
+            entries.iter().map(|(entry, _)| entry.length).sum(),
+            entries
+                .iter()
+                .map(|(entry, _)| entry.length.div_ceil(piece_length))
+                .sum(),
+        )?;
+
+        // each v1 piece is also a v2 piece (i.e. a node in the file's merkle tree)
+        let hashes = Self::hash_pieces(num_threads, tracker, |hash| {
+            for ((entry, _), &padding) in entries.iter().zip(&paddings) {
+                // a recorded symlink has no content
+                if entry.symlink_target.is_some() {
+                    continue;
+                }
 
-                // read bytes
-                file.by_ref().take(to_read).read_to_end(&mut bytes)?;
-                piece.append(&mut bytes);
-                file_remaining -= to_read;
+                // files <= `piece_length` have no piece layer, so their
+                // only piece is hashed up to their `pieces root`
+                let merkle_width = if entry.length > piece_length {
+                    blocks_per_piece
+                } else {
+                    util::u64_to_usize(entry.length)?
+                        .div_ceil(merkle::BLOCK_SIZE)
+                        .next_power_of_two()
+                };
 
-                // if piece is completely filled, hash it
-                if piece.len() == util::u64_to_usize(piece_length)? {
-                    pieces.push(Sha1::digest(&piece).to_vec());
-                    piece.clear();
+                let mut offset = 0;
+                while offset < entry.length {
+                    let to_read = cmp::min(piece_length, entry.length - offset);
+                    offset += to_read;
+                    hash(PieceJob {
+                        ranges: vec![(entry.path.clone(), offset - to_read, to_read)],
+                        // pad the last piece with zeros (as if it's followed by a padding file)
+                        padding: if offset == entry.length { padding } else { 0 },
+                        merkle_width: Some(merkle_width),
+                    })?;
                 }
             }
+            Ok(())
+        })?;
+
+        let mut total_length = 0;
+        let mut files = Vec::with_capacity(entries.len());
+        let mut pieces = Vec::with_capacity(hashes.len());
+        let mut file_tree = BTreeMap::new();
+        let mut piece_layers = HashMap::new();
+
+        let mut hashes = hashes.into_iter();
+        for ((entry, relative), padding) in entries.into_iter().zip(paddings) {
+            let length = entry.length;
+
+            // a recorded symlink has no content
+            let (pieces_root, symlink_path) = match entry.symlink_target {
+                Some(ref target) => (None, Some(Self::symlink_path(path, &entry.path, target)?)),
+                None => {
+                    let mut layer = Vec::new();
+                    for piece in hashes
+                        .by_ref()
+                        .take(util::u64_to_usize(length.div_ceil(piece_length))?)
+                    {
+                        pieces.push(piece.sha1);
+                        layer.push(
+                            piece
+                                .merkle_root
+                                .expect("A hybrid piece is hashed without its merkle root."),
+                        );
+                    }
+
+                    if length > piece_length {
+                        let pieces_root = merkle::root_from_piece_layer(
+                            layer.clone(),
+                            util::u64_to_usize(piece_length)?,
+                        );
+                        piece_layers.insert(pieces_root.clone(), layer);
+                        (Some(pieces_root), None)
+                    } else {
+                        (layer.pop(), None)
+                    }
+                }
+            };
+            total_length += length;
+
+            Self::insert_into_file_tree(
+                &mut file_tree,
+                &relative
+                    .components()
+                    .map(|c| c.as_os_str().to_string_lossy().into_owned())
+                    .collect::<Vec<String>>(),
+                v2::File {
+                    length: util::u64_to_i64(length)?,
+                    pieces_root,
+                    extra_fields: symlink_path.as_ref().map(|p| Self::symlink_fields(p)),
+                },
+            )?;
 
             files.push(File {
                 length: util::u64_to_i64(length)?,
-                path: PathBuf::from(util::last_component(path)?),
+                path: relative,
+                attr: symlink_path.as_ref().map(|_| FileAttributes {
+                    symlink: true,
+                    ..Default::default()
+                }),
+                sha1: None,
+                symlink_path,
                 extra_fields: None,
             });
+            if padding != 0 {
+                total_length += padding;
+                files.push(Self::padding_file(padding)?);
+            }
         }
 
-        // if piece is empty then the total file size is divisible by the piece length
-        // otherwise the last piece is partially filled and we have to hash it
-        if !piece.is_empty() {
-            pieces.push(Sha1::digest(&piece).to_vec());
-            piece.clear();
-        }
+        Ok(HybridContent {
+            length: util::u64_to_i64(total_length)?,
+            files: if is_dir { Some(files) } else { None },
+            pieces,
+            file_tree: v2::FileTree::Dir(file_tree),
+            piece_layers,
+        })
+    }
 
-        Ok((util::u64_to_i64(total_length)?, files, pieces))
+    // Insert `file` at the path made of `components` into `dir`.
+    //
+    // `Err` is returned if the path conflicts with an existing entry
+    // (i.e. a path is both a file and a dir, or is added twice).
+    fn insert_into_file_tree(
+        dir: &mut BTreeMap<String, v2::FileTree>,
+        components: &[String],
+        file: v2::File,
+    ) -> Result<()> {
+        if components.len() == 1 {
+            if dir.contains_key(&components[0]) {
+                bail!(ErrorKind::TorrentBuilderFailure(Cow::Owned(format!(
+                    r#"Path component "{}" appears in "file tree" more than once."#,
+                    components[0]
+                ))));
+            }
+            dir.insert(components[0].clone(), v2::FileTree::File(file));
+            Ok(())
+        } else {
+            match *dir
+                .entry(components[0].clone())
+                .or_insert_with(|| v2::FileTree::Dir(BTreeMap::new()))
+            {
+                v2::FileTree::Dir(ref mut dir) => {
+                    Self::insert_into_file_tree(dir, &components[1..], file)
+                }
+                v2::FileTree::File(_) => {
+                    bail!(ErrorKind::TorrentBuilderFailure(Cow::Owned(format!(
+                        r#"Path component "{}" is both a file and a dir in "file tree"."#,
+                        components[0]
+                    ))))
+                }
+            }
+        }
     }
 }
 
@@ -489,6 +1646,21 @@ mod torrent_builder_tests {
     use super::*;
     use std::iter::FromIterator;
 
+    // `TorrentBuilder::read_file()` without progress reporting
+    fn read_file(
+        path: &str,
+        piece_length: Integer,
+        num_threads: usize,
+    ) -> Result<(Integer, Vec<Piece>)> {
+        let mut callback = |_: &BuildProgress| ();
+        let mut tracker = ProgressTracker {
+            progress: Default::default(),
+            callback: &mut callback,
+            cancellation_token: None,
+        };
+        TorrentBuilder::read_file(path, piece_length, num_threads, &mut tracker)
+    }
+
     #[test]
     fn new_ok() {
         assert_eq!(
@@ -556,6 +1728,206 @@ mod torrent_builder_tests {
         );
     }
 
+    #[test]
+    fn set_url_list_ok() {
+        let builder = TorrentBuilder::new("dir/", 42);
+
+        let builder = builder.set_url_list(vec!["url1".to_owned(), "url2".to_owned()]);
+        assert_eq!(
+            builder,
+            TorrentBuilder {
+                url_list: Some(vec!["url1".to_owned(), "url2".to_owned()]),
+                path: PathBuf::from("dir"),
+                piece_length: 42,
+                ..Default::default()
+            }
+        );
+
+        let builder = builder.set_url_list(vec!["url3".to_owned()]);
+        assert_eq!(
+            builder,
+            TorrentBuilder {
+                url_list: Some(vec!["url3".to_owned()]),
+                path: PathBuf::from("dir"),
+                piece_length: 42,
+                ..Default::default()
+            }
+        );
+    }
+
+    #[test]
+    fn set_http_seeds_ok() {
+        let builder = TorrentBuilder::new("dir/", 42);
+
+        let builder = builder.set_http_seeds(vec!["url1".to_owned()]);
+        assert_eq!(
+            builder,
+            TorrentBuilder {
+                http_seeds: Some(vec!["url1".to_owned()]),
+                path: PathBuf::from("dir"),
+                piece_length: 42,
+                ..Default::default()
+            }
+        );
+
+        let builder = builder.set_http_seeds(vec!["url2".to_owned()]);
+        assert_eq!(
+            builder,
+            TorrentBuilder {
+                http_seeds: Some(vec!["url2".to_owned()]),
+                path: PathBuf::from("dir"),
+                piece_length: 42,
+                ..Default::default()
+            }
+        );
+    }
+
+    #[test]
+    fn set_creation_date_ok() {
+        let builder = TorrentBuilder::new("dir/", 42);
+
+        let builder = builder.set_creation_date(Some(1_500_000_000));
+        assert_eq!(
+            builder,
+            TorrentBuilder {
+                creation_date: Some(1_500_000_000),
+                path: PathBuf::from("dir"),
+                piece_length: 42,
+                ..Default::default()
+            }
+        );
+
+        let builder = builder.set_creation_date(None);
+        assert_eq!(builder, TorrentBuilder::new("dir/", 42));
+    }
+
+    #[test]
+    fn set_creation_date_now_ok() {
+        let before = util::u64_to_i64(
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap()
+                .as_secs(),
+        )
+        .unwrap();
+        let builder = TorrentBuilder::new("dir/", 42).set_creation_date_now();
+        let creation_date = builder.creation_date.unwrap();
+
+        assert!(creation_date >= before);
+        assert!(creation_date - before < 60);
+    }
+
+    #[test]
+    fn set_comment_ok() {
+        let builder = TorrentBuilder::new("dir/", 42);
+
+        let builder = builder.set_comment(Some("comment".to_owned()));
+        assert_eq!(
+            builder,
+            TorrentBuilder {
+                comment: Some("comment".to_owned()),
+                path: PathBuf::from("dir"),
+                piece_length: 42,
+                ..Default::default()
+            }
+        );
+
+        let builder = builder.set_comment(Some("comment2".to_owned()));
+        assert_eq!(
+            builder,
+            TorrentBuilder {
+                comment: Some("comment2".to_owned()),
+                path: PathBuf::from("dir"),
+                piece_length: 42,
+                ..Default::default()
+            }
+        );
+    }
+
+    #[test]
+    fn set_created_by_ok() {
+        let builder = TorrentBuilder::new("dir/", 42);
+
+        let builder = builder.set_created_by(Some("lava_torrent".to_owned()));
+        assert_eq!(
+            builder,
+            TorrentBuilder {
+                created_by: Some("lava_torrent".to_owned()),
+                path: PathBuf::from("dir"),
+                piece_length: 42,
+                ..Default::default()
+            }
+        );
+
+        let builder = builder.set_created_by(None);
+        assert_eq!(builder, TorrentBuilder::new("dir/", 42));
+    }
+
+    #[test]
+    fn set_encoding_ok() {
+        let builder = TorrentBuilder::new("dir/", 42);
+
+        let builder = builder.set_encoding(Some("UTF-8".to_owned()));
+        assert_eq!(
+            builder,
+            TorrentBuilder {
+                encoding: Some("UTF-8".to_owned()),
+                path: PathBuf::from("dir"),
+                piece_length: 42,
+                ..Default::default()
+            }
+        );
+
+        let builder = builder.set_encoding(None);
+        assert_eq!(builder, TorrentBuilder::new("dir/", 42));
+    }
+
+    #[test]
+    fn set_nodes_ok() {
+        let builder = TorrentBuilder::new("dir/", 42);
+
+        let builder = builder.set_nodes(vec![("127.0.0.1".to_owned(), 6881)]);
+        assert_eq!(
+            builder,
+            TorrentBuilder {
+                nodes: Some(vec![("127.0.0.1".to_owned(), 6881)]),
+                path: PathBuf::from("dir"),
+                piece_length: 42,
+                ..Default::default()
+            }
+        );
+
+        let builder = builder.set_nodes(vec![("router.example.com".to_owned(), 6882)]);
+        assert_eq!(
+            builder,
+            TorrentBuilder {
+                nodes: Some(vec![("router.example.com".to_owned(), 6882)]),
+                path: PathBuf::from("dir"),
+                piece_length: 42,
+                ..Default::default()
+            }
+        );
+    }
+
+    #[test]
+    fn set_source_ok() {
+        let builder = TorrentBuilder::new("dir/", 42);
+
+        let builder = builder.set_source(Some("SRC".to_owned()));
+        assert_eq!(
+            builder,
+            TorrentBuilder {
+                source: Some("SRC".to_owned()),
+                path: PathBuf::from("dir"),
+                piece_length: 42,
+                ..Default::default()
+            }
+        );
+
+        let builder = builder.set_source(None);
+        assert_eq!(builder, TorrentBuilder::new("dir/", 42));
+    }
+
     #[test]
     fn set_name_ok() {
         let builder = TorrentBuilder::new("dir/", 42);
@@ -609,116 +1981,355 @@ mod torrent_builder_tests {
     }
 
     #[test]
-    fn set_piece_length_ok() {
+    fn set_piece_length_ok() {
+        let builder = TorrentBuilder::new("dir/", 42);
+
+        let builder = builder.set_piece_length(256);
+        assert_eq!(
+            builder,
+            TorrentBuilder {
+                path: PathBuf::from("dir"),
+                piece_length: 256,
+                ..Default::default()
+            }
+        );
+
+        let builder = builder.set_piece_length(512);
+        assert_eq!(
+            builder,
+            TorrentBuilder {
+                path: PathBuf::from("dir"),
+                piece_length: 512,
+                ..Default::default()
+            }
+        );
+    }
+
+    #[test]
+    fn add_extra_field_ok() {
+        let builder = TorrentBuilder::new("dir/", 42);
+
+        let builder = builder.add_extra_field("k1".to_owned(), bencode_elem!("v1"));
+        assert_eq!(
+            builder,
+            TorrentBuilder {
+                path: PathBuf::from("dir"),
+                piece_length: 42,
+                extra_fields: Some(HashMap::from_iter(
+                    vec![("k1".to_owned(), bencode_elem!("v1"))].into_iter()
+                )),
+                ..Default::default()
+            }
+        );
+
+        let builder = builder.add_extra_field("k2".to_owned(), bencode_elem!("v2"));
+        assert_eq!(
+            builder,
+            TorrentBuilder {
+                path: PathBuf::from("dir"),
+                piece_length: 42,
+                extra_fields: Some(HashMap::from_iter(
+                    vec![
+                        ("k1".to_owned(), bencode_elem!("v1")),
+                        ("k2".to_owned(), bencode_elem!("v2")),
+                    ]
+                    .into_iter()
+                )),
+                ..Default::default()
+            }
+        );
+    }
+
+    #[test]
+    fn add_extra_info_field_ok() {
+        let builder = TorrentBuilder::new("dir/", 42);
+
+        let builder = builder.add_extra_info_field("k1".to_owned(), bencode_elem!("v1"));
+        assert_eq!(
+            builder,
+            TorrentBuilder {
+                path: PathBuf::from("dir"),
+                piece_length: 42,
+                extra_info_fields: Some(HashMap::from_iter(
+                    vec![("k1".to_owned(), bencode_elem!("v1"))].into_iter()
+                )),
+                ..Default::default()
+            }
+        );
+
+        let builder = builder.add_extra_info_field("k2".to_owned(), bencode_elem!("v2"));
+        assert_eq!(
+            builder,
+            TorrentBuilder {
+                path: PathBuf::from("dir"),
+                piece_length: 42,
+                extra_info_fields: Some(HashMap::from_iter(
+                    vec![
+                        ("k1".to_owned(), bencode_elem!("v1")),
+                        ("k2".to_owned(), bencode_elem!("v2")),
+                    ]
+                    .into_iter()
+                )),
+                ..Default::default()
+            }
+        );
+    }
+
+    #[test]
+    fn set_privacy_ok() {
+        let builder = TorrentBuilder::new("dir/", 42);
+
+        let builder = builder.set_privacy(true);
+        assert_eq!(
+            builder,
+            TorrentBuilder {
+                path: PathBuf::from("dir"),
+                piece_length: 42,
+                is_private: true,
+                ..Default::default()
+            }
+        );
+
+        let builder = builder.set_privacy(false);
+        assert_eq!(
+            builder,
+            TorrentBuilder {
+                path: PathBuf::from("dir"),
+                piece_length: 42,
+                ..Default::default()
+            }
+        );
+    }
+
+    #[test]
+    fn set_hybrid_ok() {
+        let builder = TorrentBuilder::new("dir/", 42);
+
+        let builder = builder.set_hybrid(true);
+        assert_eq!(
+            builder,
+            TorrentBuilder {
+                path: PathBuf::from("dir"),
+                piece_length: 42,
+                is_hybrid: true,
+                ..Default::default()
+            }
+        );
+
+        let builder = builder.set_hybrid(false);
+        assert_eq!(
+            builder,
+            TorrentBuilder {
+                path: PathBuf::from("dir"),
+                piece_length: 42,
+                ..Default::default()
+            }
+        );
+    }
+
+    #[test]
+    fn set_pad_files_ok() {
+        let builder = TorrentBuilder::new("dir/", 42);
+
+        let builder = builder.set_pad_files(true);
+        assert_eq!(
+            builder,
+            TorrentBuilder {
+                path: PathBuf::from("dir"),
+                piece_length: 42,
+                pad_files: true,
+                ..Default::default()
+            }
+        );
+
+        let builder = builder.set_pad_files(false);
+        assert_eq!(
+            builder,
+            TorrentBuilder {
+                path: PathBuf::from("dir"),
+                piece_length: 42,
+                ..Default::default()
+            }
+        );
+    }
+
+    #[test]
+    fn set_num_threads_ok() {
+        let builder = TorrentBuilder::new("dir/", 42);
+
+        let builder = builder.set_num_threads(4);
+        assert_eq!(
+            builder,
+            TorrentBuilder {
+                path: PathBuf::from("dir"),
+                piece_length: 42,
+                num_threads: 4,
+                ..Default::default()
+            }
+        );
+
+        let builder = builder.set_num_threads(0);
+        assert_eq!(
+            builder,
+            TorrentBuilder {
+                path: PathBuf::from("dir"),
+                piece_length: 42,
+                ..Default::default()
+            }
+        );
+    }
+
+    #[test]
+    fn set_cancellation_token_ok() {
+        let token = CancellationToken::new();
+        let builder = TorrentBuilder::new("dir/", 42);
+
+        let builder = builder.set_cancellation_token(token.clone());
+        assert_eq!(
+            builder,
+            TorrentBuilder {
+                path: PathBuf::from("dir"),
+                piece_length: 42,
+                cancellation_token: Some(token),
+                ..Default::default()
+            }
+        );
+
+        let token = CancellationToken::new();
+        let builder = builder.set_cancellation_token(token.clone());
+        assert_eq!(
+            builder,
+            TorrentBuilder {
+                path: PathBuf::from("dir"),
+                piece_length: 42,
+                cancellation_token: Some(token),
+                ..Default::default()
+            }
+        );
+    }
+
+    #[test]
+    fn set_auto_piece_length_ok() {
+        let builder = TorrentBuilder::new("dir/", 42);
+
+        let builder = builder.set_auto_piece_length(Some(DEFAULT_MAX_PIECE_LENGTH));
+        assert_eq!(
+            builder,
+            TorrentBuilder {
+                path: PathBuf::from("dir"),
+                piece_length: 42,
+                max_auto_piece_length: Some(DEFAULT_MAX_PIECE_LENGTH),
+                ..Default::default()
+            }
+        );
+
+        let builder = builder.set_auto_piece_length(None);
+        assert_eq!(
+            builder,
+            TorrentBuilder {
+                path: PathBuf::from("dir"),
+                piece_length: 42,
+                ..Default::default()
+            }
+        );
+    }
+
+    #[test]
+    fn set_include_hidden_ok() {
         let builder = TorrentBuilder::new("dir/", 42);
 
-        let builder = builder.set_piece_length(256);
+        let builder = builder.set_include_hidden(true);
         assert_eq!(
             builder,
             TorrentBuilder {
                 path: PathBuf::from("dir"),
-                piece_length: 256,
+                piece_length: 42,
+                include_hidden: true,
                 ..Default::default()
             }
         );
 
-        let builder = builder.set_piece_length(512);
+        let builder = builder.set_include_hidden(false);
         assert_eq!(
             builder,
             TorrentBuilder {
                 path: PathBuf::from("dir"),
-                piece_length: 512,
+                piece_length: 42,
                 ..Default::default()
             }
         );
     }
 
     #[test]
-    fn add_extra_field_ok() {
+    fn add_include_glob_ok() {
         let builder = TorrentBuilder::new("dir/", 42);
 
-        let builder = builder.add_extra_field("k1".to_owned(), bencode_elem!("v1"));
+        let builder = builder.add_include_glob("*.mkv".to_owned());
         assert_eq!(
             builder,
             TorrentBuilder {
                 path: PathBuf::from("dir"),
                 piece_length: 42,
-                extra_fields: Some(HashMap::from_iter(
-                    vec![("k1".to_owned(), bencode_elem!("v1"))].into_iter()
-                )),
+                include_globs: vec!["*.mkv".to_owned()],
                 ..Default::default()
             }
         );
 
-        let builder = builder.add_extra_field("k2".to_owned(), bencode_elem!("v2"));
+        let builder = builder.add_include_glob("*.srt".to_owned());
         assert_eq!(
             builder,
             TorrentBuilder {
                 path: PathBuf::from("dir"),
                 piece_length: 42,
-                extra_fields: Some(HashMap::from_iter(
-                    vec![
-                        ("k1".to_owned(), bencode_elem!("v1")),
-                        ("k2".to_owned(), bencode_elem!("v2")),
-                    ]
-                    .into_iter()
-                )),
+                include_globs: vec!["*.mkv".to_owned(), "*.srt".to_owned()],
                 ..Default::default()
             }
         );
     }
 
     #[test]
-    fn add_extra_info_field_ok() {
+    fn add_exclude_glob_ok() {
         let builder = TorrentBuilder::new("dir/", 42);
 
-        let builder = builder.add_extra_info_field("k1".to_owned(), bencode_elem!("v1"));
+        let builder = builder.add_exclude_glob("*.part".to_owned());
         assert_eq!(
             builder,
             TorrentBuilder {
                 path: PathBuf::from("dir"),
                 piece_length: 42,
-                extra_info_fields: Some(HashMap::from_iter(
-                    vec![("k1".to_owned(), bencode_elem!("v1"))].into_iter()
-                )),
+                exclude_globs: vec!["*.part".to_owned()],
                 ..Default::default()
             }
         );
 
-        let builder = builder.add_extra_info_field("k2".to_owned(), bencode_elem!("v2"));
+        let builder = builder.add_exclude_glob("Thumbs.db".to_owned());
         assert_eq!(
             builder,
             TorrentBuilder {
                 path: PathBuf::from("dir"),
                 piece_length: 42,
-                extra_info_fields: Some(HashMap::from_iter(
-                    vec![
-                        ("k1".to_owned(), bencode_elem!("v1")),
-                        ("k2".to_owned(), bencode_elem!("v2")),
-                    ]
-                    .into_iter()
-                )),
+                exclude_globs: vec!["*.part".to_owned(), "Thumbs.db".to_owned()],
                 ..Default::default()
             }
         );
     }
 
     #[test]
-    fn set_privacy_ok() {
+    fn set_symlink_policy_ok() {
         let builder = TorrentBuilder::new("dir/", 42);
 
-        let builder = builder.set_privacy(true);
+        let builder = builder.set_symlink_policy(SymlinkPolicy::Record);
         assert_eq!(
             builder,
             TorrentBuilder {
                 path: PathBuf::from("dir"),
                 piece_length: 42,
-                is_private: true,
+                symlink_policy: SymlinkPolicy::Record,
                 ..Default::default()
             }
         );
 
-        let builder = builder.set_privacy(false);
+        let builder = builder.set_symlink_policy(SymlinkPolicy::Follow);
         assert_eq!(
             builder,
             TorrentBuilder {
@@ -822,6 +2433,143 @@ mod torrent_builder_tests {
         }
     }
 
+    #[test]
+    fn validate_web_seeds_ok() {
+        let builder = TorrentBuilder::new("dir/", 42)
+            .set_url_list(vec!["url1".to_owned()])
+            .set_http_seeds(vec!["url2".to_owned()]);
+
+        builder.validate_web_seeds().unwrap();
+        // validation methods should not modify builder
+        assert_eq!(
+            builder,
+            TorrentBuilder::new("dir/", 42)
+                .set_url_list(vec!["url1".to_owned()])
+                .set_http_seeds(vec!["url2".to_owned()])
+        );
+    }
+
+    #[test]
+    fn validate_web_seeds_none() {
+        let builder = TorrentBuilder::new("dir/", 42);
+
+        builder.validate_web_seeds().unwrap();
+        // validation methods should not modify builder
+        assert_eq!(builder, TorrentBuilder::new("dir/", 42));
+    }
+
+    #[test]
+    fn validate_web_seeds_empty() {
+        let builder = TorrentBuilder::new("dir/", 42).set_url_list(vec![]);
+
+        match builder.validate_web_seeds() {
+            Err(Error(ErrorKind::TorrentBuilderFailure(m), _)) => {
+                assert_eq!(m, "TorrentBuilder has `url_list` but it's empty.");
+            }
+            _ => assert!(false),
+        }
+    }
+
+    #[test]
+    fn validate_web_seeds_empty_url() {
+        let builder = TorrentBuilder::new("dir/", 42)
+            .set_url_list(vec!["url1".to_owned()])
+            .set_http_seeds(vec!["url2".to_owned(), "".to_owned()]);
+
+        match builder.validate_web_seeds() {
+            Err(Error(ErrorKind::TorrentBuilderFailure(m), _)) => assert_eq!(
+                m,
+                "TorrentBuilder has `http_seeds` but it contains a 0-length url."
+            ),
+            _ => assert!(false),
+        }
+    }
+
+    #[test]
+    fn validate_metainfo_fields_ok() {
+        let builder = TorrentBuilder::new("dir/", 42)
+            .set_creation_date(Some(0))
+            .set_comment(Some("comment".to_owned()))
+            .set_created_by(Some("lava_torrent".to_owned()))
+            .set_encoding(Some("UTF-8".to_owned()))
+            .set_nodes(vec![("127.0.0.1".to_owned(), 6881)])
+            .set_source(Some("SRC".to_owned()));
+
+        builder.validate_metainfo_fields().unwrap();
+        // validation methods should not modify builder
+        assert_eq!(
+            builder,
+            TorrentBuilder::new("dir/", 42)
+                .set_creation_date(Some(0))
+                .set_comment(Some("comment".to_owned()))
+                .set_created_by(Some("lava_torrent".to_owned()))
+                .set_encoding(Some("UTF-8".to_owned()))
+                .set_nodes(vec![("127.0.0.1".to_owned(), 6881)])
+                .set_source(Some("SRC".to_owned()))
+        );
+    }
+
+    #[test]
+    fn validate_metainfo_fields_none() {
+        let builder = TorrentBuilder::new("dir/", 42);
+
+        builder.validate_metainfo_fields().unwrap();
+        // validation methods should not modify builder
+        assert_eq!(builder, TorrentBuilder::new("dir/", 42));
+    }
+
+    #[test]
+    fn validate_metainfo_fields_negative_creation_date() {
+        let builder = TorrentBuilder::new("dir/", 42).set_creation_date(Some(-1));
+
+        match builder.validate_metainfo_fields() {
+            Err(Error(ErrorKind::TorrentBuilderFailure(m), _)) => {
+                assert_eq!(m, "TorrentBuilder has `creation_date` but it's negative.");
+            }
+            _ => assert!(false),
+        }
+    }
+
+    #[test]
+    fn validate_metainfo_fields_empty_string() {
+        let builder = TorrentBuilder::new("dir/", 42)
+            .set_comment(Some("comment".to_owned()))
+            .set_source(Some("".to_owned()));
+
+        match builder.validate_metainfo_fields() {
+            Err(Error(ErrorKind::TorrentBuilderFailure(m), _)) => {
+                assert_eq!(m, "TorrentBuilder has `source` but its length is 0.");
+            }
+            _ => assert!(false),
+        }
+    }
+
+    #[test]
+    fn validate_metainfo_fields_empty_nodes() {
+        let builder = TorrentBuilder::new("dir/", 42).set_nodes(vec![]);
+
+        match builder.validate_metainfo_fields() {
+            Err(Error(ErrorKind::TorrentBuilderFailure(m), _)) => {
+                assert_eq!(m, "TorrentBuilder has `nodes` but it's empty.");
+            }
+            _ => assert!(false),
+        }
+    }
+
+    #[test]
+    fn validate_metainfo_fields_empty_host() {
+        let builder = TorrentBuilder::new("dir/", 42)
+            .set_nodes(vec![("127.0.0.1".to_owned(), 6881), ("".to_owned(), 6881)]);
+
+        match builder.validate_metainfo_fields() {
+            Err(Error(ErrorKind::TorrentBuilderFailure(m), _)) => assert_eq!(
+                m,
+                "TorrentBuilder has `nodes` but it contains a 0-length host."
+            ),
+            _ => assert!(false),
+        }
+    }
+
     #[test]
     fn validate_name_ok() {
         let builder = TorrentBuilder::new("dir/", 42).set_name("sample".to_owned());
@@ -909,6 +2657,20 @@ mod torrent_builder_tests {
         }
     }
 
+    #[test]
+    fn validate_path_include_hidden() {
+        let mut path = PathBuf::from(".").canonicalize().unwrap();
+        path.push("tests/files/.hidden");
+        let builder = TorrentBuilder::new(&path, 42).set_include_hidden(true);
+
+        builder.validate_path().unwrap();
+        // validation methods should not modify builder
+        assert_eq!(
+            builder,
+            TorrentBuilder::new(path, 42).set_include_hidden(true)
+        );
+    }
+
     #[test]
     fn validate_path_not_absolute() {
         let builder = TorrentBuilder::new("target/", 42);
@@ -955,6 +2717,102 @@ mod torrent_builder_tests {
         }
     }
 
+    #[test]
+    fn validate_piece_length_hybrid_ok() {
+        let builder = TorrentBuilder::new("dir/", 16384).set_hybrid(true);
+
+        builder.validate_piece_length().unwrap();
+        // validation methods should not modify builder
+        assert_eq!(builder, TorrentBuilder::new("dir/", 16384).set_hybrid(true));
+    }
+
+    #[test]
+    fn validate_piece_length_hybrid_too_small() {
+        let builder = TorrentBuilder::new("dir/", 8192).set_hybrid(true);
+
+        match builder.validate_piece_length() {
+            Err(Error(ErrorKind::TorrentBuilderFailure(m), _)) => assert_eq!(
+                m,
+                "TorrentBuilder is hybrid but has `piece_length` < 16384."
+            ),
+            _ => assert!(false),
+        }
+    }
+
+    #[test]
+    fn validate_globs_ok() {
+        let builder = TorrentBuilder::new("dir/", 42)
+            .add_include_glob("*.mkv".to_owned())
+            .add_exclude_glob("[abc]?.part".to_owned());
+
+        builder.validate_globs().unwrap();
+        // validation methods should not modify builder
+        assert_eq!(
+            builder,
+            TorrentBuilder::new("dir/", 42)
+                .add_include_glob("*.mkv".to_owned())
+                .add_exclude_glob("[abc]?.part".to_owned())
+        );
+    }
+
+    #[test]
+    fn validate_globs_invalid() {
+        let builder = TorrentBuilder::new("dir/", 42).add_exclude_glob("[abc".to_owned());
+
+        match builder.validate_globs() {
+            Err(Error(ErrorKind::TorrentBuilderFailure(m), _)) => {
+                assert_eq!(m, "TorrentBuilder has an invalid glob pattern [[abc].");
+            }
+            _ => assert!(false),
+        }
+    }
+
+    #[test]
+    fn validate_max_auto_piece_length_ok() {
+        let builder = TorrentBuilder::new("dir/", 42).set_auto_piece_length(Some(16384));
+
+        builder.validate_max_auto_piece_length().unwrap();
+        // validation methods should not modify builder
+        assert_eq!(
+            builder,
+            TorrentBuilder::new("dir/", 42).set_auto_piece_length(Some(16384))
+        );
+    }
+
+    #[test]
+    fn validate_max_auto_piece_length_none() {
+        let builder = TorrentBuilder::new("dir/", 42);
+
+        builder.validate_max_auto_piece_length().unwrap();
+        // validation methods should not modify builder
+        assert_eq!(builder, TorrentBuilder::new("dir/", 42));
+    }
+
+    #[test]
+    fn validate_max_auto_piece_length_too_small() {
+        let builder = TorrentBuilder::new("dir/", 42).set_auto_piece_length(Some(8192));
+
+        match builder.validate_max_auto_piece_length() {
+            Err(Error(ErrorKind::TorrentBuilderFailure(m), _)) => {
+                assert_eq!(m, "TorrentBuilder has `max_auto_piece_length` < 16384.");
+            }
+            _ => assert!(false),
+        }
+    }
+
+    #[test]
+    fn validate_max_auto_piece_length_not_power_of_two() {
+        let builder = TorrentBuilder::new("dir/", 42).set_auto_piece_length(Some(20000));
+
+        match builder.validate_max_auto_piece_length() {
+            Err(Error(ErrorKind::TorrentBuilderFailure(m), _)) => assert_eq!(
+                m,
+                "TorrentBuilder has `max_auto_piece_length` that is not a power of 2."
+            ),
+            _ => assert!(false),
+        }
+    }
+
     #[test]
     fn validate_extra_fields_ok() {
         let builder = TorrentBuilder::new("target/", 42)
@@ -1032,7 +2890,7 @@ mod torrent_builder_tests {
     #[test]
     fn read_file_ok() {
         // byte_sequence contains 256 bytes ranging from 0x0 to 0xff
-        let (length, pieces) = TorrentBuilder::read_file("tests/files/byte_sequence", 64).unwrap();
+        let (length, pieces) = read_file("tests/files/byte_sequence", 64, 1).unwrap();
         assert_eq!(length, 256);
         assert_eq!(
             pieces,
@@ -1056,4 +2914,173 @@ mod torrent_builder_tests {
             ]
         );
     }
+
+    #[test]
+    fn read_file_parallel() {
+        let (length, pieces) = read_file("tests/files/byte_sequence", 16, 1).unwrap();
+        for num_threads in 2..6 {
+            assert_eq!(
+                read_file("tests/files/byte_sequence", 16, num_threads).unwrap(),
+                (length, pieces.clone()),
+            );
+        }
+    }
+
+    #[test]
+    fn choose_piece_length_min() {
+        let max = util::i64_to_u64(DEFAULT_MAX_PIECE_LENGTH).unwrap();
+        assert_eq!(TorrentBuilder::choose_piece_length(0, max).unwrap(), 16384);
+        assert_eq!(
+            TorrentBuilder::choose_piece_length(2048 * 16384, max).unwrap(),
+            16384
+        );
+    }
+
+    #[test]
+    fn choose_piece_length_ok() {
+        let max = util::i64_to_u64(DEFAULT_MAX_PIECE_LENGTH).unwrap();
+        assert_eq!(
+            TorrentBuilder::choose_piece_length(2048 * 16384 + 1, max).unwrap(),
+            32768
+        );
+        // 4 GiB
+        assert_eq!(
+            TorrentBuilder::choose_piece_length(4 << 30, max).unwrap(),
+            2 << 20
+        );
+        // 100 GiB
+        assert_eq!(
+            TorrentBuilder::choose_piece_length(100 << 30, max).unwrap(),
+            16 << 20
+        );
+    }
+
+    #[test]
+    fn choose_piece_length_max() {
+        // 1 TiB
+        assert_eq!(
+            TorrentBuilder::choose_piece_length(1 << 40, 4 << 20).unwrap(),
+            4 << 20
+        );
+        assert_eq!(
+            TorrentBuilder::choose_piece_length(1 << 40, 16384).unwrap(),
+            16384
+        );
+    }
+
+    #[test]
+    fn progress_tracker_ok() {
+        let mut reported = Vec::new();
+        {
+            let mut callback = |progress: &BuildProgress| reported.push(progress.clone());
+            let mut tracker = ProgressTracker {
+                progress: Default::default(),
+                callback: &mut callback,
+                cancellation_token: None,
+            };
+            tracker.start(6, 2).unwrap();
+            tracker.set_current_file(Path::new("file"));
+            tracker.piece_hashed(4).unwrap();
+            tracker.piece_hashed(2).unwrap();
+        }
+
+        assert_eq!(
+            reported,
+            vec![
+                BuildProgress {
+                    bytes_hashed: 0,
+                    bytes_total: 6,
+                    current_file: PathBuf::new(),
+                    pieces_done: 0,
+                    pieces_total: 2,
+                },
+                BuildProgress {
+                    bytes_hashed: 4,
+                    bytes_total: 6,
+                    current_file: PathBuf::from("file"),
+                    pieces_done: 1,
+                    pieces_total: 2,
+                },
+                BuildProgress {
+                    bytes_hashed: 6,
+                    bytes_total: 6,
+                    current_file: PathBuf::from("file"),
+                    pieces_done: 2,
+                    pieces_total: 2,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn progress_tracker_cancelled() {
+        let token = CancellationToken::new();
+        let mut callback = |_: &BuildProgress| ();
+        let mut tracker = ProgressTracker {
+            progress: Default::default(),
+            callback: &mut callback,
+            cancellation_token: Some(&token),
+        };
+        tracker.start(6, 2).unwrap();
+        tracker.piece_hashed(4).unwrap();
+
+        token.clone().cancel();
+        match tracker.piece_hashed(2) {
+            Err(Error(ErrorKind::TorrentBuilderCancelled, _)) => (),
+            _ => assert!(false),
+        }
+    }
+
+    #[test]
+    fn insert_into_file_tree_ok() {
+        let file = v2::File {
+            length: 0,
+            pieces_root: None,
+            extra_fields: None,
+        };
+        let mut tree = BTreeMap::new();
+        TorrentBuilder::insert_into_file_tree(
+            &mut tree,
+            &["a".to_owned(), "b".to_owned()],
+            file.clone(),
+        )
+        .unwrap();
+        TorrentBuilder::insert_into_file_tree(&mut tree, &["c".to_owned()], file.clone()).unwrap();
+
+        let mut dir = BTreeMap::new();
+        dir.insert("b".to_owned(), v2::FileTree::File(file.clone()));
+        let mut expected = BTreeMap::new();
+        expected.insert("a".to_owned(), v2::FileTree::Dir(dir));
+        expected.insert("c".to_owned(), v2::FileTree::File(file));
+        assert_eq!(tree, expected);
+    }
+    #[test]
+    fn insert_into_file_tree_file_is_dir() {
+        let file = v2::File {
+            length: 0,
+            pieces_root: None,
+            extra_fields: None,
+        };
+        let mut tree = BTreeMap::new();
+        TorrentBuilder::insert_into_file_tree(&mut tree, &["a".to_owned()], file.clone()).unwrap();
+
+        match TorrentBuilder::insert_into_file_tree(
+            &mut tree,
+            &["a".to_owned(), "b".to_owned()],
+            file.clone(),
+        ) {
+            Err(Error(ErrorKind::TorrentBuilderFailure(m), _)) => assert_eq!(
+                m,
+                r#"Path component "a" is both a file and a dir in "file tree"."#
+            ),
+            _ => assert!(false),
+        }
+        match TorrentBuilder::insert_into_file_tree(&mut tree, &["a".to_owned()], file) {
+            Err(Error(ErrorKind::TorrentBuilderFailure(m), _)) => assert_eq!(
+                m,
+                r#"Path component "a" appears in "file tree" more than once."#
+            ),
+            _ => assert!(false),
+        }
+    }
 }
@@ -4,22 +4,37 @@
 use bencode::BencodeElem;
 use error::*;
 use itertools::Itertools;
+use magnet::{MagnetLink, MagnetLinkOptions};
 use sha1::{Digest, Sha1};
+use sha2::Sha256;
 use std::borrow::Cow;
+use std::cmp;
 use std::collections::HashMap;
 use std::fmt;
-use std::path::{Path, PathBuf};
+use std::path::{Component, Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use torrent::{is_raw_info_current, InfoHashSource};
+use util;
 
 mod build;
 mod read;
+mod verify;
 mod write;
 
 const PIECE_STRING_LENGTH: usize = 20;
 
+/// A reasonable maximum for [`TorrentBuilder::set_auto_piece_length()`] (16 MiB).
+///
+/// [`TorrentBuilder::set_auto_piece_length()`]: struct.TorrentBuilder.html#method.set_auto_piece_length
+pub const DEFAULT_MAX_PIECE_LENGTH: Integer = 16 * 1024 * 1024;
+
 /// Corresponds to a bencode dictionary.
 pub type Dictionary = HashMap<String, BencodeElem>;
 /// Corresponds to the `announce-list` in [BEP 12](http://bittorrent.org/beps/bep_0012.html).
 pub type AnnounceList = Vec<Vec<String>>;
+/// A DHT node (`host`, `port`) in `nodes`, as defined in [BEP 5](http://bittorrent.org/beps/bep_0005.html).
+pub type Node = (String, u16);
 /// A piece in `pieces`--the SHA1 hash of a torrent block.
 pub type Piece = Vec<u8>;
 /// Corresponds to a bencode integer. The underlying type is `i64`.
@@ -31,34 +46,98 @@ pub type Integer = i64;
 /// A file contained in a torrent.
 ///
 /// Modeled after the specifications
-/// in [BEP 3](http://bittorrent.org/beps/bep_0003.html). Unknown/extension
+/// in [BEP 3](http://bittorrent.org/beps/bep_0003.html) and
+/// [BEP 47](http://bittorrent.org/beps/bep_0047.html). Unknown/extension
 /// fields will be placed in `extra_fields`. If you need
 /// any of those extra fields you would have to parse it yourself.
 #[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct File {
     /// File size in bytes.
     pub length: Integer,
     /// File path, relative to [`Torrent`](struct.Torrent.html)'s `name` field.
     pub path: PathBuf,
-    /// Fields not defined in [BEP 3](http://bittorrent.org/beps/bep_0003.html).
+    /// File attributes (`attr`) as defined in [BEP 47](http://bittorrent.org/beps/bep_0047.html).
+    pub attr: Option<FileAttributes>,
+    /// SHA1 hash of the file's content as defined in
+    /// [BEP 47](http://bittorrent.org/beps/bep_0047.html).
+    #[cfg_attr(feature = "serde", serde(default, with = "::serde_util::option_bytes"))]
+    pub sha1: Option<Vec<u8>>,
+    /// Target of the symbolic link (`symlink path`), relative to
+    /// [`Torrent`](struct.Torrent.html)'s `name` field, as defined in
+    /// [BEP 47](http://bittorrent.org/beps/bep_0047.html). It should be `Some`
+    /// only if `attr` contains `l`.
+    pub symlink_path: Option<PathBuf>,
+    /// Fields not defined in [BEP 3](http://bittorrent.org/beps/bep_0003.html)
+    /// or [BEP 47](http://bittorrent.org/beps/bep_0047.html).
     pub extra_fields: Option<Dictionary>,
 }
 
+/// File attributes (`attr`) as defined in [BEP 47](http://bittorrent.org/beps/bep_0047.html).
+///
+/// `attr` is a string where each character is an attribute. It can be
+/// parsed with `FileAttributes::from()` and formatted with `to_string()`.
+#[derive(Clone, Debug, Default, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct FileAttributes {
+    /// `p`: the file is a padding file, which contains only zeros.
+    pub padding: bool,
+    /// `x`: the file is executable.
+    pub executable: bool,
+    /// `h`: the file is hidden.
+    pub hidden: bool,
+    /// `l`: the file is a symbolic link. Its target is in `symlink_path`.
+    pub symlink: bool,
+    /// Characters not defined in [BEP 47](http://bittorrent.org/beps/bep_0047.html).
+    pub unknown: String,
+    /// The `attr` string these attributes are parsed from, or `None` if they
+    /// are not parsed (e.g. they are created by `TorrentBuilder`).
+    ///
+    /// `to_string()` returns this string as-is as long as it agrees with the
+    /// fields above, so that the characters keep their original order (which
+    /// matters for the info hash). Otherwise the string is rebuilt.
+    ///
+    /// `raw` is not compared by `==`, so attributes parsed from
+    /// `"xh"` and `"hx"` are equal.
+    pub raw: Option<String>,
+}
+
 /// Everything found in a *.torrent* file.
 ///
 /// Modeled after the specifications
-/// in [BEP 3](http://bittorrent.org/beps/bep_0003.html) and
-/// [BEP 12](http://bittorrent.org/beps/bep_0012.html). Unknown/extension
+/// in [BEP 3](http://bittorrent.org/beps/bep_0003.html),
+/// [BEP 5](http://bittorrent.org/beps/bep_0005.html),
+/// [BEP 12](http://bittorrent.org/beps/bep_0012.html),
+/// [BEP 17](http://bittorrent.org/beps/bep_0017.html) and
+/// [BEP 19](http://bittorrent.org/beps/bep_0019.html). Unknown/extension
 /// fields will be placed in `extra_fields` (if the unknown
 /// fields are found in the `info` dictionary then they are placed in
 /// `extra_info_fields`). If you need any of those extra fields you would
 /// have to parse it yourself.
-#[derive(Clone, Debug, Eq, PartialEq)]
+#[derive(Clone, Debug, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Torrent {
     /// URL of the torrent's tracker.
     pub announce: Option<String>,
     /// Announce list as defined in [BEP 12](http://bittorrent.org/beps/bep_0012.html).
     pub announce_list: Option<AnnounceList>,
+    /// Web seeds (`url-list`) as defined in [BEP 19](http://bittorrent.org/beps/bep_0019.html).
+    pub url_list: Option<Vec<String>>,
+    /// HTTP seeds (`httpseeds`) as defined in [BEP 17](http://bittorrent.org/beps/bep_0017.html).
+    pub http_seeds: Option<Vec<String>>,
+    /// Creation time of the torrent (`creation date`), in standard UNIX epoch format.
+    pub creation_date: Option<Integer>,
+    /// Free-form textual comment of the author.
+    pub comment: Option<String>,
+    /// Name and version of the program used to create the torrent (`created by`).
+    pub created_by: Option<String>,
+    /// String encoding used in the `info` dictionary (e.g. `UTF-8`).
+    pub encoding: Option<String>,
+    /// DHT nodes for bootstrapping as defined in [BEP 5](http://bittorrent.org/beps/bep_0005.html).
+    pub nodes: Option<Vec<Node>>,
+    /// Source of the torrent (`source` in `info`), commonly set by private
+    /// trackers so that the info hash differs from other trackers'.
+    pub source: Option<String>,
     /// Total torrent size in bytes (i.e. sum of all files' sizes).
     pub length: Integer,
     /// If the torrent contains only 1 file then `files` is `None`.
@@ -69,11 +148,31 @@ pub struct Torrent {
     /// Block size in bytes.
     pub piece_length: Integer,
     /// SHA1 hashs of each block.
+    #[cfg_attr(feature = "serde", serde(with = "::serde_util::pieces"))]
     pub pieces: Vec<Piece>,
-    /// Top-level fields not defined in [BEP 3](http://bittorrent.org/beps/bep_0003.html).
+    /// Top-level fields not listed above.
     pub extra_fields: Option<Dictionary>,
-    /// Fields in `info` not defined in [BEP 3](http://bittorrent.org/beps/bep_0003.html).
+    /// Fields in `info` not listed above.
     pub extra_info_fields: Option<Dictionary>,
+    /// The original bytes of the `info` dict, as found in the parsed
+    /// *.torrent* file. It is `None` if this `Torrent` is not parsed
+    /// from bytes (e.g. it is created by `TorrentBuilder`).
+    ///
+    /// These bytes are used to calculate the info hash, and are written
+    /// verbatim by `write_into()`, as long as they still match the fields
+    /// that belong to `info`. Once any of those fields is modified, the
+    /// `info` dict is re-encoded from the fields instead.
+    #[cfg_attr(feature = "serde", serde(default, with = "::serde_util::option_bytes"))]
+    pub raw_info: Option<Vec<u8>>,
+    /// Whether `url-list` is a single string (instead of a list) in the
+    /// parsed *.torrent* file. If so, `write_into()` keeps that form as
+    /// long as `url_list` has at most 1 url. An empty `url_list` is then
+    /// written as an empty string.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub url_list_is_string: bool,
+    /// Same as `url_list_is_string`, but for `httpseeds`.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub http_seeds_is_string: bool,
 }
 
 /// Builder for creating `Torrent`s from files.
@@ -90,48 +189,293 @@ pub struct Torrent {
 ///  (e.g. [`set_announce()`]). Fields can be updated in the same way.
 ///
 /// # Notes
-/// **\*nix hidden files/dirs are ignored.**
-///
-/// Reasoning:
-/// when handling these special "files", there are many decisions to make:
-/// - Should they be ignored, included, or selectively ignored/included?
-/// - Should included/ignored entries be marked specially (e.g. [BEP 47])?
-/// - Should users be allowed to configure the settings?
-/// - If users can configure the settings, what would be the ideal defaults?
-/// - ...
+/// **\*nix hidden files/dirs are ignored by default.**
 ///
-/// Apparently it's not easy to make these decisions.
-/// Therefore these files are ignored for now.
 /// Clients like Deluge and qBittorrent also ignore hidden entries.
+/// They can be included with [`set_include_hidden()`]. Entries can also be
+/// filtered with [`add_include_glob()`] and [`add_exclude_glob()`], and
+/// symbolic links are handled according to [`set_symlink_policy()`].
 ///
 /// [`Torrent::read_from_file()`]: struct.Torrent.html#method.read_from_file
 /// [`Torrent::read_from_bytes()`]: struct.Torrent.html#method.read_from_bytes
 /// [`new()`]: #method.new
 /// [`set_announce()`]: #method.set_announce
-/// [BEP 47]: http://bittorrent.org/beps/bep_0047.html
+/// [`set_include_hidden()`]: #method.set_include_hidden
+/// [`add_include_glob()`]: #method.add_include_glob
+/// [`add_exclude_glob()`]: #method.add_exclude_glob
+/// [`set_symlink_policy()`]: #method.set_symlink_policy
 #[derive(Clone, Debug, Default, Eq, PartialEq)]
 pub struct TorrentBuilder {
     announce: Option<String>,
     announce_list: Option<AnnounceList>,
+    url_list: Option<Vec<String>>,
+    http_seeds: Option<Vec<String>>,
+    creation_date: Option<Integer>,
+    comment: Option<String>,
+    created_by: Option<String>,
+    encoding: Option<String>,
+    nodes: Option<Vec<Node>>,
+    source: Option<String>,
     name: Option<String>,
     path: PathBuf,
     piece_length: Integer,
     extra_fields: Option<Dictionary>,
     extra_info_fields: Option<Dictionary>,
     is_private: bool,
+    is_hybrid: bool,
+    pad_files: bool,
+    num_threads: usize,
+    cancellation_token: Option<CancellationToken>,
+    max_auto_piece_length: Option<Integer>,
+    include_hidden: bool,
+    include_globs: Vec<String>,
+    exclude_globs: Vec<String>,
+    symlink_policy: SymlinkPolicy,
+}
+
+/// How [`TorrentBuilder`] handles symbolic links found in a directory.
+///
+/// [`TorrentBuilder`]: struct.TorrentBuilder.html
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum SymlinkPolicy {
+    /// Follow symbolic links, i.e. treat them as the files/dirs they point to.
+    /// This is the default.
+    #[default]
+    Follow,
+    /// Ignore symbolic links.
+    Skip,
+    /// Record symbolic links as empty files with `attr` set to `l` and
+    /// `symlink path` set to their targets, as defined in [BEP 47].
+    /// Their targets must be inside the root directory.
+    ///
+    /// [BEP 47]: http://bittorrent.org/beps/bep_0047.html
+    Record,
+}
+
+/// Progress of a [`TorrentBuilder`] that is building a `Torrent`.
+///
+/// It is passed to the callback given to [`build_with_progress()`].
+///
+/// [`TorrentBuilder`]: struct.TorrentBuilder.html
+/// [`build_with_progress()`]: struct.TorrentBuilder.html#method.build_with_progress
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct BuildProgress {
+    /// Number of bytes hashed so far. Padding is not included.
+    pub bytes_hashed: u64,
+    /// Total number of bytes to hash. Padding is not included.
+    pub bytes_total: u64,
+    /// Path of the file being read.
+    pub current_file: PathBuf,
+    /// Number of pieces hashed so far.
+    pub pieces_done: usize,
+    /// Total number of pieces to hash.
+    pub pieces_total: usize,
+}
+
+/// A token for cancelling a [`TorrentBuilder`] that is building a `Torrent`.
+///
+/// Clones of a token share the same state, so a clone can be
+/// given to the builder with [`set_cancellation_token()`] while the original
+/// is kept (e.g. by another thread) to call [`cancel()`].
+///
+/// [`TorrentBuilder`]: struct.TorrentBuilder.html
+/// [`set_cancellation_token()`]: struct.TorrentBuilder.html#method.set_cancellation_token
+/// [`cancel()`]: #method.cancel
+#[derive(Clone, Debug, Default)]
+pub struct CancellationToken(Arc<AtomicBool>);
+
+/// Status of a piece or a file, as reported by [`Torrent::verify()`].
+///
+/// [`Torrent::verify()`]: struct.Torrent.html#method.verify
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum VerificationStatus {
+    /// The content on disk matches the torrent.
+    Ok,
+    /// The content on disk is complete, but its hash does not match.
+    Mismatch,
+    /// A file does not exist on disk.
+    Missing,
+    /// A file on disk is shorter than its `length`.
+    Short,
+    /// A file cannot be checked, because every failed piece that covers it
+    /// also covers another file (e.g. one that is `Missing`). Only used for files.
+    Unknown,
+}
+
+/// Result of verifying content on disk against a `Torrent`.
+///
+/// It is returned by [`Torrent::verify()`].
+///
+/// [`Torrent::verify()`]: struct.Torrent.html#method.verify
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct VerificationReport {
+    /// Status of each piece, in the same order as the torrent's `pieces`.
+    pub pieces: Vec<VerificationStatus>,
+    /// Status of each file, in the same order as the torrent's `files`.
+    /// For a single-file torrent it contains exactly 1 element.
+    pub files: Vec<VerificationStatus>,
+}
+
+/// A contiguous range of bytes in a file that is covered by a piece.
+///
+/// It is returned by [`Torrent::piece_segments()`].
+///
+/// [`Torrent::piece_segments()`]: struct.Torrent.html#method.piece_segments
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct FileSegment {
+    /// Index of the file in `files` (`0` for single-file torrents).
+    pub file_index: usize,
+    /// Offset of the range within the file.
+    pub offset: Integer,
+    /// Length of the range in bytes.
+    pub length: Integer,
+}
+
+/// The pieces that cover a file.
+///
+/// It is returned by [`Torrent::file_pieces()`].
+///
+/// [`Torrent::file_pieces()`]: struct.Torrent.html#method.file_pieces
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct FilePieces {
+    /// Index of the first piece that covers the file.
+    pub first_piece: usize,
+    /// Index of the last piece that covers the file.
+    pub last_piece: usize,
+    /// Offset of the file's first byte within the first piece.
+    pub first_piece_offset: Integer,
+    /// Offset within the last piece where the file ends (exclusive),
+    /// i.e. the # of bytes in the last piece that precede the file's end.
+    pub last_piece_end: Integer,
+}
+
+impl CancellationToken {
+    /// Create a new `CancellationToken` that is not cancelled.
+    pub fn new() -> CancellationToken {
+        Default::default()
+    }
+
+    /// Cancel the building of the `Torrent`.
+    ///
+    /// The builder checks the token between pieces, so it may hash
+    /// a few more pieces before returning `Err`.
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::SeqCst);
+    }
+
+    /// Check if `cancel()` has been called on this token (or any of its clones).
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::SeqCst)
+    }
+}
+
+// tokens are equal if they share the same state
+impl PartialEq for CancellationToken {
+    fn eq(&self, other: &CancellationToken) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl Eq for CancellationToken {}
+
+impl<'a> From<&'a str> for FileAttributes {
+    fn from(attr: &'a str) -> FileAttributes {
+        let mut attributes = FileAttributes::default();
+        for c in attr.chars() {
+            match c {
+                'p' => attributes.padding = true,
+                'x' => attributes.executable = true,
+                'h' => attributes.hidden = true,
+                'l' => attributes.symlink = true,
+                _ => attributes.unknown.push(c),
+            }
+        }
+        attributes.raw = Some(attr.to_owned());
+        attributes
+    }
+}
+
+// `raw` is left out, as it only records how the attributes are ordered.
+impl PartialEq for FileAttributes {
+    fn eq(&self, other: &FileAttributes) -> bool {
+        (self.padding == other.padding)
+            && (self.executable == other.executable)
+            && (self.hidden == other.hidden)
+            && (self.symlink == other.symlink)
+            && (self.unknown == other.unknown)
+    }
+}
+
+impl fmt::Display for FileAttributes {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if let Some(ref raw) = self.raw {
+            // `==` ignores `raw`, so this checks the flags only
+            if FileAttributes::from(raw.as_str()) == *self {
+                return write!(f, "{}", raw);
+            }
+        }
+
+        for &(is_set, c) in &[
+            (self.padding, 'p'),
+            (self.executable, 'x'),
+            (self.hidden, 'h'),
+            (self.symlink, 'l'),
+        ] {
+            if is_set {
+                write!(f, "{}", c)?;
+            }
+        }
+        write!(f, "{}", self.unknown)
+    }
 }
 
 impl File {
+    /// Check if this file is a padding file (i.e. `attr` contains `p`)
+    /// as defined in [BEP 47](http://bittorrent.org/beps/bep_0047.html).
+    pub fn is_padding(&self) -> bool {
+        self.attr.as_ref().is_some_and(|attr| attr.padding)
+    }
+
+    /// Check if this file is a symbolic link (i.e. `attr` contains `l`)
+    /// as defined in [BEP 47](http://bittorrent.org/beps/bep_0047.html).
+    pub fn is_symlink(&self) -> bool {
+        self.attr.as_ref().is_some_and(|attr| attr.symlink)
+    }
+
     /// Construct the `File`'s absolute path using `parent`.
     ///
     /// Caller has to ensure that `parent` is an absolute path.
     /// Otherwise an error would be returned.
     ///
     /// This method effectively appends/joins `self.path` to `parent`.
+    /// Since `self.path` usually comes from a *.torrent* file, an error
+    /// would be returned if it is empty or has any component that is not
+    /// a normal one (e.g. `..` or `/`), so that the result is always
+    /// inside `parent`.
+    ///
+    /// NOTE: **In `0.5.0` and earlier, `self.path` was joined as-is. Now such paths
+    /// return `Err(ErrorKind::InvalidArgument)`, e.g. when verifying content
+    /// with [`Torrent::verify()`].**
+    ///
+    /// [`Torrent::verify()`]: struct.Torrent.html#method.verify
     pub fn absolute_path<P>(&self, parent: P) -> Result<PathBuf>
     where
         P: AsRef<Path>,
     {
+        // e.g. "/etc/passwd" or "a/../../x" would escape `parent`
+        if self.path.as_os_str().is_empty()
+            || !self
+                .path
+                .components()
+                .all(|component| matches!(component, Component::Normal(_)))
+        {
+            bail!(ErrorKind::InvalidArgument(Cow::Borrowed(
+                "File path is empty or contains components that are not normal."
+            )))
+        }
+
         let result = parent.as_ref().join(&self.path);
         if result.is_absolute() {
             Ok(result)
@@ -185,6 +529,9 @@ impl Torrent {
             "pieces".to_owned(),
             BencodeElem::Bytes(self.pieces.clone().into_iter().flatten().collect()),
         );
+        if let Some(ref source) = self.source {
+            info.insert("source".to_owned(), BencodeElem::String(source.clone()));
+        }
 
         if let Some(ref extra_info_fields) = self.extra_info_fields {
             info.extend(extra_info_fields.clone());
@@ -196,23 +543,64 @@ impl Torrent {
     /// Calculate the `Torrent`'s info hash as defined in
     /// [BEP 3](http://bittorrent.org/beps/bep_0003.html).
     ///
+    /// The original bytes of `info` (i.e. `raw_info`) are hashed if
+    /// they are available and still match the fields in `info`. Otherwise the re-encoded `info` dict is hashed.
+    /// To choose explicitly, use [`info_hash_from()`].
+    ///
     /// Note that the calculated info hash is not cached.
     /// So if this method is called multiple times, multiple
     /// calculations will be performed. To avoid that, the
     /// caller should cache the return value as needed.
+    ///
+    /// [`info_hash_from()`]: #method.info_hash_from
     pub fn info_hash(&self) -> String {
-        format!("{:x}", Sha1::digest(&self.construct_info().encode()))
+        self.info_hash_from(InfoHashSource::Original)
     }
 
     /// Calculate the `Torrent`'s info hash as defined in
     /// [BEP 3](http://bittorrent.org/beps/bep_0003.html).
     ///
+    /// The original bytes of `info` (i.e. `raw_info`) are hashed if
+    /// they are available and still match the fields in `info`. Otherwise the re-encoded `info` dict is hashed.
+    /// To choose explicitly, use [`info_hash_bytes_from()`].
+    ///
     /// Note that the calculated info hash is not cached.
     /// So if this method is called multiple times, multiple
     /// calculations will be performed. To avoid that, the
     /// caller should cache the return value as needed.
+    ///
+    /// [`info_hash_bytes_from()`]: #method.info_hash_bytes_from
     pub fn info_hash_bytes(&self) -> Vec<u8> {
-        Sha1::digest(&self.construct_info().encode()).to_vec()
+        self.info_hash_bytes_from(InfoHashSource::Original)
+    }
+
+    /// Calculate the `Torrent`'s info hash by hashing the
+    /// bytes specified by `source`.
+    ///
+    /// Note that the calculated info hash is not cached.
+    pub fn info_hash_from(&self, source: InfoHashSource) -> String {
+        format!("{:x}", Sha1::digest(&self.info_bytes(source)))
+    }
+
+    /// Calculate the `Torrent`'s info hash by hashing the
+    /// bytes specified by `source`.
+    ///
+    /// Note that the calculated info hash is not cached.
+    pub fn info_hash_bytes_from(&self, source: InfoHashSource) -> Vec<u8> {
+        Sha1::digest(&self.info_bytes(source)).to_vec()
+    }
+
+    // bytes of the `info` dict to be hashed
+    fn info_bytes(&self, source: InfoHashSource) -> Cow<'_, [u8]> {
+        let reencoded = self.construct_info().encode();
+        match (source, &self.raw_info) {
+            (InfoHashSource::Original, Some(raw_info))
+                if is_raw_info_current(raw_info, &reencoded) =>
+            {
+                Cow::Borrowed(raw_info)
+            }
+            _ => Cow::Owned(reencoded),
+        }
     }
 
     /// Calculate the `Torrent`'s magnet link as defined in
@@ -230,28 +618,244 @@ impl Torrent {
     /// If neither `self.announce` nor `self.announce_list` is present, the output
     /// won't contain any `tr` parameter.
     ///
-    /// The `x.pe` parameter (for peer addresses) is currently not supported.
+    /// Each web seed in `self.url_list` is included as a `ws` parameter.
+    ///
+    /// All values are percent-encoded. To include other parameters
+    /// (e.g. `x.pe`) use [`magnet_link_with()`] instead.
+    ///
+    /// [`magnet_link_with()`]: #method.magnet_link_with
     pub fn magnet_link(&self) -> String {
-        if let Some(ref list) = self.announce_list {
-            format!(
-                "magnet:?xt=urn:btih:{}&dn={}{}",
-                self.info_hash(),
-                self.name,
-                list.iter().format_with("", |tier, f| f(&format_args!(
-                    "{}",
-                    tier.iter()
-                        .format_with("", |url, f| f(&format_args!("&tr={}", url)))
-                ))),
-            )
+        self.magnet_link_with(&MagnetLinkOptions::new().set_web_seeds(true))
+    }
+
+    /// Calculate the `Torrent`'s magnet link with the parameters
+    /// specified in `options`.
+    ///
+    /// `xt`, `dn`, and `tr` are set in the same way as [`magnet_link()`].
+    /// Depending on `options`, the following parameters might be included as well:
+    /// - `xt=urn:btmh:`: the v2 info hash, only if this torrent is hybrid
+    ///   (i.e. `meta version` is `2`).
+    /// - `xl`: the length of the content, i.e. [`content_length()`]
+    ///   (padding files are excluded).
+    /// - `ws`: web seeds in `self.url_list`.
+    /// - `x.pe`: peer addresses.
+    /// - `so`: indices of the files to download.
+    ///
+    /// All values are percent-encoded.
+    ///
+    /// [`magnet_link()`]: #method.magnet_link
+    /// [`content_length()`]: #method.content_length
+    pub fn magnet_link_with(&self, options: &MagnetLinkOptions) -> String {
+        let trackers = if let Some(ref list) = self.announce_list {
+            list.iter().flatten().cloned().collect()
         } else if let Some(ref announce) = self.announce {
-            format!(
-                "magnet:?xt=urn:btih:{}&dn={}&tr={}",
-                self.info_hash(),
-                self.name,
-                announce,
-            )
+            vec![announce.clone()]
+        } else {
+            Vec::new()
+        };
+
+        MagnetLink {
+            info_hash: Some(self.info_hash_bytes()),
+            info_hash_v2: if options.include_info_hash_v2 {
+                self.info_hash_v2_bytes()
+            } else {
+                None
+            },
+            name: Some(self.name.clone()),
+            length: if options.include_length {
+                Some(self.content_length())
+            } else {
+                None
+            },
+            trackers,
+            web_seeds: match self.url_list {
+                Some(ref url_list) if options.include_web_seeds => url_list.clone(),
+                _ => Vec::new(),
+            },
+            peers: options.peers.clone(),
+            select_only: options.select_only.clone(),
+            extra_params: Vec::new(),
+        }
+        .to_string()
+    }
+
+    // SHA256 hash of the `info` dict if this torrent is hybrid
+    fn info_hash_v2_bytes(&self) -> Option<Vec<u8>> {
+        match self
+            .extra_info_fields
+            .as_ref()
+            .and_then(|fields| fields.get("meta version"))
+        {
+            Some(&BencodeElem::Integer(2)) => {
+                Some(Sha256::digest(&self.info_bytes(InfoHashSource::Original)).to_vec())
+            }
+            _ => None,
+        }
+    }
+
+    /// Return the files in `files`, except padding files as defined in
+    /// [BEP 47](http://bittorrent.org/beps/bep_0047.html).
+    ///
+    /// An empty `Vec` is returned for single-file torrents.
+    pub fn content_files(&self) -> Vec<&File> {
+        match self.files {
+            Some(ref files) => files.iter().filter(|file| !file.is_padding()).collect(),
+            None => Vec::new(),
+        }
+    }
+
+    /// Return the total size of the torrent's content in bytes,
+    /// i.e. `length` minus the size of all padding files as defined in
+    /// [BEP 47](http://bittorrent.org/beps/bep_0047.html).
+    pub fn content_length(&self) -> Integer {
+        match self.files {
+            Some(ref files) => files
+                .iter()
+                .filter(|file| !file.is_padding())
+                .map(|file| file.length)
+                .sum(),
+            None => self.length,
+        }
+    }
+
+    /// Return the length of the piece at `index` in bytes.
+    ///
+    /// It is `piece_length` for all pieces except the last one, which
+    /// can be shorter.
+    ///
+    /// If `index` is out of range, or the piece starts at or after the end of
+    /// the content (i.e. `pieces` has more entries than `length` needs),
+    /// then `Err(InvalidArgument)` will be returned. If `piece_length <= 0`
+    /// (which can only happen with a malformed torrent), then
+    /// `Err(MalformedTorrent)` will be returned.
+    pub fn piece_length_at(&self, index: usize) -> Result<Integer> {
+        let (start, end) = self.piece_bounds(index)?;
+        Ok(end - start)
+    }
+
+    /// Return the ranges of bytes in files covered by the piece at `index`,
+    /// in the same order as the files appear in `files`.
+    ///
+    /// Empty files cover no piece and thus never appear in the result.
+    /// Padding files as defined in [BEP 47] are treated as regular files.
+    ///
+    /// If `index` is out of range, or the piece starts at or after the end of
+    /// the content, then `Err(InvalidArgument)` will be returned.
+    /// If `piece_length <= 0` or the files' lengths overflow (which can only
+    /// happen with a malformed torrent), then `Err(MalformedTorrent)` will be returned.
+    ///
+    /// [BEP 47]: http://bittorrent.org/beps/bep_0047.html
+    pub fn piece_segments(&self, index: usize) -> Result<Vec<FileSegment>> {
+        let (start, end) = self.piece_bounds(index)?;
+        let mut segments = Vec::new();
+        let mut file_start = 0;
+
+        for (file_index, length) in self.file_lengths().into_iter().enumerate() {
+            let file_end = Self::add_lengths(file_start, length)?;
+            if (length > 0) && (file_start < end) && (file_end > start) {
+                let segment_start = cmp::max(start, file_start);
+                segments.push(FileSegment {
+                    file_index,
+                    offset: segment_start - file_start,
+                    length: cmp::min(end, file_end) - segment_start,
+                });
+            }
+            if file_end >= end {
+                break;
+            }
+            file_start = file_end;
+        }
+
+        Ok(segments)
+    }
+
+    /// Return the pieces that cover the file at `file_index` in `files`
+    /// (`0` for single-file torrents).
+    ///
+    /// `None` is returned if the file is empty, as it covers no piece.
+    ///
+    /// If `file_index` is out of range, then `Err(InvalidArgument)` will be returned.
+    /// If `piece_length <= 0` or the file's end overflows (which can only happen
+    /// with a malformed torrent), then `Err(MalformedTorrent)` will be returned.
+    pub fn file_pieces(&self, file_index: usize) -> Result<Option<FilePieces>> {
+        let lengths = self.file_lengths();
+        if file_index >= lengths.len() {
+            bail!(ErrorKind::InvalidArgument(Cow::Borrowed(
+                "File index out of range."
+            )));
+        }
+
+        let length = lengths[file_index];
+        if length == 0 {
+            return Ok(None);
+        }
+
+        let piece_length = self.checked_piece_length()?;
+        let start = lengths[..file_index]
+            .iter()
+            .try_fold(0, |acc, &length| Self::add_lengths(acc, length))?;
+        let end = Self::add_lengths(start, length)?;
+        let last_piece = (end - 1) / piece_length;
+        Ok(Some(FilePieces {
+            first_piece: util::i64_to_usize(start / piece_length)?,
+            last_piece: util::i64_to_usize(last_piece)?,
+            first_piece_offset: start % piece_length,
+            last_piece_end: end - last_piece * piece_length,
+        }))
+    }
+
+    // Lengths of all files, including padding files. A single-file
+    // torrent is treated as a torrent with 1 file.
+    fn file_lengths(&self) -> Vec<Integer> {
+        match self.files {
+            Some(ref files) => files.iter().map(|file| file.length).collect(),
+            None => vec![self.length],
+        }
+    }
+
+    // Return the offsets of the first byte and the end (exclusive)
+    // of the piece at `index`.
+    fn piece_bounds(&self, index: usize) -> Result<(Integer, Integer)> {
+        if index >= self.pieces.len() {
+            bail!(ErrorKind::InvalidArgument(Cow::Borrowed(
+                "Piece index out of range."
+            )));
+        }
+
+        // `pieces` could have more entries than `length` needs,
+        // in which case the start could even overflow
+        let piece_length = self.checked_piece_length()?;
+        match util::usize_to_i64(index)?.checked_mul(piece_length) {
+            Some(start) if start < self.length => Ok((
+                start,
+                cmp::min(start.saturating_add(piece_length), self.length),
+            )),
+            _ => bail!(ErrorKind::InvalidArgument(Cow::Borrowed(
+                "Piece starts at or after the end of the content."
+            ))),
+        }
+    }
+
+    // `piece_length` is not validated when a `Torrent` is constructed
+    // manually, so it has to be checked before dividing by it.
+    fn checked_piece_length(&self) -> Result<Integer> {
+        if self.piece_length > 0 {
+            Ok(self.piece_length)
         } else {
-            format!("magnet:?xt=urn:btih:{}&dn={}", self.info_hash(), self.name,)
+            bail!(ErrorKind::MalformedTorrent(Cow::Borrowed(
+                r#""piece length" <= 0."#
+            )))
+        }
+    }
+
+    // Lengths are not validated when a `Torrent` is constructed
+    // manually, so adding them could overflow.
+    fn add_lengths(a: Integer, b: Integer) -> Result<Integer> {
+        match a.checked_add(b) {
+            Some(sum) => Ok(sum),
+            None => bail!(ErrorKind::MalformedTorrent(Cow::Borrowed(
+                "Torrent's length overflowed in i64."
+            ))),
         }
     }
 
@@ -283,6 +887,16 @@ impl fmt::Display for File {
             self.length
         )?;
 
+        if let Some(ref attr) = self.attr {
+            writeln!(f, "-attr: {}", attr)?;
+        }
+        if let Some(ref sha1) = self.sha1 {
+            writeln!(f, "-sha1: {}", util::encode_hex(sha1))?;
+        }
+        if let Some(ref symlink_path) = self.symlink_path {
+            writeln!(f, "-symlink path: {}", symlink_path.display())?;
+        }
+
         if let Some(ref fields) = self.extra_fields {
             write!(
                 f,
@@ -298,6 +912,31 @@ impl fmt::Display for File {
     }
 }
 
+// `raw_info`, `url_list_is_string` and `http_seeds_is_string` are left out,
+// as they are only by-products of parsing (e.g. a built `Torrent` should
+// equal its parsed copy).
+impl PartialEq for Torrent {
+    fn eq(&self, other: &Torrent) -> bool {
+        (self.announce == other.announce)
+            && (self.announce_list == other.announce_list)
+            && (self.url_list == other.url_list)
+            && (self.http_seeds == other.http_seeds)
+            && (self.creation_date == other.creation_date)
+            && (self.comment == other.comment)
+            && (self.created_by == other.created_by)
+            && (self.encoding == other.encoding)
+            && (self.nodes == other.nodes)
+            && (self.source == other.source)
+            && (self.length == other.length)
+            && (self.files == other.files)
+            && (self.name == other.name)
+            && (self.piece_length == other.piece_length)
+            && (self.pieces == other.pieces)
+            && (self.extra_fields == other.extra_fields)
+            && (self.extra_info_fields == other.extra_info_fields)
+    }
+}
+
 impl fmt::Display for Torrent {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         writeln!(f, "{}.torrent", self.name)?;
@@ -314,6 +953,39 @@ impl fmt::Display for Torrent {
                 )))
             )?;
         }
+        if let Some(ref urls) = self.url_list {
+            writeln!(f, "-url-list: [{}]", ::itertools::join(urls, ", "))?;
+        }
+        if let Some(ref urls) = self.http_seeds {
+            writeln!(f, "-httpseeds: [{}]", ::itertools::join(urls, ", "))?;
+        }
+        if let Some(creation_date) = self.creation_date {
+            writeln!(f, "-creation date: {}", creation_date)?;
+        }
+        if let Some(ref comment) = self.comment {
+            writeln!(f, "-comment: {}", comment)?;
+        }
+        if let Some(ref created_by) = self.created_by {
+            writeln!(f, "-created by: {}", created_by)?;
+        }
+        if let Some(ref encoding) = self.encoding {
+            writeln!(f, "-encoding: {}", encoding)?;
+        }
+        if let Some(ref nodes) = self.nodes {
+            writeln!(
+                f,
+                "-nodes: [{}]",
+                nodes
+                    .iter()
+                    .format_with(", ", |(host, port), f| f(&format_args!(
+                        "{}:{}",
+                        host, port
+                    )))
+            )?;
+        }
+        if let Some(ref source) = self.source {
+            writeln!(f, "-source: {}", source)?;
+        }
         writeln!(f, "-size: {} bytes", self.length)?;
         writeln!(f, "-piece length: {} bytes", self.piece_length)?;
 
@@ -368,6 +1040,9 @@ mod file_tests {
         let file = File {
             length: 42,
             path: PathBuf::from("dir1/file"),
+            attr: None,
+            sha1: None,
+            symlink_path: None,
             extra_fields: None,
         };
 
@@ -382,6 +1057,9 @@ mod file_tests {
         let file = File {
             length: 42,
             path: PathBuf::from("dir1/file"),
+            attr: None,
+            sha1: None,
+            symlink_path: None,
             extra_fields: None,
         };
 
@@ -392,6 +1070,92 @@ mod file_tests {
             _ => assert!(false),
         }
     }
+
+    #[test]
+    fn absolute_path_escapes_parent() {
+        for path in &["/etc/passwd", "a/../../x", "../x", ""] {
+            let file = File {
+                length: 42,
+                path: PathBuf::from(path),
+                attr: None,
+                sha1: None,
+                symlink_path: None,
+                extra_fields: None,
+            };
+
+            match file.absolute_path("/root") {
+                Err(Error(ErrorKind::InvalidArgument(m), _)) => {
+                    assert_eq!(
+                        m,
+                        "File path is empty or contains components that are not normal."
+                    );
+                }
+                _ => assert!(false),
+            }
+        }
+    }
+
+    #[test]
+    fn file_attributes_from_str() {
+        let attr = FileAttributes::from("lpzx");
+        assert_eq!(
+            attr,
+            FileAttributes {
+                padding: true,
+                executable: true,
+                hidden: false,
+                symlink: true,
+                unknown: "z".to_owned(),
+                raw: None,
+            }
+        );
+        assert_eq!(attr.raw, Some("lpzx".to_owned()));
+    }
+
+    #[test]
+    fn file_attributes_eq_ignores_raw() {
+        assert_eq!(FileAttributes::from("xh"), FileAttributes::from("hx"));
+        assert_ne!(FileAttributes::from("xh"), FileAttributes::from("x"));
+    }
+
+    #[test]
+    fn file_attributes_display() {
+        let attr = FileAttributes {
+            padding: true,
+            hidden: true,
+            unknown: "z".to_owned(),
+            ..Default::default()
+        };
+        assert_eq!(attr.to_string(), "phz");
+    }
+
+    #[test]
+    fn file_attributes_display_raw() {
+        // the original order is kept
+        let mut attr = FileAttributes::from("lx");
+        assert_eq!(attr.to_string(), "lx");
+
+        // unless the attributes are modified
+        attr.hidden = true;
+        assert_eq!(attr.to_string(), "xhl");
+    }
+
+    #[test]
+    fn is_padding_ok() {
+        let mut file = File {
+            length: 42,
+            path: PathBuf::from(".pad/42"),
+            attr: Some(FileAttributes::from("p")),
+            sha1: None,
+            symlink_path: None,
+            extra_fields: None,
+        };
+        assert!(file.is_padding());
+        assert!(!file.is_symlink());
+
+        file.attr = None;
+        assert!(!file.is_padding());
+    }
 }
 
 #[cfg(test)]
@@ -404,15 +1168,27 @@ mod torrent_tests {
         let torrent = Torrent {
             announce: Some("url".to_owned()),
             announce_list: None,
+            url_list: None,
+            http_seeds: None,
+            creation_date: None,
+            comment: None,
+            created_by: None,
+            encoding: None,
+            nodes: None,
+            source: None,
             length: 4,
             files: None,
             name: "sample".to_owned(),
             piece_length: 2,
             pieces: vec![vec![1, 2], vec![3, 4]],
             extra_fields: None,
-            extra_info_fields: Some(HashMap::from_iter(
-                vec![("key".to_owned(), bencode_elem!("val"))].into_iter(),
-            )),
+            extra_info_fields: Some(HashMap::from_iter(vec![(
+                "key".to_owned(),
+                bencode_elem!("val"),
+            )])),
+            raw_info: None,
+            url_list_is_string: false,
+            http_seeds_is_string: false,
         };
 
         assert_eq!(
@@ -427,11 +1203,60 @@ mod torrent_tests {
         );
     }
 
+    #[test]
+    fn construct_info_with_source() {
+        let torrent = Torrent {
+            announce: Some("url".to_owned()),
+            announce_list: None,
+            url_list: None,
+            http_seeds: None,
+            creation_date: Some(1_500_000_000),
+            comment: Some("no comment".to_owned()),
+            created_by: Some("lava_torrent".to_owned()),
+            encoding: Some("UTF-8".to_owned()),
+            nodes: Some(vec![
+                ("127.0.0.1".to_owned(), 6881),
+                ("router.example.com".to_owned(), 6882),
+            ]),
+            source: Some("SRC".to_owned()),
+            length: 4,
+            files: None,
+            name: "sample".to_owned(),
+            piece_length: 2,
+            pieces: vec![vec![1, 2], vec![3, 4]],
+            extra_fields: None,
+            extra_info_fields: None,
+            raw_info: None,
+            url_list_is_string: false,
+            http_seeds_is_string: false,
+        };
+
+        // only `source` belongs to `info`
+        assert_eq!(
+            torrent.construct_info(),
+            bencode_elem!({
+                ("length", 4),
+                ("name", "sample"),
+                ("piece length", 2),
+                ("pieces", (1, 2, 3, 4)),
+                ("source", "SRC"),
+            }),
+        );
+    }
+
     #[test]
     fn info_hash_ok() {
         let torrent = Torrent {
             announce: Some("url".to_owned()),
             announce_list: None,
+            url_list: None,
+            http_seeds: None,
+            creation_date: None,
+            comment: None,
+            created_by: None,
+            encoding: None,
+            nodes: None,
+            source: None,
             length: 4,
             files: None,
             name: "sample".to_owned(),
@@ -439,6 +1264,9 @@ mod torrent_tests {
             pieces: vec![vec![1, 2], vec![3, 4]],
             extra_fields: None,
             extra_info_fields: None,
+            raw_info: None,
+            url_list_is_string: false,
+            http_seeds_is_string: false,
         };
 
         assert_eq!(
@@ -452,6 +1280,14 @@ mod torrent_tests {
         let torrent = Torrent {
             announce: Some("url".to_owned()),
             announce_list: None,
+            url_list: None,
+            http_seeds: None,
+            creation_date: None,
+            comment: None,
+            created_by: None,
+            encoding: None,
+            nodes: None,
+            source: None,
             length: 4,
             files: None,
             name: "sample".to_owned(),
@@ -459,6 +1295,9 @@ mod torrent_tests {
             pieces: vec![vec![1, 2], vec![3, 4]],
             extra_fields: None,
             extra_info_fields: None,
+            raw_info: None,
+            url_list_is_string: false,
+            http_seeds_is_string: false,
         };
 
         assert_eq!(
@@ -477,6 +1316,14 @@ mod torrent_tests {
                 vec!["url1".to_owned()],
                 vec!["url2".to_owned(), "url3".to_owned()],
             ]),
+            url_list: None,
+            http_seeds: None,
+            creation_date: None,
+            comment: None,
+            created_by: None,
+            encoding: None,
+            nodes: None,
+            source: None,
             length: 4,
             files: None,
             name: "sample".to_owned(),
@@ -484,6 +1331,9 @@ mod torrent_tests {
             pieces: vec![vec![1, 2], vec![3, 4]],
             extra_fields: None,
             extra_info_fields: None,
+            raw_info: None,
+            url_list_is_string: false,
+            http_seeds_is_string: false,
         };
 
         assert_eq!(
@@ -494,20 +1344,568 @@ mod torrent_tests {
         );
     }
 
+    #[test]
+    fn magnet_link_percent_encoded() {
+        let torrent = Torrent {
+            announce: Some("http://tracker.com/announce?key=a&b".to_owned()),
+            announce_list: None,
+            url_list: None,
+            http_seeds: None,
+            creation_date: None,
+            comment: None,
+            created_by: None,
+            encoding: None,
+            nodes: None,
+            source: None,
+            length: 4,
+            files: None,
+            name: "sample & co é".to_owned(),
+            piece_length: 2,
+            pieces: vec![vec![1, 2], vec![3, 4]],
+            extra_fields: None,
+            extra_info_fields: None,
+            raw_info: None,
+            url_list_is_string: false,
+            http_seeds_is_string: false,
+        };
+
+        assert_eq!(
+            torrent.magnet_link(),
+            format!(
+                "magnet:?xt=urn:btih:{}&dn=sample%20%26%20co%20%C3%A9\
+                 &tr=http://tracker.com/announce?key%3Da%26b",
+                torrent.info_hash()
+            )
+        );
+    }
+
+    #[test]
+    fn magnet_link_with_options() {
+        let torrent = Torrent {
+            announce: Some("url".to_owned()),
+            announce_list: None,
+            url_list: Some(vec![
+                "http://ws1.com/".to_owned(),
+                "http://ws2.com/".to_owned(),
+            ]),
+            http_seeds: None,
+            creation_date: None,
+            comment: None,
+            created_by: None,
+            encoding: None,
+            nodes: None,
+            source: None,
+            length: 4,
+            files: None,
+            name: "sample".to_owned(),
+            piece_length: 2,
+            pieces: vec![vec![1, 2], vec![3, 4]],
+            extra_fields: None,
+            extra_info_fields: None,
+            raw_info: None,
+            url_list_is_string: false,
+            http_seeds_is_string: false,
+        };
+        let options = MagnetLinkOptions::new()
+            .set_length(true)
+            .set_web_seeds(true)
+            .set_info_hash_v2(true) // no effect as torrent is not hybrid
+            .add_peer("[::1]:6881".to_owned())
+            .set_select_only(vec![0..=0, 2..=4]);
+
+        assert_eq!(
+            torrent.magnet_link_with(&options),
+            format!(
+                "magnet:?xt=urn:btih:{}&dn=sample&xl=4&tr=url\
+                 &ws=http://ws1.com/&ws=http://ws2.com/\
+                 &x.pe=%5B::1%5D:6881&so=0,2-4",
+                torrent.info_hash()
+            )
+        );
+    }
+
+    #[test]
+    fn magnet_link_with_padding() {
+        let file = |length, path: &str, attr| File {
+            length,
+            path: PathBuf::from(path),
+            attr,
+            sha1: None,
+            symlink_path: None,
+            extra_fields: None,
+        };
+        let torrent = Torrent {
+            announce: None,
+            announce_list: None,
+            url_list: None,
+            http_seeds: None,
+            creation_date: None,
+            comment: None,
+            created_by: None,
+            encoding: None,
+            nodes: None,
+            source: None,
+            length: 6,
+            files: Some(vec![
+                file(3, "a", None),
+                file(1, ".pad/1", Some(FileAttributes::from("p"))),
+                file(2, "b", None),
+            ]),
+            name: "sample".to_owned(),
+            piece_length: 2,
+            pieces: vec![vec![1, 2], vec![3, 4], vec![5, 6]],
+            extra_fields: None,
+            extra_info_fields: None,
+            raw_info: None,
+            url_list_is_string: false,
+            http_seeds_is_string: false,
+        };
+        let options = MagnetLinkOptions::new().set_length(true);
+
+        // `xl` excludes padding
+        assert_eq!(
+            torrent.magnet_link_with(&options),
+            format!("magnet:?xt=urn:btih:{}&dn=sample&xl=5", torrent.info_hash())
+        );
+    }
+
+    #[test]
+    fn magnet_link_with_web_seeds() {
+        let torrent = Torrent {
+            announce: None,
+            announce_list: None,
+            url_list: Some(vec!["http://ws.com/".to_owned()]),
+            http_seeds: Some(vec!["http://hs.com/".to_owned()]),
+            creation_date: None,
+            comment: None,
+            created_by: None,
+            encoding: None,
+            nodes: None,
+            source: None,
+            length: 4,
+            files: None,
+            name: "sample".to_owned(),
+            piece_length: 2,
+            pieces: vec![vec![1, 2], vec![3, 4]],
+            extra_fields: None,
+            extra_info_fields: None,
+            raw_info: None,
+            url_list_is_string: false,
+            http_seeds_is_string: false,
+        };
+
+        // only `url-list` is included
+        assert_eq!(
+            torrent.magnet_link(),
+            format!(
+                "magnet:?xt=urn:btih:{}&dn=sample&ws=http://ws.com/",
+                torrent.info_hash()
+            )
+        );
+        assert_eq!(
+            torrent.magnet_link_with(&MagnetLinkOptions::new()),
+            format!("magnet:?xt=urn:btih:{}&dn=sample", torrent.info_hash())
+        );
+    }
+
+    #[test]
+    fn magnet_link_with_hybrid() {
+        let torrent = Torrent {
+            announce: None,
+            announce_list: None,
+            url_list: None,
+            http_seeds: None,
+            creation_date: None,
+            comment: None,
+            created_by: None,
+            encoding: None,
+            nodes: None,
+            source: None,
+            length: 4,
+            files: None,
+            name: "sample".to_owned(),
+            piece_length: 2,
+            pieces: vec![vec![1, 2], vec![3, 4]],
+            extra_fields: None,
+            extra_info_fields: Some(HashMap::from_iter(vec![(
+                "meta version".to_owned(),
+                bencode_elem!(2),
+            )])),
+            raw_info: None,
+            url_list_is_string: false,
+            http_seeds_is_string: false,
+        };
+        let info_hash_v2 = Sha256::digest(&torrent.construct_info().encode());
+
+        assert_eq!(
+            torrent.magnet_link_with(&MagnetLinkOptions::new().set_info_hash_v2(true)),
+            format!(
+                "magnet:?xt=urn:btih:{}&xt=urn:btmh:1220{:02x}&dn=sample",
+                torrent.info_hash(),
+                info_hash_v2.iter().format("")
+            )
+        );
+        // v2 info hash is not included by default
+        assert_eq!(
+            torrent.magnet_link(),
+            format!("magnet:?xt=urn:btih:{}&dn=sample", torrent.info_hash())
+        );
+    }
+
+    #[test]
+    fn content_files_ok() {
+        let file = |length, path: &str, attr: Option<&str>| File {
+            length,
+            path: PathBuf::from(path),
+            attr: attr.map(FileAttributes::from),
+            sha1: None,
+            symlink_path: None,
+            extra_fields: None,
+        };
+        let torrent = Torrent {
+            announce: None,
+            announce_list: None,
+            url_list: None,
+            http_seeds: None,
+            creation_date: None,
+            comment: None,
+            created_by: None,
+            encoding: None,
+            nodes: None,
+            source: None,
+            length: 8,
+            files: Some(vec![
+                file(3, "file1", None),
+                file(1, ".pad/1", Some("p")),
+                file(4, "file2", Some("x")),
+            ]),
+            name: "sample".to_owned(),
+            piece_length: 4,
+            pieces: vec![vec![1, 2], vec![3, 4]],
+            extra_fields: None,
+            extra_info_fields: None,
+            raw_info: None,
+            url_list_is_string: false,
+            http_seeds_is_string: false,
+        };
+
+        assert_eq!(
+            torrent.content_files(),
+            vec![&file(3, "file1", None), &file(4, "file2", Some("x"))]
+        );
+        assert_eq!(torrent.content_length(), 7);
+    }
+
+    #[test]
+    fn content_files_single_file() {
+        let torrent = Torrent {
+            announce: None,
+            announce_list: None,
+            url_list: None,
+            http_seeds: None,
+            creation_date: None,
+            comment: None,
+            created_by: None,
+            encoding: None,
+            nodes: None,
+            source: None,
+            length: 4,
+            files: None,
+            name: "sample".to_owned(),
+            piece_length: 2,
+            pieces: vec![vec![1, 2], vec![3, 4]],
+            extra_fields: None,
+            extra_info_fields: None,
+            raw_info: None,
+            url_list_is_string: false,
+            http_seeds_is_string: false,
+        };
+
+        assert!(torrent.content_files().is_empty());
+        assert_eq!(torrent.content_length(), 4);
+    }
+
+    // files: [5, 0, 3, 10], piece length: 4, last piece: 2 bytes
+    fn multi_file_torrent() -> Torrent {
+        let file = |length, path: &str| File {
+            length,
+            path: PathBuf::from(path),
+            attr: None,
+            sha1: None,
+            symlink_path: None,
+            extra_fields: None,
+        };
+        Torrent {
+            announce: None,
+            announce_list: None,
+            url_list: None,
+            http_seeds: None,
+            creation_date: None,
+            comment: None,
+            created_by: None,
+            encoding: None,
+            nodes: None,
+            source: None,
+            length: 18,
+            files: Some(vec![
+                file(5, "file1"),
+                file(0, "empty"),
+                file(3, "file2"),
+                file(10, "file3"),
+            ]),
+            name: "sample".to_owned(),
+            piece_length: 4,
+            pieces: vec![vec![1]; 5],
+            extra_fields: None,
+            extra_info_fields: None,
+            raw_info: None,
+            url_list_is_string: false,
+            http_seeds_is_string: false,
+        }
+    }
+
+    #[test]
+    fn piece_length_at_ok() {
+        let torrent = multi_file_torrent();
+        assert_eq!(torrent.piece_length_at(0).unwrap(), 4);
+        assert_eq!(torrent.piece_length_at(3).unwrap(), 4);
+        assert_eq!(torrent.piece_length_at(4).unwrap(), 2);
+    }
+
+    #[test]
+    fn piece_length_at_out_of_range() {
+        match multi_file_torrent().piece_length_at(5) {
+            Err(Error(ErrorKind::InvalidArgument(m), _)) => {
+                assert_eq!(m, "Piece index out of range.");
+            }
+            _ => assert!(false),
+        }
+    }
+
+    #[test]
+    fn piece_length_at_beyond_length() {
+        // 5 pieces are more than `length` needs
+        let torrent = Torrent {
+            files: None,
+            length: 10,
+            ..multi_file_torrent()
+        };
+        assert_eq!(torrent.piece_length_at(2).unwrap(), 2);
+
+        match torrent.piece_length_at(4) {
+            Err(Error(ErrorKind::InvalidArgument(m), _)) => {
+                assert_eq!(m, "Piece starts at or after the end of the content.");
+            }
+            _ => assert!(false),
+        }
+        match torrent.piece_segments(4) {
+            Err(Error(ErrorKind::InvalidArgument(m), _)) => {
+                assert_eq!(m, "Piece starts at or after the end of the content.");
+            }
+            _ => assert!(false),
+        }
+    }
+
+    #[test]
+    fn piece_length_at_overflow() {
+        let torrent = Torrent {
+            files: None,
+            length: 1,
+            piece_length: 1 << 62,
+            pieces: vec![vec![1]; 3],
+            ..multi_file_torrent()
+        };
+        assert_eq!(torrent.piece_length_at(0).unwrap(), 1);
+
+        for index in 1..3 {
+            match torrent.piece_length_at(index) {
+                Err(Error(ErrorKind::InvalidArgument(m), _)) => {
+                    assert_eq!(m, "Piece starts at or after the end of the content.");
+                }
+                _ => assert!(false),
+            }
+        }
+    }
+
+    #[test]
+    fn piece_segments_ok() {
+        let torrent = multi_file_torrent();
+        let segment = |file_index, offset, length| FileSegment {
+            file_index,
+            offset,
+            length,
+        };
+
+        assert_eq!(torrent.piece_segments(0).unwrap(), vec![segment(0, 0, 4)]);
+        // the empty file is skipped
+        assert_eq!(
+            torrent.piece_segments(1).unwrap(),
+            vec![segment(0, 4, 1), segment(2, 0, 3)]
+        );
+        assert_eq!(torrent.piece_segments(2).unwrap(), vec![segment(3, 0, 4)]);
+        assert_eq!(torrent.piece_segments(3).unwrap(), vec![segment(3, 4, 4)]);
+        assert_eq!(torrent.piece_segments(4).unwrap(), vec![segment(3, 8, 2)]);
+    }
+
+    #[test]
+    fn piece_segments_single_file() {
+        let torrent = Torrent {
+            files: None,
+            length: 10,
+            pieces: vec![vec![1]; 3],
+            ..multi_file_torrent()
+        };
+
+        assert_eq!(
+            torrent.piece_segments(2).unwrap(),
+            vec![FileSegment {
+                file_index: 0,
+                offset: 8,
+                length: 2,
+            }]
+        );
+    }
+
+    #[test]
+    fn piece_segments_out_of_range() {
+        match multi_file_torrent().piece_segments(5) {
+            Err(Error(ErrorKind::InvalidArgument(m), _)) => {
+                assert_eq!(m, "Piece index out of range.");
+            }
+            _ => assert!(false),
+        }
+    }
+
+    #[test]
+    fn file_pieces_ok() {
+        let torrent = multi_file_torrent();
+
+        assert_eq!(
+            torrent.file_pieces(0).unwrap(),
+            Some(FilePieces {
+                first_piece: 0,
+                last_piece: 1,
+                first_piece_offset: 0,
+                last_piece_end: 1,
+            })
+        );
+        assert_eq!(torrent.file_pieces(1).unwrap(), None);
+        assert_eq!(
+            torrent.file_pieces(2).unwrap(),
+            Some(FilePieces {
+                first_piece: 1,
+                last_piece: 1,
+                first_piece_offset: 1,
+                last_piece_end: 4,
+            })
+        );
+        // the last piece is short
+        assert_eq!(
+            torrent.file_pieces(3).unwrap(),
+            Some(FilePieces {
+                first_piece: 2,
+                last_piece: 4,
+                first_piece_offset: 0,
+                last_piece_end: 2,
+            })
+        );
+    }
+
+    #[test]
+    fn file_pieces_single_file() {
+        let torrent = Torrent {
+            files: None,
+            length: 10,
+            pieces: vec![vec![1]; 3],
+            ..multi_file_torrent()
+        };
+
+        assert_eq!(
+            torrent.file_pieces(0).unwrap(),
+            Some(FilePieces {
+                first_piece: 0,
+                last_piece: 2,
+                first_piece_offset: 0,
+                last_piece_end: 2,
+            })
+        );
+    }
+
+    #[test]
+    fn file_pieces_overflow() {
+        let mut torrent = multi_file_torrent();
+        torrent.files.as_mut().unwrap()[0].length = Integer::MAX;
+
+        // the end of file2 and the start of file3 overflow
+        for file_index in 2..4 {
+            match torrent.file_pieces(file_index) {
+                Err(Error(ErrorKind::MalformedTorrent(m), _)) => {
+                    assert_eq!(m, "Torrent's length overflowed in i64.");
+                }
+                _ => assert!(false),
+            }
+        }
+    }
+
+    #[test]
+    fn file_pieces_piece_length_not_positive() {
+        for &piece_length in &[0, -4] {
+            let torrent = Torrent {
+                piece_length,
+                ..multi_file_torrent()
+            };
+
+            match torrent.file_pieces(0) {
+                Err(Error(ErrorKind::MalformedTorrent(m), _)) => {
+                    assert_eq!(m, r#""piece length" <= 0."#);
+                }
+                _ => assert!(false),
+            }
+            match torrent.piece_segments(0) {
+                Err(Error(ErrorKind::MalformedTorrent(m), _)) => {
+                    assert_eq!(m, r#""piece length" <= 0."#);
+                }
+                _ => assert!(false),
+            }
+        }
+    }
+
+    #[test]
+    fn file_pieces_out_of_range() {
+        match multi_file_torrent().file_pieces(4) {
+            Err(Error(ErrorKind::InvalidArgument(m), _)) => {
+                assert_eq!(m, "File index out of range.");
+            }
+            _ => assert!(false),
+        }
+    }
+
     #[test]
     fn is_private_ok() {
         let torrent = Torrent {
             announce: Some("url".to_owned()),
             announce_list: None,
+            url_list: None,
+            http_seeds: None,
+            creation_date: None,
+            comment: None,
+            created_by: None,
+            encoding: None,
+            nodes: None,
+            source: None,
             length: 4,
             files: None,
             name: "sample".to_owned(),
             piece_length: 2,
             pieces: vec![vec![1, 2], vec![3, 4]],
             extra_fields: None,
-            extra_info_fields: Some(HashMap::from_iter(
-                vec![("private".to_owned(), bencode_elem!(1))].into_iter(),
-            )),
+            extra_info_fields: Some(HashMap::from_iter(vec![(
+                "private".to_owned(),
+                bencode_elem!(1),
+            )])),
+            raw_info: None,
+            url_list_is_string: false,
+            http_seeds_is_string: false,
         };
 
         assert!(torrent.is_private());
@@ -518,6 +1916,14 @@ mod torrent_tests {
         let torrent = Torrent {
             announce: Some("url".to_owned()),
             announce_list: None,
+            url_list: None,
+            http_seeds: None,
+            creation_date: None,
+            comment: None,
+            created_by: None,
+            encoding: None,
+            nodes: None,
+            source: None,
             length: 4,
             files: None,
             name: "sample".to_owned(),
@@ -525,6 +1931,9 @@ mod torrent_tests {
             pieces: vec![vec![1, 2], vec![3, 4]],
             extra_fields: None,
             extra_info_fields: None,
+            raw_info: None,
+            url_list_is_string: false,
+            http_seeds_is_string: false,
         };
 
         assert!(!torrent.is_private());
@@ -535,15 +1944,24 @@ mod torrent_tests {
         let torrent = Torrent {
             announce: Some("url".to_owned()),
             announce_list: None,
+            url_list: None,
+            http_seeds: None,
+            creation_date: None,
+            comment: None,
+            created_by: None,
+            encoding: None,
+            nodes: None,
+            source: None,
             length: 4,
             files: None,
             name: "sample".to_owned(),
             piece_length: 2,
             pieces: vec![vec![1, 2], vec![3, 4]],
             extra_fields: None,
-            extra_info_fields: Some(HashMap::from_iter(
-                vec![("".to_owned(), bencode_elem!(1))].into_iter(),
-            )),
+            extra_info_fields: Some(HashMap::from_iter(vec![("".to_owned(), bencode_elem!(1))])),
+            raw_info: None,
+            url_list_is_string: false,
+            http_seeds_is_string: false,
         };
 
         assert!(!torrent.is_private());
@@ -554,15 +1972,27 @@ mod torrent_tests {
         let torrent = Torrent {
             announce: Some("url".to_owned()),
             announce_list: None,
+            url_list: None,
+            http_seeds: None,
+            creation_date: None,
+            comment: None,
+            created_by: None,
+            encoding: None,
+            nodes: None,
+            source: None,
             length: 4,
             files: None,
             name: "sample".to_owned(),
             piece_length: 2,
             pieces: vec![vec![1, 2], vec![3, 4]],
             extra_fields: None,
-            extra_info_fields: Some(HashMap::from_iter(
-                vec![("private".to_owned(), bencode_elem!("1"))].into_iter(),
-            )),
+            extra_info_fields: Some(HashMap::from_iter(vec![(
+                "private".to_owned(),
+                bencode_elem!("1"),
+            )])),
+            raw_info: None,
+            url_list_is_string: false,
+            http_seeds_is_string: false,
         };
 
         assert!(!torrent.is_private());
@@ -573,15 +2003,27 @@ mod torrent_tests {
         let torrent = Torrent {
             announce: Some("url".to_owned()),
             announce_list: None,
+            url_list: None,
+            http_seeds: None,
+            creation_date: None,
+            comment: None,
+            created_by: None,
+            encoding: None,
+            nodes: None,
+            source: None,
             length: 4,
             files: None,
             name: "sample".to_owned(),
             piece_length: 2,
             pieces: vec![vec![1, 2], vec![3, 4]],
             extra_fields: None,
-            extra_info_fields: Some(HashMap::from_iter(
-                vec![("private".to_owned(), bencode_elem!(2))].into_iter(),
-            )),
+            extra_info_fields: Some(HashMap::from_iter(vec![(
+                "private".to_owned(),
+                bencode_elem!(2),
+            )])),
+            raw_info: None,
+            url_list_is_string: false,
+            http_seeds_is_string: false,
         };
 
         assert!(!torrent.is_private());
@@ -598,6 +2040,9 @@ mod file_display_tests {
         let file = File {
             length: 42,
             path: PathBuf::from("dir1/file"),
+            attr: None,
+            sha1: None,
+            symlink_path: None,
             extra_fields: None,
         };
 
@@ -609,18 +2054,40 @@ mod file_display_tests {
         );
     }
 
+    #[test]
+    fn file_display_with_attr() {
+        let file = File {
+            length: 0,
+            path: PathBuf::from("link"),
+            attr: Some(FileAttributes::from("l")),
+            sha1: Some(vec![0xff; 20]),
+            symlink_path: Some(PathBuf::from("dir1/file")),
+            extra_fields: None,
+        };
+
+        assert_eq!(
+            file.to_string(),
+            "link\n\
+             -size: 0 bytes\n\
+             -attr: l\n\
+             -sha1: ffffffffffffffffffffffffffffffffffffffff\n\
+             -symlink path: dir1/file\n\
+             ========================================\n"
+        );
+    }
+
     #[test]
     fn file_display_with_extra_fields() {
         let file = File {
             length: 42,
             path: PathBuf::from("dir1/file"),
-            extra_fields: Some(HashMap::from_iter(
-                vec![
-                    ("comment2".to_owned(), bencode_elem!("no comment")),
-                    ("comment1".to_owned(), bencode_elem!("no comment")),
-                ]
-                .into_iter(),
-            )),
+            attr: None,
+            sha1: None,
+            symlink_path: None,
+            extra_fields: Some(HashMap::from_iter(vec![
+                ("comment2".to_owned(), bencode_elem!("no comment")),
+                ("comment1".to_owned(), bencode_elem!("no comment")),
+            ])),
         };
 
         assert_eq!(
@@ -644,6 +2111,14 @@ mod torrent_display_tests {
         let torrent = Torrent {
             announce: Some("url".to_owned()),
             announce_list: None,
+            url_list: None,
+            http_seeds: None,
+            creation_date: None,
+            comment: None,
+            created_by: None,
+            encoding: None,
+            nodes: None,
+            source: None,
             length: 4,
             files: None,
             name: "sample".to_owned(),
@@ -651,6 +2126,9 @@ mod torrent_display_tests {
             pieces: vec![vec![1, 2], vec![3, 4]],
             extra_fields: None,
             extra_info_fields: None,
+            raw_info: None,
+            url_list_is_string: false,
+            http_seeds_is_string: false,
         };
 
         assert_eq!(
@@ -671,6 +2149,14 @@ mod torrent_display_tests {
                 vec!["url1".to_owned(), "url2".to_owned()],
                 vec!["url3".to_owned(), "url4".to_owned()],
             ]),
+            url_list: None,
+            http_seeds: None,
+            creation_date: None,
+            comment: None,
+            created_by: None,
+            encoding: None,
+            nodes: None,
+            source: None,
             length: 4,
             files: None,
             name: "sample".to_owned(),
@@ -678,6 +2164,9 @@ mod torrent_display_tests {
             pieces: vec![vec![1, 2], vec![3, 4]],
             extra_fields: None,
             extra_info_fields: None,
+            raw_info: None,
+            url_list_is_string: false,
+            http_seeds_is_string: false,
         };
 
         assert_eq!(
@@ -691,24 +2180,76 @@ mod torrent_display_tests {
         );
     }
 
+    #[test]
+    fn torrent_display_with_metainfo_fields() {
+        let torrent = Torrent {
+            announce: Some("url".to_owned()),
+            announce_list: None,
+            url_list: None,
+            http_seeds: None,
+            creation_date: Some(1_500_000_000),
+            comment: Some("no comment".to_owned()),
+            created_by: Some("lava_torrent".to_owned()),
+            encoding: Some("UTF-8".to_owned()),
+            nodes: Some(vec![
+                ("127.0.0.1".to_owned(), 6881),
+                ("router.example.com".to_owned(), 6882),
+            ]),
+            source: Some("SRC".to_owned()),
+            length: 4,
+            files: None,
+            name: "sample".to_owned(),
+            piece_length: 2,
+            pieces: vec![vec![1, 2], vec![3, 4]],
+            extra_fields: None,
+            extra_info_fields: None,
+            raw_info: None,
+            url_list_is_string: false,
+            http_seeds_is_string: false,
+        };
+
+        assert_eq!(
+            torrent.to_string(),
+            "sample.torrent\n\
+             -announce: url\n\
+             -creation date: 1500000000\n\
+             -comment: no comment\n\
+             -created by: lava_torrent\n\
+             -encoding: UTF-8\n\
+             -nodes: [127.0.0.1:6881, router.example.com:6882]\n\
+             -source: SRC\n\
+             -size: 4 bytes\n\
+             -piece length: 2 bytes\n\
+             -pieces: [[0102], [0304]]\n"
+        );
+    }
+
     #[test]
     fn torrent_display_with_extra_fields() {
         let torrent = Torrent {
             announce: Some("url".to_owned()),
             announce_list: None,
+            url_list: None,
+            http_seeds: None,
+            creation_date: None,
+            comment: None,
+            created_by: None,
+            encoding: None,
+            nodes: None,
+            source: None,
             length: 4,
             files: None,
             name: "sample".to_owned(),
             piece_length: 2,
             pieces: vec![vec![1, 2], vec![3, 4]],
-            extra_fields: Some(HashMap::from_iter(
-                vec![
-                    ("comment2".to_owned(), bencode_elem!("no comment")),
-                    ("comment1".to_owned(), bencode_elem!("no comment")),
-                ]
-                .into_iter(),
-            )),
+            extra_fields: Some(HashMap::from_iter(vec![
+                ("comment2".to_owned(), bencode_elem!("no comment")),
+                ("comment1".to_owned(), bencode_elem!("no comment")),
+            ])),
             extra_info_fields: None,
+            raw_info: None,
+            url_list_is_string: false,
+            http_seeds_is_string: false,
         };
 
         assert_eq!(
@@ -728,19 +2269,27 @@ mod torrent_display_tests {
         let torrent = Torrent {
             announce: Some("url".to_owned()),
             announce_list: None,
+            url_list: None,
+            http_seeds: None,
+            creation_date: None,
+            comment: None,
+            created_by: None,
+            encoding: None,
+            nodes: None,
+            source: None,
             length: 4,
             files: None,
             name: "sample".to_owned(),
             piece_length: 2,
             pieces: vec![vec![1, 2], vec![3, 4]],
             extra_fields: None,
-            extra_info_fields: Some(HashMap::from_iter(
-                vec![
-                    ("comment2".to_owned(), bencode_elem!("no comment")),
-                    ("comment1".to_owned(), bencode_elem!("no comment")),
-                ]
-                .into_iter(),
-            )),
+            extra_info_fields: Some(HashMap::from_iter(vec![
+                ("comment2".to_owned(), bencode_elem!("no comment")),
+                ("comment1".to_owned(), bencode_elem!("no comment")),
+            ])),
+            raw_info: None,
+            url_list_is_string: false,
+            http_seeds_is_string: false,
         };
 
         assert_eq!(
@@ -760,16 +2309,30 @@ mod torrent_display_tests {
         let torrent = Torrent {
             announce: Some("url".to_owned()),
             announce_list: None,
+            url_list: None,
+            http_seeds: None,
+            creation_date: None,
+            comment: None,
+            created_by: None,
+            encoding: None,
+            nodes: None,
+            source: None,
             length: 4,
             files: Some(vec![
                 File {
                     length: 2,
                     path: PathBuf::from("dir1/dir2/file1"),
+                    attr: None,
+                    sha1: None,
+                    symlink_path: None,
                     extra_fields: None,
                 },
                 File {
                     length: 2,
                     path: PathBuf::from("dir1/dir2/file2"),
+                    attr: None,
+                    sha1: None,
+                    symlink_path: None,
                     extra_fields: None,
                 },
             ]),
@@ -778,6 +2341,9 @@ mod torrent_display_tests {
             pieces: vec![vec![1, 2], vec![3, 4]],
             extra_fields: None,
             extra_info_fields: None,
+            raw_info: None,
+            url_list_is_string: false,
+            http_seeds_is_string: false,
         };
 
         assert_eq!(
@@ -1,7 +1,9 @@
 use super::*;
-use bencode::BencodeElem;
+use bencode::{BencodeElem, BencodeRef};
 use std::borrow::Cow;
 use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::io::{BufReader, Read};
 use std::path::{Path, PathBuf};
 use util;
 
@@ -11,6 +13,9 @@ impl File {
             BencodeElem::Dictionary(mut dict) => Ok(File {
                 length: Self::extract_file_length(&mut dict)?,
                 path: Self::extract_file_path(&mut dict)?,
+                attr: Self::extract_file_attr(&mut dict),
+                sha1: Self::extract_file_sha1(&mut dict),
+                symlink_path: Self::extract_file_symlink_path(&mut dict),
                 extra_fields: Self::extract_file_extra_fields(dict),
             }),
             _ => bail!(ErrorKind::MalformedTorrent(Cow::Borrowed(
@@ -80,6 +85,52 @@ impl File {
         }
     }
 
+    // `attr`, `sha1` and `symlink path` (BEP 47) are optional, so they are
+    // only extracted if well-formed. Otherwise they are left in `dict` (and
+    // end up in `extra_fields`) instead of failing the whole torrent.
+    fn extract_file_attr(dict: &mut HashMap<String, BencodeElem>) -> Option<FileAttributes> {
+        let attr = match dict.get("attr") {
+            Some(BencodeElem::String(attr)) => Some(FileAttributes::from(attr.as_str())),
+            _ => None,
+        };
+        if attr.is_some() {
+            dict.remove("attr");
+        }
+        attr
+    }
+
+    // `sha1`s are never normalized to NFC forms (see `read_from_bytes()`),
+    // so a hash that happens to be valid UTF8 is parsed as bytes as well
+    fn extract_file_sha1(dict: &mut HashMap<String, BencodeElem>) -> Option<Vec<u8>> {
+        match dict.remove("sha1") {
+            Some(BencodeElem::Bytes(sha1)) if sha1.len() == PIECE_STRING_LENGTH => Some(sha1),
+            Some(other) => {
+                dict.insert("sha1".to_owned(), other);
+                None
+            }
+            None => None,
+        }
+    }
+
+    fn extract_file_symlink_path(dict: &mut HashMap<String, BencodeElem>) -> Option<PathBuf> {
+        let symlink_path = match dict.get("symlink path") {
+            Some(BencodeElem::List(list)) => list
+                .iter()
+                .map(|component| match component {
+                    BencodeElem::String(component) if (component != ".") && (component != "..") => {
+                        Some(component)
+                    }
+                    _ => None,
+                })
+                .collect(),
+            _ => None,
+        };
+        if symlink_path.is_some() {
+            dict.remove("symlink path");
+        }
+        symlink_path
+    }
+
     fn extract_file_extra_fields(dict: HashMap<String, BencodeElem>) -> Option<Dictionary> {
         if dict.is_empty() {
             None
@@ -98,7 +149,21 @@ impl Torrent {
     where
         B: AsRef<[u8]>,
     {
-        Self::from_parsed(BencodeElem::from_bytes(bytes)?)?.validate()
+        let bytes = bytes.as_ref();
+        let raw = BencodeRef::from_bytes(bytes)?;
+        let mut parsed = raw
+            .iter()
+            .map(|elem| elem.to_owned())
+            .collect::<Vec<BencodeElem>>();
+        if let (Some(raw), Some(parsed)) = (raw.first(), parsed.first_mut()) {
+            Self::restore_file_sha1s(raw, parsed);
+        }
+        let mut torrent = Self::from_parsed(parsed)?.validate()?;
+
+        // keep the original `info` bytes for calculating info hash
+        torrent.raw_info = BencodeElem::find_value_span(bytes, b"info")?
+            .map(|(start, end)| bytes[start..end].to_vec());
+        Ok(torrent)
     }
 
     /// Parse the content of the file at `path` and return the extracted `Torrent`.
@@ -109,7 +174,50 @@ impl Torrent {
     where
         P: AsRef<Path>,
     {
-        Self::from_parsed(BencodeElem::from_file(path)?)?.validate()
+        let mut bytes = Vec::new();
+        BufReader::new(::std::fs::File::open(path)?).read_to_end(&mut bytes)?;
+        Self::read_from_bytes(bytes)
+    }
+
+    // Strings are normalized to NFC forms when parsed, which would corrupt
+    // a file's `sha1` that happens to be valid UTF8. So the `sha1`s in `parsed`
+    // are replaced by those in `raw` (i.e. the same bencode without normalization).
+    fn restore_file_sha1s(raw: &BencodeRef, parsed: &mut BencodeElem) {
+        fn get<'a, 'b>(elem: &'b BencodeRef<'a>, key: &str) -> Option<&'b BencodeRef<'a>> {
+            match *elem {
+                BencodeRef::Dictionary(ref entries) => entries
+                    .iter()
+                    .find(|&&(k, _)| k == key.as_bytes())
+                    .map(|(_, v)| v),
+                _ => None,
+            }
+        }
+
+        let raw_files = match get(raw, "info").and_then(|info| get(info, "files")) {
+            Some(BencodeRef::List(files)) => files,
+            _ => return,
+        };
+        let files = match *parsed {
+            BencodeElem::Dictionary(ref mut dict) => match dict.get_mut("info") {
+                Some(BencodeElem::Dictionary(info)) => match info.get_mut("files") {
+                    Some(BencodeElem::List(files)) => files,
+                    _ => return,
+                },
+                _ => return,
+            },
+            _ => return,
+        };
+
+        for (raw_file, file) in raw_files.iter().zip(files) {
+            let sha1 = match get(raw_file, "sha1") {
+                Some(BencodeRef::String(sha1)) => sha1.as_bytes(),
+                Some(BencodeRef::Bytes(sha1)) => sha1,
+                _ => continue,
+            };
+            if let BencodeElem::Dictionary(ref mut file) = *file {
+                file.insert("sha1".to_owned(), BencodeElem::Bytes(sha1.to_vec()));
+            }
+        }
     }
 
     // @note: Most of validation is done when bdecoding and parsing torrent,
@@ -150,6 +258,17 @@ impl Torrent {
             // 2nd-level items
             let announce = Self::extract_announce(&mut parsed)?;
             let announce_list = Self::extract_announce_list(&mut parsed)?;
+            // remember the form of web seeds, so that it can be kept when writing
+            let url_list_is_string = matches!(parsed.get("url-list"), Some(BencodeElem::String(_)));
+            let http_seeds_is_string =
+                matches!(parsed.get("httpseeds"), Some(BencodeElem::String(_)));
+            let url_list = Self::extract_urls(&mut parsed, "url-list");
+            let http_seeds = Self::extract_urls(&mut parsed, "httpseeds");
+            let creation_date = Self::extract_optional_integer(&mut parsed, "creation date");
+            let comment = Self::extract_optional_string(&mut parsed, "comment");
+            let created_by = Self::extract_optional_string(&mut parsed, "created by");
+            let encoding = Self::extract_optional_string(&mut parsed, "encoding");
+            let nodes = Self::extract_nodes(&mut parsed);
             let info = parsed.remove("info");
             let extra_fields = Self::extract_extra_fields(parsed);
 
@@ -162,6 +281,14 @@ impl Torrent {
                     Ok(Torrent {
                         announce,
                         announce_list,
+                        url_list,
+                        http_seeds,
+                        creation_date,
+                        comment,
+                        created_by,
+                        encoding,
+                        nodes,
+                        source: Self::extract_optional_string(&mut info, "source"),
                         length: Self::extract_length(&mut info, &files)?,
                         files,
                         name: Self::extract_name(&mut info)?,
@@ -169,6 +296,9 @@ impl Torrent {
                         pieces: Self::extract_pieces(&mut info)?,
                         extra_fields,
                         extra_info_fields: Self::extract_extra_fields(info),
+                        raw_info: None,
+                        url_list_is_string,
+                        http_seeds_is_string,
                     })
                 }
                 Some(_) => bail!(ErrorKind::MalformedTorrent(Cow::Borrowed(
@@ -185,7 +315,9 @@ impl Torrent {
         }
     }
 
-    fn extract_announce(dict: &mut HashMap<String, BencodeElem>) -> Result<Option<String>> {
+    pub(crate) fn extract_announce(
+        dict: &mut HashMap<String, BencodeElem>,
+    ) -> Result<Option<String>> {
         match dict.remove("announce") {
             Some(BencodeElem::String(url)) => Ok(Some(url)),
             Some(_) => bail!(ErrorKind::MalformedTorrent(Cow::Borrowed(
@@ -195,7 +327,7 @@ impl Torrent {
         }
     }
 
-    fn extract_announce_list(
+    pub(crate) fn extract_announce_list(
         dict: &mut HashMap<String, BencodeElem>,
     ) -> Result<Option<AnnounceList>> {
         let mut announce_list = Vec::new();
@@ -236,6 +368,86 @@ impl Torrent {
         }
     }
 
+    // `url-list` (BEP 19) and `httpseeds` (BEP 17) can be
+    // either a single url or a list of urls
+    //
+    // Like `extract_optional_string()`, a malformed value is
+    // left in `dict` instead of failing the whole torrent.
+    fn extract_urls(dict: &mut HashMap<String, BencodeElem>, key: &str) -> Option<Vec<String>> {
+        let urls = match dict.get(key) {
+            // an empty string is sometimes used to mean "no url"
+            Some(BencodeElem::String(url)) if url.is_empty() => Some(Vec::new()),
+            Some(BencodeElem::String(url)) => Some(vec![url.clone()]),
+            Some(BencodeElem::List(list)) => list
+                .iter()
+                .map(|url| match url {
+                    BencodeElem::String(url) => Some(url.clone()),
+                    _ => None,
+                })
+                .collect(),
+            _ => None,
+        };
+        if urls.is_some() {
+            dict.remove(key);
+        }
+        urls
+    }
+
+    // Optional fields like `comment` are informational, so they are only
+    // extracted if well-formed. Otherwise they are left in `dict` (and
+    // end up in `extra_fields`) instead of failing the whole torrent.
+    fn extract_optional_string(
+        dict: &mut HashMap<String, BencodeElem>,
+        key: &str,
+    ) -> Option<String> {
+        match dict.remove(key) {
+            Some(BencodeElem::String(string)) => Some(string),
+            Some(other) => {
+                dict.insert(key.to_owned(), other);
+                None
+            }
+            None => None,
+        }
+    }
+
+    fn extract_optional_integer(
+        dict: &mut HashMap<String, BencodeElem>,
+        key: &str,
+    ) -> Option<Integer> {
+        match dict.remove(key) {
+            Some(BencodeElem::Integer(int)) => Some(int),
+            Some(other) => {
+                dict.insert(key.to_owned(), other);
+                None
+            }
+            None => None,
+        }
+    }
+
+    // `nodes` (BEP 5) is a list of [host, port] pairs
+    fn extract_nodes(dict: &mut HashMap<String, BencodeElem>) -> Option<Vec<Node>> {
+        fn extract_node(elem: &BencodeElem) -> Option<Node> {
+            match *elem {
+                BencodeElem::List(ref pair) if pair.len() == 2 => match (&pair[0], &pair[1]) {
+                    (BencodeElem::String(host), &BencodeElem::Integer(port)) => {
+                        u16::try_from(port).ok().map(|port| (host.clone(), port))
+                    }
+                    _ => None,
+                },
+                _ => None,
+            }
+        }
+
+        let nodes = match dict.get("nodes") {
+            Some(BencodeElem::List(list)) => list.iter().map(extract_node).collect(),
+            _ => None,
+        };
+        if nodes.is_some() {
+            dict.remove("nodes");
+        }
+        nodes
+    }
+
     fn extract_files(dict: &mut HashMap<String, BencodeElem>) -> Result<Option<Vec<File>>> {
         match dict.remove("files") {
             Some(BencodeElem::List(list)) => {
@@ -387,6 +599,9 @@ mod file_read_tests {
             File {
                 length: 42,
                 path: PathBuf::from("root/.bashrc"),
+                attr: None,
+                sha1: None,
+                symlink_path: None,
                 extra_fields: Some(HashMap::from_iter(
                     vec![("comment".to_owned(), bencode_elem!("no comment"))].into_iter()
                 )),
@@ -394,6 +609,31 @@ mod file_read_tests {
         );
     }
 
+    #[test]
+    fn extract_file_with_attr() {
+        let file = bencode_elem!({
+            ("attr", "l"),
+            ("length", 0),
+            ("path", ["link"]),
+            ("symlink path", ["dir", "file"]),
+        });
+
+        assert_eq!(
+            File::extract_file(file).unwrap(),
+            File {
+                length: 0,
+                path: PathBuf::from("link"),
+                attr: Some(FileAttributes {
+                    symlink: true,
+                    ..Default::default()
+                }),
+                sha1: None,
+                symlink_path: Some(PathBuf::from("dir/file")),
+                extra_fields: None,
+            }
+        );
+    }
+
     #[test]
     fn extract_file_not_dictionary() {
         let file = bencode_elem!([]);
@@ -408,15 +648,13 @@ mod file_read_tests {
 
     #[test]
     fn extract_file_length_ok() {
-        let mut dict =
-            HashMap::from_iter(vec![("length".to_owned(), bencode_elem!(42))].into_iter());
+        let mut dict = HashMap::from_iter(vec![("length".to_owned(), bencode_elem!(42))]);
         assert_eq!(File::extract_file_length(&mut dict).unwrap(), 42);
     }
 
     #[test]
     fn extract_file_length_is_negative() {
-        let mut dict =
-            HashMap::from_iter(vec![("length".to_owned(), bencode_elem!(-1))].into_iter());
+        let mut dict = HashMap::from_iter(vec![("length".to_owned(), bencode_elem!(-1))]);
 
         match File::extract_file_length(&mut dict) {
             Err(Error(ErrorKind::MalformedTorrent(m), _)) => assert_eq!(m, r#""length" < 0."#),
@@ -426,8 +664,7 @@ mod file_read_tests {
 
     #[test]
     fn extract_file_length_not_integer() {
-        let mut dict =
-            HashMap::from_iter(vec![("length".to_owned(), bencode_elem!("42"))].into_iter());
+        let mut dict = HashMap::from_iter(vec![("length".to_owned(), bencode_elem!("42"))]);
 
         match File::extract_file_length(&mut dict) {
             Err(Error(ErrorKind::MalformedTorrent(m), _)) => {
@@ -451,9 +688,10 @@ mod file_read_tests {
 
     #[test]
     fn extract_file_path_ok() {
-        let mut dict = HashMap::from_iter(
-            vec![("path".to_owned(), bencode_elem!(["root", ".bashrc"]))].into_iter(),
-        );
+        let mut dict = HashMap::from_iter(vec![(
+            "path".to_owned(),
+            bencode_elem!(["root", ".bashrc"]),
+        )]);
 
         assert_eq!(
             File::extract_file_path(&mut dict).unwrap(),
@@ -463,9 +701,7 @@ mod file_read_tests {
 
     #[test]
     fn extract_file_path_not_list() {
-        let mut dict = HashMap::from_iter(
-            vec![("path".to_owned(), bencode_elem!("root/.bashrc"))].into_iter(),
-        );
+        let mut dict = HashMap::from_iter(vec![("path".to_owned(), bencode_elem!("root/.bashrc"))]);
 
         match File::extract_file_path(&mut dict) {
             Err(Error(ErrorKind::MalformedTorrent(m), _)) => {
@@ -489,7 +725,7 @@ mod file_read_tests {
 
     #[test]
     fn extract_file_path_empty_list() {
-        let mut dict = HashMap::from_iter(vec![("path".to_owned(), bencode_elem!([]))].into_iter());
+        let mut dict = HashMap::from_iter(vec![("path".to_owned(), bencode_elem!([]))]);
 
         match File::extract_file_path(&mut dict) {
             Err(Error(ErrorKind::MalformedTorrent(m), _)) => {
@@ -501,16 +737,13 @@ mod file_read_tests {
 
     #[test]
     fn extract_file_path_component_not_string() {
-        let mut dict = HashMap::from_iter(
-            vec![(
-                "path".to_owned(),
-                BencodeElem::List(vec![
-                    BencodeElem::String("root".to_owned()),
-                    BencodeElem::Bytes(".bashrc".as_bytes().to_vec()),
-                ]),
-            )]
-            .into_iter(),
-        );
+        let mut dict = HashMap::from_iter(vec![(
+            "path".to_owned(),
+            BencodeElem::List(vec![
+                BencodeElem::String("root".to_owned()),
+                BencodeElem::Bytes(".bashrc".as_bytes().to_vec()),
+            ]),
+        )]);
 
         match File::extract_file_path(&mut dict) {
             Err(Error(ErrorKind::MalformedTorrent(m), _)) => {
@@ -522,16 +755,13 @@ mod file_read_tests {
 
     #[test]
     fn extract_file_path_component_invalid() {
-        let mut dict = HashMap::from_iter(
-            vec![(
-                "path".to_owned(),
-                BencodeElem::List(vec![
-                    BencodeElem::String("root".to_owned()),
-                    BencodeElem::String(".".to_owned()),
-                ]),
-            )]
-            .into_iter(),
-        );
+        let mut dict = HashMap::from_iter(vec![(
+            "path".to_owned(),
+            BencodeElem::List(vec![
+                BencodeElem::String("root".to_owned()),
+                BencodeElem::String(".".to_owned()),
+            ]),
+        )]);
 
         match File::extract_file_path(&mut dict) {
             Err(Error(ErrorKind::MalformedTorrent(m), _)) => {
@@ -543,16 +773,13 @@ mod file_read_tests {
 
     #[test]
     fn extract_file_path_component_invalid_2() {
-        let mut dict = HashMap::from_iter(
-            vec![(
-                "path".to_owned(),
-                BencodeElem::List(vec![
-                    BencodeElem::String("root".to_owned()),
-                    BencodeElem::String("..".to_owned()),
-                ]),
-            )]
-            .into_iter(),
-        );
+        let mut dict = HashMap::from_iter(vec![(
+            "path".to_owned(),
+            BencodeElem::List(vec![
+                BencodeElem::String("root".to_owned()),
+                BencodeElem::String("..".to_owned()),
+            ]),
+        )]);
 
         match File::extract_file_path(&mut dict) {
             Err(Error(ErrorKind::MalformedTorrent(m), _)) => {
@@ -562,6 +789,111 @@ mod file_read_tests {
         }
     }
 
+    #[test]
+    fn extract_file_attr_ok() {
+        let mut dict = HashMap::from_iter(vec![("attr".to_owned(), bencode_elem!("xh"))]);
+        assert_eq!(
+            File::extract_file_attr(&mut dict),
+            Some(FileAttributes {
+                executable: true,
+                hidden: true,
+                ..Default::default()
+            })
+        );
+        assert!(dict.is_empty());
+    }
+
+    #[test]
+    fn extract_file_attr_missing() {
+        assert_eq!(File::extract_file_attr(&mut HashMap::new()), None);
+    }
+
+    #[test]
+    fn extract_file_attr_not_string() {
+        let mut dict = HashMap::from_iter(vec![("attr".to_owned(), bencode_elem!(1))]);
+
+        assert_eq!(File::extract_file_attr(&mut dict), None);
+        // malformed `attr` is kept as an extra field
+        assert_eq!(dict.get("attr"), Some(&bencode_elem!(1)));
+    }
+
+    #[test]
+    fn extract_file_sha1_ok() {
+        let mut dict = HashMap::from_iter(vec![(
+            "sha1".to_owned(),
+            BencodeElem::Bytes(vec![0xff; PIECE_STRING_LENGTH]),
+        )]);
+        assert_eq!(
+            File::extract_file_sha1(&mut dict),
+            Some(vec![0xff; PIECE_STRING_LENGTH])
+        );
+        assert!(dict.is_empty());
+    }
+
+    #[test]
+    fn extract_file_sha1_bad_length() {
+        let mut dict =
+            HashMap::from_iter(vec![("sha1".to_owned(), BencodeElem::Bytes(vec![0xff; 4]))]);
+
+        assert_eq!(File::extract_file_sha1(&mut dict), None);
+        // malformed `sha1` is kept as an extra field
+        assert_eq!(dict.get("sha1"), Some(&BencodeElem::Bytes(vec![0xff; 4])));
+    }
+
+    #[test]
+    fn extract_file_sha1_not_bytes() {
+        let mut dict = HashMap::from_iter(vec![("sha1".to_owned(), bencode_elem!([]))]);
+
+        assert_eq!(File::extract_file_sha1(&mut dict), None);
+        assert_eq!(dict.get("sha1"), Some(&bencode_elem!([])));
+    }
+
+    #[test]
+    fn extract_file_symlink_path_ok() {
+        let mut dict = HashMap::from_iter(vec![(
+            "symlink path".to_owned(),
+            bencode_elem!(["dir", "file"]),
+        )]);
+        assert_eq!(
+            File::extract_file_symlink_path(&mut dict),
+            Some(PathBuf::from("dir/file"))
+        );
+        assert!(dict.is_empty());
+    }
+
+    #[test]
+    fn extract_file_symlink_path_not_list() {
+        let mut dict =
+            HashMap::from_iter(vec![("symlink path".to_owned(), bencode_elem!("dir/file"))]);
+
+        assert_eq!(File::extract_file_symlink_path(&mut dict), None);
+        // malformed `symlink path` is kept as an extra field
+        assert_eq!(dict.get("symlink path"), Some(&bencode_elem!("dir/file")));
+    }
+
+    #[test]
+    fn extract_file_symlink_path_component_not_string() {
+        let mut dict =
+            HashMap::from_iter(vec![("symlink path".to_owned(), bencode_elem!(["dir", 1]))]);
+
+        assert_eq!(File::extract_file_symlink_path(&mut dict), None);
+        assert_eq!(dict.get("symlink path"), Some(&bencode_elem!(["dir", 1])));
+    }
+
+    #[test]
+    fn extract_file_symlink_path_component_invalid() {
+        let mut dict = HashMap::from_iter(vec![(
+            "symlink path".to_owned(),
+            bencode_elem!(["..", "file"]),
+        )]);
+
+        assert_eq!(File::extract_file_symlink_path(&mut dict), None);
+        assert_eq!(
+            dict.get("symlink path"),
+            Some(&bencode_elem!(["..", "file"]))
+        );
+    }
+
     #[test]
     fn extract_file_extra_fields_ok() {
         assert_eq!(
@@ -594,6 +926,14 @@ mod torrent_read_tests {
         let torrent = Torrent {
             announce: Some("url".to_owned()),
             announce_list: None,
+            url_list: None,
+            http_seeds: None,
+            creation_date: None,
+            comment: None,
+            created_by: None,
+            encoding: None,
+            nodes: None,
+            source: None,
             length: 4,
             files: None,
             name: "sample".to_owned(),
@@ -601,6 +941,9 @@ mod torrent_read_tests {
             pieces: vec![vec![1, 2], vec![3, 4]],
             extra_fields: None,
             extra_info_fields: None,
+            raw_info: None,
+            url_list_is_string: false,
+            http_seeds_is_string: false,
         };
 
         // use `clone()` here so we can test that `torrent` is not modified
@@ -613,6 +956,14 @@ mod torrent_read_tests {
         let torrent = Torrent {
             announce: Some("url".to_owned()),
             announce_list: None,
+            url_list: None,
+            http_seeds: None,
+            creation_date: None,
+            comment: None,
+            created_by: None,
+            encoding: None,
+            nodes: None,
+            source: None,
             length: 6,
             files: None,
             name: "sample".to_owned(),
@@ -620,6 +971,9 @@ mod torrent_read_tests {
             pieces: vec![vec![1, 2], vec![3, 4]],
             extra_fields: None,
             extra_info_fields: None,
+            raw_info: None,
+            url_list_is_string: false,
+            http_seeds_is_string: false,
         };
 
         match torrent.validate() {
@@ -635,6 +989,14 @@ mod torrent_read_tests {
         let torrent = Torrent {
             announce: Some("url".to_owned()),
             announce_list: None,
+            url_list: None,
+            http_seeds: None,
+            creation_date: None,
+            comment: None,
+            created_by: None,
+            encoding: None,
+            nodes: None,
+            source: None,
             length: 0,
             files: None,
             name: "sample".to_owned(),
@@ -642,6 +1004,9 @@ mod torrent_read_tests {
             pieces: vec![vec![1, 2], vec![3, 4]],
             extra_fields: None,
             extra_info_fields: None,
+            raw_info: None,
+            url_list_is_string: false,
+            http_seeds_is_string: false,
         };
 
         match torrent.validate() {
@@ -655,13 +1020,24 @@ mod torrent_read_tests {
         let torrent = Torrent {
             announce: Some("url".to_owned()),
             announce_list: None,
+            url_list: None,
+            http_seeds: None,
+            creation_date: None,
+            comment: None,
+            created_by: None,
+            encoding: None,
+            nodes: None,
+            source: None,
             length: 1,
             files: None,
             name: "sample".to_owned(),
-            piece_length: i64::max_value(),
+            piece_length: i64::MAX,
             pieces: vec![vec![1, 2], vec![3, 4], vec![5, 6]],
             extra_fields: None,
             extra_info_fields: None,
+            raw_info: None,
+            url_list_is_string: false,
+            http_seeds_is_string: false,
         };
 
         match torrent.validate() {
@@ -693,6 +1069,14 @@ mod torrent_read_tests {
             Torrent {
                 announce: Some("url".to_owned()),
                 announce_list: None,
+                url_list: None,
+                http_seeds: None,
+                creation_date: None,
+                comment: None,
+                created_by: None,
+                encoding: None,
+                nodes: None,
+                source: None,
                 length: 2,
                 files: None,
                 name: "??".to_owned(),
@@ -703,10 +1087,52 @@ mod torrent_read_tests {
                 ]],
                 extra_fields: None,
                 extra_info_fields: None,
+                raw_info: None,
+                url_list_is_string: false,
+                http_seeds_is_string: false,
             }
         );
     }
 
+    #[test]
+    fn from_parsed_with_metainfo_fields() {
+        let dict = vec![bencode_elem!({
+            ("announce", "url"),
+            ("comment", "no comment"),
+            ("created by", "lava_torrent"),
+            ("creation date", 1_500_000_000),
+            ("encoding", "UTF-8"),
+            ("nodes", [["127.0.0.1", 6881], ["router.example.com", 6882]]),
+            ("info", {
+                ("name", "??"),
+                ("length", 2),
+                ("piece length", 2),
+                (
+                    "pieces",
+                    (0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x09,
+                        0x0a, 0x0b, 0x0c, 0x0d, 0x0e, 0x0f, 0x10, 0x11, 0x12, 0x13)
+                ),
+                ("source", "SRC"),
+            }),
+        })];
+
+        let torrent = Torrent::from_parsed(dict).unwrap();
+        assert_eq!(torrent.creation_date, Some(1_500_000_000));
+        assert_eq!(torrent.comment, Some("no comment".to_owned()));
+        assert_eq!(torrent.created_by, Some("lava_torrent".to_owned()));
+        assert_eq!(torrent.encoding, Some("UTF-8".to_owned()));
+        assert_eq!(
+            torrent.nodes,
+            Some(vec![
+                ("127.0.0.1".to_owned(), 6881),
+                ("router.example.com".to_owned(), 6882),
+            ])
+        );
+        assert_eq!(torrent.source, Some("SRC".to_owned()));
+        assert_eq!(torrent.extra_fields, None);
+        assert_eq!(torrent.extra_info_fields, None);
+    }
+
     #[test]
     fn from_parsed_top_level_multiple_elem() {
         let dict = vec![bencode_elem!({}), bencode_elem!([])];
@@ -775,8 +1201,7 @@ mod torrent_read_tests {
 
     #[test]
     fn extract_announce_ok() {
-        let mut dict =
-            HashMap::from_iter(vec![("announce".to_owned(), bencode_elem!("url"))].into_iter());
+        let mut dict = HashMap::from_iter(vec![("announce".to_owned(), bencode_elem!("url"))]);
 
         assert_eq!(
             Torrent::extract_announce(&mut dict).unwrap(),
@@ -793,13 +1218,10 @@ mod torrent_read_tests {
 
     #[test]
     fn extract_announce_not_string() {
-        let mut dict = HashMap::from_iter(
-            vec![(
-                "announce".to_owned(),
-                BencodeElem::Bytes("url".as_bytes().to_vec()),
-            )]
-            .into_iter(),
-        );
+        let mut dict = HashMap::from_iter(vec![(
+            "announce".to_owned(),
+            BencodeElem::Bytes("url".as_bytes().to_vec()),
+        )]);
 
         match Torrent::extract_announce(&mut dict) {
             Err(Error(ErrorKind::MalformedTorrent(m), _)) => assert_eq!(
@@ -849,13 +1271,10 @@ mod torrent_read_tests {
 
     #[test]
     fn extract_announce_list_ok() {
-        let mut dict = HashMap::from_iter(
-            vec![(
-                "announce-list".to_owned(),
-                bencode_elem!([["url1", "url2"], ["url3", "url4"]]),
-            )]
-            .into_iter(),
-        );
+        let mut dict = HashMap::from_iter(vec![(
+            "announce-list".to_owned(),
+            bencode_elem!([["url1", "url2"], ["url3", "url4"]]),
+        )]);
 
         assert_eq!(
             Torrent::extract_announce_list(&mut dict).unwrap(),
@@ -885,18 +1304,175 @@ mod torrent_read_tests {
     }
 
     #[test]
-    fn extract_files_ok() {
-        let mut dict = HashMap::from_iter(
-            vec![(
-                "files".to_owned(),
-                bencode_elem!([{
-                    ("length", 42),
-                    ("path", ["root", ".bashrc"]),
-                    ("comment", "no comment"),
-                }]),
-            )]
-            .into_iter(),
+    fn extract_urls_ok() {
+        let mut dict = HashMap::from_iter(vec![(
+            "url-list".to_owned(),
+            bencode_elem!(["url1", "url2"]),
+        )]);
+
+        assert_eq!(
+            Torrent::extract_urls(&mut dict, "url-list"),
+            Some(vec!["url1".to_owned(), "url2".to_owned()])
+        );
+        assert!(dict.is_empty());
+    }
+
+    #[test]
+    fn extract_urls_single_url() {
+        let mut dict = HashMap::from_iter(vec![("httpseeds".to_owned(), bencode_elem!("url"))]);
+
+        assert_eq!(
+            Torrent::extract_urls(&mut dict, "httpseeds"),
+            Some(vec!["url".to_owned()])
+        );
+    }
+
+    #[test]
+    fn extract_urls_empty_string() {
+        let mut dict = HashMap::from_iter(vec![("url-list".to_owned(), bencode_elem!(""))]);
+
+        assert_eq!(
+            Torrent::extract_urls(&mut dict, "url-list"),
+            Some(Vec::new())
+        );
+    }
+
+    #[test]
+    fn extract_urls_missing() {
+        let mut dict = HashMap::new();
+        assert_eq!(Torrent::extract_urls(&mut dict, "url-list"), None);
+    }
+
+    #[test]
+    fn extract_urls_not_string_or_list() {
+        let mut dict = HashMap::from_iter(vec![("url-list".to_owned(), bencode_elem!(1))]);
+        assert_eq!(Torrent::extract_urls(&mut dict, "url-list"), None);
+        // malformed `url-list` is kept as is
+        assert_eq!(
+            dict,
+            HashMap::from_iter(vec![("url-list".to_owned(), bencode_elem!(1))])
+        );
+    }
+
+    #[test]
+    fn extract_urls_non_string_element() {
+        let mut dict =
+            HashMap::from_iter(vec![("httpseeds".to_owned(), bencode_elem!(["url", 1]))]);
+
+        assert_eq!(Torrent::extract_urls(&mut dict, "httpseeds"), None);
+        assert_eq!(
+            dict,
+            HashMap::from_iter(vec![("httpseeds".to_owned(), bencode_elem!(["url", 1]))])
         );
+    }
+
+    #[test]
+    fn extract_optional_string_ok() {
+        let mut dict = HashMap::from_iter(vec![("comment".to_owned(), bencode_elem!("text"))]);
+        assert_eq!(
+            Torrent::extract_optional_string(&mut dict, "comment"),
+            Some("text".to_owned())
+        );
+        assert!(dict.is_empty());
+    }
+
+    #[test]
+    fn extract_optional_string_missing() {
+        let mut dict = HashMap::new();
+        assert_eq!(Torrent::extract_optional_string(&mut dict, "comment"), None);
+    }
+
+    #[test]
+    fn extract_optional_string_not_string() {
+        // e.g. a comment in a non-UTF8 encoding
+        let mut dict = HashMap::from_iter(vec![("comment".to_owned(), bencode_elem!((0xff)))]);
+        assert_eq!(Torrent::extract_optional_string(&mut dict, "comment"), None);
+        assert_eq!(
+            dict,
+            HashMap::from_iter(vec![("comment".to_owned(), bencode_elem!((0xff)))])
+        );
+    }
+
+    #[test]
+    fn extract_optional_integer_ok() {
+        let mut dict = HashMap::from_iter(vec![("creation date".to_owned(), bencode_elem!(42))]);
+        assert_eq!(
+            Torrent::extract_optional_integer(&mut dict, "creation date"),
+            Some(42)
+        );
+        assert!(dict.is_empty());
+    }
+
+    #[test]
+    fn extract_optional_integer_not_integer() {
+        let mut dict = HashMap::from_iter(vec![("creation date".to_owned(), bencode_elem!("42"))]);
+        assert_eq!(
+            Torrent::extract_optional_integer(&mut dict, "creation date"),
+            None
+        );
+        assert_eq!(
+            dict,
+            HashMap::from_iter(vec![("creation date".to_owned(), bencode_elem!("42"))])
+        );
+    }
+
+    #[test]
+    fn extract_nodes_ok() {
+        let mut dict = HashMap::from_iter(vec![(
+            "nodes".to_owned(),
+            bencode_elem!([["127.0.0.1", 6881], ["::1", 6882]]),
+        )]);
+        assert_eq!(
+            Torrent::extract_nodes(&mut dict),
+            Some(vec![
+                ("127.0.0.1".to_owned(), 6881),
+                ("::1".to_owned(), 6882)
+            ])
+        );
+        assert!(dict.is_empty());
+    }
+
+    #[test]
+    fn extract_nodes_missing() {
+        let mut dict = HashMap::new();
+        assert_eq!(Torrent::extract_nodes(&mut dict), None);
+    }
+
+    #[test]
+    fn extract_nodes_invalid_port() {
+        let mut dict = HashMap::from_iter(vec![(
+            "nodes".to_owned(),
+            bencode_elem!([["127.0.0.1", 6881], ["::1", 65536]]),
+        )]);
+        assert_eq!(Torrent::extract_nodes(&mut dict), None);
+        // malformed `nodes` is kept as is
+        assert_eq!(
+            dict,
+            HashMap::from_iter(vec![(
+                "nodes".to_owned(),
+                bencode_elem!([["127.0.0.1", 6881], ["::1", 65536]]),
+            )])
+        );
+    }
+
+    #[test]
+    fn extract_nodes_not_pair() {
+        let mut dict =
+            HashMap::from_iter(vec![("nodes".to_owned(), bencode_elem!([["127.0.0.1"]]))]);
+        assert_eq!(Torrent::extract_nodes(&mut dict), None);
+        assert!(dict.contains_key("nodes"));
+    }
+
+    #[test]
+    fn extract_files_ok() {
+        let mut dict = HashMap::from_iter(vec![(
+            "files".to_owned(),
+            bencode_elem!([{
+                ("length", 42),
+                ("path", ["root", ".bashrc"]),
+                ("comment", "no comment"),
+            }]),
+        )]);
 
         let files = Torrent::extract_files(&mut dict).unwrap().unwrap();
         assert_eq!(files.len(), 1);
@@ -905,6 +1481,9 @@ mod torrent_read_tests {
             File {
                 length: 42,
                 path: PathBuf::from("root/.bashrc"),
+                attr: None,
+                sha1: None,
+                symlink_path: None,
                 extra_fields: Some(HashMap::from_iter(
                     vec![("comment".to_owned(), bencode_elem!("no comment"))].into_iter()
                 )),
@@ -944,18 +1523,19 @@ mod torrent_read_tests {
 
     #[test]
     fn extract_length_ok() {
-        let mut dict =
-            HashMap::from_iter(vec![("length".to_owned(), bencode_elem!(42))].into_iter());
+        let mut dict = HashMap::from_iter(vec![("length".to_owned(), bencode_elem!(42))]);
         assert_eq!(Torrent::extract_length(&mut dict, &None).unwrap(), 42);
     }
 
     #[test]
     fn extract_length_conflict_with_files() {
-        let mut dict =
-            HashMap::from_iter(vec![("length".to_owned(), bencode_elem!(42))].into_iter());
+        let mut dict = HashMap::from_iter(vec![("length".to_owned(), bencode_elem!(42))]);
         let files = Some(vec![File {
             length: 100,
             path: PathBuf::new(),
+            attr: None,
+            sha1: None,
+            symlink_path: None,
             extra_fields: None,
         }]);
 
@@ -969,8 +1549,7 @@ mod torrent_read_tests {
 
     #[test]
     fn extract_length_not_integer() {
-        let mut dict =
-            HashMap::from_iter(vec![("length".to_owned(), bencode_elem!("42"))].into_iter());
+        let mut dict = HashMap::from_iter(vec![("length".to_owned(), bencode_elem!("42"))]);
 
         match Torrent::extract_length(&mut dict, &None) {
             Err(Error(ErrorKind::MalformedTorrent(m), _)) => {
@@ -998,6 +1577,9 @@ mod torrent_read_tests {
         let files = Some(vec![File {
             length: 100,
             path: PathBuf::new(),
+            attr: None,
+            sha1: None,
+            symlink_path: None,
             extra_fields: None,
         }]);
 
@@ -1011,11 +1593,17 @@ mod torrent_read_tests {
             File {
                 length: 1,
                 path: PathBuf::new(),
+                attr: None,
+                sha1: None,
+                symlink_path: None,
                 extra_fields: None,
             },
             File {
-                length: i64::max_value(),
+                length: i64::MAX,
                 path: PathBuf::new(),
+                attr: None,
+                sha1: None,
+                symlink_path: None,
                 extra_fields: None,
             },
         ]);
@@ -1030,8 +1618,7 @@ mod torrent_read_tests {
 
     #[test]
     fn extract_name_ok() {
-        let mut dict =
-            HashMap::from_iter(vec![("name".to_owned(), bencode_elem!("not name"))].into_iter());
+        let mut dict = HashMap::from_iter(vec![("name".to_owned(), bencode_elem!("not name"))]);
 
         assert_eq!(
             Torrent::extract_name(&mut dict).unwrap(),
@@ -1041,13 +1628,10 @@ mod torrent_read_tests {
 
     #[test]
     fn extract_name_not_string() {
-        let mut dict = HashMap::from_iter(
-            vec![(
-                "name".to_owned(),
-                BencodeElem::Bytes("not name".as_bytes().to_vec()),
-            )]
-            .into_iter(),
-        );
+        let mut dict = HashMap::from_iter(vec![(
+            "name".to_owned(),
+            BencodeElem::Bytes("not name".as_bytes().to_vec()),
+        )]);
 
         match Torrent::extract_name(&mut dict) {
             Err(Error(ErrorKind::MalformedTorrent(m), _)) => assert_eq!(
@@ -1072,15 +1656,13 @@ mod torrent_read_tests {
 
     #[test]
     fn extract_piece_length_ok() {
-        let mut dict =
-            HashMap::from_iter(vec![("piece length".to_owned(), bencode_elem!(1))].into_iter());
+        let mut dict = HashMap::from_iter(vec![("piece length".to_owned(), bencode_elem!(1))]);
         assert_eq!(Torrent::extract_piece_length(&mut dict).unwrap(), 1);
     }
 
     #[test]
     fn extract_piece_length_not_integer() {
-        let mut dict =
-            HashMap::from_iter(vec![("piece length".to_owned(), bencode_elem!("1"))].into_iter());
+        let mut dict = HashMap::from_iter(vec![("piece length".to_owned(), bencode_elem!("1"))]);
 
         match Torrent::extract_piece_length(&mut dict) {
             Err(Error(ErrorKind::MalformedTorrent(m), _)) => {
@@ -1104,8 +1686,7 @@ mod torrent_read_tests {
 
     #[test]
     fn extract_piece_length_not_positive() {
-        let mut dict =
-            HashMap::from_iter(vec![("piece length".to_owned(), bencode_elem!(0))].into_iter());
+        let mut dict = HashMap::from_iter(vec![("piece length".to_owned(), bencode_elem!(0))]);
 
         match Torrent::extract_piece_length(&mut dict) {
             Err(Error(ErrorKind::MalformedTorrent(m), _)) => {
@@ -1117,16 +1698,13 @@ mod torrent_read_tests {
 
     #[test]
     fn extract_pieces_ok() {
-        let mut dict = HashMap::from_iter(
-            vec![(
-                "pieces".to_owned(),
-                BencodeElem::Bytes(vec![
-                    0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x09, 0x0a, 0x0b, 0x0c,
-                    0x0d, 0x0e, 0x0f, 0x10, 0x11, 0x12, 0x13,
-                ]),
-            )]
-            .into_iter(),
-        );
+        let mut dict = HashMap::from_iter(vec![(
+            "pieces".to_owned(),
+            BencodeElem::Bytes(vec![
+                0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x09, 0x0a, 0x0b, 0x0c, 0x0d,
+                0x0e, 0x0f, 0x10, 0x11, 0x12, 0x13,
+            ]),
+        )]);
 
         let pieces = Torrent::extract_pieces(&mut dict).unwrap();
         assert_eq!(pieces.len(), 1);
@@ -1142,8 +1720,7 @@ mod torrent_read_tests {
 
     #[test]
     fn extract_pieces_not_bytes() {
-        let mut dict =
-            HashMap::from_iter(vec![("pieces".to_owned(), bencode_elem!("???"))].into_iter());
+        let mut dict = HashMap::from_iter(vec![("pieces".to_owned(), bencode_elem!("???"))]);
 
         match Torrent::extract_pieces(&mut dict) {
             Err(Error(ErrorKind::MalformedTorrent(m), _)) => {
@@ -1167,8 +1744,7 @@ mod torrent_read_tests {
 
     #[test]
     fn extract_pieces_empty() {
-        let mut dict =
-            HashMap::from_iter(vec![("pieces".to_owned(), bencode_elem!(()))].into_iter());
+        let mut dict = HashMap::from_iter(vec![("pieces".to_owned(), bencode_elem!(()))]);
 
         match Torrent::extract_pieces(&mut dict) {
             Err(Error(ErrorKind::MalformedTorrent(m), _)) => {
@@ -1180,16 +1756,13 @@ mod torrent_read_tests {
 
     #[test]
     fn extract_pieces_invalid_length() {
-        let mut dict = HashMap::from_iter(
-            vec![(
-                "pieces".to_owned(),
-                BencodeElem::Bytes(vec![
-                    0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x09, 0x0a, 0x0b, 0x0c,
-                    0x0d, 0x0e, 0x0f, 0x10, 0x11, 0x12,
-                ]),
-            )]
-            .into_iter(),
-        );
+        let mut dict = HashMap::from_iter(vec![(
+            "pieces".to_owned(),
+            BencodeElem::Bytes(vec![
+                0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x09, 0x0a, 0x0b, 0x0c, 0x0d,
+                0x0e, 0x0f, 0x10, 0x11, 0x12,
+            ]),
+        )]);
 
         match Torrent::extract_pieces(&mut dict) {
             Err(Error(ErrorKind::MalformedTorrent(m), _)) => assert_eq!(
@@ -0,0 +1,202 @@
+use super::*;
+use std::cmp;
+use std::fs;
+use std::io::{self, BufReader, Read};
+
+// A file in the torrent and what is found on disk.
+struct FileOnDisk {
+    length: u64,
+    // `None` if the file is not read (i.e. padding files and symlinks)
+    path: Option<PathBuf>,
+    // `Ok`, `Missing` or `Short`
+    status: VerificationStatus,
+}
+
+impl FileOnDisk {
+    fn inspect<P>(file: &File, parent: P) -> Result<FileOnDisk>
+    where
+        P: AsRef<Path>,
+    {
+        let length = util::i64_to_u64(file.length)?;
+        if file.is_padding() || file.is_symlink() {
+            return Ok(FileOnDisk {
+                length,
+                path: None,
+                status: VerificationStatus::Ok,
+            });
+        }
+
+        let path = file.absolute_path(parent)?;
+        let status = match fs::metadata(&path) {
+            Ok(ref metadata) if !metadata.is_file() => VerificationStatus::Missing,
+            Ok(ref metadata) if metadata.len() < length => VerificationStatus::Short,
+            Ok(_) => VerificationStatus::Ok,
+            Err(ref e) if e.kind() == io::ErrorKind::NotFound => VerificationStatus::Missing,
+            Err(e) => return Err(e.into()),
+        };
+
+        Ok(FileOnDisk {
+            length,
+            path: Some(path),
+            status,
+        })
+    }
+}
+
+impl VerificationReport {
+    /// Check if all pieces are `Ok`, i.e. the content is complete
+    /// and can be seeded.
+    pub fn is_complete(&self) -> bool {
+        self.pieces
+            .iter()
+            .all(|&status| status == VerificationStatus::Ok)
+    }
+}
+
+impl Torrent {
+    /// Verify the content in `base_dir` against `pieces`, e.g. to
+    /// check which pieces have to be downloaded when resuming.
+    ///
+    /// The content is expected to be laid out in the same way
+    /// as clients save it, i.e. a single-file torrent is at `base_dir/name`
+    /// and the files of a multi-file torrent are at `base_dir/name/path`.
+    /// Paths are constructed with [`File::absolute_path()`], so `base_dir`
+    /// must be an absolute path, and `Err` is returned if any path (including
+    /// `name`) would lead outside of `base_dir`.
+    ///
+    /// Padding files as defined in [BEP 47] are not read, as clients usually
+    /// don't create them. They are treated as zeros. Symbolic links
+    /// (which have no content) are not read either.
+    ///
+    /// The returned report contains the status of each piece and each file:
+    /// - A piece is `Missing` (or `Short`) if any of the files it covers
+    ///   is missing (or too short to fill the piece). Otherwise it is `Ok`
+    ///   or `Mismatch` depending on its hash.
+    /// - A file is `Missing` or `Short` if it is so on disk. Otherwise
+    ///   it is `Ok` if all the pieces it covers are `Ok`, and `Mismatch` if
+    ///   a piece covering only this file (and padding files) is `Mismatch`.
+    ///   If the only failed pieces covering a file also cover other files,
+    ///   then the file is `Unknown`, as its own bytes might be intact.
+    ///   Only the first `length` bytes of a file are checked. Padding files
+    ///   and symbolic links are always `Ok`.
+    ///
+    /// If an IO error other than "file not found" is encountered,
+    /// then `Err(error)` will be returned.
+    ///
+    /// [`File::absolute_path()`]: struct.File.html#method.absolute_path
+    /// [BEP 47]: http://bittorrent.org/beps/bep_0047.html
+    pub fn verify<P>(&self, base_dir: P) -> Result<VerificationReport>
+    where
+        P: AsRef<Path>,
+    {
+        // a single-file torrent is effectively a file named `name`
+        let root = File {
+            length: self.length,
+            path: PathBuf::from(&self.name),
+            attr: None,
+            sha1: None,
+            symlink_path: None,
+            extra_fields: None,
+        };
+        let files = match self.files {
+            Some(ref files) => {
+                let parent = root.absolute_path(base_dir)?;
+                files
+                    .iter()
+                    .map(|file| FileOnDisk::inspect(file, &parent))
+                    .collect::<Result<Vec<_>>>()?
+            }
+            None => vec![FileOnDisk::inspect(&root, base_dir)?],
+        };
+
+        let piece_length = util::i64_to_usize(self.piece_length)?;
+        let mut report = VerificationReport {
+            pieces: Vec::with_capacity(self.pieces.len()),
+            files: files.iter().map(|file| file.status).collect(),
+        };
+
+        // files are read sequentially, so only 1 of them is open at a time
+        let mut current = 0;
+        let mut offset = 0;
+        let mut reader = None;
+
+        for expected in &self.pieces {
+            let mut piece = Vec::with_capacity(piece_length);
+            let mut status = VerificationStatus::Ok;
+            let mut covered = Vec::new();
+
+            while (piece.len() < piece_length) && (current < files.len()) {
+                let file = &files[current];
+                let to_read = cmp::min(
+                    file.length - offset,
+                    util::usize_to_u64(piece_length - piece.len())?,
+                );
+
+                if to_read > 0 {
+                    covered.push(current);
+                    let filled = piece.len() + util::u64_to_usize(to_read)?;
+
+                    match file.path {
+                        Some(ref path) if file.status != VerificationStatus::Missing => {
+                            if reader.is_none() {
+                                reader = Some(BufReader::new(fs::File::open(path)?));
+                            }
+                            let reader = reader.as_mut().expect("`reader` is set above.");
+                            let read = reader.take(to_read).read_to_end(&mut piece)?;
+
+                            if util::usize_to_u64(read)? < to_read {
+                                piece.resize(filled, 0);
+                                if status != VerificationStatus::Missing {
+                                    status = VerificationStatus::Short;
+                                }
+                            }
+                        }
+                        Some(_) => {
+                            piece.resize(filled, 0);
+                            status = VerificationStatus::Missing;
+                        }
+                        None => piece.resize(filled, 0),
+                    }
+                    offset += to_read;
+                }
+
+                if offset == file.length {
+                    current += 1;
+                    offset = 0;
+                    reader = None;
+                }
+            }
+
+            if (status == VerificationStatus::Ok) && (Sha1::digest(&piece)[..] != expected[..]) {
+                status = VerificationStatus::Mismatch;
+            }
+            if status != VerificationStatus::Ok {
+                // padding files and symlinks are always `Ok`
+                covered.retain(|&i| files[i].path.is_some());
+                // if the piece covers other files as well, then
+                // the file's own bytes might be intact
+                let file_status =
+                    if (status == VerificationStatus::Mismatch) && (covered.len() == 1) {
+                        VerificationStatus::Mismatch
+                    } else {
+                        VerificationStatus::Unknown
+                    };
+                for &i in &covered {
+                    // `Mismatch` takes precedence over `Unknown`
+                    match report.files[i] {
+                        VerificationStatus::Ok => report.files[i] = file_status,
+                        VerificationStatus::Unknown
+                            if file_status == VerificationStatus::Mismatch =>
+                        {
+                            report.files[i] = file_status
+                        }
+                        _ => (),
+                    }
+                }
+            }
+            report.pieces.push(status);
+        }
+
+        Ok(report)
+    }
+}
@@ -1,6 +1,8 @@
 use super::*;
+use bencode::write::write_dictionary_with_raw_value;
 use bencode::BencodeElem;
 use std::io::{BufWriter, Write};
+use torrent::is_raw_info_current;
 
 impl File {
     pub(crate) fn into_bencode_elem(self) -> BencodeElem {
@@ -17,6 +19,26 @@ impl File {
             ),
         );
 
+        if let Some(attr) = self.attr {
+            result.insert("attr".to_owned(), BencodeElem::String(attr.to_string()));
+        }
+        if let Some(sha1) = self.sha1 {
+            result.insert("sha1".to_owned(), BencodeElem::Bytes(sha1));
+        }
+        if let Some(symlink_path) = self.symlink_path {
+            result.insert(
+                "symlink path".to_owned(),
+                BencodeElem::List(
+                    symlink_path
+                        .iter()
+                        .map(|component| {
+                            BencodeElem::String(component.to_string_lossy().into_owned())
+                        })
+                        .collect(),
+                ),
+            );
+        }
+
         if let Some(extra_fields) = self.extra_fields {
             result.extend(extra_fields);
         }
@@ -27,10 +49,20 @@ impl File {
 
 impl Torrent {
     /// Encode `self` as bencode and write the result to `dst`.
+    ///
+    /// If `raw_info` is `Some` and still matches the fields that belong to
+    /// `info` (i.e. none of them has been modified since parsing), its bytes
+    /// are written verbatim as `info`, so that the info hash does not change.
+    /// Otherwise `info` is encoded from those fields.
     pub fn write_into<W>(self, dst: &mut W) -> Result<()>
     where
         W: Write,
     {
+        // checked before the fields are moved out of `self`
+        let write_raw_info = self
+            .raw_info
+            .as_ref()
+            .is_some_and(|raw_info| is_raw_info_current(raw_info, &self.construct_info().encode()));
         let mut result: HashMap<String, BencodeElem> = HashMap::new();
         let mut info: HashMap<String, BencodeElem> = HashMap::new();
 
@@ -55,6 +87,56 @@ impl Torrent {
             );
         }
 
+        if let Some(urls) = self.url_list {
+            result.insert(
+                "url-list".to_owned(),
+                Self::encode_urls(urls, self.url_list_is_string),
+            );
+        }
+
+        if let Some(urls) = self.http_seeds {
+            result.insert(
+                "httpseeds".to_owned(),
+                Self::encode_urls(urls, self.http_seeds_is_string),
+            );
+        }
+
+        if let Some(creation_date) = self.creation_date {
+            result.insert(
+                "creation date".to_owned(),
+                BencodeElem::Integer(creation_date),
+            );
+        }
+
+        if let Some(comment) = self.comment {
+            result.insert("comment".to_owned(), BencodeElem::String(comment));
+        }
+
+        if let Some(created_by) = self.created_by {
+            result.insert("created by".to_owned(), BencodeElem::String(created_by));
+        }
+
+        if let Some(encoding) = self.encoding {
+            result.insert("encoding".to_owned(), BencodeElem::String(encoding));
+        }
+
+        if let Some(nodes) = self.nodes {
+            result.insert(
+                "nodes".to_owned(),
+                BencodeElem::List(
+                    nodes
+                        .into_iter()
+                        .map(|(host, port)| {
+                            BencodeElem::List(vec![
+                                BencodeElem::String(host),
+                                BencodeElem::Integer(Integer::from(port)),
+                            ])
+                        })
+                        .collect(),
+                ),
+            );
+        }
+
         if let Some(files) = self.files {
             info.insert(
                 "files".to_owned(),
@@ -78,18 +160,28 @@ impl Torrent {
             "pieces".to_owned(),
             BencodeElem::Bytes(self.pieces.into_iter().flatten().collect()),
         );
+        if let Some(source) = self.source {
+            info.insert("source".to_owned(), BencodeElem::String(source));
+        }
 
         if let Some(extra_info_fields) = self.extra_info_fields {
             info.extend(extra_info_fields);
         }
 
-        result.insert("info".to_owned(), BencodeElem::Dictionary(info));
-
         if let Some(extra_fields) = self.extra_fields {
             result.extend(extra_fields);
         }
 
-        BencodeElem::Dictionary(result).write_into(dst)
+        match self.raw_info {
+            // write the original bytes so that the info hash is preserved
+            Some(raw_info) if write_raw_info => {
+                write_dictionary_with_raw_value(&result, "info", &raw_info, dst)
+            }
+            _ => {
+                result.insert("info".to_owned(), BencodeElem::Dictionary(info));
+                BencodeElem::Dictionary(result).write_into(dst)
+            }
+        }
     }
 
     /// Encode `self` as bencode and write the result to `path`.
@@ -118,6 +210,16 @@ impl Torrent {
         self.write_into(&mut result)?;
         Ok(result)
     }
+
+    // `url-list` and `httpseeds` can be either a list or a single string,
+    // in which case an empty string means "no url"
+    fn encode_urls(mut urls: Vec<String>, is_string: bool) -> BencodeElem {
+        match urls.len() {
+            0 if is_string => BencodeElem::String(String::new()),
+            1 if is_string => BencodeElem::String(urls.remove(0)),
+            _ => BencodeElem::List(urls.into_iter().map(BencodeElem::String).collect()),
+        }
+    }
 }
 
 #[cfg(test)]
@@ -130,6 +232,9 @@ mod file_write_tests {
         let file = File {
             length: 42,
             path: PathBuf::from("dir1/dir2/file"),
+            attr: None,
+            sha1: None,
+            symlink_path: None,
             extra_fields: None,
         };
 
@@ -144,9 +249,13 @@ mod file_write_tests {
         let file = File {
             length: 42,
             path: PathBuf::from("dir1/dir2/file"),
-            extra_fields: Some(HashMap::from_iter(
-                vec![("comment".to_owned(), bencode_elem!("no comment"))].into_iter(),
-            )),
+            attr: None,
+            sha1: None,
+            symlink_path: None,
+            extra_fields: Some(HashMap::from_iter(vec![(
+                "comment".to_owned(),
+                bencode_elem!("no comment"),
+            )])),
         };
 
         assert_eq!(
@@ -158,6 +267,57 @@ mod file_write_tests {
             })
         )
     }
+
+    #[test]
+    fn into_bencode_elem_with_attr() {
+        let file = File {
+            length: 0,
+            path: PathBuf::from("link"),
+            attr: Some(FileAttributes {
+                executable: true,
+                symlink: true,
+                ..Default::default()
+            }),
+            sha1: Some(vec![0xff; 20]),
+            symlink_path: Some(PathBuf::from("dir/file")),
+            extra_fields: None,
+        };
+
+        let sha1 = BencodeElem::Bytes(vec![0xff; 20]);
+        assert_eq!(
+            file.into_bencode_elem(),
+            bencode_elem!({
+                ("attr", "xl"),
+                ("length", 0),
+                ("path", ["link"]),
+                ("sha1", sha1),
+                ("symlink path", ["dir", "file"]),
+            })
+        )
+    }
+
+    #[test]
+    fn into_bencode_elem_with_parsed_attr() {
+        let file = File {
+            length: 0,
+            path: PathBuf::from("link"),
+            attr: Some(FileAttributes::from("lx")),
+            sha1: None,
+            symlink_path: Some(PathBuf::from("dir/file")),
+            extra_fields: None,
+        };
+
+        // the original order is kept
+        assert_eq!(
+            file.into_bencode_elem(),
+            bencode_elem!({
+                ("attr", "lx"),
+                ("length", 0),
+                ("path", ["link"]),
+                ("symlink path", ["dir", "file"]),
+            })
+        )
+    }
 }
 
 #[cfg(test)]
@@ -172,6 +332,14 @@ mod torrent_write_tests {
         let torrent = Torrent {
             announce: Some("url".to_owned()),
             announce_list: None,
+            url_list: None,
+            http_seeds: None,
+            creation_date: None,
+            comment: None,
+            created_by: None,
+            encoding: None,
+            nodes: None,
+            source: None,
             length: 4,
             files: None,
             name: "sample".to_owned(),
@@ -179,6 +347,9 @@ mod torrent_write_tests {
             pieces: vec![vec![1, 2], vec![3, 4]],
             extra_fields: None,
             extra_info_fields: None,
+            raw_info: None,
+            url_list_is_string: false,
+            http_seeds_is_string: false,
         };
         let mut result = Vec::new();
 
@@ -206,6 +377,14 @@ mod torrent_write_tests {
                 vec!["url1".to_owned(), "url2".to_owned()],
                 vec!["url3".to_owned(), "url4".to_owned()],
             ]),
+            url_list: None,
+            http_seeds: None,
+            creation_date: None,
+            comment: None,
+            created_by: None,
+            encoding: None,
+            nodes: None,
+            source: None,
             length: 4,
             files: None,
             name: "sample".to_owned(),
@@ -213,6 +392,9 @@ mod torrent_write_tests {
             pieces: vec![vec![1, 2], vec![3, 4]],
             extra_fields: None,
             extra_info_fields: None,
+            raw_info: None,
+            url_list_is_string: false,
+            http_seeds_is_string: false,
         };
         let mut result = Vec::new();
 
@@ -233,24 +415,127 @@ mod torrent_write_tests {
         );
     }
 
+    #[test]
+    fn write_with_web_seeds() {
+        let torrent = Torrent {
+            announce: Some("url".to_owned()),
+            announce_list: None,
+            url_list: Some(vec!["ws1".to_owned(), "ws2".to_owned()]),
+            http_seeds: Some(vec!["hs".to_owned()]),
+            creation_date: None,
+            comment: None,
+            created_by: None,
+            encoding: None,
+            nodes: None,
+            source: None,
+            length: 4,
+            files: None,
+            name: "sample".to_owned(),
+            piece_length: 2,
+            pieces: vec![vec![1, 2], vec![3, 4]],
+            extra_fields: None,
+            extra_info_fields: None,
+            raw_info: None,
+            url_list_is_string: false,
+            http_seeds_is_string: false,
+        };
+        let mut result = Vec::new();
+
+        torrent.write_into(&mut result).unwrap();
+        assert_eq!(
+            result,
+            bencode_elem!({
+                ("announce", "url"),
+                ("httpseeds", ["hs"]),
+                ("info", {
+                    ("length", 4),
+                    ("name", "sample"),
+                    ("piece length", 2),
+                    ("pieces", (1, 2, 3, 4)),
+                }),
+                ("url-list", ["ws1", "ws2"]),
+            })
+            .encode()
+        );
+    }
+
+    #[test]
+    fn write_with_metainfo_fields() {
+        let torrent = Torrent {
+            announce: Some("url".to_owned()),
+            announce_list: None,
+            url_list: None,
+            http_seeds: None,
+            creation_date: Some(1_500_000_000),
+            comment: Some("no comment".to_owned()),
+            created_by: Some("lava_torrent".to_owned()),
+            encoding: Some("UTF-8".to_owned()),
+            nodes: Some(vec![
+                ("127.0.0.1".to_owned(), 6881),
+                ("router.example.com".to_owned(), 6882),
+            ]),
+            source: Some("SRC".to_owned()),
+            length: 4,
+            files: None,
+            name: "sample".to_owned(),
+            piece_length: 2,
+            pieces: vec![vec![1, 2], vec![3, 4]],
+            extra_fields: None,
+            extra_info_fields: None,
+            raw_info: None,
+            url_list_is_string: false,
+            http_seeds_is_string: false,
+        };
+        let mut result = Vec::new();
+
+        torrent.write_into(&mut result).unwrap();
+        assert_eq!(
+            result,
+            bencode_elem!({
+                ("announce", "url"),
+                ("comment", "no comment"),
+                ("created by", "lava_torrent"),
+                ("creation date", 1_500_000_000),
+                ("encoding", "UTF-8"),
+                ("info", {
+                    ("length", 4),
+                    ("name", "sample"),
+                    ("piece length", 2),
+                    ("pieces", (1, 2, 3, 4)),
+                    ("source", "SRC"),
+                }),
+                ("nodes", [["127.0.0.1", 6881], ["router.example.com", 6882]]),
+            })
+            .encode()
+        );
+    }
+
     #[test]
     fn write_with_extra_fields() {
         let torrent = Torrent {
             announce: Some("url".to_owned()),
             announce_list: None,
+            url_list: None,
+            http_seeds: None,
+            creation_date: None,
+            comment: None,
+            created_by: None,
+            encoding: None,
+            nodes: None,
+            source: None,
             length: 4,
             files: None,
             name: "sample".to_owned(),
             piece_length: 2,
             pieces: vec![vec![1, 2], vec![3, 4]],
-            extra_fields: Some(HashMap::from_iter(
-                vec![
-                    ("comment2".to_owned(), bencode_elem!("no comment")),
-                    ("comment1".to_owned(), bencode_elem!("no comment")),
-                ]
-                .into_iter(),
-            )),
+            extra_fields: Some(HashMap::from_iter(vec![
+                ("comment2".to_owned(), bencode_elem!("no comment")),
+                ("comment1".to_owned(), bencode_elem!("no comment")),
+            ])),
             extra_info_fields: None,
+            raw_info: None,
+            url_list_is_string: false,
+            http_seeds_is_string: false,
         };
         let mut result = Vec::new();
 
@@ -277,19 +562,27 @@ mod torrent_write_tests {
         let torrent = Torrent {
             announce: Some("url".to_owned()),
             announce_list: None,
+            url_list: None,
+            http_seeds: None,
+            creation_date: None,
+            comment: None,
+            created_by: None,
+            encoding: None,
+            nodes: None,
+            source: None,
             length: 4,
             files: None,
             name: "sample".to_owned(),
             piece_length: 2,
             pieces: vec![vec![1, 2], vec![3, 4]],
             extra_fields: None,
-            extra_info_fields: Some(HashMap::from_iter(
-                vec![
-                    ("comment2".to_owned(), bencode_elem!("no comment")),
-                    ("comment1".to_owned(), bencode_elem!("no comment")),
-                ]
-                .into_iter(),
-            )),
+            extra_info_fields: Some(HashMap::from_iter(vec![
+                ("comment2".to_owned(), bencode_elem!("no comment")),
+                ("comment1".to_owned(), bencode_elem!("no comment")),
+            ])),
+            raw_info: None,
+            url_list_is_string: false,
+            http_seeds_is_string: false,
         };
         let mut result = Vec::new();
 
@@ -316,16 +609,30 @@ mod torrent_write_tests {
         let torrent = Torrent {
             announce: Some("url".to_owned()),
             announce_list: None,
+            url_list: None,
+            http_seeds: None,
+            creation_date: None,
+            comment: None,
+            created_by: None,
+            encoding: None,
+            nodes: None,
+            source: None,
             length: 4,
             files: Some(vec![
                 File {
                     length: 2,
                     path: PathBuf::from("dir1/dir2/file1"),
+                    attr: None,
+                    sha1: None,
+                    symlink_path: None,
                     extra_fields: None,
                 },
                 File {
                     length: 2,
                     path: PathBuf::from("dir1/dir2/file2"),
+                    attr: None,
+                    sha1: None,
+                    symlink_path: None,
                     extra_fields: None,
                 },
             ]),
@@ -334,6 +641,9 @@ mod torrent_write_tests {
             pieces: vec![vec![1, 2], vec![3, 4]],
             extra_fields: None,
             extra_info_fields: None,
+            raw_info: None,
+            url_list_is_string: false,
+            http_seeds_is_string: false,
         };
         let mut result = Vec::new();
 
@@ -361,6 +671,14 @@ mod torrent_write_tests {
         let torrent = Torrent {
             announce: Some("url".to_owned()),
             announce_list: None,
+            url_list: None,
+            http_seeds: None,
+            creation_date: None,
+            comment: None,
+            created_by: None,
+            encoding: None,
+            nodes: None,
+            source: None,
             length: 4,
             files: None,
             name: "sample".to_owned(),
@@ -368,6 +686,9 @@ mod torrent_write_tests {
             pieces: vec![vec![1, 2], vec![3, 4]],
             extra_fields: None,
             extra_info_fields: None,
+            raw_info: None,
+            url_list_is_string: false,
+            http_seeds_is_string: false,
         };
 
         assert_eq!(
@@ -393,6 +714,14 @@ mod torrent_write_tests {
                 vec!["url1".to_owned(), "url2".to_owned()],
                 vec!["url3".to_owned(), "url4".to_owned()],
             ]),
+            url_list: None,
+            http_seeds: None,
+            creation_date: None,
+            comment: None,
+            created_by: None,
+            encoding: None,
+            nodes: None,
+            source: None,
             length: 4,
             files: None,
             name: "sample".to_owned(),
@@ -400,6 +729,9 @@ mod torrent_write_tests {
             pieces: vec![vec![1, 2], vec![3, 4]],
             extra_fields: None,
             extra_info_fields: None,
+            raw_info: None,
+            url_list_is_string: false,
+            http_seeds_is_string: false,
         };
 
         assert_eq!(
@@ -423,19 +755,27 @@ mod torrent_write_tests {
         let torrent = Torrent {
             announce: Some("url".to_owned()),
             announce_list: None,
+            url_list: None,
+            http_seeds: None,
+            creation_date: None,
+            comment: None,
+            created_by: None,
+            encoding: None,
+            nodes: None,
+            source: None,
             length: 4,
             files: None,
             name: "sample".to_owned(),
             piece_length: 2,
             pieces: vec![vec![1, 2], vec![3, 4]],
-            extra_fields: Some(HashMap::from_iter(
-                vec![
-                    ("comment2".to_owned(), bencode_elem!("no comment")),
-                    ("comment1".to_owned(), bencode_elem!("no comment")),
-                ]
-                .into_iter(),
-            )),
+            extra_fields: Some(HashMap::from_iter(vec![
+                ("comment2".to_owned(), bencode_elem!("no comment")),
+                ("comment1".to_owned(), bencode_elem!("no comment")),
+            ])),
             extra_info_fields: None,
+            raw_info: None,
+            url_list_is_string: false,
+            http_seeds_is_string: false,
         };
 
         assert_eq!(
@@ -460,19 +800,27 @@ mod torrent_write_tests {
         let torrent = Torrent {
             announce: Some("url".to_owned()),
             announce_list: None,
+            url_list: None,
+            http_seeds: None,
+            creation_date: None,
+            comment: None,
+            created_by: None,
+            encoding: None,
+            nodes: None,
+            source: None,
             length: 4,
             files: None,
             name: "sample".to_owned(),
             piece_length: 2,
             pieces: vec![vec![1, 2], vec![3, 4]],
             extra_fields: None,
-            extra_info_fields: Some(HashMap::from_iter(
-                vec![
-                    ("comment2".to_owned(), bencode_elem!("no comment")),
-                    ("comment1".to_owned(), bencode_elem!("no comment")),
-                ]
-                .into_iter(),
-            )),
+            extra_info_fields: Some(HashMap::from_iter(vec![
+                ("comment2".to_owned(), bencode_elem!("no comment")),
+                ("comment1".to_owned(), bencode_elem!("no comment")),
+            ])),
+            raw_info: None,
+            url_list_is_string: false,
+            http_seeds_is_string: false,
         };
 
         assert_eq!(
@@ -497,16 +845,30 @@ mod torrent_write_tests {
         let torrent = Torrent {
             announce: Some("url".to_owned()),
             announce_list: None,
+            url_list: None,
+            http_seeds: None,
+            creation_date: None,
+            comment: None,
+            created_by: None,
+            encoding: None,
+            nodes: None,
+            source: None,
             length: 4,
             files: Some(vec![
                 File {
                     length: 2,
                     path: PathBuf::from("dir1/dir2/file1"),
+                    attr: None,
+                    sha1: None,
+                    symlink_path: None,
                     extra_fields: None,
                 },
                 File {
                     length: 2,
                     path: PathBuf::from("dir1/dir2/file2"),
+                    attr: None,
+                    sha1: None,
+                    symlink_path: None,
                     extra_fields: None,
                 },
             ]),
@@ -515,6 +877,9 @@ mod torrent_write_tests {
             pieces: vec![vec![1, 2], vec![3, 4]],
             extra_fields: None,
             extra_info_fields: None,
+            raw_info: None,
+            url_list_is_string: false,
+            http_seeds_is_string: false,
         };
 
         assert_eq!(
@@ -534,4 +899,28 @@ mod torrent_write_tests {
             .encode()
         );
     }
+
+    #[test]
+    fn encode_urls_ok() {
+        let urls = vec!["url1".to_owned(), "url2".to_owned()];
+        assert_eq!(
+            Torrent::encode_urls(urls.clone(), false),
+            bencode_elem!(["url1", "url2"])
+        );
+        // more than 1 url can only be written as a list
+        assert_eq!(
+            Torrent::encode_urls(urls, true),
+            bencode_elem!(["url1", "url2"])
+        );
+    }
+
+    #[test]
+    fn encode_urls_string() {
+        assert_eq!(
+            Torrent::encode_urls(vec!["url".to_owned()], true),
+            bencode_elem!("url")
+        );
+        assert_eq!(Torrent::encode_urls(Vec::new(), true), bencode_elem!(""));
+        assert_eq!(Torrent::encode_urls(Vec::new(), false), bencode_elem!([]));
+    }
 }
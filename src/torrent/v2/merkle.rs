@@ -0,0 +1,125 @@
+// Merkle tree helpers shared by v2 torrent parsing and creation.
+//
+// Per BEP 52, each file is split into 16 KiB blocks, and the SHA256
+// hashes of the blocks form the leaves of the file's merkle tree.
+// Leaves beyond the end of the file are set to zero (i.e. 32 zero bytes).
+
+use sha2::{Digest, Sha256};
+
+pub(crate) const BLOCK_SIZE: usize = 16384;
+pub(crate) const HASH_LENGTH: usize = 32;
+
+// hash 2 nodes to get their parent
+pub(crate) fn hash_pair(left: &[u8], right: &[u8]) -> Vec<u8> {
+    let mut hasher = Sha256::new();
+    hasher.input(left);
+    hasher.input(right);
+    hasher.result().to_vec()
+}
+
+// root of a subtree with `n_leaves` zero leaves
+//
+// `n_leaves` must be a power of 2
+pub(crate) fn pad_hash(n_leaves: usize) -> Vec<u8> {
+    let mut hash = vec![0; HASH_LENGTH];
+    let mut width = 1;
+
+    while width < n_leaves {
+        hash = hash_pair(&hash, &hash);
+        width *= 2;
+    }
+    hash
+}
+
+// root of a tree with `width` leaves, where the first leaves are
+// taken from `layer` and the remaining ones are set to `pad`
+//
+// `width` must be a power of 2 that is >= `layer.len()`
+pub(crate) fn root(mut layer: Vec<Vec<u8>>, width: usize, pad: &[u8]) -> Vec<u8> {
+    if layer.len() > width {
+        panic!(
+            "merkle::root() expects at most {} leaves, {} received.",
+            width,
+            layer.len()
+        )
+    }
+
+    layer.resize(width, pad.to_vec());
+    while layer.len() > 1 {
+        layer = layer
+            .chunks(2)
+            .map(|pair| hash_pair(&pair[0], &pair[1]))
+            .collect();
+    }
+    layer.pop().unwrap_or_else(|| pad.to_vec())
+}
+
+// root of a file's merkle tree given the file's piece layer
+//
+// `piece_length` must be a power of 2 that is >= `BLOCK_SIZE`
+pub(crate) fn root_from_piece_layer(layer: Vec<Vec<u8>>, piece_length: usize) -> Vec<u8> {
+    let width = layer.len().next_power_of_two();
+    root(layer, width, &pad_hash(piece_length / BLOCK_SIZE))
+}
+
+#[cfg(test)]
+mod merkle_tests {
+    use super::*;
+
+    #[test]
+    fn hash_pair_ok() {
+        let mut bytes = vec![1; HASH_LENGTH];
+        bytes.extend(vec![2; HASH_LENGTH]);
+
+        assert_eq!(
+            hash_pair(&[1; HASH_LENGTH], &[2; HASH_LENGTH]),
+            Sha256::digest(&bytes).to_vec()
+        );
+    }
+
+    #[test]
+    fn pad_hash_ok() {
+        let zero = vec![0; HASH_LENGTH];
+        assert_eq!(pad_hash(1), zero);
+        assert_eq!(pad_hash(2), hash_pair(&zero, &zero));
+        assert_eq!(
+            pad_hash(4),
+            hash_pair(&hash_pair(&zero, &zero), &hash_pair(&zero, &zero))
+        );
+    }
+
+    #[test]
+    fn root_single_leaf() {
+        assert_eq!(
+            root(vec![vec![1; HASH_LENGTH]], 1, &[0; HASH_LENGTH]),
+            vec![1; HASH_LENGTH]
+        );
+    }
+
+    #[test]
+    fn root_with_padding() {
+        let (a, b, c, pad) = (vec![1; 32], vec![2; 32], vec![3; 32], vec![4; 32]);
+
+        assert_eq!(
+            root(vec![a.clone(), b.clone(), c.clone()], 4, &pad),
+            hash_pair(&hash_pair(&a, &b), &hash_pair(&c, &pad))
+        );
+    }
+
+    #[test]
+    fn root_from_piece_layer_ok() {
+        let (a, b, c) = (vec![1; 32], vec![2; 32], vec![3; 32]);
+        let pad = pad_hash(2);
+
+        assert_eq!(
+            root_from_piece_layer(vec![a.clone(), b.clone(), c.clone()], 2 * BLOCK_SIZE),
+            hash_pair(&hash_pair(&a, &b), &hash_pair(&c, &pad))
+        );
+    }
+
+    #[test]
+    #[should_panic]
+    fn root_too_many_leaves() {
+        root(vec![vec![1; HASH_LENGTH]; 3], 2, &[0; HASH_LENGTH]);
+    }
+}
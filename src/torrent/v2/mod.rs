@@ -0,0 +1,497 @@
+//! Module for `.torrent` files ([v2](http://bittorrent.org/beps/bep_0052.html))
+//! related parsing/encoding.
+//!
+//! Hybrid torrents (i.e. torrents that are both [v1] and [v2]) can be
+//! parsed by this module as well. In that case the [v1] fields
+//! (e.g. `pieces`) will be placed in `extra_info_fields`.
+//!
+//! [v1]: ../v1/index.html
+//! [v2]: http://bittorrent.org/beps/bep_0052.html
+
+use bencode::BencodeElem;
+use error::*;
+use itertools::Itertools;
+use sha2::{Digest, Sha256};
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::path::PathBuf;
+use torrent::v1::{AnnounceList, Dictionary, Integer, Piece};
+use torrent::{is_raw_info_current, InfoHashSource};
+
+pub(crate) mod merkle;
+mod read;
+mod write;
+
+const PIECE_STRING_LENGTH: usize = merkle::HASH_LENGTH;
+const META_VERSION: Integer = 2;
+
+/// Corresponds to `piece layers` in [BEP 52](http://bittorrent.org/beps/bep_0052.html).
+///
+/// Maps a file's `pieces root` to the SHA256 hashes of its pieces
+/// (i.e. the piece layer of the file's merkle tree).
+pub type PieceLayers = HashMap<Vec<u8>, Vec<Piece>>;
+
+/// A node in a torrent's `file tree`.
+///
+/// Directory entries are kept in a `BTreeMap` since the order of files
+/// in a [v2](http://bittorrent.org/beps/bep_0052.html) torrent is defined
+/// by the order of their paths.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum FileTree {
+    /// A file, i.e. a node whose only key is an empty string.
+    File(File),
+    /// A directory, mapping path components to child nodes.
+    Dir(BTreeMap<String, FileTree>),
+}
+
+/// A file contained in a torrent.
+///
+/// Modeled after the specifications
+/// in [BEP 52](http://bittorrent.org/beps/bep_0052.html). Unknown/extension
+/// fields will be placed in `extra_fields`. If you need
+/// any of those extra fields you would have to parse it yourself.
+///
+/// Unlike [v1 `File`](../v1/struct.File.html), this struct does not
+/// contain the file's path. The path is determined by the file's
+/// position in the [`FileTree`](enum.FileTree.html).
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct File {
+    /// File size in bytes.
+    pub length: Integer,
+    /// Root hash of the file's merkle tree. This field is `None`
+    /// for empty files.
+    #[cfg_attr(feature = "serde", serde(default, with = "::serde_util::option_bytes"))]
+    pub pieces_root: Option<Vec<u8>>,
+    /// Fields not defined in [BEP 52](http://bittorrent.org/beps/bep_0052.html).
+    pub extra_fields: Option<Dictionary>,
+}
+
+/// Everything found in a [v2](http://bittorrent.org/beps/bep_0052.html) *.torrent* file.
+///
+/// Modeled after the specifications
+/// in [BEP 52](http://bittorrent.org/beps/bep_0052.html) and
+/// [BEP 12](http://bittorrent.org/beps/bep_0012.html). Unknown/extension
+/// fields will be placed in `extra_fields` (if the unknown
+/// fields are found in the `info` dictionary then they are placed in
+/// `extra_info_fields`). If you need any of those extra fields you would
+/// have to parse it yourself.
+///
+/// `meta version` is not stored as it is always `2`.
+#[derive(Clone, Debug, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Torrent {
+    /// URL of the torrent's tracker.
+    pub announce: Option<String>,
+    /// Announce list as defined in [BEP 12](http://bittorrent.org/beps/bep_0012.html).
+    pub announce_list: Option<AnnounceList>,
+    /// Total torrent size in bytes (i.e. sum of all files' sizes).
+    pub length: Integer,
+    /// The root of the torrent's `file tree`. It is always a directory.
+    pub file_tree: FileTree,
+    /// Suggested name of the torrent (e.g. the root directory's name).
+    pub name: String,
+    /// Block size in bytes.
+    pub piece_length: Integer,
+    /// Piece layers of all files larger than `piece_length`.
+    #[cfg_attr(feature = "serde", serde(with = "::serde_util::piece_layers"))]
+    pub piece_layers: PieceLayers,
+    /// Top-level fields not defined in [BEP 52](http://bittorrent.org/beps/bep_0052.html).
+    pub extra_fields: Option<Dictionary>,
+    /// Fields in `info` not defined in [BEP 52](http://bittorrent.org/beps/bep_0052.html).
+    pub extra_info_fields: Option<Dictionary>,
+    /// The original bytes of the `info` dict, as found in the parsed
+    /// *.torrent* file. It is `None` if this `Torrent` is not parsed
+    /// from bytes (e.g. it is created by `TorrentBuilder`).
+    ///
+    /// These bytes are used to calculate the info hash, and are written
+    /// verbatim by `write_into()`, as long as they still match the fields
+    /// that belong to `info`. Once any of those fields is modified, the
+    /// `info` dict is re-encoded from the fields instead.
+    #[cfg_attr(feature = "serde", serde(default, with = "::serde_util::option_bytes"))]
+    pub raw_info: Option<Vec<u8>>,
+}
+
+impl FileTree {
+    /// Return all files in this tree along with their paths,
+    /// in the order defined by [BEP 52](http://bittorrent.org/beps/bep_0052.html).
+    ///
+    /// Paths are relative to the root of this tree. If `self` is
+    /// a `File` then its path would be empty.
+    pub fn files(&self) -> Vec<(PathBuf, &File)> {
+        let mut files = Vec::new();
+        self.collect_files(PathBuf::new(), &mut files);
+        files
+    }
+
+    fn collect_files<'a>(&'a self, path: PathBuf, files: &mut Vec<(PathBuf, &'a File)>) {
+        match *self {
+            FileTree::File(ref file) => files.push((path, file)),
+            FileTree::Dir(ref entries) => {
+                for (component, node) in entries {
+                    node.collect_files(path.join(component), files);
+                }
+            }
+        }
+    }
+}
+
+impl Torrent {
+    /// Return all files in this torrent along with their paths.
+    ///
+    /// This is a shortcut for `self.file_tree.files()`.
+    pub fn files(&self) -> Vec<(PathBuf, &File)> {
+        self.file_tree.files()
+    }
+
+    /// Construct the `info` dict based on the fields of `self`.
+    ///
+    /// Certain operations on torrents, such as calculating info
+    /// hashs, require the extracted `info` dict. This
+    /// convenience method does that.
+    ///
+    /// Note that the `info` dict
+    /// is constructed each time this method is called (i.e.
+    /// the return value is not cached). If caching is needed
+    /// then the caller should handle that.
+    ///
+    /// Since `self` is taken by reference, and the result is
+    /// returned by value, certain values will be cloned. Please
+    /// be aware of this overhead.
+    pub fn construct_info(&self) -> BencodeElem {
+        let mut info: HashMap<String, BencodeElem> = HashMap::new();
+
+        info.insert(
+            "file tree".to_owned(),
+            self.file_tree.clone().into_bencode_elem(),
+        );
+        info.insert(
+            "meta version".to_owned(),
+            BencodeElem::Integer(META_VERSION),
+        );
+        info.insert("name".to_owned(), BencodeElem::String(self.name.clone()));
+        info.insert(
+            "piece length".to_owned(),
+            BencodeElem::Integer(self.piece_length),
+        );
+
+        if let Some(ref extra_info_fields) = self.extra_info_fields {
+            info.extend(extra_info_fields.clone());
+        }
+
+        BencodeElem::Dictionary(info)
+    }
+
+    /// Calculate the `Torrent`'s info hash as defined in
+    /// [BEP 52](http://bittorrent.org/beps/bep_0052.html)
+    /// (i.e. the SHA256 hash of the `info` dict).
+    ///
+    /// The original bytes of `info` (i.e. `raw_info`) are hashed if
+    /// they are available and still match the fields in `info`. Otherwise the re-encoded `info` dict is hashed.
+    /// To choose explicitly, use [`info_hash_from()`].
+    ///
+    /// Note that the calculated info hash is not cached.
+    /// So if this method is called multiple times, multiple
+    /// calculations will be performed. To avoid that, the
+    /// caller should cache the return value as needed.
+    ///
+    /// [`info_hash_from()`]: #method.info_hash_from
+    pub fn info_hash(&self) -> String {
+        self.info_hash_from(InfoHashSource::Original)
+    }
+
+    /// Calculate the `Torrent`'s info hash as defined in
+    /// [BEP 52](http://bittorrent.org/beps/bep_0052.html)
+    /// (i.e. the SHA256 hash of the `info` dict).
+    ///
+    /// The returned `Vec` always contains 32 bytes.
+    ///
+    /// The original bytes of `info` (i.e. `raw_info`) are hashed if
+    /// they are available and still match the fields in `info`. Otherwise the re-encoded `info` dict is hashed.
+    /// To choose explicitly, use [`info_hash_bytes_from()`].
+    ///
+    /// Note that the calculated info hash is not cached.
+    /// So if this method is called multiple times, multiple
+    /// calculations will be performed. To avoid that, the
+    /// caller should cache the return value as needed.
+    ///
+    /// [`info_hash_bytes_from()`]: #method.info_hash_bytes_from
+    pub fn info_hash_bytes(&self) -> Vec<u8> {
+        self.info_hash_bytes_from(InfoHashSource::Original)
+    }
+
+    /// Calculate the `Torrent`'s info hash by hashing the
+    /// bytes specified by `source`.
+    ///
+    /// Note that the calculated info hash is not cached.
+    pub fn info_hash_from(&self, source: InfoHashSource) -> String {
+        format!("{:x}", Sha256::digest(&self.info_bytes(source)))
+    }
+
+    /// Calculate the `Torrent`'s info hash by hashing the
+    /// bytes specified by `source`.
+    ///
+    /// Note that the calculated info hash is not cached.
+    pub fn info_hash_bytes_from(&self, source: InfoHashSource) -> Vec<u8> {
+        Sha256::digest(&self.info_bytes(source)).to_vec()
+    }
+
+    // bytes of the `info` dict to be hashed
+    fn info_bytes(&self, source: InfoHashSource) -> Cow<'_, [u8]> {
+        let reencoded = self.construct_info().encode();
+        match (source, &self.raw_info) {
+            (InfoHashSource::Original, Some(raw_info))
+                if is_raw_info_current(raw_info, &reencoded) =>
+            {
+                Cow::Borrowed(raw_info)
+            }
+            _ => Cow::Owned(reencoded),
+        }
+    }
+
+    /// Check if this torrent is private as defined in
+    /// [BEP 27](http://bittorrent.org/beps/bep_0027.html).
+    ///
+    /// Returns `true` if `private` maps to a bencode integer `1`.
+    /// Returns `false` otherwise.
+    pub fn is_private(&self) -> bool {
+        if let Some(ref dict) = self.extra_info_fields {
+            match dict.get("private") {
+                Some(&BencodeElem::Integer(val)) => val == 1,
+                Some(_) => false,
+                None => false,
+            }
+        } else {
+            false
+        }
+    }
+}
+
+impl fmt::Display for File {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "-size: {} bytes", self.length)?;
+
+        if let Some(ref root) = self.pieces_root {
+            writeln!(f, "-pieces root: {:02x}", root.iter().format(""))?;
+        }
+
+        if let Some(ref fields) = self.extra_fields {
+            write!(
+                f,
+                "{}",
+                fields
+                    .iter()
+                    .sorted_by_key(|&(key, _)| key.as_bytes())
+                    .format_with("", |(k, v), f| f(&format_args!("-{}: {}\n", k, v)))
+            )?;
+        }
+
+        writeln!(f, "========================================")
+    }
+}
+
+// `raw_info` is left out, as it is only a by-product of parsing
+// (e.g. a built `Torrent` should equal its parsed copy).
+impl PartialEq for Torrent {
+    fn eq(&self, other: &Torrent) -> bool {
+        (self.announce == other.announce)
+            && (self.announce_list == other.announce_list)
+            && (self.length == other.length)
+            && (self.file_tree == other.file_tree)
+            && (self.name == other.name)
+            && (self.piece_length == other.piece_length)
+            && (self.piece_layers == other.piece_layers)
+            && (self.extra_fields == other.extra_fields)
+            && (self.extra_info_fields == other.extra_info_fields)
+    }
+}
+
+impl fmt::Display for Torrent {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "{}.torrent", self.name)?;
+        if let Some(ref announce) = self.announce {
+            writeln!(f, "-announce: {}", announce)?;
+        }
+        if let Some(ref tiers) = self.announce_list {
+            writeln!(
+                f,
+                "-announce-list: [{}]",
+                tiers.iter().format_with(", ", |tier, f| f(&format_args!(
+                    "[{}]",
+                    ::itertools::join(tier, ", ")
+                )))
+            )?;
+        }
+        writeln!(f, "-size: {} bytes", self.length)?;
+        writeln!(f, "-piece length: {} bytes", self.piece_length)?;
+
+        if let Some(ref fields) = self.extra_fields {
+            write!(
+                f,
+                "{}",
+                fields
+                    .iter()
+                    .sorted_by_key(|&(key, _)| key.as_bytes())
+                    .format_with("", |(k, v), f| f(&format_args!("-{}: {}\n", k, v)))
+            )?;
+        }
+
+        if let Some(ref fields) = self.extra_info_fields {
+            write!(
+                f,
+                "{}",
+                fields
+                    .iter()
+                    .sorted_by_key(|&(key, _)| key.as_bytes())
+                    .format_with("", |(k, v), f| f(&format_args!("-{}: {}\n", k, v)))
+            )?;
+        }
+
+        writeln!(f, "-files:")?;
+        for (counter, (path, file)) in self.files().into_iter().enumerate() {
+            writeln!(f, "[{}] {}\n{}", counter + 1, path.display(), file)?;
+        }
+
+        writeln!(f, "-piece layers: {}", self.piece_layers.len())
+    }
+}
+
+#[cfg(test)]
+mod file_tree_tests {
+    use super::*;
+
+    #[test]
+    fn files_ok() {
+        let file1 = File {
+            length: 1,
+            pieces_root: Some(vec![1; 32]),
+            extra_fields: None,
+        };
+        let file2 = File {
+            length: 2,
+            pieces_root: Some(vec![2; 32]),
+            extra_fields: None,
+        };
+        let file3 = File {
+            length: 0,
+            pieces_root: None,
+            extra_fields: None,
+        };
+
+        let mut dir = BTreeMap::new();
+        dir.insert("b".to_owned(), FileTree::File(file2.clone()));
+        dir.insert("a".to_owned(), FileTree::File(file1.clone()));
+        let mut root = BTreeMap::new();
+        root.insert("dir".to_owned(), FileTree::Dir(dir));
+        root.insert("c".to_owned(), FileTree::File(file3.clone()));
+
+        assert_eq!(
+            FileTree::Dir(root).files(),
+            vec![
+                (PathBuf::from("c"), &file3),
+                (PathBuf::from("dir/a"), &file1),
+                (PathBuf::from("dir/b"), &file2),
+            ]
+        );
+    }
+
+    #[test]
+    fn files_single_file() {
+        let file = File {
+            length: 1,
+            pieces_root: Some(vec![1; 32]),
+            extra_fields: None,
+        };
+
+        assert_eq!(
+            FileTree::File(file.clone()).files(),
+            vec![(PathBuf::new(), &file)]
+        );
+    }
+}
+
+#[cfg(test)]
+mod torrent_tests {
+    use super::*;
+    use std::iter::FromIterator;
+
+    fn sample() -> Torrent {
+        let mut root = BTreeMap::new();
+        root.insert(
+            "file".to_owned(),
+            FileTree::File(File {
+                length: 4,
+                pieces_root: Some(vec![0; 32]),
+                extra_fields: None,
+            }),
+        );
+
+        Torrent {
+            announce: Some("url".to_owned()),
+            announce_list: None,
+            length: 4,
+            file_tree: FileTree::Dir(root),
+            name: "sample".to_owned(),
+            piece_length: 16384,
+            piece_layers: HashMap::new(),
+            extra_fields: None,
+            extra_info_fields: None,
+            raw_info: None,
+        }
+    }
+
+    #[test]
+    fn construct_info_ok() {
+        let torrent = Torrent {
+            extra_info_fields: Some(HashMap::from_iter(vec![(
+                "key".to_owned(),
+                bencode_elem!("val"),
+            )])),
+            ..sample()
+        };
+        let pieces_root = vec![0_u8; 32];
+
+        assert_eq!(
+            torrent.construct_info(),
+            bencode_elem!({
+                ("file tree", {
+                    ("file", {
+                        ("", {
+                            ("length", 4),
+                            ("pieces root", pieces_root),
+                        })
+                    })
+                }),
+                ("meta version", 2),
+                ("name", "sample"),
+                ("piece length", 16384),
+                ("key", "val"),
+            }),
+        );
+    }
+
+    #[test]
+    fn info_hash_ok() {
+        assert_eq!(
+            sample().info_hash(),
+            format!("{:x}", Sha256::digest(&sample().construct_info().encode())),
+        );
+        assert_eq!(sample().info_hash_bytes().len(), 32);
+    }
+
+    #[test]
+    fn is_private_ok() {
+        let torrent = Torrent {
+            extra_info_fields: Some(HashMap::from_iter(vec![(
+                "private".to_owned(),
+                bencode_elem!(1),
+            )])),
+            ..sample()
+        };
+
+        assert!(torrent.is_private());
+        assert!(!sample().is_private());
+    }
+}
@@ -0,0 +1,944 @@
+use super::*;
+use bencode::{BencodeElem, ParseOptions};
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashMap};
+use std::io::{BufReader, Read};
+use std::path::{self, Path};
+use torrent::v1;
+use util;
+
+impl File {
+    fn extract_file(elem: BencodeElem) -> Result<File> {
+        match elem {
+            BencodeElem::Dictionary(mut dict) => {
+                let length = Self::extract_file_length(&mut dict)?;
+                let pieces_root = Self::extract_file_pieces_root(&mut dict, length)?;
+
+                Ok(File {
+                    length,
+                    pieces_root,
+                    extra_fields: Self::extract_file_extra_fields(dict),
+                })
+            }
+            _ => bail!(ErrorKind::MalformedTorrent(Cow::Borrowed(
+                r#"A file in "file tree" does not map to a dictionary."#
+            ))),
+        }
+    }
+
+    fn extract_file_length(dict: &mut HashMap<String, BencodeElem>) -> Result<Integer> {
+        match dict.remove("length") {
+            Some(BencodeElem::Integer(len)) => {
+                if len >= 0 {
+                    Ok(len)
+                } else {
+                    bail!(ErrorKind::MalformedTorrent(Cow::Borrowed(
+                        r#""length" < 0."#
+                    )))
+                }
+            }
+            Some(_) => bail!(ErrorKind::MalformedTorrent(Cow::Borrowed(
+                r#""length" does not map to an integer."#
+            ))),
+            None => bail!(ErrorKind::MalformedTorrent(Cow::Borrowed(
+                r#""length" does not exist."#
+            ))),
+        }
+    }
+
+    fn extract_file_pieces_root(
+        dict: &mut HashMap<String, BencodeElem>,
+        length: Integer,
+    ) -> Result<Option<Vec<u8>>> {
+        // a 32-byte hash could happen to be valid UTF8,
+        // in which case it would be parsed as a string
+        let root = match dict.remove("pieces root") {
+            Some(BencodeElem::Bytes(bytes)) => bytes,
+            Some(BencodeElem::String(string)) => string.into_bytes(),
+            Some(_) => bail!(ErrorKind::MalformedTorrent(Cow::Borrowed(
+                r#""pieces root" does not map to a sequence of bytes."#
+            ))),
+            // "For empty files this key is not present."
+            None if length == 0 => return Ok(None),
+            None => bail!(ErrorKind::MalformedTorrent(Cow::Borrowed(
+                r#""pieces root" does not exist."#
+            ))),
+        };
+
+        if root.len() == PIECE_STRING_LENGTH {
+            Ok(Some(root))
+        } else {
+            bail!(ErrorKind::MalformedTorrent(Cow::Owned(format!(
+                r#""pieces root"' length is not {}."#,
+                PIECE_STRING_LENGTH,
+            ))))
+        }
+    }
+
+    fn extract_file_extra_fields(dict: HashMap<String, BencodeElem>) -> Option<Dictionary> {
+        if dict.is_empty() {
+            None
+        } else {
+            Some(dict)
+        }
+    }
+}
+
+impl FileTree {
+    fn extract_file_tree(elem: BencodeElem) -> Result<FileTree> {
+        match elem {
+            BencodeElem::Dictionary(mut dict) => {
+                // a file is represented by a dictionary with
+                // a single key--the empty string
+                if let Some(file) = dict.remove("") {
+                    if dict.is_empty() {
+                        Ok(FileTree::File(File::extract_file(file)?))
+                    } else {
+                        bail!(ErrorKind::MalformedTorrent(Cow::Borrowed(
+                            r#"A node in "file tree" is both a file and a directory."#
+                        )))
+                    }
+                } else if dict.is_empty() {
+                    bail!(ErrorKind::MalformedTorrent(Cow::Borrowed(
+                        r#""file tree" contains an empty directory."#
+                    )))
+                } else {
+                    let mut entries = BTreeMap::new();
+                    for (component, node) in dict {
+                        // "Path components exactly matching '.' and '..'
+                        // must be sanitized."
+                        if (component == ".") || (component == "..") {
+                            bail!(ErrorKind::MalformedTorrent(Cow::Borrowed(
+                                r#""file tree" contains "." or ".."."#
+                            )));
+                        }
+                        // components are joined into paths by `FileTree::files()`,
+                        // so they must not contain separators (an empty
+                        // component is the file marker handled above)
+                        if component.chars().any(path::is_separator) {
+                            bail!(ErrorKind::MalformedTorrent(Cow::Borrowed(
+                                r#""file tree" contains a path component with a path separator."#
+                            )));
+                        }
+                        entries.insert(component, Self::extract_file_tree(node)?);
+                    }
+                    Ok(FileTree::Dir(entries))
+                }
+            }
+            BencodeElem::RawDictionary(_) => bail!(ErrorKind::MalformedTorrent(Cow::Borrowed(
+                r#""file tree" contains a path component that is not valid UTF8."#
+            ))),
+            _ => bail!(ErrorKind::MalformedTorrent(Cow::Borrowed(
+                r#""file tree" contains a non-dictionary element."#
+            ))),
+        }
+    }
+}
+
+impl Torrent {
+    /// Parse `bytes` and return the extracted `Torrent`.
+    ///
+    /// If `bytes` is missing any required field (e.g. `info`), or if any other
+    /// error is encountered (e.g. `IOError`), then `Err(error)` will be returned.
+    ///
+    /// `piece layers` are validated against the `pieces root` of each file.
+    ///
+    /// Unlike v1 torrents, strings are **not** normalized to NFC
+    /// (see [`ParseOptions`]), as a `pieces root` or a piece layer
+    /// could happen to be valid UTF8 and must be kept intact.
+    ///
+    /// [`ParseOptions`]: ../../bencode/struct.ParseOptions.html
+    pub fn read_from_bytes<B>(bytes: B) -> Result<Torrent>
+    where
+        B: AsRef<[u8]>,
+    {
+        let bytes = bytes.as_ref();
+        let options = ParseOptions::new().set_normalize_nfc(false);
+        let mut torrent =
+            Self::from_parsed(BencodeElem::from_bytes_with(bytes, &options)?)?.validate()?;
+
+        // keep the original `info` bytes for calculating info hash
+        torrent.raw_info = BencodeElem::find_value_span(bytes, b"info")?
+            .map(|(start, end)| bytes[start..end].to_vec());
+        Ok(torrent)
+    }
+
+    /// Parse the content of the file at `path` and return the extracted `Torrent`.
+    ///
+    /// If the file at `path` is missing any required field (e.g. `info`), or if any other
+    /// error is encountered (e.g. `IOError`), then `Err(error)` will be returned.
+    ///
+    /// `piece layers` are validated against the `pieces root` of each file.
+    pub fn read_from_file<P>(path: P) -> Result<Torrent>
+    where
+        P: AsRef<Path>,
+    {
+        let mut bytes = Vec::new();
+        BufReader::new(::std::fs::File::open(path)?).read_to_end(&mut bytes)?;
+        Self::read_from_bytes(bytes)
+    }
+
+    // Check that every file larger than `piece_length` has a piece layer,
+    // and that the piece layer matches the file's `pieces root`.
+    fn validate(self) -> Result<Torrent> {
+        let piece_length = util::i64_to_usize(self.piece_length)?;
+
+        for (path, file) in self.files() {
+            if file.length <= self.piece_length {
+                continue;
+            }
+            // `pieces root` must exist as `length` > 0
+            let root = file.pieces_root.as_ref().unwrap();
+
+            match self.piece_layers.get(root) {
+                Some(layer) => {
+                    let n_pieces = (file.length - 1) / self.piece_length + 1;
+                    if util::usize_to_u64(layer.len())? != util::i64_to_u64(n_pieces)? {
+                        bail!(ErrorKind::MalformedTorrent(Cow::Owned(format!(
+                            "Piece layer of [{}] contains {} pieces, {} expected.",
+                            path.display(),
+                            layer.len(),
+                            n_pieces,
+                        ))));
+                    }
+                    if merkle::root_from_piece_layer(layer.clone(), piece_length) != *root {
+                        bail!(ErrorKind::MalformedTorrent(Cow::Owned(format!(
+                            r#"Piece layer of [{}] does not match its "pieces root"."#,
+                            path.display(),
+                        ))));
+                    }
+                }
+                None => bail!(ErrorKind::MalformedTorrent(Cow::Owned(format!(
+                    "Piece layer of [{}] does not exist.",
+                    path.display(),
+                )))),
+            }
+        }
+
+        Ok(self)
+    }
+
+    fn from_parsed(mut parsed: Vec<BencodeElem>) -> Result<Torrent> {
+        if parsed.len() != 1 {
+            bail!(ErrorKind::MalformedTorrent(Cow::Owned(format!(
+                "Torrent should contain 1 and only 1 top-level element, {} found.",
+                parsed.len()
+            ))));
+        }
+
+        if let BencodeElem::Dictionary(mut parsed) = parsed.remove(0) {
+            // 2nd-level items
+            let announce = v1::Torrent::extract_announce(&mut parsed)?;
+            let announce_list = v1::Torrent::extract_announce_list(&mut parsed)?;
+            let piece_layers = Self::extract_piece_layers(&mut parsed)?;
+            let info = parsed.remove("info");
+            let extra_fields = Self::extract_extra_fields(parsed);
+
+            match info {
+                Some(BencodeElem::Dictionary(mut info)) => {
+                    // 3rd-level items
+                    Self::extract_meta_version(&mut info)?;
+                    let file_tree = Self::extract_file_tree(&mut info)?;
+
+                    Ok(Torrent {
+                        announce,
+                        announce_list,
+                        length: Self::extract_length(&file_tree)?,
+                        file_tree,
+                        name: Self::extract_name(&mut info)?,
+                        piece_length: Self::extract_piece_length(&mut info)?,
+                        piece_layers,
+                        extra_fields,
+                        extra_info_fields: Self::extract_extra_fields(info),
+                        raw_info: None,
+                    })
+                }
+                Some(_) => bail!(ErrorKind::MalformedTorrent(Cow::Borrowed(
+                    r#""info" is not a dictionary."#
+                ))),
+                None => bail!(ErrorKind::MalformedTorrent(Cow::Borrowed(
+                    r#""info" does not exist."#
+                ))),
+            }
+        } else {
+            bail!(ErrorKind::MalformedTorrent(Cow::Borrowed(
+                "Torrent's top-level element is not a dictionary."
+            )))
+        }
+    }
+
+    fn extract_piece_layers(dict: &mut HashMap<String, BencodeElem>) -> Result<PieceLayers> {
+        // keys are 32-byte hashes, so `piece layers` would only be
+        // parsed as a `Dictionary` if all of them happen to be valid UTF8
+        let layers: Vec<(Vec<u8>, BencodeElem)> = match dict.remove("piece layers") {
+            Some(BencodeElem::RawDictionary(layers)) => layers.into_iter().collect(),
+            Some(BencodeElem::Dictionary(layers)) => layers
+                .into_iter()
+                .map(|(root, layer)| (root.into_bytes(), layer))
+                .collect(),
+            Some(_) => bail!(ErrorKind::MalformedTorrent(Cow::Borrowed(
+                r#""piece layers" does not map to a dictionary."#
+            ))),
+            // the correctness of a missing `piece layers` is checked in `validate()`
+            None => Vec::new(),
+        };
+
+        let mut piece_layers = HashMap::with_capacity(layers.len());
+        for (root, layer) in layers {
+            let layer = match layer {
+                BencodeElem::Bytes(bytes) => bytes,
+                BencodeElem::String(string) => string.into_bytes(),
+                _ => bail!(ErrorKind::MalformedTorrent(Cow::Borrowed(
+                    r#""piece layers" contains a non-bytes element."#
+                ))),
+            };
+
+            if root.len() != PIECE_STRING_LENGTH {
+                bail!(ErrorKind::MalformedTorrent(Cow::Owned(format!(
+                    r#""piece layers" contains a key whose length is not {}."#,
+                    PIECE_STRING_LENGTH,
+                ))));
+            } else if layer.is_empty() || (layer.len() % PIECE_STRING_LENGTH) != 0 {
+                bail!(ErrorKind::MalformedTorrent(Cow::Owned(format!(
+                    r#""piece layers" contains a layer whose length is not a multiple of {}."#,
+                    PIECE_STRING_LENGTH,
+                ))));
+            }
+
+            piece_layers.insert(
+                root,
+                layer
+                    .chunks(PIECE_STRING_LENGTH)
+                    .map(|chunk| chunk.to_vec())
+                    .collect(),
+            );
+        }
+        Ok(piece_layers)
+    }
+
+    fn extract_meta_version(dict: &mut HashMap<String, BencodeElem>) -> Result<()> {
+        match dict.remove("meta version") {
+            Some(BencodeElem::Integer(META_VERSION)) => Ok(()),
+            Some(BencodeElem::Integer(version)) => bail!(ErrorKind::MalformedTorrent(Cow::Owned(
+                format!(r#"Unsupported "meta version" {}."#, version)
+            ))),
+            Some(_) => bail!(ErrorKind::MalformedTorrent(Cow::Borrowed(
+                r#""meta version" does not map to an integer."#
+            ))),
+            None => bail!(ErrorKind::MalformedTorrent(Cow::Borrowed(
+                r#""meta version" does not exist."#
+            ))),
+        }
+    }
+
+    fn extract_file_tree(dict: &mut HashMap<String, BencodeElem>) -> Result<FileTree> {
+        match dict.remove("file tree") {
+            Some(elem) => match FileTree::extract_file_tree(elem)? {
+                FileTree::File(_) => bail!(ErrorKind::MalformedTorrent(Cow::Borrowed(
+                    r#""file tree" maps to a file instead of a directory."#
+                ))),
+                tree => Ok(tree),
+            },
+            None => bail!(ErrorKind::MalformedTorrent(Cow::Borrowed(
+                r#""file tree" does not exist."#
+            ))),
+        }
+    }
+
+    fn extract_length(file_tree: &FileTree) -> Result<Integer> {
+        let mut length: Integer = 0;
+        for (_, file) in file_tree.files() {
+            match length.checked_add(file.length) {
+                Some(sum) => {
+                    length = sum;
+                }
+                None => {
+                    bail!(ErrorKind::MalformedTorrent(Cow::Borrowed(
+                        r#"Torrent's length overflowed in i64."#
+                    )));
+                }
+            }
+        }
+        Ok(length)
+    }
+
+    fn extract_name(dict: &mut HashMap<String, BencodeElem>) -> Result<String> {
+        match dict.remove("name") {
+            Some(BencodeElem::String(name)) => Ok(name),
+            Some(_) => bail!(ErrorKind::MalformedTorrent(Cow::Borrowed(
+                r#""name" does not map to a string (or maps to invalid UTF8)."#
+            ))),
+            None => bail!(ErrorKind::MalformedTorrent(Cow::Borrowed(
+                r#""name" does not exist."#
+            ))),
+        }
+    }
+
+    fn extract_piece_length(dict: &mut HashMap<String, BencodeElem>) -> Result<Integer> {
+        match dict.remove("piece length") {
+            Some(BencodeElem::Integer(len)) => {
+                // "It must be a power of two and at least 16KiB."
+                if len < util::u64_to_i64(util::usize_to_u64(merkle::BLOCK_SIZE)?)? {
+                    bail!(ErrorKind::MalformedTorrent(Cow::Owned(format!(
+                        r#""piece length" < {}."#,
+                        merkle::BLOCK_SIZE
+                    ))))
+                } else if (len & (len - 1)) != 0 {
+                    bail!(ErrorKind::MalformedTorrent(Cow::Borrowed(
+                        r#""piece length" is not a power of 2."#
+                    )))
+                } else {
+                    Ok(len)
+                }
+            }
+            Some(_) => bail!(ErrorKind::MalformedTorrent(Cow::Borrowed(
+                r#""piece length" does not map to an integer."#
+            ))),
+            None => bail!(ErrorKind::MalformedTorrent(Cow::Borrowed(
+                r#""piece length" does not exist."#
+            ))),
+        }
+    }
+
+    fn extract_extra_fields(dict: HashMap<String, BencodeElem>) -> Option<Dictionary> {
+        if dict.is_empty() {
+            None
+        } else {
+            Some(dict)
+        }
+    }
+}
+
+#[cfg(test)]
+mod file_read_tests {
+    use super::*;
+    use std::iter::FromIterator;
+
+    #[test]
+    fn extract_file_ok() {
+        let root = vec![1_u8; 32];
+        let file = bencode_elem!({
+            ("length", 42),
+            ("pieces root", root),
+            ("comment", "no comment"),
+        });
+
+        assert_eq!(
+            File::extract_file(file).unwrap(),
+            File {
+                length: 42,
+                pieces_root: Some(vec![1; 32]),
+                extra_fields: Some(HashMap::from_iter(vec![(
+                    "comment".to_owned(),
+                    bencode_elem!("no comment")
+                )])),
+            }
+        );
+    }
+
+    #[test]
+    fn extract_file_empty_file() {
+        let file = bencode_elem!({ ("length", 0) });
+
+        assert_eq!(
+            File::extract_file(file).unwrap(),
+            File {
+                length: 0,
+                pieces_root: None,
+                extra_fields: None,
+            }
+        );
+    }
+
+    #[test]
+    fn extract_file_not_dictionary() {
+        match File::extract_file(bencode_elem!([])) {
+            Err(Error(ErrorKind::MalformedTorrent(m), _)) => {
+                assert_eq!(m, r#"A file in "file tree" does not map to a dictionary."#);
+            }
+            _ => assert!(false),
+        }
+    }
+
+    #[test]
+    fn extract_file_length_is_negative() {
+        let mut dict = HashMap::from_iter(vec![("length".to_owned(), bencode_elem!(-1))]);
+
+        match File::extract_file_length(&mut dict) {
+            Err(Error(ErrorKind::MalformedTorrent(m), _)) => assert_eq!(m, r#""length" < 0."#),
+            _ => assert!(false),
+        }
+    }
+
+    #[test]
+    fn extract_file_length_missing() {
+        let mut dict = HashMap::new();
+
+        match File::extract_file_length(&mut dict) {
+            Err(Error(ErrorKind::MalformedTorrent(m), _)) => {
+                assert_eq!(m, r#""length" does not exist."#);
+            }
+            _ => assert!(false),
+        }
+    }
+
+    #[test]
+    fn extract_file_pieces_root_as_string() {
+        let mut dict = HashMap::from_iter(vec![(
+            "pieces root".to_owned(),
+            bencode_elem!("aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa"),
+        )]);
+
+        assert_eq!(
+            File::extract_file_pieces_root(&mut dict, 1).unwrap(),
+            Some(vec![b'a'; 32])
+        );
+    }
+
+    #[test]
+    fn extract_file_pieces_root_missing() {
+        let mut dict = HashMap::new();
+
+        match File::extract_file_pieces_root(&mut dict, 1) {
+            Err(Error(ErrorKind::MalformedTorrent(m), _)) => {
+                assert_eq!(m, r#""pieces root" does not exist."#);
+            }
+            _ => assert!(false),
+        }
+    }
+
+    #[test]
+    fn extract_file_pieces_root_invalid_length() {
+        let mut dict = HashMap::from_iter(vec![("pieces root".to_owned(), bencode_elem!((1, 2)))]);
+
+        match File::extract_file_pieces_root(&mut dict, 1) {
+            Err(Error(ErrorKind::MalformedTorrent(m), _)) => {
+                assert_eq!(m, r#""pieces root"' length is not 32."#);
+            }
+            _ => assert!(false),
+        }
+    }
+
+    #[test]
+    fn extract_file_pieces_root_not_bytes() {
+        let mut dict = HashMap::from_iter(vec![("pieces root".to_owned(), bencode_elem!(1))]);
+
+        match File::extract_file_pieces_root(&mut dict, 1) {
+            Err(Error(ErrorKind::MalformedTorrent(m), _)) => {
+                assert_eq!(m, r#""pieces root" does not map to a sequence of bytes."#);
+            }
+            _ => assert!(false),
+        }
+    }
+}
+
+#[cfg(test)]
+mod file_tree_read_tests {
+    use super::*;
+    use std::iter::FromIterator;
+
+    #[test]
+    fn extract_file_tree_ok() {
+        let tree = bencode_elem!({
+            ("dir", { ("file1", { ("", { ("length", 0) }) }) }),
+            ("file2", { ("", { ("length", 0) }) }),
+        });
+        let file = File {
+            length: 0,
+            pieces_root: None,
+            extra_fields: None,
+        };
+
+        let mut dir = BTreeMap::new();
+        dir.insert("file1".to_owned(), FileTree::File(file.clone()));
+        let mut root = BTreeMap::new();
+        root.insert("dir".to_owned(), FileTree::Dir(dir));
+        root.insert("file2".to_owned(), FileTree::File(file));
+
+        assert_eq!(
+            FileTree::extract_file_tree(tree).unwrap(),
+            FileTree::Dir(root)
+        );
+    }
+
+    #[test]
+    fn extract_file_tree_file_and_dir() {
+        let tree =
+            bencode_elem!({ ("", { ("length", 0) }), ("file", { ("", { ("length", 0) }) }) });
+
+        match FileTree::extract_file_tree(tree) {
+            Err(Error(ErrorKind::MalformedTorrent(m), _)) => {
+                assert_eq!(
+                    m,
+                    r#"A node in "file tree" is both a file and a directory."#
+                );
+            }
+            _ => assert!(false),
+        }
+    }
+
+    #[test]
+    fn extract_file_tree_empty_dir() {
+        let tree = bencode_elem!({ ("dir", {}) });
+
+        match FileTree::extract_file_tree(tree) {
+            Err(Error(ErrorKind::MalformedTorrent(m), _)) => {
+                assert_eq!(m, r#""file tree" contains an empty directory."#);
+            }
+            _ => assert!(false),
+        }
+    }
+
+    #[test]
+    fn extract_file_tree_invalid_component() {
+        let tree = bencode_elem!({ ("..", { ("", { ("length", 0) }) }) });
+
+        match FileTree::extract_file_tree(tree) {
+            Err(Error(ErrorKind::MalformedTorrent(m), _)) => {
+                assert_eq!(m, r#""file tree" contains "." or ".."."#);
+            }
+            _ => assert!(false),
+        }
+    }
+
+    #[test]
+    fn extract_file_tree_empty_component() {
+        // the empty key marks a file, so it can never be a directory
+        let tree = bencode_elem!({ ("", { ("a", { ("", { ("length", 0) }) }) }) });
+
+        match FileTree::extract_file_tree(tree) {
+            Err(Error(ErrorKind::MalformedTorrent(m), _)) => {
+                assert_eq!(m, r#""length" does not exist."#);
+            }
+            _ => assert!(false),
+        }
+    }
+
+    #[test]
+    fn extract_file_tree_component_with_separator() {
+        for component in &["/etc", "a/../../x"] {
+            let tree = BencodeElem::Dictionary(HashMap::from_iter(vec![(
+                component.to_string(),
+                bencode_elem!({ ("", { ("length", 0) }) }),
+            )]));
+
+            match FileTree::extract_file_tree(tree) {
+                Err(Error(ErrorKind::MalformedTorrent(m), _)) => assert_eq!(
+                    m,
+                    r#""file tree" contains a path component with a path separator."#
+                ),
+                _ => assert!(false),
+            }
+        }
+    }
+
+    #[test]
+    fn extract_file_tree_not_dictionary() {
+        let tree = bencode_elem!({ ("file", []) });
+
+        match FileTree::extract_file_tree(tree) {
+            Err(Error(ErrorKind::MalformedTorrent(m), _)) => {
+                assert_eq!(m, r#""file tree" contains a non-dictionary element."#);
+            }
+            _ => assert!(false),
+        }
+    }
+
+    #[test]
+    fn extract_file_tree_non_utf8_component() {
+        let tree = bencode_elem!(r{ ([0xff, 0xfe], { ("", { ("length", 0) }) }) });
+
+        match FileTree::extract_file_tree(tree) {
+            Err(Error(ErrorKind::MalformedTorrent(m), _)) => assert_eq!(
+                m,
+                r#""file tree" contains a path component that is not valid UTF8."#
+            ),
+            _ => assert!(false),
+        }
+    }
+}
+
+#[cfg(test)]
+mod torrent_read_tests {
+    // @note: `read_from_bytes()` and `read_from_file()` are not tested
+    // as they are best left to integration tests (in `tests/`).
+    use super::*;
+    use std::iter::FromIterator;
+
+    fn single_file_torrent(length: Integer, root: Vec<u8>, layers: PieceLayers) -> Torrent {
+        let mut tree = BTreeMap::new();
+        tree.insert(
+            "file".to_owned(),
+            FileTree::File(File {
+                length,
+                pieces_root: Some(root),
+                extra_fields: None,
+            }),
+        );
+
+        Torrent {
+            announce: None,
+            announce_list: None,
+            length,
+            file_tree: FileTree::Dir(tree),
+            name: "sample".to_owned(),
+            piece_length: 16384,
+            piece_layers: layers,
+            extra_fields: None,
+            extra_info_fields: None,
+            raw_info: None,
+        }
+    }
+
+    #[test]
+    fn validate_ok() {
+        let layer = vec![vec![1; 32], vec![2; 32]];
+        let root = merkle::root_from_piece_layer(layer.clone(), 16384);
+        let torrent =
+            single_file_torrent(20000, root.clone(), HashMap::from_iter(vec![(root, layer)]));
+
+        assert_eq!(torrent.clone().validate().unwrap(), torrent);
+    }
+
+    #[test]
+    fn validate_small_file() {
+        // files <= `piece length` do not have piece layers
+        let torrent = single_file_torrent(16384, vec![1; 32], HashMap::new());
+        assert_eq!(torrent.clone().validate().unwrap(), torrent);
+    }
+
+    #[test]
+    fn validate_missing_layer() {
+        let torrent = single_file_torrent(20000, vec![1; 32], HashMap::new());
+
+        match torrent.validate() {
+            Err(Error(ErrorKind::MalformedTorrent(m), _)) => {
+                assert_eq!(m, "Piece layer of [file] does not exist.");
+            }
+            _ => assert!(false),
+        }
+    }
+
+    #[test]
+    fn validate_layer_length_mismatch() {
+        let layer = vec![vec![1; 32]];
+        let root = merkle::root_from_piece_layer(layer.clone(), 16384);
+        let torrent =
+            single_file_torrent(20000, root.clone(), HashMap::from_iter(vec![(root, layer)]));
+
+        match torrent.validate() {
+            Err(Error(ErrorKind::MalformedTorrent(m), _)) => {
+                assert_eq!(m, "Piece layer of [file] contains 1 pieces, 2 expected.");
+            }
+            _ => assert!(false),
+        }
+    }
+
+    #[test]
+    fn validate_layer_root_mismatch() {
+        let layer = vec![vec![1; 32], vec![2; 32]];
+        let torrent = single_file_torrent(
+            20000,
+            vec![3; 32],
+            HashMap::from_iter(vec![(vec![3; 32], layer)]),
+        );
+
+        match torrent.validate() {
+            Err(Error(ErrorKind::MalformedTorrent(m), _)) => assert_eq!(
+                m,
+                r#"Piece layer of [file] does not match its "pieces root"."#
+            ),
+            _ => assert!(false),
+        }
+    }
+
+    #[test]
+    fn from_parsed_ok() {
+        let root = vec![1_u8; 32];
+        let parsed = vec![bencode_elem!({
+            ("announce", "url"),
+            ("info", {
+                ("file tree", { ("file", { ("", { ("length", 2), ("pieces root", root) }) }) }),
+                ("meta version", 2),
+                ("name", "sample"),
+                ("piece length", 16384),
+            }),
+            ("piece layers", {}),
+        })];
+
+        assert_eq!(
+            Torrent::from_parsed(parsed).unwrap(),
+            Torrent {
+                announce: Some("url".to_owned()),
+                ..single_file_torrent(2, vec![1; 32], HashMap::new())
+            }
+        );
+    }
+
+    #[test]
+    fn from_parsed_top_level_not_dict() {
+        match Torrent::from_parsed(vec![bencode_elem!([])]) {
+            Err(Error(ErrorKind::MalformedTorrent(m), _)) => {
+                assert_eq!(m, "Torrent's top-level element is not a dictionary.");
+            }
+            _ => assert!(false),
+        }
+    }
+
+    #[test]
+    fn from_parsed_info_missing() {
+        match Torrent::from_parsed(vec![bencode_elem!({ ("announce", "url") })]) {
+            Err(Error(ErrorKind::MalformedTorrent(m), _)) => {
+                assert_eq!(m, r#""info" does not exist."#);
+            }
+            _ => assert!(false),
+        }
+    }
+
+    #[test]
+    fn extract_piece_layers_ok() {
+        let mut dict = HashMap::from_iter(vec![(
+            "piece layers".to_owned(),
+            BencodeElem::RawDictionary(HashMap::from_iter(vec![(
+                vec![0xff; 32],
+                BencodeElem::Bytes(vec![0xfe; 64]),
+            )])),
+        )]);
+
+        assert_eq!(
+            Torrent::extract_piece_layers(&mut dict).unwrap(),
+            HashMap::from_iter(vec![(vec![0xff; 32], vec![vec![0xfe; 32], vec![0xfe; 32]])])
+        );
+    }
+
+    #[test]
+    fn extract_piece_layers_missing() {
+        let mut dict = HashMap::new();
+        assert_eq!(
+            Torrent::extract_piece_layers(&mut dict).unwrap(),
+            HashMap::new()
+        );
+    }
+
+    #[test]
+    fn extract_piece_layers_invalid_key() {
+        let mut dict = HashMap::from_iter(vec![(
+            "piece layers".to_owned(),
+            BencodeElem::RawDictionary(HashMap::from_iter(vec![(
+                vec![0xff; 31],
+                BencodeElem::Bytes(vec![0xfe; 64]),
+            )])),
+        )]);
+
+        match Torrent::extract_piece_layers(&mut dict) {
+            Err(Error(ErrorKind::MalformedTorrent(m), _)) => assert_eq!(
+                m,
+                r#""piece layers" contains a key whose length is not 32."#
+            ),
+            _ => assert!(false),
+        }
+    }
+
+    #[test]
+    fn extract_piece_layers_invalid_layer() {
+        let mut dict = HashMap::from_iter(vec![(
+            "piece layers".to_owned(),
+            BencodeElem::RawDictionary(HashMap::from_iter(vec![(
+                vec![0xff; 32],
+                BencodeElem::Bytes(vec![0xfe; 63]),
+            )])),
+        )]);
+
+        match Torrent::extract_piece_layers(&mut dict) {
+            Err(Error(ErrorKind::MalformedTorrent(m), _)) => assert_eq!(
+                m,
+                r#""piece layers" contains a layer whose length is not a multiple of 32."#
+            ),
+            _ => assert!(false),
+        }
+    }
+
+    #[test]
+    fn extract_meta_version_ok() {
+        let mut dict = HashMap::from_iter(vec![("meta version".to_owned(), bencode_elem!(2))]);
+        Torrent::extract_meta_version(&mut dict).unwrap();
+    }
+
+    #[test]
+    fn extract_meta_version_unsupported() {
+        let mut dict = HashMap::from_iter(vec![("meta version".to_owned(), bencode_elem!(3))]);
+
+        match Torrent::extract_meta_version(&mut dict) {
+            Err(Error(ErrorKind::MalformedTorrent(m), _)) => {
+                assert_eq!(m, r#"Unsupported "meta version" 3."#);
+            }
+            _ => assert!(false),
+        }
+    }
+
+    #[test]
+    fn extract_meta_version_missing() {
+        let mut dict = HashMap::new();
+
+        match Torrent::extract_meta_version(&mut dict) {
+            Err(Error(ErrorKind::MalformedTorrent(m), _)) => {
+                assert_eq!(m, r#""meta version" does not exist."#);
+            }
+            _ => assert!(false),
+        }
+    }
+
+    #[test]
+    fn extract_file_tree_is_file() {
+        let mut dict = HashMap::from_iter(vec![(
+            "file tree".to_owned(),
+            bencode_elem!({ ("", { ("length", 0) }) }),
+        )]);
+
+        match Torrent::extract_file_tree(&mut dict) {
+            Err(Error(ErrorKind::MalformedTorrent(m), _)) => {
+                assert_eq!(m, r#""file tree" maps to a file instead of a directory."#);
+            }
+            _ => assert!(false),
+        }
+    }
+
+    #[test]
+    fn extract_length_overflow() {
+        let tree = bencode_elem!({
+            ("a", { ("", { ("length", 1), ("pieces root", "aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa") }) }),
+            ("b", { ("", { ("length", (i64::MAX)), ("pieces root", "aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa") }) }),
+        });
+
+        match Torrent::extract_length(&FileTree::extract_file_tree(tree).unwrap()) {
+            Err(Error(ErrorKind::MalformedTorrent(m), _)) => {
+                assert_eq!(m, r#"Torrent's length overflowed in i64."#);
+            }
+            _ => assert!(false),
+        }
+    }
+
+    #[test]
+    fn extract_piece_length_too_small() {
+        let mut dict = HashMap::from_iter(vec![("piece length".to_owned(), bencode_elem!(8192))]);
+
+        match Torrent::extract_piece_length(&mut dict) {
+            Err(Error(ErrorKind::MalformedTorrent(m), _)) => {
+                assert_eq!(m, r#""piece length" < 16384."#);
+            }
+            _ => assert!(false),
+        }
+    }
+
+    #[test]
+    fn extract_piece_length_not_power_of_two() {
+        let mut dict = HashMap::from_iter(vec![("piece length".to_owned(), bencode_elem!(20000))]);
+
+        match Torrent::extract_piece_length(&mut dict) {
+            Err(Error(ErrorKind::MalformedTorrent(m), _)) => {
+                assert_eq!(m, r#""piece length" is not a power of 2."#);
+            }
+            _ => assert!(false),
+        }
+    }
+}
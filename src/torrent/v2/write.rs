@@ -0,0 +1,241 @@
+use super::*;
+use bencode::write::write_dictionary_with_raw_value;
+use bencode::BencodeElem;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+use torrent::is_raw_info_current;
+
+impl File {
+    pub(crate) fn into_bencode_elem(self) -> BencodeElem {
+        let mut result: HashMap<String, BencodeElem> = HashMap::new();
+
+        result.insert("length".to_owned(), BencodeElem::Integer(self.length));
+        if let Some(pieces_root) = self.pieces_root {
+            result.insert("pieces root".to_owned(), BencodeElem::Bytes(pieces_root));
+        }
+
+        if let Some(extra_fields) = self.extra_fields {
+            result.extend(extra_fields);
+        }
+
+        BencodeElem::Dictionary(result)
+    }
+}
+
+impl FileTree {
+    pub(crate) fn into_bencode_elem(self) -> BencodeElem {
+        match self {
+            FileTree::File(file) => BencodeElem::Dictionary(
+                vec![("".to_owned(), file.into_bencode_elem())]
+                    .into_iter()
+                    .collect(),
+            ),
+            FileTree::Dir(entries) => BencodeElem::Dictionary(
+                entries
+                    .into_iter()
+                    .map(|(component, node)| (component, node.into_bencode_elem()))
+                    .collect(),
+            ),
+        }
+    }
+}
+
+impl Torrent {
+    /// Encode `self` as bencode and write the result to `dst`.
+    ///
+    /// If `raw_info` is `Some` and still matches the fields that belong to
+    /// `info` (i.e. none of them has been modified since parsing), its bytes
+    /// are written verbatim as `info`, so that the info hash does not change.
+    /// Otherwise `info` is encoded from those fields.
+    pub fn write_into<W>(self, dst: &mut W) -> Result<()>
+    where
+        W: Write,
+    {
+        let info = self.construct_info();
+        let write_raw_info = self
+            .raw_info
+            .as_ref()
+            .is_some_and(|raw_info| is_raw_info_current(raw_info, &info.encode()));
+        let mut result: HashMap<String, BencodeElem> = HashMap::new();
+
+        if let Some(announce) = self.announce {
+            result.insert("announce".to_owned(), BencodeElem::String(announce));
+        }
+
+        if let Some(list) = self.announce_list {
+            result.insert(
+                "announce-list".to_owned(),
+                BencodeElem::List(
+                    list.into_iter()
+                        .map(|tier| {
+                            BencodeElem::List(
+                                tier.into_iter()
+                                    .map(BencodeElem::String) // url -> string
+                                    .collect::<Vec<BencodeElem>>(),
+                            )
+                        })
+                        .collect::<Vec<BencodeElem>>(),
+                ),
+            );
+        }
+
+        result.insert(
+            "piece layers".to_owned(),
+            BencodeElem::RawDictionary(
+                self.piece_layers
+                    .into_iter()
+                    .map(|(root, layer)| (root, BencodeElem::Bytes(layer.concat())))
+                    .collect(),
+            ),
+        );
+
+        if let Some(extra_fields) = self.extra_fields {
+            result.extend(extra_fields);
+        }
+
+        match self.raw_info {
+            // write the original bytes so that the info hash is preserved
+            Some(raw_info) if write_raw_info => {
+                write_dictionary_with_raw_value(&result, "info", &raw_info, dst)
+            }
+            _ => {
+                result.insert("info".to_owned(), info);
+                BencodeElem::Dictionary(result).write_into(dst)
+            }
+        }
+    }
+
+    /// Encode `self` as bencode and write the result to `path`.
+    ///
+    /// `path` must be the path to a file.
+    ///
+    /// "This function will create a file if it does
+    /// not exist, and will truncate it if it does."
+    ///
+    /// Note: it is the client's responsibility to ensure
+    /// that all directories in `path` actually exist (e.g.
+    /// by calling [`create_dir_all`](https://doc.rust-lang.org/std/fs/fn.create_dir_all.html)).
+    pub fn write_into_file<P>(self, path: P) -> Result<()>
+    where
+        P: AsRef<Path>,
+    {
+        let file = ::std::fs::File::create(&path)?;
+        self.write_into(&mut BufWriter::new(&file))?;
+        file.sync_all()?;
+        Ok(())
+    }
+
+    /// Encode `self` as bencode and return the result in a `Vec`.
+    pub fn encode(self) -> Result<Vec<u8>> {
+        let mut result = Vec::new();
+        self.write_into(&mut result)?;
+        Ok(result)
+    }
+}
+
+#[cfg(test)]
+mod file_tree_write_tests {
+    use super::*;
+    use std::iter::FromIterator;
+
+    #[test]
+    fn file_into_bencode_elem_ok() {
+        let file = File {
+            length: 42,
+            pieces_root: Some(vec![1, 2, 3]),
+            extra_fields: None,
+        };
+
+        assert_eq!(
+            file.into_bencode_elem(),
+            bencode_elem!({ ("length", 42), ("pieces root", (1, 2, 3)) }),
+        )
+    }
+
+    #[test]
+    fn file_into_bencode_elem_empty_file() {
+        let file = File {
+            length: 0,
+            pieces_root: None,
+            extra_fields: Some(HashMap::from_iter(vec![(
+                "comment".to_owned(),
+                bencode_elem!("no comment"),
+            )])),
+        };
+
+        assert_eq!(
+            file.into_bencode_elem(),
+            bencode_elem!({ ("length", 0), ("comment", "no comment") }),
+        )
+    }
+
+    #[test]
+    fn file_tree_into_bencode_elem_ok() {
+        let mut dir = BTreeMap::new();
+        dir.insert(
+            "file".to_owned(),
+            FileTree::File(File {
+                length: 0,
+                pieces_root: None,
+                extra_fields: None,
+            }),
+        );
+        let mut root = BTreeMap::new();
+        root.insert("dir".to_owned(), FileTree::Dir(dir));
+
+        assert_eq!(
+            FileTree::Dir(root).into_bencode_elem(),
+            bencode_elem!({ ("dir", { ("file", { ("", { ("length", 0) }) }) }) }),
+        )
+    }
+}
+
+#[cfg(test)]
+mod torrent_write_tests {
+    // @note: `write_into_file()` is not tested as it is
+    // best left to integration tests (in `tests/`).
+    use super::*;
+    use std::iter::FromIterator;
+
+    #[test]
+    fn encode_ok() {
+        let mut root = BTreeMap::new();
+        root.insert(
+            "file".to_owned(),
+            FileTree::File(File {
+                length: 4,
+                pieces_root: Some(vec![1, 2]),
+                extra_fields: None,
+            }),
+        );
+        let torrent = Torrent {
+            announce: Some("url".to_owned()),
+            announce_list: None,
+            length: 4,
+            file_tree: FileTree::Dir(root),
+            name: "sample".to_owned(),
+            piece_length: 16384,
+            piece_layers: HashMap::from_iter(vec![(vec![1, 2], vec![vec![3, 4], vec![5, 6]])]),
+            extra_fields: None,
+            extra_info_fields: None,
+            raw_info: None,
+        };
+
+        let piece_layers = bencode_elem!(r{ ([1, 2], (3, 4, 5, 6)) });
+
+        assert_eq!(
+            torrent.encode().unwrap(),
+            bencode_elem!({
+                ("announce", "url"),
+                ("info", {
+                    ("file tree", { ("file", { ("", { ("length", 4), ("pieces root", (1, 2)) }) }) }),
+                    ("meta version", 2),
+                    ("name", "sample"),
+                    ("piece length", 16384),
+                }),
+                ("piece layers", piece_layers),
+            })
+            .encode()
+        );
+    }
+}
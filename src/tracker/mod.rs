@@ -1,28 +1,117 @@
-//! [Experimental] Module containing structs for tracker responses.
+//! [Experimental] Module containing structs for tracker requests and responses.
 //!
 //! These structs are provided only for user convenience. Since they
 //! are experimental, they might be removed or replaced in the future.
 //!
 //! At the moment, `lava_torrent` does not handle communication
-//! with trackers. Users will have to send requests themselves and
+//! with trackers. Users can build announce/scrape URLs with [`AnnounceRequest`]
+//! and [`ScrapeRequest`], and encode responses (e.g. to implement a tracker),
+//! but will have to send requests themselves and
 //! pass the received responses to `lava_torrent` for parsing.
+//! Messages of UDP trackers are handled by [`udp`].
+//!
+//! [`AnnounceRequest`]: struct.AnnounceRequest.html
+//! [`ScrapeRequest`]: struct.ScrapeRequest.html
+//! [`udp`]: udp/index.html
 
 use bencode::BencodeElem;
 use error::*;
 use itertools::Itertools;
 use std::borrow::Cow;
 use std::collections::HashMap;
-use std::convert::TryInto;
+use std::convert::{TryFrom, TryInto};
 use std::fmt;
-use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
 use torrent::v1::{Dictionary, Integer};
 
+mod request;
+pub mod udp;
+mod write;
+
+// length of a compact peer: IP address + 2-byte port
+const COMPACT_PEER_V4_LENGTH: usize = 6;
+const COMPACT_PEER_V6_LENGTH: usize = 18;
+
+const V1_HASH_LENGTH: usize = 20;
+const PEER_ID_LENGTH: usize = 20;
+
+/// The `event` of an announce request.
+///
+/// An announce without `event` is one of the
+/// announces done at regular intervals.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum AnnounceEvent {
+    /// The first request to the tracker.
+    Started,
+    /// The download has completed. It is not sent if the download
+    /// was already complete when the client started.
+    Completed,
+    /// The client is shutting down gracefully.
+    Stopped,
+}
+
+/// An announce request sent to an HTTP tracker.
+///
+/// Modeled after the specifications in
+/// [BEP 3](http://bittorrent.org/beps/bep_0003.html),
+/// [BEP 7](http://bittorrent.org/beps/bep_0007.html),
+/// [BEP 23](http://bittorrent.org/beps/bep_0023.html) and
+/// [theory.org](https://wiki.theory.org/index.php/BitTorrentSpecification#Tracker_Request_Parameters).
+///
+/// The request can be rendered onto an announce URL (e.g. one
+/// taken from [`Torrent::announce`]) with [`to_url()`], which
+/// takes care of percent-encoding the binary `info_hash` and `peer_id`.
+///
+/// [`Torrent::announce`]: ../torrent/v1/struct.Torrent.html#structfield.announce
+/// [`to_url()`]: #method.to_url
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct AnnounceRequest {
+    /// The 20-byte v1 info hash of the torrent.
+    #[cfg_attr(feature = "serde", serde(with = "::serde_util::bytes"))]
+    pub info_hash: Vec<u8>,
+    /// The 20-byte id of the client.
+    #[cfg_attr(feature = "serde", serde(with = "::serde_util::bytes"))]
+    pub peer_id: Vec<u8>,
+    /// The port the client is listening on.
+    pub port: u16,
+    /// Total number of bytes uploaded since `Started` was sent.
+    pub uploaded: Integer,
+    /// Total number of bytes downloaded since `Started` was sent.
+    pub downloaded: Integer,
+    /// Number of bytes the client still has to download.
+    pub left: Integer,
+    /// `None` for regular announces.
+    pub event: Option<AnnounceEvent>,
+    /// Whether the client accepts compact peer lists as defined
+    /// in [BEP 23](http://bittorrent.org/beps/bep_0023.html).
+    pub compact: Option<bool>,
+    /// Number of peers the client would like to receive.
+    pub numwant: Option<Integer>,
+    /// A random value that lets the tracker identify the client
+    /// if its IP address changes.
+    pub key: Option<String>,
+    /// `tracker_id` from a previous [`TrackerResponse`](struct.TrackerResponse.html)
+    /// (`trackerid`).
+    pub tracker_id: Option<String>,
+    /// The IP address or DNS name of the client (`ip`).
+    pub ip: Option<String>,
+    /// The IPv4 address of the client as defined in
+    /// [BEP 7](http://bittorrent.org/beps/bep_0007.html).
+    pub ipv4: Option<Ipv4Addr>,
+    /// The IPv6 address of the client as defined in
+    /// [BEP 7](http://bittorrent.org/beps/bep_0007.html).
+    pub ipv6: Option<Ipv6Addr>,
+}
+
 /// Peer information returned in a tracker response.
 ///
 /// Modeled after the specifications in
 /// [BEP 3](http://bittorrent.org/beps/bep_0003.html) and
 /// [BEP 23](http://www.bittorrent.org/beps/bep_0023.html).
 #[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Peer {
     /// A string of length 20 which this peer uses as its id.
     /// This field will be `None` for compact peer info.
@@ -33,6 +122,20 @@ pub struct Peer {
     pub extra_fields: Option<Dictionary>,
 }
 
+/// How peers are encoded in a tracker response.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum PeerListFormat {
+    /// IPv4 peers are encoded as 6-byte entries in `peers`
+    /// ([BEP 23](http://bittorrent.org/beps/bep_0023.html)), and IPv6 peers are
+    /// encoded as 18-byte entries in `peers6`
+    /// ([BEP 7](http://bittorrent.org/beps/bep_0007.html)).
+    /// Peer ids and extra fields are dropped.
+    Compact,
+    /// All peers are encoded as dictionaries in `peers`.
+    Dictionary,
+}
+
 /// Everything found in a tracker response.
 ///
 /// Modeled after the specifications in
@@ -41,13 +144,18 @@ pub struct Peer {
 /// Unknown/extension fields will be placed in `extra_fields`. If you
 /// need any of those extra fields you would have to parse it yourself.
 #[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct TrackerResponse {
     /// The number of seconds the downloader should wait between
     /// regular requests.
     pub interval: Integer,
     /// A list of dictionaries corresponding to `Peer`.
+    ///
+    /// IPv6 peers in `peers6` as defined in
+    /// [BEP 7](http://bittorrent.org/beps/bep_0007.html)
+    /// are appended to this list.
     pub peers: Vec<Peer>,
-    /// Warning message.
+    /// Warning message (`warning message`).
     pub warning: Option<String>,
     /// Minimum announce interval. If present clients must not
     /// re-announce more frequently than this.
@@ -59,6 +167,9 @@ pub struct TrackerResponse {
     pub complete: Option<Integer>,
     /// Number of non-seeder peers, i.e. leechers.
     pub incomplete: Option<Integer>,
+    /// The IP address of the client as seen by the tracker (`external ip`)
+    /// as defined in [BEP 24](http://bittorrent.org/beps/bep_0024.html).
+    pub external_ip: Option<IpAddr>,
     /// Fields not listed above.
     pub extra_fields: Option<Dictionary>,
 }
@@ -70,6 +181,7 @@ pub struct TrackerResponse {
 /// Unknown/extension fields will be placed in `extra_fields`. If you
 /// need any of those extra fields you would have to parse it yourself.
 #[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct SwarmMetadata {
     /// The number of active peers that have completed downloading.
     pub complete: Integer,
@@ -87,13 +199,39 @@ pub struct SwarmMetadata {
 /// [BEP 48](http://www.bittorrent.org/beps/bep_0048.html) and
 /// [theory.org](https://wiki.theory.org/index.php/BitTorrentSpecification#Tracker_.27scrape.27_Convention).
 #[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct TrackerScrapeResponse {
     /// File info (info hash -> metadata).
+    #[cfg_attr(feature = "serde", serde(with = "::serde_util::bytes_map"))]
     pub files: HashMap<Vec<u8>, SwarmMetadata>,
-    /// Fields not listed above.
+    /// The number of seconds the client should wait between scrapes
+    /// (`min_request_interval` in `flags`).
+    pub min_request_interval: Option<Integer>,
+    /// Fields not listed above. Unknown entries in `flags` are
+    /// kept in `flags`.
     pub extra_fields: Option<Dictionary>,
 }
 
+/// A scrape request sent to an HTTP tracker.
+///
+/// Modeled after the specifications in
+/// [BEP 48](http://www.bittorrent.org/beps/bep_0048.html).
+///
+/// The scrape URL is derived from an announce URL, so the request can be
+/// rendered onto an announce URL (e.g. one taken from [`Torrent::announce`])
+/// with [`to_url()`].
+///
+/// [`Torrent::announce`]: ../torrent/v1/struct.Torrent.html#structfield.announce
+/// [`to_url()`]: #method.to_url
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ScrapeRequest {
+    /// The 20-byte v1 info hashes of the torrents to scrape. If it's
+    /// empty then the tracker might return all the torrents it tracks.
+    #[cfg_attr(feature = "serde", serde(with = "::serde_util::pieces"))]
+    pub info_hashes: Vec<Vec<u8>>,
+}
+
 impl Peer {
     /// Go through `dict` and return the extracted `Peer`.
     ///
@@ -150,18 +288,21 @@ impl Peer {
 
     /// Parse `bytes` and return the extracted `Peer`.
     ///
-    /// `bytes` must contain exactly 6 bytes.
+    /// `bytes` must contain exactly 6 bytes (IPv4) or 18 bytes (IPv6).
     fn from_bytes<B>(bytes: B) -> Peer
-        where
-            B: AsRef<[u8]>,
+    where
+        B: AsRef<[u8]>,
     {
         let bytes = bytes.as_ref();
-        if bytes.len() != 6 {
-            panic!("Peer::from_bytes() expects 6 bytes, {} received.", bytes.len())
-        }
-
-        let ip = Ipv4Addr::from(u32::from_be_bytes(bytes[..4].try_into().unwrap()));
-        let port = u16::from_be_bytes(bytes[4..].try_into().unwrap());
+        let ip = match bytes.len() {
+            COMPACT_PEER_V4_LENGTH => IpAddr::from(<[u8; 4]>::try_from(&bytes[..4]).unwrap()),
+            COMPACT_PEER_V6_LENGTH => IpAddr::from(<[u8; 16]>::try_from(&bytes[..16]).unwrap()),
+            _ => panic!(
+                "Peer::from_bytes() expects 6 or 18 bytes, {} received.",
+                bytes.len()
+            ),
+        };
+        let port = u16::from_be_bytes(bytes[(bytes.len() - 2)..].try_into().unwrap());
 
         Peer {
             id: None,
@@ -177,8 +318,8 @@ impl TrackerResponse {
     /// If `bytes` is missing any required field (e.g. `interval`), or if any other
     /// error is encountered (e.g. `IOError`), then `Err(error)` will be returned.
     pub fn from_bytes<B>(bytes: B) -> Result<TrackerResponse>
-        where
-            B: AsRef<[u8]>,
+    where
+        B: AsRef<[u8]>,
     {
         let mut parsed = BencodeElem::from_bytes(bytes)?;
         if parsed.len() != 1 {
@@ -196,7 +337,9 @@ impl TrackerResponse {
         };
 
         match parsed.remove("failure reason") {
-            Some(BencodeElem::String(reason)) => bail!(ErrorKind::TrackerErrorResponse(Cow::Owned(reason))),
+            Some(BencodeElem::String(reason)) => {
+                bail!(ErrorKind::TrackerErrorResponse(Cow::Owned(reason)))
+            }
             Some(_) => bail!(ErrorKind::MalformedResponse(Cow::Borrowed(
                 r#""failure reason" does not map to a string (or maps to invalid UTF8)."#
             ))),
@@ -213,22 +356,48 @@ impl TrackerResponse {
             ))),
         };
 
-        let peers = match parsed.remove("peers") {
+        let peers6 = match parsed.remove("peers6") {
+            Some(BencodeElem::Bytes(bytes)) => {
+                Some(Self::extract_peers_from_bytes(bytes.as_ref(), "peers6")?)
+            }
+            Some(BencodeElem::String(str)) => {
+                Some(Self::extract_peers_from_bytes(str.as_bytes(), "peers6")?)
+            }
+            Some(_) => bail!(ErrorKind::MalformedResponse(Cow::Borrowed(
+                r#""peers6" does not map to a string of bytes."#
+            ))),
+            None => None,
+        };
+
+        let mut peers = match parsed.remove("peers") {
             Some(BencodeElem::List(list)) => Self::extract_peers_from_list(list)?,
-            Some(BencodeElem::Bytes(bytes)) => Self::extract_peers_from_bytes(bytes.as_ref())?,
-            Some(BencodeElem::String(str)) => Self::extract_peers_from_bytes(str.as_bytes())?,
+            Some(BencodeElem::Bytes(bytes)) => {
+                Self::extract_peers_from_bytes(bytes.as_ref(), "peers")?
+            }
+            Some(BencodeElem::String(str)) => {
+                Self::extract_peers_from_bytes(str.as_bytes(), "peers")?
+            }
             Some(_) => bail!(ErrorKind::MalformedResponse(Cow::Borrowed(
                 r#""peers" does not map to a dict or a string of bytes."#
             ))),
+            // an IPv6-only tracker might send only `peers6`
+            None if peers6.is_some() => Vec::new(),
             None => bail!(ErrorKind::MalformedResponse(Cow::Borrowed(
                 r#""peers" does not exist."#
             ))),
         };
+        if let Some(peers6) = peers6 {
+            peers.extend(peers6);
+        }
 
-        let warning = match parsed.remove("warning") {
+        // some trackers use `warning` instead of `warning message`
+        let warning = match parsed
+            .remove("warning message")
+            .or_else(|| parsed.remove("warning"))
+        {
             Some(BencodeElem::String(warning)) => Some(warning),
             Some(_) => bail!(ErrorKind::MalformedResponse(Cow::Borrowed(
-                r#""warning" does not map to a string (or maps to invalid UTF8)."#
+                r#""warning message" does not map to a string (or maps to invalid UTF8)."#
             ))),
             None => None,
         };
@@ -264,6 +433,16 @@ impl TrackerResponse {
             None => None,
         };
 
+        // a 4-byte IPv4 address or a 16-byte IPv6 address
+        let external_ip = match parsed.remove("external ip") {
+            Some(BencodeElem::Bytes(bytes)) => Some(Self::extract_external_ip(&bytes)?),
+            Some(BencodeElem::String(str)) => Some(Self::extract_external_ip(str.as_bytes())?),
+            Some(_) => bail!(ErrorKind::MalformedResponse(Cow::Borrowed(
+                r#""external ip" does not map to a string of bytes."#
+            ))),
+            None => None,
+        };
+
         let extra_fields = if parsed.is_empty() {
             None
         } else {
@@ -278,6 +457,7 @@ impl TrackerResponse {
             tracker_id,
             complete,
             incomplete,
+            external_ip,
             extra_fields,
         })
     }
@@ -293,19 +473,33 @@ impl TrackerResponse {
             .collect()
     }
 
-    fn extract_peers_from_bytes(bytes: &[u8]) -> Result<Vec<Peer>> {
-        if (bytes.len() % 6) != 0 {
-            bail!(ErrorKind::MalformedResponse(Cow::Borrowed(
-                r#"Compact "peers" contains incorrect number of bytes"#
-            )))
+    // `key` is either `peers` (IPv4) or `peers6` (IPv6)
+    fn extract_peers_from_bytes(bytes: &[u8], key: &str) -> Result<Vec<Peer>> {
+        let peer_length = if key == "peers6" {
+            COMPACT_PEER_V6_LENGTH
+        } else {
+            COMPACT_PEER_V4_LENGTH
+        };
+        if (bytes.len() % peer_length) != 0 {
+            bail!(ErrorKind::MalformedResponse(Cow::Owned(format!(
+                r#"Compact "{}" contains incorrect number of bytes"#,
+                key
+            ))))
         }
 
-        let n_peers = bytes.len() / 6;
-        let mut peers = Vec::with_capacity(n_peers);
-        for i in 0..(n_peers) {
-            peers.push(Peer::from_bytes(bytes[(i * 6)..((i + 1) * 6)].as_ref()));
+        Ok(bytes.chunks(peer_length).map(Peer::from_bytes).collect())
+    }
+
+    fn extract_external_ip(bytes: &[u8]) -> Result<IpAddr> {
+        if let Ok(ip) = <[u8; 4]>::try_from(bytes) {
+            Ok(IpAddr::from(ip))
+        } else if let Ok(ip) = <[u8; 16]>::try_from(bytes) {
+            Ok(IpAddr::from(ip))
+        } else {
+            bail!(ErrorKind::MalformedResponse(Cow::Borrowed(
+                r#""external ip" contains incorrect number of bytes"#
+            )))
         }
-        Ok(peers)
     }
 }
 
@@ -359,8 +553,8 @@ impl TrackerScrapeResponse {
     /// If `bytes` is missing any required field (e.g. `files`), or if any other
     /// error is encountered (e.g. `IOError`), then `Err(error)` will be returned.
     pub fn from_bytes<B>(bytes: B) -> Result<TrackerScrapeResponse>
-        where
-            B: AsRef<[u8]>,
+    where
+        B: AsRef<[u8]>,
     {
         let mut parsed = BencodeElem::from_bytes(bytes)?;
         if parsed.len() != 1 {
@@ -378,13 +572,36 @@ impl TrackerScrapeResponse {
 
         let files = match parsed.remove("files") {
             Some(BencodeElem::RawDictionary(dict)) => dict,
+            // info hashes that are all valid UTF8 are parsed as strings
+            Some(BencodeElem::Dictionary(dict)) => {
+                dict.into_iter().map(|(k, v)| (k.into_bytes(), v)).collect()
+            }
             Some(_) => bail!(ErrorKind::MalformedResponse(Cow::Borrowed(
-                r#""files" does not map to a raw dict."#
+                r#""files" does not map to a dict."#
             ))),
             None => bail!(ErrorKind::MalformedResponse(Cow::Borrowed(
                 r#""files" does not exist."#
             ))),
         };
+        let min_request_interval = match parsed.remove("flags") {
+            Some(BencodeElem::Dictionary(mut flags)) => {
+                let min_request_interval = match flags.remove("min_request_interval") {
+                    Some(BencodeElem::Integer(interval)) => Some(interval),
+                    Some(_) => bail!(ErrorKind::MalformedResponse(Cow::Borrowed(
+                        r#""min_request_interval" does not map to an integer."#
+                    ))),
+                    None => None,
+                };
+                if !flags.is_empty() {
+                    parsed.insert("flags".to_owned(), BencodeElem::Dictionary(flags));
+                }
+                min_request_interval
+            }
+            Some(_) => bail!(ErrorKind::MalformedResponse(Cow::Borrowed(
+                r#""flags" does not map to a dict."#
+            ))),
+            None => None,
+        };
         let extra_fields = if parsed.is_empty() {
             None
         } else {
@@ -404,6 +621,7 @@ impl TrackerScrapeResponse {
 
         Ok(TrackerScrapeResponse {
             files,
+            min_request_interval,
             extra_fields,
         })
     }
@@ -449,6 +667,9 @@ impl fmt::Display for TrackerResponse {
         if let Some(ref incomplete) = self.incomplete {
             writeln!(f, "-incomplete: {}", incomplete)?;
         }
+        if let Some(ref external_ip) = self.external_ip {
+            writeln!(f, "-external_ip: {}", external_ip)?;
+        }
 
         if let Some(ref fields) = self.extra_fields {
             write!(
@@ -461,7 +682,12 @@ impl fmt::Display for TrackerResponse {
             )?;
         }
 
-        writeln!(f, "-peers ({}):\n{}", self.peers.len(), self.peers.iter().format(""))
+        writeln!(
+            f,
+            "-peers ({}):\n{}",
+            self.peers.len(),
+            self.peers.iter().format("")
+        )
     }
 }
 
@@ -499,6 +725,9 @@ impl fmt::Display for TrackerScrapeResponse {
                     v
                 )))
         )?;
+        if let Some(ref min_request_interval) = self.min_request_interval {
+            writeln!(f, "-min_request_interval: {}", min_request_interval)?;
+        }
 
         if let Some(ref fields) = self.extra_fields {
             write!(
@@ -515,4 +744,296 @@ impl fmt::Display for TrackerScrapeResponse {
     }
 }
 
-// @todo: add unit tests
+#[cfg(test)]
+mod tracker_response_tests {
+    use super::*;
+
+    // a response containing `interval` and `entries`
+    fn response(entries: Vec<(&str, BencodeElem)>) -> Vec<u8> {
+        let mut dict: HashMap<String, BencodeElem> = entries
+            .into_iter()
+            .map(|(key, value)| (key.to_owned(), value))
+            .collect();
+        dict.insert("interval".to_owned(), BencodeElem::Integer(900));
+        BencodeElem::Dictionary(dict).encode()
+    }
+
+    fn ipv6_bytes(last: u8, port: u16) -> Vec<u8> {
+        let mut bytes = vec![0x20, 0x01, 0x0d, 0xb8];
+        bytes.extend_from_slice(&[0; 11]);
+        bytes.push(last);
+        bytes.extend_from_slice(&port.to_be_bytes());
+        bytes
+    }
+
+    #[test]
+    fn peer_from_bytes_v4() {
+        assert_eq!(
+            Peer::from_bytes([127, 0, 0, 1, 0x1a, 0xe1]),
+            Peer {
+                id: None,
+                addr: "127.0.0.1:6881".parse().unwrap(),
+                extra_fields: None,
+            }
+        );
+    }
+
+    #[test]
+    fn peer_from_bytes_v6() {
+        assert_eq!(
+            Peer::from_bytes(ipv6_bytes(1, 6881)),
+            Peer {
+                id: None,
+                addr: "[2001:db8::1]:6881".parse().unwrap(),
+                extra_fields: None,
+            }
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Peer::from_bytes() expects 6 or 18 bytes, 7 received.")]
+    fn peer_from_bytes_bad_length() {
+        Peer::from_bytes([0; 7]);
+    }
+
+    #[test]
+    fn from_bytes_compact_peers() {
+        let response = TrackerResponse::from_bytes(response(vec![(
+            "peers",
+            BencodeElem::Bytes(vec![127, 0, 0, 1, 0x1a, 0xe1, 10, 0, 0, 2, 0x1a, 0xe2]),
+        )]))
+        .unwrap();
+
+        assert_eq!(
+            response
+                .peers
+                .iter()
+                .map(|peer| peer.addr)
+                .collect::<Vec<_>>(),
+            vec![
+                "127.0.0.1:6881".parse().unwrap(),
+                "10.0.0.2:6882".parse().unwrap(),
+            ]
+        );
+        assert_eq!(response.external_ip, None);
+        assert_eq!(response.extra_fields, None);
+    }
+
+    #[test]
+    fn from_bytes_with_peers6() {
+        let response = TrackerResponse::from_bytes(response(vec![
+            ("peers", BencodeElem::Bytes(vec![127, 0, 0, 1, 0x1a, 0xe1])),
+            (
+                "peers6",
+                BencodeElem::Bytes([ipv6_bytes(1, 6881), ipv6_bytes(2, 6882)].concat()),
+            ),
+        ]))
+        .unwrap();
+
+        assert_eq!(
+            response
+                .peers
+                .iter()
+                .map(|peer| peer.addr)
+                .collect::<Vec<_>>(),
+            vec![
+                "127.0.0.1:6881".parse().unwrap(),
+                "[2001:db8::1]:6881".parse().unwrap(),
+                "[2001:db8::2]:6882".parse().unwrap(),
+            ]
+        );
+        assert_eq!(response.extra_fields, None);
+    }
+
+    #[test]
+    fn from_bytes_peers6_only() {
+        let response = TrackerResponse::from_bytes(response(vec![(
+            "peers6",
+            BencodeElem::Bytes(ipv6_bytes(1, 6881)),
+        )]))
+        .unwrap();
+
+        assert_eq!(response.peers.len(), 1);
+        assert_eq!(
+            response.peers[0].addr,
+            "[2001:db8::1]:6881".parse().unwrap()
+        );
+    }
+
+    #[test]
+    fn from_bytes_peers6_bad_length() {
+        match TrackerResponse::from_bytes(response(vec![
+            ("peers", BencodeElem::Bytes(vec![])),
+            ("peers6", BencodeElem::Bytes(vec![0xff; 6])),
+        ])) {
+            Err(Error(ErrorKind::MalformedResponse(m), _)) => {
+                assert_eq!(m, r#"Compact "peers6" contains incorrect number of bytes"#)
+            }
+            _ => assert!(false),
+        }
+    }
+
+    #[test]
+    fn from_bytes_peers_missing() {
+        match TrackerResponse::from_bytes(response(vec![])) {
+            Err(Error(ErrorKind::MalformedResponse(m), _)) => {
+                assert_eq!(m, r#""peers" does not exist."#)
+            }
+            _ => assert!(false),
+        }
+    }
+
+    #[test]
+    fn from_bytes_with_external_ip_v4() {
+        let response = TrackerResponse::from_bytes(response(vec![
+            ("external ip", BencodeElem::Bytes(vec![192, 0, 2, 0xff])),
+            ("peers", BencodeElem::Bytes(vec![])),
+        ]))
+        .unwrap();
+
+        assert_eq!(response.external_ip, Some("192.0.2.255".parse().unwrap()));
+        assert_eq!(response.extra_fields, None);
+    }
+
+    #[test]
+    fn from_bytes_with_external_ip_v6() {
+        let mut external_ip = ipv6_bytes(1, 0);
+        external_ip.truncate(16);
+        let response = TrackerResponse::from_bytes(response(vec![
+            ("external ip", BencodeElem::Bytes(external_ip)),
+            ("peers", BencodeElem::Bytes(vec![])),
+        ]))
+        .unwrap();
+
+        assert_eq!(response.external_ip, Some("2001:db8::1".parse().unwrap()));
+    }
+
+    #[test]
+    fn from_bytes_external_ip_bad_length() {
+        match TrackerResponse::from_bytes(response(vec![
+            ("external ip", BencodeElem::Bytes(vec![0xff; 3])),
+            ("peers", BencodeElem::Bytes(vec![])),
+        ])) {
+            Err(Error(ErrorKind::MalformedResponse(m), _)) => {
+                assert_eq!(m, r#""external ip" contains incorrect number of bytes"#)
+            }
+            _ => assert!(false),
+        }
+    }
+
+    #[test]
+    fn display_with_external_ip() {
+        let response = TrackerResponse::from_bytes(response(vec![
+            ("external ip", BencodeElem::Bytes(vec![192, 0, 2, 0xff])),
+            ("peers", BencodeElem::Bytes(vec![])),
+        ]))
+        .unwrap();
+
+        assert_eq!(
+            response.to_string(),
+            "-interval: 900\n-external_ip: 192.0.2.255\n-peers (0):\n\n"
+        );
+    }
+}
+
+#[cfg(test)]
+mod tracker_scrape_response_tests {
+    use super::*;
+    use std::iter::FromIterator;
+
+    // a scrape response with 1 file and `flags`
+    fn scrape_response(flags: &[u8]) -> Vec<u8> {
+        let mut bytes = b"d5:filesd20:ABCDEFGHIJKLMNOPQRST\
+            d8:completei1e10:downloadedi2e10:incompletei3eee5:flags"
+            .to_vec();
+        bytes.extend_from_slice(flags);
+        bytes.push(b'e');
+        bytes
+    }
+
+    #[test]
+    fn from_bytes_ok() {
+        let response = TrackerScrapeResponse::from_bytes(
+            b"d5:filesd20:ABCDEFGHIJKLMNOPQRST\
+              d8:completei1e10:downloadedi2e10:incompletei3eeee",
+        )
+        .unwrap();
+
+        assert_eq!(
+            response,
+            TrackerScrapeResponse {
+                files: HashMap::from_iter(vec![(
+                    b"ABCDEFGHIJKLMNOPQRST".to_vec(),
+                    SwarmMetadata {
+                        complete: 1,
+                        incomplete: 3,
+                        downloaded: 2,
+                        extra_fields: None,
+                    }
+                )]),
+                min_request_interval: None,
+                extra_fields: None,
+            }
+        );
+    }
+
+    #[test]
+    fn from_bytes_raw_info_hash() {
+        let mut bytes = b"d5:filesd20:".to_vec();
+        bytes.extend_from_slice(&[0xff; 20]);
+        bytes.extend_from_slice(b"d8:completei1e10:downloadedi2e10:incompletei3eeee");
+        let response = TrackerScrapeResponse::from_bytes(bytes).unwrap();
+
+        assert_eq!(
+            response.files.keys().collect::<Vec<_>>(),
+            vec![&vec![0xff; 20]]
+        );
+    }
+
+    #[test]
+    fn from_bytes_with_flags() {
+        let response =
+            TrackerScrapeResponse::from_bytes(scrape_response(b"d20:min_request_intervali60ee"))
+                .unwrap();
+
+        assert_eq!(response.min_request_interval, Some(60));
+        assert_eq!(response.extra_fields, None);
+    }
+
+    #[test]
+    fn from_bytes_with_unknown_flags() {
+        let response = TrackerScrapeResponse::from_bytes(scrape_response(
+            b"d3:keyi1e20:min_request_intervali60ee",
+        ))
+        .unwrap();
+
+        assert_eq!(response.min_request_interval, Some(60));
+        assert_eq!(
+            response.extra_fields,
+            Some(HashMap::from_iter(vec![(
+                "flags".to_owned(),
+                bencode_elem!({ ("key", 1) }),
+            )]))
+        );
+    }
+
+    #[test]
+    fn from_bytes_flags_not_dict() {
+        match TrackerScrapeResponse::from_bytes(scrape_response(b"i60e")) {
+            Err(Error(ErrorKind::MalformedResponse(m), _)) => {
+                assert_eq!(m, r#""flags" does not map to a dict."#)
+            }
+            _ => assert!(false),
+        }
+    }
+
+    #[test]
+    fn from_bytes_min_request_interval_not_integer() {
+        match TrackerScrapeResponse::from_bytes(scrape_response(b"d20:min_request_interval2:60e")) {
+            Err(Error(ErrorKind::MalformedResponse(m), _)) => {
+                assert_eq!(m, r#""min_request_interval" does not map to an integer."#)
+            }
+            _ => assert!(false),
+        }
+    }
+}
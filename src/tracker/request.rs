@@ -0,0 +1,560 @@
+use super::*;
+use torrent::v1::{AnnounceList, Torrent};
+use util;
+
+const ANNOUNCE: &str = "announce";
+const SCRAPE: &str = "scrape";
+
+impl AnnounceEvent {
+    /// The value of `event` in an announce request.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            AnnounceEvent::Started => "started",
+            AnnounceEvent::Completed => "completed",
+            AnnounceEvent::Stopped => "stopped",
+        }
+    }
+}
+
+impl AnnounceRequest {
+    /// Create a new `AnnounceRequest`.
+    ///
+    /// `uploaded`, `downloaded` and `left` are initialized to 0,
+    /// `compact` is initialized to `Some(true)` as
+    /// many trackers only support compact peer lists,
+    /// and other optional fields are initialized to `None`.
+    pub fn new(info_hash: Vec<u8>, peer_id: Vec<u8>, port: u16) -> AnnounceRequest {
+        AnnounceRequest {
+            info_hash,
+            peer_id,
+            port,
+            compact: Some(true),
+            ..Default::default()
+        }
+    }
+
+    /// Create a new `AnnounceRequest` for `torrent`.
+    ///
+    /// Same as [`new()`], except that `info_hash` is set to the info hash
+    /// of `torrent`, and `left` is set to its [`content_length()`] (i.e.
+    /// nothing has been downloaded yet).
+    ///
+    /// [`new()`]: #method.new
+    /// [`content_length()`]: ../torrent/v1/struct.Torrent.html#method.content_length
+    pub fn from_torrent(torrent: &Torrent, peer_id: Vec<u8>, port: u16) -> AnnounceRequest {
+        AnnounceRequest {
+            left: torrent.content_length(),
+            ..Self::new(torrent.info_hash_bytes(), peer_id, port)
+        }
+    }
+
+    /// Render `self` as a query string (without the leading `?`).
+    ///
+    /// All values are percent-encoded, so the result can be
+    /// appended to an announce URL as is.
+    ///
+    /// If `info_hash` or `peer_id` does not contain exactly 20 bytes,
+    /// or if any of `uploaded`, `downloaded`, `left` and `numwant` is
+    /// negative, then `Err(error)` will be returned.
+    pub fn query_string(&self) -> Result<String> {
+        self.validate()?;
+
+        let mut params = vec![
+            ("info_hash", util::percent_encode(&self.info_hash, &[])),
+            ("peer_id", util::percent_encode(&self.peer_id, &[])),
+            ("port", self.port.to_string()),
+            ("uploaded", self.uploaded.to_string()),
+            ("downloaded", self.downloaded.to_string()),
+            ("left", self.left.to_string()),
+        ];
+        if let Some(event) = self.event {
+            params.push(("event", event.as_str().to_owned()));
+        }
+        if let Some(compact) = self.compact {
+            params.push(("compact", if compact { "1" } else { "0" }.to_owned()));
+        }
+        if let Some(numwant) = self.numwant {
+            params.push(("numwant", numwant.to_string()));
+        }
+        if let Some(ref key) = self.key {
+            params.push(("key", Self::encode(key)));
+        }
+        if let Some(ref tracker_id) = self.tracker_id {
+            params.push(("trackerid", Self::encode(tracker_id)));
+        }
+        if let Some(ref ip) = self.ip {
+            params.push(("ip", Self::encode(ip)));
+        }
+        if let Some(ipv4) = self.ipv4 {
+            params.push(("ipv4", ipv4.to_string()));
+        }
+        if let Some(ipv6) = self.ipv6 {
+            params.push(("ipv6", Self::encode(&ipv6.to_string())));
+        }
+
+        Ok(params
+            .into_iter()
+            .format_with("&", |(key, value), f| f(&format_args!("{}={}", key, value)))
+            .to_string())
+    }
+
+    /// Render `self` onto `announce`, the URL of a tracker.
+    ///
+    /// Existing query parameters in `announce` (e.g. a passkey
+    /// used by private trackers) are preserved.
+    ///
+    /// If `self` is invalid (see [`query_string()`]), then `Err(error)`
+    /// will be returned.
+    ///
+    /// [`query_string()`]: #method.query_string
+    pub fn to_url(&self, announce: &str) -> Result<String> {
+        Ok(append_query(announce, &self.query_string()?))
+    }
+
+    /// Render `self` onto each tracker of `torrent`.
+    ///
+    /// As specified in [BEP 12], `announce_list` is used if it exists
+    /// and `announce` is ignored. Tiers are preserved, so that the
+    /// result can be used in the same way as `announce_list`. If
+    /// `torrent` has no tracker then an empty list is returned.
+    ///
+    /// If `self` is invalid (see [`query_string()`]), then `Err(error)`
+    /// will be returned.
+    ///
+    /// [BEP 12]: http://bittorrent.org/beps/bep_0012.html
+    /// [`query_string()`]: #method.query_string
+    pub fn to_urls(&self, torrent: &Torrent) -> Result<AnnounceList> {
+        let tiers = match (&torrent.announce_list, &torrent.announce) {
+            (Some(announce_list), _) if !announce_list.is_empty() => announce_list.clone(),
+            (_, Some(announce)) => vec![vec![announce.clone()]],
+            _ => Vec::new(),
+        };
+
+        tiers
+            .iter()
+            .map(|tier| tier.iter().map(|url| self.to_url(url)).collect())
+            .collect()
+    }
+
+    fn validate(&self) -> Result<()> {
+        if self.info_hash.len() != V1_HASH_LENGTH {
+            bail!(ErrorKind::InvalidArgument(Cow::Owned(format!(
+                "`info_hash` should contain {} bytes, {} found.",
+                V1_HASH_LENGTH,
+                self.info_hash.len()
+            ))));
+        }
+        if self.peer_id.len() != PEER_ID_LENGTH {
+            bail!(ErrorKind::InvalidArgument(Cow::Owned(format!(
+                "`peer_id` should contain {} bytes, {} found.",
+                PEER_ID_LENGTH,
+                self.peer_id.len()
+            ))));
+        }
+
+        for (field, value) in &[
+            ("uploaded", Some(self.uploaded)),
+            ("downloaded", Some(self.downloaded)),
+            ("left", Some(self.left)),
+            ("numwant", self.numwant),
+        ] {
+            if value.is_some_and(|value| value < 0) {
+                bail!(ErrorKind::InvalidArgument(Cow::Owned(format!(
+                    "`{}` is negative.",
+                    field
+                ))));
+            }
+        }
+        Ok(())
+    }
+
+    fn encode(value: &str) -> String {
+        util::percent_encode(value, util::QUERY_VALUE_SAFE)
+    }
+}
+
+impl ScrapeRequest {
+    /// Create a new `ScrapeRequest`.
+    pub fn new(info_hashes: Vec<Vec<u8>>) -> ScrapeRequest {
+        ScrapeRequest { info_hashes }
+    }
+
+    /// Derive the scrape URL from `announce`, the URL of a tracker.
+    ///
+    /// As specified in [BEP 48], the text following the last `/`
+    /// (in the path) must start with `announce`, which is then replaced
+    /// with `scrape` (e.g. `http://example.com/announce.php?key=1`
+    /// becomes `http://example.com/scrape.php?key=1`). Otherwise the
+    /// tracker does not support scraping and `Err(error)` will be returned.
+    ///
+    /// [BEP 48]: http://www.bittorrent.org/beps/bep_0048.html
+    pub fn scrape_url(announce: &str) -> Result<String> {
+        // `/` in the query is not part of the path
+        let path_end = announce.find('?').unwrap_or(announce.len());
+        match announce[..path_end].rfind('/') {
+            Some(i) if announce[(i + 1)..].starts_with(ANNOUNCE) => Ok(format!(
+                "{}{}{}",
+                &announce[..=i],
+                SCRAPE,
+                &announce[(i + 1 + ANNOUNCE.len())..]
+            )),
+            _ => bail!(ErrorKind::InvalidArgument(Cow::Owned(format!(
+                "[{}] does not support scraping.",
+                announce
+            )))),
+        }
+    }
+
+    /// Render `self` as a query string (without the leading `?`),
+    /// i.e. an `info_hash` parameter for each info hash.
+    ///
+    /// If any info hash does not contain exactly 20 bytes,
+    /// then `Err(error)` will be returned.
+    pub fn query_string(&self) -> Result<String> {
+        for info_hash in &self.info_hashes {
+            if info_hash.len() != V1_HASH_LENGTH {
+                bail!(ErrorKind::InvalidArgument(Cow::Owned(format!(
+                    "`info_hash` should contain {} bytes, {} found.",
+                    V1_HASH_LENGTH,
+                    info_hash.len()
+                ))));
+            }
+        }
+
+        Ok(self
+            .info_hashes
+            .iter()
+            .format_with("&", |info_hash, f| {
+                f(&format_args!(
+                    "info_hash={}",
+                    util::percent_encode(info_hash, &[])
+                ))
+            })
+            .to_string())
+    }
+
+    /// Derive the scrape URL from `announce` (see [`scrape_url()`]) and
+    /// render `self` onto it.
+    ///
+    /// If the tracker does not support scraping or if `self` is invalid
+    /// (see [`query_string()`]), then `Err(error)` will be returned.
+    ///
+    /// [`scrape_url()`]: #method.scrape_url
+    /// [`query_string()`]: #method.query_string
+    pub fn to_url(&self, announce: &str) -> Result<String> {
+        let url = Self::scrape_url(announce)?;
+        let query = self.query_string()?;
+        if query.is_empty() {
+            Ok(url)
+        } else {
+            Ok(append_query(&url, &query))
+        }
+    }
+}
+
+// append `query` to `url`, preserving existing query parameters
+fn append_query(url: &str, query: &str) -> String {
+    if url.ends_with('?') || url.ends_with('&') {
+        format!("{}{}", url, query)
+    } else if url.contains('?') {
+        format!("{}&{}", url, query)
+    } else {
+        format!("{}?{}", url, query)
+    }
+}
+
+#[cfg(test)]
+mod announce_request_tests {
+    use super::*;
+
+    fn request() -> AnnounceRequest {
+        AnnounceRequest::new(
+            vec![
+                0x12, 0x34, 0x56, 0x78, 0x9a, 0xbc, 0xde, 0xf1, 0x23, 0x45, 0x67, 0x89, 0xab, 0xcd,
+                0xef, 0x12, 0x34, 0x56, 0x78, 0x9a,
+            ],
+            b"-LT0500-abcdefghijkl".to_vec(),
+            6881,
+        )
+    }
+
+    fn torrent() -> Torrent {
+        Torrent::read_from_file("tests/samples/files.torrent").unwrap()
+    }
+
+    #[test]
+    fn new_ok() {
+        let request = request();
+        assert_eq!(request.port, 6881);
+        assert_eq!(request.uploaded, 0);
+        assert_eq!(request.downloaded, 0);
+        assert_eq!(request.left, 0);
+        assert_eq!(request.event, None);
+        assert_eq!(request.compact, Some(true));
+        assert_eq!(request.numwant, None);
+    }
+
+    #[test]
+    fn from_torrent_ok() {
+        let torrent = torrent();
+        let request = AnnounceRequest::from_torrent(&torrent, vec![0; 20], 6881);
+
+        assert_eq!(request.info_hash, torrent.info_hash_bytes());
+        assert_eq!(request.left, torrent.content_length());
+        assert_eq!(request.compact, Some(true));
+    }
+
+    #[test]
+    fn query_string_ok() {
+        assert_eq!(
+            request().query_string().unwrap(),
+            "info_hash=%124Vx%9A%BC%DE%F1%23Eg%89%AB%CD%EF%124Vx%9A\
+             &peer_id=-LT0500-abcdefghijkl&port=6881\
+             &uploaded=0&downloaded=0&left=0&compact=1"
+        );
+    }
+
+    #[test]
+    fn query_string_all_fields() {
+        let request = AnnounceRequest {
+            uploaded: 1,
+            downloaded: 2,
+            left: 3,
+            event: Some(AnnounceEvent::Started),
+            compact: Some(false),
+            numwant: Some(50),
+            key: Some("k y".to_owned()),
+            tracker_id: Some("id&1".to_owned()),
+            ip: Some("example.com".to_owned()),
+            ipv4: Some(Ipv4Addr::new(10, 0, 0, 1)),
+            ipv6: Some(Ipv6Addr::LOCALHOST),
+            ..request()
+        };
+
+        assert!(request.query_string().unwrap().ends_with(
+            "&port=6881&uploaded=1&downloaded=2&left=3&event=started&compact=0\
+             &numwant=50&key=k%20y&trackerid=id%261&ip=example.com\
+             &ipv4=10.0.0.1&ipv6=::1"
+        ));
+    }
+
+    #[test]
+    fn query_string_bad_info_hash() {
+        let request = AnnounceRequest {
+            info_hash: vec![0; 19],
+            ..request()
+        };
+
+        match request.query_string() {
+            Err(Error(ErrorKind::InvalidArgument(m), _)) => {
+                assert_eq!(m, "`info_hash` should contain 20 bytes, 19 found.")
+            }
+            _ => assert!(false),
+        }
+    }
+
+    #[test]
+    fn query_string_bad_peer_id() {
+        let request = AnnounceRequest {
+            peer_id: b"-LT0500-".to_vec(),
+            ..request()
+        };
+
+        match request.query_string() {
+            Err(Error(ErrorKind::InvalidArgument(m), _)) => {
+                assert_eq!(m, "`peer_id` should contain 20 bytes, 8 found.")
+            }
+            _ => assert!(false),
+        }
+    }
+
+    #[test]
+    fn query_string_negative() {
+        let request = AnnounceRequest {
+            numwant: Some(-1),
+            ..request()
+        };
+
+        match request.query_string() {
+            Err(Error(ErrorKind::InvalidArgument(m), _)) => assert_eq!(m, "`numwant` is negative."),
+            _ => assert!(false),
+        }
+    }
+
+    #[test]
+    fn to_url_ok() {
+        let request = request();
+        let query = request.query_string().unwrap();
+
+        assert_eq!(
+            request.to_url("http://t.com/announce").unwrap(),
+            format!("http://t.com/announce?{}", query)
+        );
+        assert_eq!(
+            request.to_url("http://t.com/announce?passkey=abc").unwrap(),
+            format!("http://t.com/announce?passkey=abc&{}", query)
+        );
+        assert_eq!(
+            request.to_url("http://t.com/announce?").unwrap(),
+            format!("http://t.com/announce?{}", query)
+        );
+    }
+
+    #[test]
+    fn to_urls_announce_list() {
+        let request = request();
+        let query = request.query_string().unwrap();
+        let torrent = Torrent {
+            announce: Some("http://a.com/announce".to_owned()),
+            announce_list: Some(vec![
+                vec!["http://b.com/announce".to_owned()],
+                vec![
+                    "http://c.com/announce".to_owned(),
+                    "http://d.com/announce".to_owned(),
+                ],
+            ]),
+            ..torrent()
+        };
+
+        assert_eq!(
+            request.to_urls(&torrent).unwrap(),
+            vec![
+                vec![format!("http://b.com/announce?{}", query)],
+                vec![
+                    format!("http://c.com/announce?{}", query),
+                    format!("http://d.com/announce?{}", query),
+                ],
+            ]
+        );
+    }
+
+    #[test]
+    fn to_urls_announce() {
+        let request = request();
+        let query = request.query_string().unwrap();
+        let torrent = Torrent {
+            announce: Some("http://a.com/announce".to_owned()),
+            announce_list: None,
+            ..torrent()
+        };
+
+        assert_eq!(
+            request.to_urls(&torrent).unwrap(),
+            vec![vec![format!("http://a.com/announce?{}", query)]]
+        );
+    }
+
+    #[test]
+    fn to_urls_no_tracker() {
+        let torrent = Torrent {
+            announce: None,
+            announce_list: None,
+            ..torrent()
+        };
+
+        assert!(request().to_urls(&torrent).unwrap().is_empty());
+    }
+}
+
+#[cfg(test)]
+mod scrape_request_tests {
+    use super::*;
+
+    #[test]
+    fn scrape_url_ok() {
+        for &(announce, scrape) in &[
+            ("http://example.com/announce", "http://example.com/scrape"),
+            (
+                "http://example.com/x/announce",
+                "http://example.com/x/scrape",
+            ),
+            (
+                "http://example.com/announce.php",
+                "http://example.com/scrape.php",
+            ),
+            (
+                "http://example.com/announce?x2%0644",
+                "http://example.com/scrape?x2%0644",
+            ),
+            (
+                "http://example.com/announce?path=/a/b",
+                "http://example.com/scrape?path=/a/b",
+            ),
+        ] {
+            assert_eq!(ScrapeRequest::scrape_url(announce).unwrap(), scrape);
+        }
+    }
+
+    #[test]
+    fn scrape_url_not_supported() {
+        for &announce in &[
+            "http://example.com/a",
+            "http://example.com/announce/x",
+            "http://example.com/x%064announce",
+            "http://example.com/?announce",
+            "example.com",
+        ] {
+            match ScrapeRequest::scrape_url(announce) {
+                Err(Error(ErrorKind::InvalidArgument(m), _)) => {
+                    assert_eq!(m, format!("[{}] does not support scraping.", announce))
+                }
+                _ => assert!(false),
+            }
+        }
+    }
+
+    #[test]
+    fn query_string_ok() {
+        let request = ScrapeRequest::new(vec![vec![0xaa; 20], b"ABCDEFGHIJKLMNOPQRST".to_vec()]);
+
+        assert_eq!(
+            request.query_string().unwrap(),
+            "info_hash=%AA%AA%AA%AA%AA%AA%AA%AA%AA%AA%AA%AA%AA%AA%AA%AA%AA%AA%AA%AA\
+             &info_hash=ABCDEFGHIJKLMNOPQRST"
+        );
+    }
+
+    #[test]
+    fn query_string_bad_info_hash() {
+        let request = ScrapeRequest::new(vec![vec![0; 20], vec![0; 32]]);
+
+        match request.query_string() {
+            Err(Error(ErrorKind::InvalidArgument(m), _)) => {
+                assert_eq!(m, "`info_hash` should contain 20 bytes, 32 found.")
+            }
+            _ => assert!(false),
+        }
+    }
+
+    #[test]
+    fn to_url_ok() {
+        let request = ScrapeRequest::new(vec![b"ABCDEFGHIJKLMNOPQRST".to_vec()]);
+
+        assert_eq!(
+            request
+                .to_url("http://example.com/announce?passkey=abc")
+                .unwrap(),
+            "http://example.com/scrape?passkey=abc&info_hash=ABCDEFGHIJKLMNOPQRST"
+        );
+    }
+
+    #[test]
+    fn to_url_no_info_hash() {
+        assert_eq!(
+            ScrapeRequest::default()
+                .to_url("http://example.com/announce")
+                .unwrap(),
+            "http://example.com/scrape"
+        );
+    }
+
+    #[test]
+    fn to_url_not_supported() {
+        match ScrapeRequest::default().to_url("http://example.com/a") {
+            Err(Error(ErrorKind::InvalidArgument(m), _)) => {
+                assert_eq!(m, "[http://example.com/a] does not support scraping.")
+            }
+            _ => assert!(false),
+        }
+    }
+}
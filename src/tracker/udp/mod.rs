@@ -0,0 +1,302 @@
+//! [Experimental] Module containing structs for UDP tracker messages.
+//!
+//! Modeled after the specifications in
+//! [BEP 15](http://bittorrent.org/beps/bep_0015.html) and
+//! [BEP 41](http://bittorrent.org/beps/bep_0041.html).
+//!
+//! Like the rest of [`tracker`], this module does not do any networking.
+//! Requests and responses can be encoded into (and decoded from) the
+//! payload of a UDP datagram, but users will have to send and receive
+//! the datagrams, retransmit on timeout and match transaction ids themselves.
+//!
+//! A typical exchange with a tracker (e.g. one taken from
+//! [`Torrent::announce`]) goes like this:
+//! 1. Parse the announce URL with [`TrackerUrl::parse()`] to get the
+//!    address of the tracker and the [BEP 41] URL data.
+//! 2. Send a [`ConnectRequest`] and receive a [`ConnectResponse`],
+//!    which carries a `connection_id`.
+//! 3. Send [`AnnounceRequest`]s and [`ScrapeRequest`]s with that `connection_id`.
+//!
+//! Any request may be answered with an [`ErrorResponse`] instead.
+//!
+//! [`tracker`]: ../index.html
+//! [`Torrent::announce`]: ../../torrent/v1/struct.Torrent.html#structfield.announce
+//! [`TrackerUrl::parse()`]: struct.TrackerUrl.html#method.parse
+//! [BEP 41]: http://bittorrent.org/beps/bep_0041.html
+//! [`ConnectRequest`]: struct.ConnectRequest.html
+//! [`ConnectResponse`]: struct.ConnectResponse.html
+//! [`AnnounceRequest`]: struct.AnnounceRequest.html
+//! [`ScrapeRequest`]: struct.ScrapeRequest.html
+//! [`ErrorResponse`]: struct.ErrorResponse.html
+
+use super::{AnnounceEvent, Peer, PEER_ID_LENGTH, V1_HASH_LENGTH};
+use error::*;
+use std::borrow::Cow;
+use std::convert::TryInto;
+use std::net::Ipv4Addr;
+
+mod read;
+mod write;
+
+/// The magic constant that starts a connect request.
+pub const PROTOCOL_ID: u64 = 0x41727101980;
+
+const ACTION_CONNECT: u32 = 0;
+const ACTION_ANNOUNCE: u32 = 1;
+const ACTION_SCRAPE: u32 = 2;
+const ACTION_ERROR: u32 = 3;
+
+// (BEP 41) option types
+const OPTION_END_OF_OPTIONS: u8 = 0;
+const OPTION_NOP: u8 = 1;
+const OPTION_URL_DATA: u8 = 2;
+
+const CONNECT_REQUEST_LENGTH: usize = 16;
+const CONNECT_RESPONSE_LENGTH: usize = 16;
+const ANNOUNCE_REQUEST_LENGTH: usize = 98;
+const ANNOUNCE_RESPONSE_HEADER_LENGTH: usize = 20;
+// connection id + action + transaction id
+const REQUEST_HEADER_LENGTH: usize = 16;
+// action + transaction id
+const RESPONSE_HEADER_LENGTH: usize = 8;
+// seeders + completed + leechers
+const SCRAPE_STATS_LENGTH: usize = 12;
+
+/// The IP version of the socket used to contact a tracker.
+///
+/// Peers in an announce response have no type information. Their
+/// format depends on how the tracker was reached: 6 bytes
+/// per peer over IPv4 and 18 bytes per peer over IPv6.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum IpVersion {
+    /// The tracker was contacted over IPv4.
+    V4,
+    /// The tracker was contacted over IPv6.
+    V6,
+}
+
+/// A UDP tracker URL (`udp://host:port/path?query`), split into
+/// what is needed to reach the tracker.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct TrackerUrl {
+    /// Host name or IP address of the tracker (without brackets for IPv6).
+    pub host: String,
+    /// Port of the tracker.
+    pub port: u16,
+    /// The path and query of the URL, which should be sent in
+    /// [`AnnounceRequest::url_data`] as defined in
+    /// [BEP 41](http://bittorrent.org/beps/bep_0041.html).
+    ///
+    /// [`AnnounceRequest::url_data`]: struct.AnnounceRequest.html#structfield.url_data
+    #[cfg_attr(feature = "serde", serde(with = "::serde_util::bytes"))]
+    pub url_data: Vec<u8>,
+}
+
+/// The first request sent to a UDP tracker, used to obtain a `connection_id`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ConnectRequest {
+    /// A random value chosen by the client, echoed in the response.
+    pub transaction_id: u32,
+}
+
+/// Response to a [`ConnectRequest`](struct.ConnectRequest.html).
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ConnectResponse {
+    /// `transaction_id` of the request.
+    pub transaction_id: u32,
+    /// Id to be used in subsequent requests. Clients can use it for
+    /// 1 minute, and trackers accept it for 2 minutes.
+    pub connection_id: u64,
+}
+
+/// An announce request sent to a UDP tracker.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct AnnounceRequest {
+    /// `connection_id` from a [`ConnectResponse`](struct.ConnectResponse.html).
+    pub connection_id: u64,
+    /// A random value chosen by the client, echoed in the response.
+    pub transaction_id: u32,
+    /// The 20-byte v1 info hash of the torrent.
+    #[cfg_attr(feature = "serde", serde(with = "::serde_util::bytes"))]
+    pub info_hash: Vec<u8>,
+    /// The 20-byte id of the client.
+    #[cfg_attr(feature = "serde", serde(with = "::serde_util::bytes"))]
+    pub peer_id: Vec<u8>,
+    /// Total number of bytes downloaded since `Started` was sent.
+    pub downloaded: u64,
+    /// Number of bytes the client still has to download.
+    pub left: u64,
+    /// Total number of bytes uploaded since `Started` was sent.
+    pub uploaded: u64,
+    /// `None` for regular announces.
+    pub event: Option<AnnounceEvent>,
+    /// The IPv4 address of the client. `None` means the
+    /// tracker should use the sender address of the datagram.
+    pub ip: Option<Ipv4Addr>,
+    /// A random value that lets the tracker identify the client
+    /// if its IP address changes.
+    pub key: u32,
+    /// Number of peers the client would like to receive.
+    /// `None` lets the tracker decide.
+    pub num_want: Option<u32>,
+    /// The port the client is listening on.
+    pub port: u16,
+    /// The path and query of the announce URL as defined in
+    /// [BEP 41](http://bittorrent.org/beps/bep_0041.html),
+    /// e.g. `/announce?passkey=x`. Empty if there is none.
+    #[cfg_attr(feature = "serde", serde(with = "::serde_util::bytes"))]
+    pub url_data: Vec<u8>,
+}
+
+/// Response to an [`AnnounceRequest`](struct.AnnounceRequest.html).
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct AnnounceResponse {
+    /// `transaction_id` of the request.
+    pub transaction_id: u32,
+    /// The number of seconds the client should wait between announces.
+    pub interval: u32,
+    /// The number of peers that have not completed downloading.
+    pub leechers: u32,
+    /// The number of peers that have completed downloading.
+    pub seeders: u32,
+    /// Peers of the swarm. All of them are either IPv4 or IPv6,
+    /// and they have neither `id` nor `extra_fields`.
+    pub peers: Vec<Peer>,
+}
+
+/// A scrape request sent to a UDP tracker.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ScrapeRequest {
+    /// `connection_id` from a [`ConnectResponse`](struct.ConnectResponse.html).
+    pub connection_id: u64,
+    /// A random value chosen by the client, echoed in the response.
+    pub transaction_id: u32,
+    /// The 20-byte v1 info hashes of the torrents to scrape.
+    /// About 74 of them fit in a single datagram.
+    #[cfg_attr(feature = "serde", serde(with = "::serde_util::pieces"))]
+    pub info_hashes: Vec<Vec<u8>>,
+}
+
+/// Statistics of a torrent in a [`ScrapeResponse`](struct.ScrapeResponse.html).
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ScrapeStats {
+    /// The number of peers that have completed downloading.
+    pub seeders: u32,
+    /// The number of times the torrent has been downloaded.
+    pub completed: u32,
+    /// The number of peers that have not completed downloading.
+    pub leechers: u32,
+}
+
+/// Response to a [`ScrapeRequest`](struct.ScrapeRequest.html).
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ScrapeResponse {
+    /// `transaction_id` of the request.
+    pub transaction_id: u32,
+    /// Statistics in the same order as `info_hashes` in the request.
+    pub stats: Vec<ScrapeStats>,
+}
+
+/// Response sent by a tracker when it fails to handle a request.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ErrorResponse {
+    /// `transaction_id` of the request.
+    pub transaction_id: u32,
+    /// A human-readable error message.
+    pub message: String,
+}
+
+/// Any request that can be sent to a UDP tracker.
+///
+/// Useful for trackers, which have to handle
+/// whatever arrives at their socket.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Request {
+    /// A [`ConnectRequest`](struct.ConnectRequest.html).
+    Connect(ConnectRequest),
+    /// An [`AnnounceRequest`](struct.AnnounceRequest.html).
+    Announce(AnnounceRequest),
+    /// A [`ScrapeRequest`](struct.ScrapeRequest.html).
+    Scrape(ScrapeRequest),
+}
+
+/// Any response that can be sent by a UDP tracker.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Response {
+    /// A [`ConnectResponse`](struct.ConnectResponse.html).
+    Connect(ConnectResponse),
+    /// An [`AnnounceResponse`](struct.AnnounceResponse.html).
+    Announce(AnnounceResponse),
+    /// A [`ScrapeResponse`](struct.ScrapeResponse.html).
+    Scrape(ScrapeResponse),
+    /// An [`ErrorResponse`](struct.ErrorResponse.html).
+    Error(ErrorResponse),
+}
+
+// the value of `event` in an announce request
+fn event_to_u32(event: Option<AnnounceEvent>) -> u32 {
+    match event {
+        None => 0,
+        Some(AnnounceEvent::Completed) => 1,
+        Some(AnnounceEvent::Started) => 2,
+        Some(AnnounceEvent::Stopped) => 3,
+    }
+}
+
+fn event_from_u32(event: u32) -> Result<Option<AnnounceEvent>> {
+    match event {
+        0 => Ok(None),
+        1 => Ok(Some(AnnounceEvent::Completed)),
+        2 => Ok(Some(AnnounceEvent::Started)),
+        3 => Ok(Some(AnnounceEvent::Stopped)),
+        _ => bail!(ErrorKind::MalformedRequest(Cow::Owned(format!(
+            "Unknown announce event {}.",
+            event
+        )))),
+    }
+}
+
+impl AnnounceRequest {
+    /// Create a new `AnnounceRequest`.
+    ///
+    /// `downloaded`, `left`, `uploaded` and `key` are initialized to 0,
+    /// `url_data` is initialized to empty, and other optional
+    /// fields are initialized to `None`.
+    pub fn new(
+        connection_id: u64,
+        transaction_id: u32,
+        info_hash: Vec<u8>,
+        peer_id: Vec<u8>,
+        port: u16,
+    ) -> AnnounceRequest {
+        AnnounceRequest {
+            connection_id,
+            transaction_id,
+            info_hash,
+            peer_id,
+            port,
+            ..Default::default()
+        }
+    }
+}
+
+// big-endian integers at fixed offsets, callers must check the length
+fn read_u32(bytes: &[u8], offset: usize) -> u32 {
+    u32::from_be_bytes(bytes[offset..(offset + 4)].try_into().unwrap())
+}
+
+fn read_u64(bytes: &[u8], offset: usize) -> u64 {
+    u64::from_be_bytes(bytes[offset..(offset + 8)].try_into().unwrap())
+}
@@ -0,0 +1,833 @@
+use super::super::{COMPACT_PEER_V4_LENGTH, COMPACT_PEER_V6_LENGTH};
+use super::*;
+
+const URL_SCHEME: &str = "udp://";
+
+impl TrackerUrl {
+    /// Parse a UDP tracker URL (e.g. `udp://tracker.example.com:6969/announce`).
+    ///
+    /// `url_data` is everything from the first `/` or `?`
+    /// after the port up to (but not including) the fragment.
+    ///
+    /// If `url` is not a `udp://` URL or doesn't contain
+    /// a valid host and port, `Err(error)` will be returned.
+    pub fn parse(url: &str) -> Result<TrackerUrl> {
+        let rest = match url.get(..URL_SCHEME.len()) {
+            Some(scheme) if scheme.eq_ignore_ascii_case(URL_SCHEME) => &url[URL_SCHEME.len()..],
+            _ => bail!(ErrorKind::InvalidArgument(Cow::Owned(format!(
+                "[{}] is not a UDP tracker URL.",
+                url
+            )))),
+        };
+        let rest = match rest.find('#') {
+            Some(end) => &rest[..end],
+            None => rest,
+        };
+        let (authority, url_data) = match rest.find(['/', '?']) {
+            Some(end) => rest.split_at(end),
+            None => (rest, ""),
+        };
+
+        match Self::split_authority(authority) {
+            Some((host, port)) => Ok(TrackerUrl {
+                host: host.to_owned(),
+                port,
+                url_data: url_data.as_bytes().to_vec(),
+            }),
+            None => bail!(ErrorKind::InvalidArgument(Cow::Owned(format!(
+                "[{}] does not contain a valid host and port.",
+                url
+            )))),
+        }
+    }
+
+    // `host:port` or `[ipv6]:port`
+    fn split_authority(authority: &str) -> Option<(&str, u16)> {
+        let (host, port) = if let Some(bracketed) = authority.strip_prefix('[') {
+            let (host, rest) = bracketed.split_once(']')?;
+            (host, rest.strip_prefix(':')?)
+        } else {
+            authority.rsplit_once(':')?
+        };
+
+        if host.is_empty() {
+            return None;
+        }
+        port.parse().ok().map(|port| (host, port))
+    }
+}
+
+impl ConnectRequest {
+    /// Parse `bytes` and return the extracted `ConnectRequest`.
+    pub fn from_bytes<B>(bytes: B) -> Result<ConnectRequest>
+    where
+        B: AsRef<[u8]>,
+    {
+        let bytes = bytes.as_ref();
+        check_request(bytes, ACTION_CONNECT, CONNECT_REQUEST_LENGTH)?;
+        if read_u64(bytes, 0) != PROTOCOL_ID {
+            bail!(ErrorKind::MalformedRequest(Cow::Borrowed(
+                "Connect request does not contain the protocol id."
+            )));
+        }
+
+        Ok(ConnectRequest {
+            transaction_id: read_u32(bytes, 12),
+        })
+    }
+}
+
+impl ConnectResponse {
+    /// Parse `bytes` and return the extracted `ConnectResponse`.
+    ///
+    /// If `bytes` is an error response, then `Err(error)`
+    /// with `ErrorKind::TrackerErrorResponse` will be returned.
+    pub fn from_bytes<B>(bytes: B) -> Result<ConnectResponse>
+    where
+        B: AsRef<[u8]>,
+    {
+        let bytes = bytes.as_ref();
+        check_response(bytes, ACTION_CONNECT, CONNECT_RESPONSE_LENGTH)?;
+
+        Ok(ConnectResponse {
+            transaction_id: read_u32(bytes, 4),
+            connection_id: read_u64(bytes, 8),
+        })
+    }
+}
+
+impl AnnounceRequest {
+    /// Parse `bytes` and return the extracted `AnnounceRequest`.
+    ///
+    /// [BEP 41](http://bittorrent.org/beps/bep_0041.html) options
+    /// following the request are parsed as well. URL data
+    /// options are concatenated into `url_data`, and unknown
+    /// options are skipped.
+    pub fn from_bytes<B>(bytes: B) -> Result<AnnounceRequest>
+    where
+        B: AsRef<[u8]>,
+    {
+        let bytes = bytes.as_ref();
+        check_request(bytes, ACTION_ANNOUNCE, ANNOUNCE_REQUEST_LENGTH)?;
+
+        let ip = match read_u32(bytes, 84) {
+            0 => None,
+            ip => Some(Ipv4Addr::from(ip)),
+        };
+        // -1 (or any negative value) means default
+        let num_want = match read_u32(bytes, 92) {
+            num_want if num_want > i32::MAX as u32 => None,
+            num_want => Some(num_want),
+        };
+
+        Ok(AnnounceRequest {
+            connection_id: read_u64(bytes, 0),
+            transaction_id: read_u32(bytes, 12),
+            info_hash: bytes[16..36].to_vec(),
+            peer_id: bytes[36..56].to_vec(),
+            downloaded: read_u64(bytes, 56),
+            left: read_u64(bytes, 64),
+            uploaded: read_u64(bytes, 72),
+            event: event_from_u32(read_u32(bytes, 80))?,
+            ip,
+            key: read_u32(bytes, 88),
+            num_want,
+            port: u16::from_be_bytes([bytes[96], bytes[97]]),
+            url_data: Self::extract_url_data(&bytes[ANNOUNCE_REQUEST_LENGTH..])?,
+        })
+    }
+
+    fn extract_url_data(mut options: &[u8]) -> Result<Vec<u8>> {
+        let mut url_data = Vec::new();
+
+        while let Some((&option, rest)) = options.split_first() {
+            match option {
+                OPTION_END_OF_OPTIONS => break,
+                OPTION_NOP => options = rest,
+                _ => {
+                    // all other options have a length
+                    let data = match rest.split_first() {
+                        Some((&length, data)) if data.len() >= usize::from(length) => {
+                            &data[..usize::from(length)]
+                        }
+                        _ => bail!(ErrorKind::MalformedRequest(Cow::Owned(format!(
+                            "Option of type {} is truncated.",
+                            option
+                        )))),
+                    };
+                    if option == OPTION_URL_DATA {
+                        url_data.extend_from_slice(data);
+                    }
+                    options = &rest[(1 + data.len())..];
+                }
+            }
+        }
+
+        Ok(url_data)
+    }
+}
+
+impl AnnounceResponse {
+    /// Parse `bytes` and return the extracted `AnnounceResponse`.
+    ///
+    /// `ip_version` should be the IP version of the socket used
+    /// to contact the tracker, as it determines how peers are encoded.
+    ///
+    /// If `bytes` is an error response, then `Err(error)`
+    /// with `ErrorKind::TrackerErrorResponse` will be returned.
+    pub fn from_bytes<B>(bytes: B, ip_version: IpVersion) -> Result<AnnounceResponse>
+    where
+        B: AsRef<[u8]>,
+    {
+        let bytes = bytes.as_ref();
+        check_response(bytes, ACTION_ANNOUNCE, ANNOUNCE_RESPONSE_HEADER_LENGTH)?;
+
+        let peers = &bytes[ANNOUNCE_RESPONSE_HEADER_LENGTH..];
+        let peer_length = match ip_version {
+            IpVersion::V4 => COMPACT_PEER_V4_LENGTH,
+            IpVersion::V6 => COMPACT_PEER_V6_LENGTH,
+        };
+        if (peers.len() % peer_length) != 0 {
+            bail!(ErrorKind::MalformedResponse(Cow::Borrowed(
+                "Peer list contains incorrect number of bytes."
+            )));
+        }
+
+        Ok(AnnounceResponse {
+            transaction_id: read_u32(bytes, 4),
+            interval: read_u32(bytes, 8),
+            leechers: read_u32(bytes, 12),
+            seeders: read_u32(bytes, 16),
+            peers: peers.chunks(peer_length).map(Peer::from_bytes).collect(),
+        })
+    }
+}
+
+impl ScrapeRequest {
+    /// Parse `bytes` and return the extracted `ScrapeRequest`.
+    pub fn from_bytes<B>(bytes: B) -> Result<ScrapeRequest>
+    where
+        B: AsRef<[u8]>,
+    {
+        let bytes = bytes.as_ref();
+        check_request(bytes, ACTION_SCRAPE, REQUEST_HEADER_LENGTH)?;
+
+        let info_hashes = &bytes[REQUEST_HEADER_LENGTH..];
+        if (info_hashes.len() % V1_HASH_LENGTH) != 0 {
+            bail!(ErrorKind::MalformedRequest(Cow::Borrowed(
+                "Info hashes contain incorrect number of bytes."
+            )));
+        }
+
+        Ok(ScrapeRequest {
+            connection_id: read_u64(bytes, 0),
+            transaction_id: read_u32(bytes, 12),
+            info_hashes: info_hashes
+                .chunks(V1_HASH_LENGTH)
+                .map(<[u8]>::to_vec)
+                .collect(),
+        })
+    }
+}
+
+impl ScrapeResponse {
+    /// Parse `bytes` and return the extracted `ScrapeResponse`.
+    ///
+    /// If `bytes` is an error response, then `Err(error)`
+    /// with `ErrorKind::TrackerErrorResponse` will be returned.
+    pub fn from_bytes<B>(bytes: B) -> Result<ScrapeResponse>
+    where
+        B: AsRef<[u8]>,
+    {
+        let bytes = bytes.as_ref();
+        check_response(bytes, ACTION_SCRAPE, RESPONSE_HEADER_LENGTH)?;
+
+        let stats = &bytes[RESPONSE_HEADER_LENGTH..];
+        if (stats.len() % SCRAPE_STATS_LENGTH) != 0 {
+            bail!(ErrorKind::MalformedResponse(Cow::Borrowed(
+                "Scrape statistics contain incorrect number of bytes."
+            )));
+        }
+
+        Ok(ScrapeResponse {
+            transaction_id: read_u32(bytes, 4),
+            stats: stats
+                .chunks(SCRAPE_STATS_LENGTH)
+                .map(|stats| ScrapeStats {
+                    seeders: read_u32(stats, 0),
+                    completed: read_u32(stats, 4),
+                    leechers: read_u32(stats, 8),
+                })
+                .collect(),
+        })
+    }
+}
+
+impl ErrorResponse {
+    /// Parse `bytes` and return the extracted `ErrorResponse`.
+    ///
+    /// Invalid UTF8 in the message is replaced
+    /// with `U+FFFD REPLACEMENT CHARACTER`.
+    pub fn from_bytes<B>(bytes: B) -> Result<ErrorResponse>
+    where
+        B: AsRef<[u8]>,
+    {
+        let bytes = bytes.as_ref();
+        check_response(bytes, ACTION_ERROR, RESPONSE_HEADER_LENGTH)?;
+
+        Ok(ErrorResponse {
+            transaction_id: read_u32(bytes, 4),
+            message: String::from_utf8_lossy(&bytes[RESPONSE_HEADER_LENGTH..]).into_owned(),
+        })
+    }
+}
+
+impl Request {
+    /// Parse `bytes` and return the extracted request,
+    /// whose type is determined by its action.
+    pub fn from_bytes<B>(bytes: B) -> Result<Request>
+    where
+        B: AsRef<[u8]>,
+    {
+        let bytes = bytes.as_ref();
+        if bytes.len() < REQUEST_HEADER_LENGTH {
+            bail!(too_short(
+                bytes,
+                REQUEST_HEADER_LENGTH,
+                ErrorKind::MalformedRequest
+            ));
+        }
+
+        match read_u32(bytes, 8) {
+            ACTION_CONNECT => Ok(Request::Connect(ConnectRequest::from_bytes(bytes)?)),
+            ACTION_ANNOUNCE => Ok(Request::Announce(AnnounceRequest::from_bytes(bytes)?)),
+            ACTION_SCRAPE => Ok(Request::Scrape(ScrapeRequest::from_bytes(bytes)?)),
+            action => bail!(ErrorKind::MalformedRequest(Cow::Owned(format!(
+                "Unknown action {}.",
+                action
+            )))),
+        }
+    }
+}
+
+impl Response {
+    /// Parse `bytes` and return the extracted response,
+    /// whose type is determined by its action.
+    ///
+    /// `ip_version` should be the IP version of the socket used
+    /// to contact the tracker, as it determines how peers in an
+    /// announce response are encoded.
+    ///
+    /// Unlike the `from_bytes()` of each response, an error
+    /// response is returned as `Ok(Response::Error(_))`.
+    pub fn from_bytes<B>(bytes: B, ip_version: IpVersion) -> Result<Response>
+    where
+        B: AsRef<[u8]>,
+    {
+        let bytes = bytes.as_ref();
+        if bytes.len() < RESPONSE_HEADER_LENGTH {
+            bail!(too_short(
+                bytes,
+                RESPONSE_HEADER_LENGTH,
+                ErrorKind::MalformedResponse
+            ));
+        }
+
+        match read_u32(bytes, 0) {
+            ACTION_CONNECT => Ok(Response::Connect(ConnectResponse::from_bytes(bytes)?)),
+            ACTION_ANNOUNCE => Ok(Response::Announce(AnnounceResponse::from_bytes(
+                bytes, ip_version,
+            )?)),
+            ACTION_SCRAPE => Ok(Response::Scrape(ScrapeResponse::from_bytes(bytes)?)),
+            ACTION_ERROR => Ok(Response::Error(ErrorResponse::from_bytes(bytes)?)),
+            action => bail!(ErrorKind::MalformedResponse(Cow::Owned(format!(
+                "Unknown action {}.",
+                action
+            )))),
+        }
+    }
+}
+
+fn too_short<F>(bytes: &[u8], expected: usize, kind: F) -> ErrorKind
+where
+    F: FnOnce(Cow<'static, str>) -> ErrorKind,
+{
+    kind(Cow::Owned(format!(
+        "Message contains {} bytes, at least {} expected.",
+        bytes.len(),
+        expected
+    )))
+}
+
+// check the length and action of a request
+fn check_request(bytes: &[u8], expected_action: u32, min_length: usize) -> Result<()> {
+    if bytes.len() < min_length {
+        bail!(too_short(bytes, min_length, ErrorKind::MalformedRequest));
+    }
+
+    let action = read_u32(bytes, 8);
+    if action != expected_action {
+        bail!(ErrorKind::MalformedRequest(Cow::Owned(format!(
+            "Request has action {}, {} expected.",
+            action, expected_action
+        ))));
+    }
+    Ok(())
+}
+
+// check the length and action of a response, turning error
+// responses into `ErrorKind::TrackerErrorResponse`
+fn check_response(bytes: &[u8], expected_action: u32, min_length: usize) -> Result<()> {
+    if bytes.len() < RESPONSE_HEADER_LENGTH {
+        bail!(too_short(
+            bytes,
+            RESPONSE_HEADER_LENGTH,
+            ErrorKind::MalformedResponse
+        ));
+    }
+
+    let action = read_u32(bytes, 0);
+    if (action == ACTION_ERROR) && (expected_action != ACTION_ERROR) {
+        bail!(ErrorKind::TrackerErrorResponse(Cow::Owned(
+            ErrorResponse::from_bytes(bytes)?.message
+        )));
+    }
+    if action != expected_action {
+        bail!(ErrorKind::MalformedResponse(Cow::Owned(format!(
+            "Response has action {}, {} expected.",
+            action, expected_action
+        ))));
+    }
+    if bytes.len() < min_length {
+        bail!(too_short(bytes, min_length, ErrorKind::MalformedResponse));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod udp_read_tests {
+    // @note: `write.rs` tests roundtrips, so these tests
+    // focus on decoding hand-written fixtures.
+    use super::*;
+    use std::net::{Ipv6Addr, SocketAddr};
+
+    fn header(first: &[u8], second: &[u8]) -> Vec<u8> {
+        let mut result = first.to_vec();
+        result.extend_from_slice(second);
+        result
+    }
+
+    fn announce_request_fixture() -> Vec<u8> {
+        let mut result = Vec::new();
+        result.extend_from_slice(&0x0102_0304_0506_0708u64.to_be_bytes()); // connection id
+        result.extend_from_slice(&[0, 0, 0, 1]); // action
+        result.extend_from_slice(&[0, 0, 0, 42]); // transaction id
+        result.extend_from_slice(&[0xab; 20]); // info hash
+        result.extend_from_slice(b"-LT0001-123456789012"); // peer id
+        result.extend_from_slice(&100u64.to_be_bytes()); // downloaded
+        result.extend_from_slice(&200u64.to_be_bytes()); // left
+        result.extend_from_slice(&300u64.to_be_bytes()); // uploaded
+        result.extend_from_slice(&[0, 0, 0, 2]); // event
+        result.extend_from_slice(&[10, 0, 0, 1]); // ip
+        result.extend_from_slice(&[0, 0, 0, 7]); // key
+        result.extend_from_slice(&[0xff; 4]); // num want
+        result.extend_from_slice(&[0x1a, 0xe1]); // port
+        result
+    }
+
+    #[test]
+    fn tracker_url_parse_ok() {
+        assert_eq!(
+            TrackerUrl::parse("udp://tracker.example.com:6969/announce?passkey=x#frag").unwrap(),
+            TrackerUrl {
+                host: "tracker.example.com".to_owned(),
+                port: 6969,
+                url_data: b"/announce?passkey=x".to_vec(),
+            }
+        );
+    }
+
+    #[test]
+    fn tracker_url_parse_no_path() {
+        assert_eq!(
+            TrackerUrl::parse("UDP://10.0.0.1:80").unwrap(),
+            TrackerUrl {
+                host: "10.0.0.1".to_owned(),
+                port: 80,
+                url_data: Vec::new(),
+            }
+        );
+    }
+
+    #[test]
+    fn tracker_url_parse_ipv6() {
+        assert_eq!(
+            TrackerUrl::parse("udp://[::1]:1337?a=b").unwrap(),
+            TrackerUrl {
+                host: "::1".to_owned(),
+                port: 1337,
+                url_data: b"?a=b".to_vec(),
+            }
+        );
+    }
+
+    #[test]
+    fn tracker_url_parse_not_udp() {
+        match TrackerUrl::parse("http://example.com:80/announce") {
+            Err(Error(ErrorKind::InvalidArgument(m), _)) => assert_eq!(
+                m,
+                "[http://example.com:80/announce] is not a UDP tracker URL."
+            ),
+            _ => assert!(false),
+        }
+    }
+
+    #[test]
+    fn tracker_url_parse_invalid_port() {
+        for url in &[
+            "udp://example.com/announce",
+            "udp://example.com:99999",
+            "udp://:80",
+            "udp://[::1]/announce",
+        ] {
+            match TrackerUrl::parse(url) {
+                Err(Error(ErrorKind::InvalidArgument(m), _)) => assert_eq!(
+                    m,
+                    format!("[{}] does not contain a valid host and port.", url)
+                ),
+                _ => assert!(false),
+            }
+        }
+    }
+
+    #[test]
+    fn connect_request_ok() {
+        let bytes = header(
+            &[0, 0, 0x04, 0x17, 0x27, 0x10, 0x19, 0x80],
+            &[0, 0, 0, 0, 0, 0, 1, 2],
+        );
+        assert_eq!(
+            ConnectRequest::from_bytes(bytes).unwrap(),
+            ConnectRequest {
+                transaction_id: 0x102,
+            }
+        );
+    }
+
+    #[test]
+    fn connect_request_no_protocol_id() {
+        let bytes = header(&[0; 8], &[0, 0, 0, 0, 0, 0, 1, 2]);
+        match ConnectRequest::from_bytes(bytes) {
+            Err(Error(ErrorKind::MalformedRequest(m), _)) => {
+                assert_eq!(m, "Connect request does not contain the protocol id.")
+            }
+            _ => assert!(false),
+        }
+    }
+
+    #[test]
+    fn connect_request_too_short() {
+        match ConnectRequest::from_bytes([0; 15]) {
+            Err(Error(ErrorKind::MalformedRequest(m), _)) => {
+                assert_eq!(m, "Message contains 15 bytes, at least 16 expected.")
+            }
+            _ => assert!(false),
+        }
+    }
+
+    #[test]
+    fn connect_response_ok() {
+        let bytes = header(&[0, 0, 0, 0, 0, 0, 0, 9], &[1, 2, 3, 4, 5, 6, 7, 8]);
+        assert_eq!(
+            ConnectResponse::from_bytes(bytes).unwrap(),
+            ConnectResponse {
+                transaction_id: 9,
+                connection_id: 0x0102_0304_0506_0708,
+            }
+        );
+    }
+
+    #[test]
+    fn connect_response_error() {
+        let bytes = header(&[0, 0, 0, 3, 0, 0, 0, 9], b"go away");
+        match ConnectResponse::from_bytes(bytes) {
+            Err(Error(ErrorKind::TrackerErrorResponse(m), _)) => assert_eq!(m, "go away"),
+            _ => assert!(false),
+        }
+    }
+
+    #[test]
+    fn connect_response_wrong_action() {
+        let bytes = header(&[0, 0, 0, 1, 0, 0, 0, 9], &[0; 8]);
+        match ConnectResponse::from_bytes(bytes) {
+            Err(Error(ErrorKind::MalformedResponse(m), _)) => {
+                assert_eq!(m, "Response has action 1, 0 expected.")
+            }
+            _ => assert!(false),
+        }
+    }
+
+    #[test]
+    fn announce_request_ok() {
+        let mut bytes = announce_request_fixture();
+        bytes.extend_from_slice(&[2, 4]);
+        bytes.extend_from_slice(b"/ann");
+        bytes.extend_from_slice(&[1, 9, 1, 0xee, 2, 0, 2, 6]); // NOP, unknown, empty URL data
+        bytes.extend_from_slice(b"ounce?");
+        bytes.extend_from_slice(&[0, 2, 1, b'x']); // `x` is after EndOfOptions
+
+        assert_eq!(
+            AnnounceRequest::from_bytes(bytes).unwrap(),
+            AnnounceRequest {
+                connection_id: 0x0102_0304_0506_0708,
+                transaction_id: 42,
+                info_hash: vec![0xab; 20],
+                peer_id: b"-LT0001-123456789012".to_vec(),
+                downloaded: 100,
+                left: 200,
+                uploaded: 300,
+                event: Some(AnnounceEvent::Started),
+                ip: Some(Ipv4Addr::new(10, 0, 0, 1)),
+                key: 7,
+                num_want: None,
+                port: 6881,
+                url_data: b"/announce?".to_vec(),
+            }
+        );
+    }
+
+    #[test]
+    fn announce_request_defaults() {
+        let mut bytes = announce_request_fixture();
+        bytes[80..84].copy_from_slice(&[0; 4]);
+        bytes[84..88].copy_from_slice(&[0; 4]);
+        bytes[92..96].copy_from_slice(&[0, 0, 0, 50]);
+
+        let request = AnnounceRequest::from_bytes(bytes).unwrap();
+        assert_eq!(request.event, None);
+        assert_eq!(request.ip, None);
+        assert_eq!(request.num_want, Some(50));
+        assert!(request.url_data.is_empty());
+    }
+
+    #[test]
+    fn announce_request_unknown_event() {
+        let mut bytes = announce_request_fixture();
+        bytes[80..84].copy_from_slice(&[0, 0, 0, 4]);
+        match AnnounceRequest::from_bytes(bytes) {
+            Err(Error(ErrorKind::MalformedRequest(m), _)) => {
+                assert_eq!(m, "Unknown announce event 4.")
+            }
+            _ => assert!(false),
+        }
+    }
+
+    #[test]
+    fn announce_request_truncated_option() {
+        for options in &[&[2][..], &[2, 5, b'a']] {
+            let mut bytes = announce_request_fixture();
+            bytes.extend_from_slice(options);
+            match AnnounceRequest::from_bytes(bytes) {
+                Err(Error(ErrorKind::MalformedRequest(m), _)) => {
+                    assert_eq!(m, "Option of type 2 is truncated.")
+                }
+                _ => assert!(false),
+            }
+        }
+    }
+
+    #[test]
+    fn announce_request_too_short() {
+        let mut bytes = announce_request_fixture();
+        bytes.pop();
+        match AnnounceRequest::from_bytes(bytes) {
+            Err(Error(ErrorKind::MalformedRequest(m), _)) => {
+                assert_eq!(m, "Message contains 97 bytes, at least 98 expected.")
+            }
+            _ => assert!(false),
+        }
+    }
+
+    #[test]
+    fn announce_response_v4_ok() {
+        let mut bytes = header(&[0, 0, 0, 1, 0, 0, 0, 5], &[0, 0, 7, 8]);
+        bytes.extend_from_slice(&[0, 0, 0, 2, 0, 0, 0, 3]);
+        bytes.extend_from_slice(&[127, 0, 0, 1, 0x1a, 0xe1, 10, 0, 0, 2, 0, 80]);
+
+        assert_eq!(
+            AnnounceResponse::from_bytes(bytes, IpVersion::V4).unwrap(),
+            AnnounceResponse {
+                transaction_id: 5,
+                interval: 1800,
+                leechers: 2,
+                seeders: 3,
+                peers: vec![
+                    Peer {
+                        id: None,
+                        addr: SocketAddr::from(([127, 0, 0, 1], 6881)),
+                        extra_fields: None,
+                    },
+                    Peer {
+                        id: None,
+                        addr: SocketAddr::from(([10, 0, 0, 2], 80)),
+                        extra_fields: None,
+                    },
+                ],
+            }
+        );
+    }
+
+    #[test]
+    fn announce_response_v6_ok() {
+        let mut bytes = header(&[0, 0, 0, 1, 0, 0, 0, 5], &[0; 12]);
+        bytes.extend_from_slice(&Ipv6Addr::LOCALHOST.octets());
+        bytes.extend_from_slice(&[0x1a, 0xe1]);
+
+        assert_eq!(
+            AnnounceResponse::from_bytes(bytes, IpVersion::V6)
+                .unwrap()
+                .peers,
+            vec![Peer {
+                id: None,
+                addr: SocketAddr::from((Ipv6Addr::LOCALHOST, 6881)),
+                extra_fields: None,
+            }]
+        );
+    }
+
+    #[test]
+    fn announce_response_incorrect_peers() {
+        let mut bytes = header(&[0, 0, 0, 1, 0, 0, 0, 5], &[0; 12]);
+        bytes.extend_from_slice(&[127, 0, 0, 1, 0x1a, 0xe1]);
+        match AnnounceResponse::from_bytes(bytes, IpVersion::V6) {
+            Err(Error(ErrorKind::MalformedResponse(m), _)) => {
+                assert_eq!(m, "Peer list contains incorrect number of bytes.")
+            }
+            _ => assert!(false),
+        }
+    }
+
+    #[test]
+    fn scrape_request_ok() {
+        let mut bytes = header(&[0, 0, 0, 0, 0, 0, 0, 1], &[0, 0, 0, 2, 0, 0, 0, 3]);
+        bytes.extend_from_slice(&[1; 20]);
+        bytes.extend_from_slice(&[2; 20]);
+
+        assert_eq!(
+            ScrapeRequest::from_bytes(bytes).unwrap(),
+            ScrapeRequest {
+                connection_id: 1,
+                transaction_id: 3,
+                info_hashes: vec![vec![1; 20], vec![2; 20]],
+            }
+        );
+    }
+
+    #[test]
+    fn scrape_request_incorrect_info_hashes() {
+        let mut bytes = header(&[0, 0, 0, 0, 0, 0, 0, 1], &[0, 0, 0, 2, 0, 0, 0, 3]);
+        bytes.extend_from_slice(&[1; 19]);
+        match ScrapeRequest::from_bytes(bytes) {
+            Err(Error(ErrorKind::MalformedRequest(m), _)) => {
+                assert_eq!(m, "Info hashes contain incorrect number of bytes.")
+            }
+            _ => assert!(false),
+        }
+    }
+
+    #[test]
+    fn scrape_response_ok() {
+        let mut bytes = header(&[0, 0, 0, 2], &[0, 0, 0, 3]);
+        bytes.extend_from_slice(&[0, 0, 0, 1, 0, 0, 0, 2, 0, 0, 0, 3]);
+        bytes.extend_from_slice(&[0, 0, 0, 4, 0, 0, 0, 5, 0, 0, 0, 6]);
+
+        assert_eq!(
+            ScrapeResponse::from_bytes(bytes).unwrap(),
+            ScrapeResponse {
+                transaction_id: 3,
+                stats: vec![
+                    ScrapeStats {
+                        seeders: 1,
+                        completed: 2,
+                        leechers: 3,
+                    },
+                    ScrapeStats {
+                        seeders: 4,
+                        completed: 5,
+                        leechers: 6,
+                    },
+                ],
+            }
+        );
+    }
+
+    #[test]
+    fn scrape_response_incorrect_stats() {
+        let bytes = header(&[0, 0, 0, 2, 0, 0, 0, 3], &[0; 11]);
+        match ScrapeResponse::from_bytes(bytes) {
+            Err(Error(ErrorKind::MalformedResponse(m), _)) => {
+                assert_eq!(m, "Scrape statistics contain incorrect number of bytes.")
+            }
+            _ => assert!(false),
+        }
+    }
+
+    #[test]
+    fn error_response_ok() {
+        let bytes = header(&[0, 0, 0, 3, 0, 0, 0, 3], b"bad \xffrequest");
+        assert_eq!(
+            ErrorResponse::from_bytes(bytes).unwrap(),
+            ErrorResponse {
+                transaction_id: 3,
+                message: "bad \u{fffd}request".to_owned(),
+            }
+        );
+    }
+
+    #[test]
+    fn request_dispatch() {
+        let mut bytes = header(&[0, 0, 0, 0, 0, 0, 0, 1], &[0, 0, 0, 2, 0, 0, 0, 3]);
+        assert_eq!(
+            Request::from_bytes(&bytes).unwrap(),
+            Request::Scrape(ScrapeRequest {
+                connection_id: 1,
+                transaction_id: 3,
+                info_hashes: Vec::new(),
+            })
+        );
+
+        bytes[11] = 4;
+        match Request::from_bytes(&bytes) {
+            Err(Error(ErrorKind::MalformedRequest(m), _)) => assert_eq!(m, "Unknown action 4."),
+            _ => assert!(false),
+        }
+    }
+
+    #[test]
+    fn response_dispatch() {
+        let mut bytes = header(&[0, 0, 0, 3, 0, 0, 0, 3], b"oops");
+        assert_eq!(
+            Response::from_bytes(&bytes, IpVersion::V4).unwrap(),
+            Response::Error(ErrorResponse {
+                transaction_id: 3,
+                message: "oops".to_owned(),
+            })
+        );
+
+        bytes[3] = 4;
+        match Response::from_bytes(&bytes, IpVersion::V4) {
+            Err(Error(ErrorKind::MalformedResponse(m), _)) => assert_eq!(m, "Unknown action 4."),
+            _ => assert!(false),
+        }
+    }
+
+    #[test]
+    fn response_too_short() {
+        match Response::from_bytes([0; 7], IpVersion::V4) {
+            Err(Error(ErrorKind::MalformedResponse(m), _)) => {
+                assert_eq!(m, "Message contains 7 bytes, at least 8 expected.")
+            }
+            _ => assert!(false),
+        }
+    }
+}
@@ -0,0 +1,503 @@
+use super::*;
+use itertools::Itertools;
+use std::convert::TryFrom;
+use std::io::Write;
+use std::net::IpAddr;
+
+// max length of the data in a (BEP 41) option
+const MAX_OPTION_LENGTH: usize = 255;
+
+// `encode()` is the same for all messages
+macro_rules! impl_encode {
+    ($($t:ty),*) => {$(
+        impl $t {
+            /// Encode `self` and return the result in a `Vec`.
+            pub fn encode(self) -> Result<Vec<u8>> {
+                let mut result = Vec::new();
+                self.write_into(&mut result)?;
+                Ok(result)
+            }
+        }
+    )*};
+}
+
+impl_encode!(
+    ConnectRequest,
+    ConnectResponse,
+    AnnounceRequest,
+    AnnounceResponse,
+    ScrapeRequest,
+    ScrapeResponse,
+    ErrorResponse,
+    Request,
+    Response
+);
+
+impl ConnectRequest {
+    /// Encode `self` and write the result to `dst`.
+    pub fn write_into<W>(self, dst: &mut W) -> Result<()>
+    where
+        W: Write,
+    {
+        dst.write_all(&PROTOCOL_ID.to_be_bytes())?;
+        dst.write_all(&ACTION_CONNECT.to_be_bytes())?;
+        dst.write_all(&self.transaction_id.to_be_bytes())?;
+        Ok(())
+    }
+}
+
+impl ConnectResponse {
+    /// Encode `self` and write the result to `dst`.
+    pub fn write_into<W>(self, dst: &mut W) -> Result<()>
+    where
+        W: Write,
+    {
+        dst.write_all(&ACTION_CONNECT.to_be_bytes())?;
+        dst.write_all(&self.transaction_id.to_be_bytes())?;
+        dst.write_all(&self.connection_id.to_be_bytes())?;
+        Ok(())
+    }
+}
+
+impl AnnounceRequest {
+    /// Encode `self` and write the result to `dst`.
+    ///
+    /// A non-empty `url_data` is split into as many
+    /// [BEP 41](http://bittorrent.org/beps/bep_0041.html)
+    /// URL data options as needed.
+    ///
+    /// If `info_hash` or `peer_id` doesn't contain 20 bytes, or
+    /// `num_want` doesn't fit in an `i32`, then `Err(error)` will be returned.
+    pub fn write_into<W>(self, dst: &mut W) -> Result<()>
+    where
+        W: Write,
+    {
+        if self.info_hash.len() != V1_HASH_LENGTH {
+            bail!(ErrorKind::InvalidArgument(Cow::Owned(format!(
+                "`info_hash` should contain {} bytes, {} found.",
+                V1_HASH_LENGTH,
+                self.info_hash.len()
+            ))));
+        }
+        if self.peer_id.len() != PEER_ID_LENGTH {
+            bail!(ErrorKind::InvalidArgument(Cow::Owned(format!(
+                "`peer_id` should contain {} bytes, {} found.",
+                PEER_ID_LENGTH,
+                self.peer_id.len()
+            ))));
+        }
+        let num_want = match self.num_want {
+            Some(num_want) => match i32::try_from(num_want) {
+                Ok(num_want) => num_want,
+                Err(_) => bail!(ErrorKind::InvalidArgument(Cow::Borrowed(
+                    "`num_want` is too large."
+                ))),
+            },
+            None => -1,
+        };
+
+        dst.write_all(&self.connection_id.to_be_bytes())?;
+        dst.write_all(&ACTION_ANNOUNCE.to_be_bytes())?;
+        dst.write_all(&self.transaction_id.to_be_bytes())?;
+        dst.write_all(&self.info_hash)?;
+        dst.write_all(&self.peer_id)?;
+        dst.write_all(&self.downloaded.to_be_bytes())?;
+        dst.write_all(&self.left.to_be_bytes())?;
+        dst.write_all(&self.uploaded.to_be_bytes())?;
+        dst.write_all(&event_to_u32(self.event).to_be_bytes())?;
+        dst.write_all(&self.ip.map_or(0, u32::from).to_be_bytes())?;
+        dst.write_all(&self.key.to_be_bytes())?;
+        dst.write_all(&num_want.to_be_bytes())?;
+        dst.write_all(&self.port.to_be_bytes())?;
+
+        for chunk in self.url_data.chunks(MAX_OPTION_LENGTH) {
+            // `chunk.len()` <= 255
+            dst.write_all(&[OPTION_URL_DATA, chunk.len() as u8])?;
+            dst.write_all(chunk)?;
+        }
+        Ok(())
+    }
+}
+
+impl AnnounceResponse {
+    /// Encode `self` and write the result to `dst`.
+    ///
+    /// Peers are encoded as 6 bytes (IPv4) or 18 bytes (IPv6). Since
+    /// clients can't tell them apart, mixing IPv4 and IPv6 peers
+    /// is not allowed, and `Err(error)` will be returned in that case.
+    pub fn write_into<W>(self, dst: &mut W) -> Result<()>
+    where
+        W: Write,
+    {
+        if self
+            .peers
+            .iter()
+            .map(|peer| matches!(peer.addr.ip(), IpAddr::V4(_)))
+            .dedup()
+            .nth(1)
+            .is_some()
+        {
+            bail!(ErrorKind::InvalidArgument(Cow::Borrowed(
+                "`peers` contain both IPv4 and IPv6 addresses."
+            )));
+        }
+
+        let mut peers = Vec::new();
+        for peer in &self.peers {
+            peer.write_compact_into(&mut peers);
+        }
+
+        dst.write_all(&ACTION_ANNOUNCE.to_be_bytes())?;
+        dst.write_all(&self.transaction_id.to_be_bytes())?;
+        dst.write_all(&self.interval.to_be_bytes())?;
+        dst.write_all(&self.leechers.to_be_bytes())?;
+        dst.write_all(&self.seeders.to_be_bytes())?;
+        dst.write_all(&peers)?;
+        Ok(())
+    }
+}
+
+impl ScrapeRequest {
+    /// Encode `self` and write the result to `dst`.
+    ///
+    /// If any of the `info_hashes` doesn't contain 20 bytes,
+    /// then `Err(error)` will be returned.
+    pub fn write_into<W>(self, dst: &mut W) -> Result<()>
+    where
+        W: Write,
+    {
+        for info_hash in &self.info_hashes {
+            if info_hash.len() != V1_HASH_LENGTH {
+                bail!(ErrorKind::InvalidArgument(Cow::Owned(format!(
+                    "`info_hash` should contain {} bytes, {} found.",
+                    V1_HASH_LENGTH,
+                    info_hash.len()
+                ))));
+            }
+        }
+
+        dst.write_all(&self.connection_id.to_be_bytes())?;
+        dst.write_all(&ACTION_SCRAPE.to_be_bytes())?;
+        dst.write_all(&self.transaction_id.to_be_bytes())?;
+        for info_hash in &self.info_hashes {
+            dst.write_all(info_hash)?;
+        }
+        Ok(())
+    }
+}
+
+impl ScrapeResponse {
+    /// Encode `self` and write the result to `dst`.
+    pub fn write_into<W>(self, dst: &mut W) -> Result<()>
+    where
+        W: Write,
+    {
+        dst.write_all(&ACTION_SCRAPE.to_be_bytes())?;
+        dst.write_all(&self.transaction_id.to_be_bytes())?;
+        for stats in &self.stats {
+            dst.write_all(&stats.seeders.to_be_bytes())?;
+            dst.write_all(&stats.completed.to_be_bytes())?;
+            dst.write_all(&stats.leechers.to_be_bytes())?;
+        }
+        Ok(())
+    }
+}
+
+impl ErrorResponse {
+    /// Encode `self` and write the result to `dst`.
+    pub fn write_into<W>(self, dst: &mut W) -> Result<()>
+    where
+        W: Write,
+    {
+        dst.write_all(&ACTION_ERROR.to_be_bytes())?;
+        dst.write_all(&self.transaction_id.to_be_bytes())?;
+        dst.write_all(self.message.as_bytes())?;
+        Ok(())
+    }
+}
+
+impl Request {
+    /// Encode `self` and write the result to `dst`.
+    pub fn write_into<W>(self, dst: &mut W) -> Result<()>
+    where
+        W: Write,
+    {
+        match self {
+            Request::Connect(request) => request.write_into(dst),
+            Request::Announce(request) => request.write_into(dst),
+            Request::Scrape(request) => request.write_into(dst),
+        }
+    }
+}
+
+impl Response {
+    /// Encode `self` and write the result to `dst`.
+    pub fn write_into<W>(self, dst: &mut W) -> Result<()>
+    where
+        W: Write,
+    {
+        match self {
+            Response::Connect(response) => response.write_into(dst),
+            Response::Announce(response) => response.write_into(dst),
+            Response::Scrape(response) => response.write_into(dst),
+            Response::Error(response) => response.write_into(dst),
+        }
+    }
+}
+
+#[cfg(test)]
+mod udp_write_tests {
+    use super::*;
+    use std::net::{Ipv6Addr, SocketAddr};
+
+    fn announce_request() -> AnnounceRequest {
+        AnnounceRequest {
+            downloaded: 100,
+            left: 200,
+            uploaded: 300,
+            event: Some(AnnounceEvent::Stopped),
+            ip: Some(Ipv4Addr::new(10, 0, 0, 1)),
+            key: 7,
+            num_want: Some(50),
+            url_data: b"/announce?passkey=x".to_vec(),
+            ..AnnounceRequest::new(1, 2, vec![0xab; 20], vec![b'p'; 20], 6881)
+        }
+    }
+
+    fn peer<A>(addr: A) -> Peer
+    where
+        A: Into<SocketAddr>,
+    {
+        Peer {
+            id: None,
+            addr: addr.into(),
+            extra_fields: None,
+        }
+    }
+
+    #[test]
+    fn connect_request_ok() {
+        let request = ConnectRequest {
+            transaction_id: 0x0102_0304,
+        };
+        assert_eq!(
+            request.encode().unwrap(),
+            vec![0, 0, 0x04, 0x17, 0x27, 0x10, 0x19, 0x80, 0, 0, 0, 0, 1, 2, 3, 4]
+        );
+    }
+
+    #[test]
+    fn connect_response_roundtrip() {
+        let response = ConnectResponse {
+            transaction_id: 3,
+            connection_id: 0x0102_0304_0506_0708,
+        };
+        let encoded = response.encode().unwrap();
+        assert_eq!(
+            encoded,
+            vec![0, 0, 0, 0, 0, 0, 0, 3, 1, 2, 3, 4, 5, 6, 7, 8]
+        );
+        assert_eq!(ConnectResponse::from_bytes(encoded).unwrap(), response);
+    }
+
+    #[test]
+    fn announce_request_roundtrip() {
+        let encoded = announce_request().encode().unwrap();
+        assert_eq!(encoded.len(), ANNOUNCE_REQUEST_LENGTH + 2 + 19);
+        assert_eq!(&encoded[80..84], &[0, 0, 0, 3]);
+        assert_eq!(&encoded[92..96], &[0, 0, 0, 50]);
+        assert_eq!(&encoded[98..100], &[OPTION_URL_DATA, 19]);
+        assert_eq!(
+            AnnounceRequest::from_bytes(encoded).unwrap(),
+            announce_request()
+        );
+    }
+
+    #[test]
+    fn announce_request_defaults() {
+        let request = AnnounceRequest::new(1, 2, vec![0xab; 20], vec![b'p'; 20], 6881);
+        let encoded = request.clone().encode().unwrap();
+        assert_eq!(encoded.len(), ANNOUNCE_REQUEST_LENGTH);
+        assert_eq!(
+            &encoded[80..96],
+            &[0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 255, 255, 255, 255]
+        );
+        assert_eq!(AnnounceRequest::from_bytes(encoded).unwrap(), request);
+    }
+
+    #[test]
+    fn announce_request_long_url_data() {
+        let request = AnnounceRequest {
+            url_data: vec![b'a'; 300],
+            ..announce_request()
+        };
+        let encoded = request.clone().encode().unwrap();
+        assert_eq!(encoded.len(), ANNOUNCE_REQUEST_LENGTH + 2 + 255 + 2 + 45);
+        assert_eq!(&encoded[98..100], &[OPTION_URL_DATA, 255]);
+        assert_eq!(&encoded[355..357], &[OPTION_URL_DATA, 45]);
+        assert_eq!(AnnounceRequest::from_bytes(encoded).unwrap(), request);
+    }
+
+    #[test]
+    fn announce_request_invalid_info_hash() {
+        let request = AnnounceRequest {
+            info_hash: vec![0; 19],
+            ..announce_request()
+        };
+        match request.encode() {
+            Err(Error(ErrorKind::InvalidArgument(m), _)) => {
+                assert_eq!(m, "`info_hash` should contain 20 bytes, 19 found.")
+            }
+            _ => assert!(false),
+        }
+    }
+
+    #[test]
+    fn announce_request_invalid_peer_id() {
+        let request = AnnounceRequest {
+            peer_id: vec![0; 8],
+            ..announce_request()
+        };
+        match request.encode() {
+            Err(Error(ErrorKind::InvalidArgument(m), _)) => {
+                assert_eq!(m, "`peer_id` should contain 20 bytes, 8 found.")
+            }
+            _ => assert!(false),
+        }
+    }
+
+    #[test]
+    fn announce_request_num_want_too_large() {
+        let request = AnnounceRequest {
+            num_want: Some(u32::MAX),
+            ..announce_request()
+        };
+        match request.encode() {
+            Err(Error(ErrorKind::InvalidArgument(m), _)) => {
+                assert_eq!(m, "`num_want` is too large.")
+            }
+            _ => assert!(false),
+        }
+    }
+
+    #[test]
+    fn announce_response_roundtrip() {
+        let response = AnnounceResponse {
+            transaction_id: 2,
+            interval: 1800,
+            leechers: 1,
+            seeders: 2,
+            peers: vec![peer(([127, 0, 0, 1], 6881)), peer(([10, 0, 0, 2], 80))],
+        };
+        let encoded = response.clone().encode().unwrap();
+        assert_eq!(encoded.len(), ANNOUNCE_RESPONSE_HEADER_LENGTH + 12);
+        assert_eq!(
+            AnnounceResponse::from_bytes(encoded, IpVersion::V4).unwrap(),
+            response
+        );
+
+        let response = AnnounceResponse {
+            peers: vec![peer((Ipv6Addr::LOCALHOST, 6881))],
+            ..response
+        };
+        let encoded = response.clone().encode().unwrap();
+        assert_eq!(encoded.len(), ANNOUNCE_RESPONSE_HEADER_LENGTH + 18);
+        assert_eq!(
+            AnnounceResponse::from_bytes(encoded, IpVersion::V6).unwrap(),
+            response
+        );
+    }
+
+    #[test]
+    fn announce_response_mixed_peers() {
+        let response = AnnounceResponse {
+            transaction_id: 2,
+            interval: 1800,
+            leechers: 1,
+            seeders: 2,
+            peers: vec![
+                peer(([127, 0, 0, 1], 6881)),
+                peer((Ipv6Addr::LOCALHOST, 6881)),
+            ],
+        };
+        match response.encode() {
+            Err(Error(ErrorKind::InvalidArgument(m), _)) => {
+                assert_eq!(m, "`peers` contain both IPv4 and IPv6 addresses.")
+            }
+            _ => assert!(false),
+        }
+    }
+
+    #[test]
+    fn scrape_request_roundtrip() {
+        let request = ScrapeRequest {
+            connection_id: 1,
+            transaction_id: 2,
+            info_hashes: vec![vec![1; 20], vec![2; 20]],
+        };
+        let encoded = request.clone().encode().unwrap();
+        assert_eq!(encoded.len(), REQUEST_HEADER_LENGTH + 40);
+        assert_eq!(ScrapeRequest::from_bytes(encoded).unwrap(), request);
+    }
+
+    #[test]
+    fn scrape_request_invalid_info_hash() {
+        let request = ScrapeRequest {
+            connection_id: 1,
+            transaction_id: 2,
+            info_hashes: vec![vec![1; 20], vec![2; 32]],
+        };
+        match request.encode() {
+            Err(Error(ErrorKind::InvalidArgument(m), _)) => {
+                assert_eq!(m, "`info_hash` should contain 20 bytes, 32 found.")
+            }
+            _ => assert!(false),
+        }
+    }
+
+    #[test]
+    fn scrape_response_roundtrip() {
+        let response = ScrapeResponse {
+            transaction_id: 2,
+            stats: vec![
+                ScrapeStats {
+                    seeders: 1,
+                    completed: 2,
+                    leechers: 3,
+                },
+                ScrapeStats::default(),
+            ],
+        };
+        let encoded = response.clone().encode().unwrap();
+        assert_eq!(encoded.len(), RESPONSE_HEADER_LENGTH + 24);
+        assert_eq!(ScrapeResponse::from_bytes(encoded).unwrap(), response);
+    }
+
+    #[test]
+    fn error_response_roundtrip() {
+        let response = ErrorResponse {
+            transaction_id: 2,
+            message: "unknown torrent".to_owned(),
+        };
+        let encoded = Response::Error(response.clone()).encode().unwrap();
+        assert_eq!(&encoded[..8], &[0, 0, 0, 3, 0, 0, 0, 2]);
+        assert_eq!(
+            Response::from_bytes(encoded, IpVersion::V4).unwrap(),
+            Response::Error(response)
+        );
+    }
+
+    #[test]
+    fn request_roundtrip() {
+        for request in [
+            Request::Connect(ConnectRequest { transaction_id: 1 }),
+            Request::Announce(announce_request()),
+            Request::Scrape(ScrapeRequest::default()),
+        ]
+        .iter()
+        {
+            let encoded = request.clone().encode().unwrap();
+            assert_eq!(&Request::from_bytes(encoded).unwrap(), request);
+        }
+    }
+}
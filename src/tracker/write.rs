@@ -0,0 +1,468 @@
+use super::*;
+use std::io::Write;
+
+impl Peer {
+    pub(crate) fn into_bencode_elem(self) -> BencodeElem {
+        let mut result: HashMap<String, BencodeElem> = HashMap::new();
+
+        if let Some(id) = self.id {
+            result.insert("peer id".to_owned(), BencodeElem::String(id));
+        }
+        result.insert(
+            "ip".to_owned(),
+            BencodeElem::String(self.addr.ip().to_string()),
+        );
+        result.insert(
+            "port".to_owned(),
+            BencodeElem::Integer(Integer::from(self.addr.port())),
+        );
+
+        if let Some(extra_fields) = self.extra_fields {
+            result.extend(extra_fields);
+        }
+
+        BencodeElem::Dictionary(result)
+    }
+
+    // 6 bytes for IPv4, 18 bytes for IPv6
+    pub(crate) fn write_compact_into(&self, dst: &mut Vec<u8>) {
+        match self.addr.ip() {
+            IpAddr::V4(ip) => dst.extend_from_slice(&ip.octets()),
+            IpAddr::V6(ip) => dst.extend_from_slice(&ip.octets()),
+        }
+        dst.extend_from_slice(&self.addr.port().to_be_bytes());
+    }
+
+    /// Encode `self` as a bencode dictionary and write the result to `dst`.
+    pub fn write_into<W>(self, dst: &mut W) -> Result<()>
+    where
+        W: Write,
+    {
+        self.into_bencode_elem().write_into(dst)
+    }
+
+    /// Encode `self` as a bencode dictionary and return the result in a `Vec`.
+    pub fn encode(self) -> Result<Vec<u8>> {
+        let mut result = Vec::new();
+        self.write_into(&mut result)?;
+        Ok(result)
+    }
+}
+
+impl TrackerResponse {
+    /// Encode `self` as bencode and write the result to `dst`.
+    ///
+    /// `peers` are encoded according to `format`. With
+    /// [`PeerListFormat::Compact`], `peers6` is written only if
+    /// there is at least 1 IPv6 peer.
+    ///
+    /// [`PeerListFormat::Compact`]: enum.PeerListFormat.html#variant.Compact
+    pub fn write_into<W>(self, dst: &mut W, format: PeerListFormat) -> Result<()>
+    where
+        W: Write,
+    {
+        let mut result: HashMap<String, BencodeElem> = HashMap::new();
+
+        result.insert("interval".to_owned(), BencodeElem::Integer(self.interval));
+
+        match format {
+            PeerListFormat::Compact => {
+                let mut peers = Vec::new();
+                let mut peers6 = Vec::new();
+                for peer in &self.peers {
+                    if peer.addr.is_ipv4() {
+                        peer.write_compact_into(&mut peers);
+                    } else {
+                        peer.write_compact_into(&mut peers6);
+                    }
+                }
+
+                result.insert("peers".to_owned(), BencodeElem::Bytes(peers));
+                if !peers6.is_empty() {
+                    result.insert("peers6".to_owned(), BencodeElem::Bytes(peers6));
+                }
+            }
+            PeerListFormat::Dictionary => {
+                result.insert(
+                    "peers".to_owned(),
+                    BencodeElem::List(
+                        self.peers
+                            .into_iter()
+                            .map(|peer| peer.into_bencode_elem())
+                            .collect(),
+                    ),
+                );
+            }
+        }
+
+        if let Some(warning) = self.warning {
+            result.insert("warning message".to_owned(), BencodeElem::String(warning));
+        }
+        if let Some(min_interval) = self.min_interval {
+            result.insert(
+                "min interval".to_owned(),
+                BencodeElem::Integer(min_interval),
+            );
+        }
+        if let Some(tracker_id) = self.tracker_id {
+            result.insert("tracker id".to_owned(), BencodeElem::String(tracker_id));
+        }
+        if let Some(complete) = self.complete {
+            result.insert("complete".to_owned(), BencodeElem::Integer(complete));
+        }
+        if let Some(incomplete) = self.incomplete {
+            result.insert("incomplete".to_owned(), BencodeElem::Integer(incomplete));
+        }
+        if let Some(external_ip) = self.external_ip {
+            let bytes = match external_ip {
+                IpAddr::V4(ip) => ip.octets().to_vec(),
+                IpAddr::V6(ip) => ip.octets().to_vec(),
+            };
+            result.insert("external ip".to_owned(), BencodeElem::Bytes(bytes));
+        }
+
+        if let Some(extra_fields) = self.extra_fields {
+            result.extend(extra_fields);
+        }
+
+        BencodeElem::Dictionary(result).write_into(dst)
+    }
+
+    /// Encode `self` as bencode and return the result in a `Vec`.
+    ///
+    /// See [`write_into()`] for how `peers` are encoded.
+    ///
+    /// [`write_into()`]: #method.write_into
+    pub fn encode(self, format: PeerListFormat) -> Result<Vec<u8>> {
+        let mut result = Vec::new();
+        self.write_into(&mut result, format)?;
+        Ok(result)
+    }
+
+    /// Write a failure response (i.e. a dictionary containing only
+    /// `failure reason`) to `dst`.
+    ///
+    /// It can be used for both announce and scrape requests.
+    pub fn write_failure_into<W>(reason: &str, dst: &mut W) -> Result<()>
+    where
+        W: Write,
+    {
+        let mut result: HashMap<String, BencodeElem> = HashMap::new();
+        result.insert(
+            "failure reason".to_owned(),
+            BencodeElem::String(reason.to_owned()),
+        );
+        BencodeElem::Dictionary(result).write_into(dst)
+    }
+
+    /// Encode a failure response and return the result in a `Vec`.
+    ///
+    /// See [`write_failure_into()`] for details.
+    ///
+    /// [`write_failure_into()`]: #method.write_failure_into
+    pub fn encode_failure(reason: &str) -> Result<Vec<u8>> {
+        let mut result = Vec::new();
+        Self::write_failure_into(reason, &mut result)?;
+        Ok(result)
+    }
+}
+
+impl SwarmMetadata {
+    pub(crate) fn into_bencode_elem(self) -> BencodeElem {
+        let mut result: HashMap<String, BencodeElem> = HashMap::new();
+
+        result.insert("complete".to_owned(), BencodeElem::Integer(self.complete));
+        result.insert(
+            "incomplete".to_owned(),
+            BencodeElem::Integer(self.incomplete),
+        );
+        result.insert(
+            "downloaded".to_owned(),
+            BencodeElem::Integer(self.downloaded),
+        );
+
+        if let Some(extra_fields) = self.extra_fields {
+            result.extend(extra_fields);
+        }
+
+        BencodeElem::Dictionary(result)
+    }
+}
+
+impl TrackerScrapeResponse {
+    /// Encode `self` as bencode and write the result to `dst`.
+    ///
+    /// `min_request_interval` is written into `flags`, along
+    /// with any entry in `extra_fields["flags"]`.
+    pub fn write_into<W>(self, dst: &mut W) -> Result<()>
+    where
+        W: Write,
+    {
+        let mut result: HashMap<String, BencodeElem> = HashMap::new();
+
+        result.insert(
+            "files".to_owned(),
+            BencodeElem::RawDictionary(
+                self.files
+                    .into_iter()
+                    .map(|(info_hash, metadata)| (info_hash, metadata.into_bencode_elem()))
+                    .collect(),
+            ),
+        );
+
+        let mut extra_fields = self.extra_fields.unwrap_or_default();
+        if let Some(min_request_interval) = self.min_request_interval {
+            let mut flags = match extra_fields.remove("flags") {
+                Some(BencodeElem::Dictionary(flags)) => flags,
+                Some(_) => bail!(ErrorKind::InvalidArgument(Cow::Borrowed(
+                    r#"`extra_fields["flags"]` is not a dictionary."#
+                ))),
+                None => HashMap::new(),
+            };
+            flags.insert(
+                "min_request_interval".to_owned(),
+                BencodeElem::Integer(min_request_interval),
+            );
+            result.insert("flags".to_owned(), BencodeElem::Dictionary(flags));
+        }
+        result.extend(extra_fields);
+
+        BencodeElem::Dictionary(result).write_into(dst)
+    }
+
+    /// Encode `self` as bencode and return the result in a `Vec`.
+    pub fn encode(self) -> Result<Vec<u8>> {
+        let mut result = Vec::new();
+        self.write_into(&mut result)?;
+        Ok(result)
+    }
+}
+
+#[cfg(test)]
+mod tracker_write_tests {
+    use super::*;
+    use std::iter::FromIterator;
+
+    fn peers() -> Vec<Peer> {
+        vec![
+            Peer {
+                id: Some("peer1".to_owned()),
+                addr: "127.0.0.1:6881".parse().unwrap(),
+                extra_fields: None,
+            },
+            Peer {
+                id: None,
+                addr: "[2001:db8::1]:6882".parse().unwrap(),
+                extra_fields: Some(HashMap::from_iter(vec![(
+                    "key".to_owned(),
+                    bencode_elem!("val"),
+                )])),
+            },
+        ]
+    }
+
+    fn response() -> TrackerResponse {
+        TrackerResponse {
+            interval: 900,
+            peers: peers(),
+            warning: None,
+            min_interval: None,
+            tracker_id: None,
+            complete: None,
+            incomplete: None,
+            external_ip: None,
+            extra_fields: None,
+        }
+    }
+
+    #[test]
+    fn peer_encode_ok() {
+        assert_eq!(
+            peers().remove(0).encode().unwrap(),
+            bencode_elem!({
+                ("ip", "127.0.0.1"),
+                ("peer id", "peer1"),
+                ("port", 6881),
+            })
+            .encode()
+        );
+    }
+
+    #[test]
+    fn encode_compact() {
+        let mut peers6 = vec![0x20, 0x01, 0x0d, 0xb8];
+        peers6.extend_from_slice(&[0; 11]);
+        peers6.extend_from_slice(&[1, 0x1a, 0xe2]);
+
+        assert_eq!(
+            response().encode(PeerListFormat::Compact).unwrap(),
+            BencodeElem::Dictionary(HashMap::from_iter(vec![
+                ("interval".to_owned(), bencode_elem!(900)),
+                (
+                    "peers".to_owned(),
+                    bencode_elem!((127, 0, 0, 1, 0x1a, 0xe1))
+                ),
+                ("peers6".to_owned(), BencodeElem::Bytes(peers6)),
+            ]))
+            .encode()
+        );
+    }
+
+    #[test]
+    fn encode_compact_ipv4_only() {
+        let response = TrackerResponse {
+            peers: vec![peers().remove(0)],
+            ..response()
+        };
+
+        assert_eq!(
+            response.encode(PeerListFormat::Compact).unwrap(),
+            bencode_elem!({
+                ("interval", 900),
+                ("peers", (127, 0, 0, 1, 0x1a, 0xe1)),
+            })
+            .encode()
+        );
+    }
+
+    #[test]
+    fn encode_dictionary() {
+        assert_eq!(
+            response().encode(PeerListFormat::Dictionary).unwrap(),
+            bencode_elem!({
+                ("interval", 900),
+                ("peers", [
+                    {
+                        ("ip", "127.0.0.1"),
+                        ("peer id", "peer1"),
+                        ("port", 6881),
+                    },
+                    {
+                        ("ip", "2001:db8::1"),
+                        ("key", "val"),
+                        ("port", 6882),
+                    },
+                ]),
+            })
+            .encode()
+        );
+    }
+
+    #[test]
+    fn encode_all_fields() {
+        let response = TrackerResponse {
+            peers: vec![],
+            warning: Some("warning".to_owned()),
+            min_interval: Some(60),
+            tracker_id: Some("id".to_owned()),
+            complete: Some(1),
+            incomplete: Some(2),
+            external_ip: Some("192.0.2.255".parse().unwrap()),
+            extra_fields: Some(HashMap::from_iter(vec![(
+                "key".to_owned(),
+                bencode_elem!("val"),
+            )])),
+            ..response()
+        };
+
+        assert_eq!(
+            response.encode(PeerListFormat::Compact).unwrap(),
+            bencode_elem!({
+                ("complete", 1),
+                ("external ip", (192, 0, 2, 0xff)),
+                ("incomplete", 2),
+                ("interval", 900),
+                ("key", "val"),
+                ("min interval", 60),
+                ("peers", ()),
+                ("tracker id", "id"),
+                ("warning message", "warning"),
+            })
+            .encode()
+        );
+    }
+
+    #[test]
+    fn encode_round_trip() {
+        let response = TrackerResponse {
+            warning: Some("warning".to_owned()),
+            external_ip: Some("2001:db8::2".parse().unwrap()),
+            ..response()
+        };
+
+        assert_eq!(
+            TrackerResponse::from_bytes(
+                response.clone().encode(PeerListFormat::Dictionary).unwrap()
+            )
+            .unwrap(),
+            response
+        );
+    }
+
+    #[test]
+    fn encode_failure_ok() {
+        let bytes = TrackerResponse::encode_failure("unregistered torrent").unwrap();
+        assert_eq!(
+            bytes,
+            bencode_elem!({ ("failure reason", "unregistered torrent") }).encode()
+        );
+
+        match TrackerResponse::from_bytes(bytes) {
+            Err(Error(ErrorKind::TrackerErrorResponse(m), _)) => {
+                assert_eq!(m, "unregistered torrent")
+            }
+            _ => assert!(false),
+        }
+    }
+
+    #[test]
+    fn scrape_encode_ok() {
+        let response = TrackerScrapeResponse {
+            files: HashMap::from_iter(vec![(
+                vec![0xff; 20],
+                SwarmMetadata {
+                    complete: 1,
+                    incomplete: 3,
+                    downloaded: 2,
+                    extra_fields: None,
+                },
+            )]),
+            min_request_interval: Some(60),
+            extra_fields: Some(HashMap::from_iter(vec![(
+                "flags".to_owned(),
+                bencode_elem!({ ("key", 1) }),
+            )])),
+        };
+
+        let mut expected = b"d5:filesd20:".to_vec();
+        expected.extend_from_slice(&[0xff; 20]);
+        expected.extend_from_slice(
+            b"d8:completei1e10:downloadedi2e10:incompletei3eee\
+              5:flagsd3:keyi1e20:min_request_intervali60eee",
+        );
+        assert_eq!(response.clone().encode().unwrap(), expected);
+        assert_eq!(
+            TrackerScrapeResponse::from_bytes(expected).unwrap(),
+            response
+        );
+    }
+
+    #[test]
+    fn scrape_encode_bad_flags() {
+        let response = TrackerScrapeResponse {
+            files: HashMap::new(),
+            min_request_interval: Some(60),
+            extra_fields: Some(HashMap::from_iter(vec![(
+                "flags".to_owned(),
+                bencode_elem!(1),
+            )])),
+        };
+
+        match response.encode() {
+            Err(Error(ErrorKind::InvalidArgument(m), _)) => {
+                assert_eq!(m, r#"`extra_fields["flags"]` is not a dictionary."#)
+            }
+            _ => assert!(false),
+        }
+    }
+}
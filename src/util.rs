@@ -1,7 +1,9 @@
 use error::*;
+use itertools::Itertools;
 use std::borrow::Cow;
 use std::convert::TryFrom;
-use std::path::{Path, PathBuf};
+use std::path::{Component, Path, PathBuf};
+use torrent::v1::SymlinkPolicy;
 
 pub(crate) fn u64_to_usize(src: u64) -> Result<usize> {
     usize::try_from(src).chain_err(|| {
@@ -33,36 +35,129 @@ pub(crate) fn u64_to_i64(src: u64) -> Result<i64> {
     })
 }
 
+pub(crate) fn usize_to_i64(src: usize) -> Result<i64> {
+    i64::try_from(src).chain_err(|| {
+        ErrorKind::FailedNumericConv(Cow::Owned(format!("[{}] does not fit into i64.", src)))
+    })
+}
+
+// an entry returned by `list_dir()`
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub(crate) struct DirEntry {
+    pub(crate) path: PathBuf,
+    pub(crate) length: u64,
+    // target of a symbolic link, only set if the link is kept as is
+    // (i.e. `SymlinkPolicy::Record`), in which case `length` is 0
+    pub(crate) symlink_target: Option<PathBuf>,
+}
+
+// options for `list_dir()`
+pub(crate) struct ListDirOptions<'a> {
+    pub(crate) include_hidden: bool,
+    pub(crate) symlink_policy: SymlinkPolicy,
+    // called with each entry's path (relative to the root) and whether
+    // it is a directory, entries for which `false` is returned are skipped
+    pub(crate) filter: &'a dyn Fn(&Path, bool) -> bool,
+}
+
+impl Default for ListDirOptions<'static> {
+    // same as the behavior of `TorrentBuilder` by default
+    fn default() -> ListDirOptions<'static> {
+        ListDirOptions {
+            include_hidden: false,
+            symlink_policy: SymlinkPolicy::Follow,
+            filter: &|_, _| true,
+        }
+    }
+}
+
 // this method is recursive, i.e. entries in subdirectories
 // are also returned
 //
-// *nix hidden files/dirs are ignored
+// hidden files/dirs, symbolic links, and filtering
+// are handled according to `options`
 //
 // returned vec is sorted by path
-pub(crate) fn list_dir<P>(path: P) -> Result<Vec<(PathBuf, u64)>>
+pub(crate) fn list_dir<P>(path: P, options: &ListDirOptions) -> Result<Vec<DirEntry>>
 where
     P: AsRef<Path>,
 {
+    let root = path.as_ref();
     let mut entries = Vec::new();
+    list_dir_into(root, root, options, &mut entries)?;
 
-    for entry in path.as_ref().read_dir()? {
+    entries.sort_by(|e1, e2| e1.path.cmp(&e2.path));
+    Ok(entries)
+}
+
+fn list_dir_into(
+    root: &Path,
+    dir: &Path,
+    options: &ListDirOptions,
+    entries: &mut Vec<DirEntry>,
+) -> Result<()> {
+    for entry in dir.read_dir()? {
         let entry = entry?;
         let path = entry.path();
-        let metadata = path.metadata()?;
+        let relative = path
+            .strip_prefix(root)
+            .expect("read_dir() returned an entry outside of the root.");
 
-        if last_component(&path)?.starts_with('.') {
+        if !options.include_hidden && last_component(&path)?.starts_with('.') {
             continue;
         } // hidden files/dirs are ignored
 
+        if path.symlink_metadata()?.file_type().is_symlink() {
+            match options.symlink_policy {
+                SymlinkPolicy::Follow => (),
+                SymlinkPolicy::Skip => continue,
+                SymlinkPolicy::Record => {
+                    if (options.filter)(relative, false) {
+                        let target = dir.join(path.read_link()?);
+                        entries.push(DirEntry {
+                            path,
+                            length: 0,
+                            symlink_target: Some(normalize_path(&target)),
+                        });
+                    }
+                    continue;
+                }
+            }
+        }
+
+        let metadata = path.metadata()?;
+        if !(options.filter)(relative, metadata.is_dir()) {
+            continue;
+        }
+
         if metadata.is_dir() {
-            entries.extend(list_dir(path)?);
+            list_dir_into(root, &path, options, entries)?;
         } else {
-            entries.push((path, metadata.len()));
+            entries.push(DirEntry {
+                path,
+                length: metadata.len(),
+                symlink_target: None,
+            });
         }
     }
 
-    entries.sort_by(|&(ref p1, _), &(ref p2, _)| p1.cmp(p2));
-    Ok(entries)
+    Ok(())
+}
+
+// remove `.` and `..` from `path` without touching the file system
+// (i.e. symbolic links are not resolved)
+pub(crate) fn normalize_path(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => (),
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            _ => normalized.push(component),
+        }
+    }
+    normalized
 }
 
 pub(crate) fn last_component<P>(path: P) -> Result<String>
@@ -79,6 +174,93 @@ where
     }
 }
 
+// characters (other than the unreserved ones) that
+// can be left unencoded in the value of a query parameter
+//
+// `&`, `=`, `+` and `#` are not included as they
+// would change the meaning of the query
+pub(crate) const QUERY_VALUE_SAFE: &[u8] = b"!$'()*,/:;?@";
+
+// percent-encode `bytes` as defined in RFC 3986
+//
+// all bytes except the unreserved characters
+// (i.e. `A-Z a-z 0-9 - . _ ~`) and those in `safe` are encoded
+pub(crate) fn percent_encode<B>(bytes: B, safe: &[u8]) -> String
+where
+    B: AsRef<[u8]>,
+{
+    let bytes = bytes.as_ref();
+    let mut result = String::with_capacity(bytes.len());
+
+    for &byte in bytes {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                result.push(char::from(byte))
+            }
+            _ if safe.contains(&byte) => result.push(char::from(byte)),
+            _ => result.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    result
+}
+
+// decode a percent-encoded string
+//
+// if `plus_as_space` is true then `+` is decoded as a space
+pub(crate) fn percent_decode(src: &str, plus_as_space: bool) -> Result<Vec<u8>> {
+    let bytes = src.as_bytes();
+    let mut result = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+
+    while i < bytes.len() {
+        match bytes[i] {
+            b'%' => {
+                let byte = bytes
+                    .get(i + 1..i + 3)
+                    .filter(|hex| hex.iter().all(u8::is_ascii_hexdigit))
+                    .and_then(|hex| ::std::str::from_utf8(hex).ok())
+                    .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+                match byte {
+                    Some(byte) => result.push(byte),
+                    None => bail!(ErrorKind::InvalidArgument(Cow::Owned(format!(
+                        "[{}] contains an invalid percent-encoded byte.",
+                        src
+                    )))),
+                }
+                i += 3;
+            }
+            b'+' if plus_as_space => {
+                result.push(b' ');
+                i += 1;
+            }
+            byte => {
+                result.push(byte);
+                i += 1;
+            }
+        }
+    }
+    Ok(result)
+}
+
+// encode `bytes` as a lowercase hex string
+pub(crate) fn encode_hex(bytes: &[u8]) -> String {
+    format!("{:02x}", bytes.iter().format(""))
+}
+
+// decode a hex string (case-insensitive)
+//
+// `None` is returned if `hex` is not a valid hex string
+pub(crate) fn decode_hex(hex: &str) -> Option<Vec<u8>> {
+    if ((hex.len() % 2) != 0) || !hex.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return None;
+    }
+
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&hex[i..(i + 2)], 16).ok())
+        .collect()
+}
+
 pub(crate) struct ByteBuffer<'a> {
     bytes: &'a [u8],
     position: usize, // current cursor position
@@ -86,7 +268,7 @@ pub(crate) struct ByteBuffer<'a> {
 }
 
 impl<'a> ByteBuffer<'a> {
-    pub(crate) fn new(bytes: &[u8]) -> ByteBuffer {
+    pub(crate) fn new(bytes: &[u8]) -> ByteBuffer<'_> {
         ByteBuffer {
             bytes,
             position: 0,
@@ -116,6 +298,11 @@ impl<'a> ByteBuffer<'a> {
     pub(crate) fn is_empty(&self) -> bool {
         self.position >= self.length
     }
+
+    // the bytes that have not been consumed yet
+    pub(crate) fn remaining(&self) -> &'a [u8] {
+        &self.bytes[self.position..]
+    }
 }
 
 impl<'a> Iterator for ByteBuffer<'a> {
@@ -138,8 +325,12 @@ mod util_tests {
     #[test]
     fn list_dir_ok() {
         assert_eq!(
-            list_dir("tests/files").unwrap(),
-            vec![
+            list_dir("tests/files", &Default::default())
+                .unwrap()
+                .into_iter()
+                .map(|entry| (entry.path, entry.length))
+                .collect::<Vec<(PathBuf, u64)>>(),
+            [
                 PathBuf::from("tests/files/byte_sequence"),
                 PathBuf::from("tests/files/symlink"),
                 PathBuf::from("tests/files/tails-amd64-3.6.1.torrent"),
@@ -156,13 +347,22 @@ mod util_tests {
     #[test]
     fn list_dir_with_subdir() {
         assert_eq!(
-            list_dir("src/torrent").unwrap(),
-            vec![
+            list_dir("src/torrent", &Default::default())
+                .unwrap()
+                .into_iter()
+                .map(|entry| (entry.path, entry.length))
+                .collect::<Vec<(PathBuf, u64)>>(),
+            [
                 PathBuf::from("src/torrent/mod.rs"),
                 PathBuf::from("src/torrent/v1/build.rs"),
                 PathBuf::from("src/torrent/v1/mod.rs"),
                 PathBuf::from("src/torrent/v1/read.rs"),
+                PathBuf::from("src/torrent/v1/verify.rs"),
                 PathBuf::from("src/torrent/v1/write.rs"),
+                PathBuf::from("src/torrent/v2/merkle.rs"),
+                PathBuf::from("src/torrent/v2/mod.rs"),
+                PathBuf::from("src/torrent/v2/read.rs"),
+                PathBuf::from("src/torrent/v2/write.rs")
             ]
             .iter()
             .map(PathBuf::from)
@@ -171,6 +371,90 @@ mod util_tests {
         );
     }
 
+    #[test]
+    fn list_dir_include_hidden() {
+        let options = ListDirOptions {
+            include_hidden: true,
+            ..Default::default()
+        };
+
+        assert_eq!(
+            list_dir("tests/files", &options)
+                .unwrap()
+                .into_iter()
+                .map(|entry| entry.path)
+                .collect::<Vec<PathBuf>>(),
+            vec![
+                PathBuf::from("tests/files/.hidden"),
+                PathBuf::from("tests/files/byte_sequence"),
+                PathBuf::from("tests/files/symlink"),
+                PathBuf::from("tests/files/tails-amd64-3.6.1.torrent"),
+                PathBuf::from("tests/files/ubuntu-16.04.4-desktop-amd64.iso.torrent"),
+            ]
+        );
+    }
+
+    #[test]
+    fn list_dir_symlink_skipped() {
+        let options = ListDirOptions {
+            symlink_policy: SymlinkPolicy::Skip,
+            ..Default::default()
+        };
+
+        assert_eq!(
+            list_dir("tests/files", &options)
+                .unwrap()
+                .into_iter()
+                .map(|entry| entry.path)
+                .collect::<Vec<PathBuf>>(),
+            vec![
+                PathBuf::from("tests/files/byte_sequence"),
+                PathBuf::from("tests/files/tails-amd64-3.6.1.torrent"),
+                PathBuf::from("tests/files/ubuntu-16.04.4-desktop-amd64.iso.torrent"),
+            ]
+        );
+    }
+
+    #[test]
+    fn list_dir_symlink_recorded() {
+        let options = ListDirOptions {
+            include_hidden: false,
+            symlink_policy: SymlinkPolicy::Record,
+            filter: &|path, _| path != Path::new("byte_sequence"),
+        };
+
+        assert_eq!(
+            list_dir("tests/files", &options).unwrap(),
+            vec![
+                DirEntry {
+                    path: PathBuf::from("tests/files/symlink"),
+                    length: 0,
+                    symlink_target: Some(PathBuf::from(
+                        "tests/files/ubuntu-16.04.4-desktop-amd64.iso.torrent"
+                    )),
+                },
+                DirEntry {
+                    path: PathBuf::from("tests/files/tails-amd64-3.6.1.torrent"),
+                    length: 94016,
+                    symlink_target: None,
+                },
+                DirEntry {
+                    path: PathBuf::from("tests/files/ubuntu-16.04.4-desktop-amd64.iso.torrent"),
+                    length: 62300,
+                    symlink_target: None,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn normalize_path_ok() {
+        assert_eq!(
+            normalize_path(Path::new("/root/./dir/../dir2/file")),
+            PathBuf::from("/root/dir2/file")
+        );
+    }
+
     #[test]
     fn last_component_ok() {
         assert_eq!(
@@ -194,6 +478,48 @@ mod util_tests {
         }
     }
 
+    #[test]
+    fn percent_encode_ok() {
+        assert_eq!(
+            percent_encode("a b&c/d~é", &[]),
+            "a%20b%26c%2Fd~%C3%A9".to_owned()
+        );
+        assert_eq!(
+            percent_encode([0x00, 0xff, b'A'], &[]),
+            "%00%FFA".to_owned()
+        );
+    }
+
+    #[test]
+    fn percent_encode_query_value() {
+        assert_eq!(
+            percent_encode("http://a.com:80/?x=1&y=2+3#z", QUERY_VALUE_SAFE),
+            "http://a.com:80/?x%3D1%26y%3D2%2B3%23z".to_owned()
+        );
+    }
+
+    #[test]
+    fn percent_decode_ok() {
+        assert_eq!(
+            percent_decode("a%20b%26c%2fd~%C3%A9+", false).unwrap(),
+            "a b&c/d~é+".as_bytes().to_vec()
+        );
+        assert_eq!(
+            percent_decode("a+b", true).unwrap(),
+            "a b".as_bytes().to_vec()
+        );
+    }
+
+    #[test]
+    fn percent_decode_invalid() {
+        match percent_decode("a%2", false) {
+            Err(Error(ErrorKind::InvalidArgument(m), _)) => {
+                assert_eq!(m, "[a%2] contains an invalid percent-encoded byte.");
+            }
+            _ => assert!(false),
+        }
+    }
+
     #[test]
     fn u64_to_usize_ok() {
         // @todo: add test for err
@@ -236,6 +562,21 @@ mod util_tests {
         }
     }
 
+    #[test]
+    fn usize_to_i64_ok() {
+        assert_eq!(usize_to_i64(42).unwrap(), 42);
+    }
+
+    #[test]
+    fn usize_to_i64_err() {
+        match usize_to_i64(usize::MAX) {
+            Err(Error(ErrorKind::FailedNumericConv(m), _)) => {
+                assert_eq!(m, format!("[{}] does not fit into i64.", usize::MAX))
+            }
+            _ => assert!(false),
+        }
+    }
+
     #[test]
     fn u64_to_i64_ok() {
         assert_eq!(u64_to_i64(42).unwrap(), 42);
@@ -243,9 +584,9 @@ mod util_tests {
 
     #[test]
     fn u64_to_i64_err() {
-        match u64_to_i64(u64::max_value()) {
+        match u64_to_i64(u64::MAX) {
             Err(Error(ErrorKind::FailedNumericConv(m), _)) => {
-                assert_eq!(m, format!("[{}] does not fit into i64.", u64::max_value()))
+                assert_eq!(m, format!("[{}] does not fit into i64.", u64::MAX))
             }
             _ => assert!(false),
         }
@@ -1,19 +1,24 @@
+#![allow(clippy::assertions_on_constants)]
+
 extern crate lava_torrent;
 extern crate rand;
 
+mod common;
+
+use common::{create_dir_tree, rand_file_name};
 use lava_torrent::bencode::BencodeElem;
 use lava_torrent::error::*;
-use lava_torrent::torrent::v1::{Integer, Torrent, TorrentBuilder};
-use rand::Rng;
+use lava_torrent::torrent::v1::{
+    BuildProgress, CancellationToken, Integer, SymlinkPolicy, Torrent, TorrentBuilder,
+    DEFAULT_MAX_PIECE_LENGTH,
+};
+use lava_torrent::torrent::v2;
+use std::fs;
+use std::os::unix;
 use std::path::PathBuf;
 
-const OUTPUT_ROOT: &str = "tests/tmp/";
 const PIECE_LENGTH: Integer = 32 * 1024; // n * 1024 KiB
 
-fn rand_file_name() -> String {
-    OUTPUT_ROOT.to_owned() + &rand::thread_rng().gen::<u16>().to_string()
-}
-
 #[test]
 fn build_single_file_ok() {
     let output_name = rand_file_name() + ".torrent";
@@ -74,6 +79,240 @@ fn build_multi_file_ok() {
     );
 }
 
+#[test]
+fn build_with_web_seeds() {
+    let output_name = rand_file_name() + ".torrent";
+
+    TorrentBuilder::new(
+        PathBuf::from("tests/files").canonicalize().unwrap(),
+        PIECE_LENGTH,
+    )
+    .set_url_list(vec![
+        "http://ws1.com/".to_owned(),
+        "http://ws2.com/".to_owned(),
+    ])
+    .set_http_seeds(vec!["http://hs.com/seed".to_owned()])
+    .build()
+    .unwrap()
+    .write_into_file(&output_name)
+    .unwrap();
+
+    let torrent = Torrent::read_from_file(output_name).unwrap();
+    assert_eq!(
+        torrent.url_list,
+        Some(vec![
+            "http://ws1.com/".to_owned(),
+            "http://ws2.com/".to_owned(),
+        ])
+    );
+    assert_eq!(
+        torrent.http_seeds,
+        Some(vec!["http://hs.com/seed".to_owned()])
+    );
+    assert_eq!(torrent.extra_fields, None);
+    assert!(torrent
+        .magnet_link()
+        .ends_with("&ws=http://ws1.com/&ws=http://ws2.com/"));
+}
+
+#[test]
+fn build_with_metainfo_fields() {
+    let output_name = rand_file_name() + ".torrent";
+
+    let torrent = TorrentBuilder::new(
+        PathBuf::from("tests/files").canonicalize().unwrap(),
+        PIECE_LENGTH,
+    )
+    .set_creation_date_now()
+    .set_comment(Some("no comment".to_owned()))
+    .set_created_by(Some("lava_torrent".to_owned()))
+    .set_encoding(Some("UTF-8".to_owned()))
+    .set_nodes(vec![("127.0.0.1".to_owned(), 6881)])
+    .set_source(Some("SRC".to_owned()))
+    .build()
+    .unwrap();
+    assert!(torrent.creation_date.unwrap() > 1_500_000_000);
+    torrent.clone().write_into_file(&output_name).unwrap();
+
+    let read = Torrent::read_from_file(output_name).unwrap();
+    assert_eq!(read.creation_date, torrent.creation_date);
+    assert_eq!(read.comment, Some("no comment".to_owned()));
+    assert_eq!(read.created_by, Some("lava_torrent".to_owned()));
+    assert_eq!(read.encoding, Some("UTF-8".to_owned()));
+    assert_eq!(read.nodes, Some(vec![("127.0.0.1".to_owned(), 6881)]));
+    assert_eq!(read.source, Some("SRC".to_owned()));
+    assert_eq!(read.extra_fields, None);
+    assert_eq!(read.extra_info_fields, None);
+    // `source` is part of `info`
+    assert_eq!(read.info_hash(), torrent.info_hash());
+    assert_ne!(
+        read.info_hash(),
+        Torrent {
+            source: None,
+            ..torrent
+        }
+        .info_hash()
+    );
+}
+
+#[test]
+fn build_multi_file_parallel() {
+    let output_name = rand_file_name() + ".torrent";
+
+    TorrentBuilder::new(
+        PathBuf::from("tests/files").canonicalize().unwrap(),
+        PIECE_LENGTH,
+    )
+    .set_announce(Some(
+        "udp://tracker.coppersurfer.tk:6969/announce".to_owned(),
+    ))
+    .add_extra_field("creation date".to_owned(), BencodeElem::Integer(1523607302))
+    .add_extra_field(
+        "encoding".to_owned(),
+        BencodeElem::String("UTF-8".to_owned()),
+    )
+    .add_extra_info_field("private".to_owned(), BencodeElem::Integer(0))
+    .set_num_threads(4)
+    .build()
+    .unwrap()
+    .write_into_file(&output_name)
+    .unwrap();
+
+    // pieces should be identical to those hashed sequentially
+    assert_eq!(
+        Torrent::read_from_file(output_name).unwrap(),
+        Torrent::read_from_file("tests/samples/files.torrent").unwrap(),
+    );
+}
+
+#[test]
+fn build_with_progress() {
+    for &num_threads in &[1, 4] {
+        let mut reported: Vec<BuildProgress> = Vec::new();
+        let torrent = TorrentBuilder::new(
+            PathBuf::from("tests/files").canonicalize().unwrap(),
+            PIECE_LENGTH,
+        )
+        .set_num_threads(num_threads)
+        .build_with_progress(|progress| reported.push(progress.clone()))
+        .unwrap();
+
+        // initial progress + 1 for each piece
+        assert_eq!(reported.len(), torrent.pieces.len() + 1);
+        for (i, progress) in reported.iter().enumerate() {
+            assert_eq!(progress.pieces_done, i);
+            assert_eq!(progress.pieces_total, torrent.pieces.len());
+            assert_eq!(progress.bytes_total, torrent.length as u64);
+        }
+        for pair in reported.windows(2) {
+            assert!(pair[0].bytes_hashed < pair[1].bytes_hashed);
+        }
+
+        let last = reported.last().unwrap();
+        assert_eq!(last.bytes_hashed, torrent.length as u64);
+        assert!(last
+            .current_file
+            .starts_with(PathBuf::from("tests/files").canonicalize().unwrap()));
+    }
+}
+
+#[test]
+fn build_hybrid_with_progress() {
+    let mut last = None;
+    let torrent = TorrentBuilder::new(
+        PathBuf::from("tests/files").canonicalize().unwrap(),
+        PIECE_LENGTH,
+    )
+    .set_hybrid(true)
+    .build_with_progress(|progress| last = Some(progress.clone()))
+    .unwrap();
+
+    let last = last.unwrap();
+    assert_eq!(last.pieces_done, torrent.pieces.len());
+    assert_eq!(last.pieces_total, torrent.pieces.len());
+    // padding is excluded
+    assert_eq!(last.bytes_hashed, torrent.content_length() as u64);
+}
+
+#[test]
+fn build_cancelled() {
+    for &num_threads in &[1, 4] {
+        let token = CancellationToken::new();
+        let mut pieces_done = 0;
+        let result = TorrentBuilder::new(
+            PathBuf::from("tests/files").canonicalize().unwrap(),
+            PIECE_LENGTH,
+        )
+        .set_num_threads(num_threads)
+        .set_cancellation_token(token.clone())
+        .build_with_progress(|progress| {
+            pieces_done = progress.pieces_done;
+            if progress.pieces_done == 2 {
+                token.cancel();
+            }
+        });
+
+        match result {
+            Err(Error(ErrorKind::TorrentBuilderCancelled, _)) => (),
+            _ => assert!(false),
+        }
+        assert_eq!(pieces_done, 2);
+    }
+}
+
+#[test]
+fn build_cancelled_before_start() {
+    let token = CancellationToken::new();
+    token.cancel();
+
+    let result = TorrentBuilder::new(
+        PathBuf::from("tests/files").canonicalize().unwrap(),
+        PIECE_LENGTH,
+    )
+    .set_cancellation_token(token)
+    .build_with_progress(|_| assert!(false));
+
+    match result {
+        Err(Error(ErrorKind::TorrentBuilderCancelled, _)) => (),
+        _ => assert!(false),
+    }
+}
+
+#[test]
+fn build_auto_piece_length() {
+    let output_name = rand_file_name() + ".torrent";
+
+    // `piece_length` passed to `new()` should be ignored
+    TorrentBuilder::new(PathBuf::from("tests/files").canonicalize().unwrap(), 1023)
+        .set_auto_piece_length(Some(DEFAULT_MAX_PIECE_LENGTH))
+        .build()
+        .unwrap()
+        .write_into_file(&output_name)
+        .unwrap();
+
+    let torrent = Torrent::read_from_file(output_name).unwrap();
+    // files are small, so the minimum is used
+    assert_eq!(torrent.piece_length, 16384);
+    assert_eq!(torrent.pieces.len(), 14);
+}
+
+#[test]
+fn build_auto_piece_length_invalid_max() {
+    let result = TorrentBuilder::new(
+        PathBuf::from("tests/files").canonicalize().unwrap(),
+        PIECE_LENGTH,
+    )
+    .set_auto_piece_length(Some(1000))
+    .build();
+
+    match result {
+        Err(Error(ErrorKind::TorrentBuilderFailure(m), _)) => {
+            assert_eq!(m, "TorrentBuilder has `max_auto_piece_length` < 16384.");
+        }
+        _ => assert!(false),
+    }
+}
+
 #[test]
 fn build_with_name() {
     let output_name = rand_file_name() + ".torrent";
@@ -176,3 +415,428 @@ fn build_hidden_file() {
         _ => assert!(false),
     }
 }
+
+#[test]
+fn build_nested_dirs() {
+    let output_name = rand_file_name() + ".torrent";
+    let root = create_dir_tree(&[
+        ("a/b/c/d/deep.txt", "deep"),
+        ("a/b/mid.txt", "mid"),
+        ("top.txt", "top"),
+    ]);
+
+    TorrentBuilder::new(&root, PIECE_LENGTH)
+        .build()
+        .unwrap()
+        .write_into_file(&output_name)
+        .unwrap();
+
+    let files = Torrent::read_from_file(&output_name)
+        .unwrap()
+        .files
+        .unwrap();
+    let paths: Vec<_> = files.iter().map(|file| file.path.clone()).collect();
+    assert_eq!(
+        paths,
+        vec![
+            PathBuf::from("a/b/c/d/deep.txt"),
+            PathBuf::from("a/b/mid.txt"),
+            PathBuf::from("top.txt"),
+        ]
+    );
+    assert_eq!(files[0].length, 4);
+    assert_eq!(files[1].length, 3);
+    assert_eq!(files[2].length, 3);
+}
+
+#[test]
+fn build_duplicate_basenames() {
+    let output_name = rand_file_name() + ".torrent";
+    let root = create_dir_tree(&[
+        ("a/b/c.txt", "abc"),
+        ("a/c.txt", "ac"),
+        ("c.txt", "c"),
+        ("x/y/z/c.txt", "xyzc"),
+    ]);
+
+    TorrentBuilder::new(&root, PIECE_LENGTH)
+        .set_hybrid(true)
+        .build()
+        .unwrap()
+        .write_into_file(&output_name)
+        .unwrap();
+
+    let expected = vec![
+        (PathBuf::from("a/b/c.txt"), 3),
+        (PathBuf::from("a/c.txt"), 2),
+        (PathBuf::from("c.txt"), 1),
+        (PathBuf::from("x/y/z/c.txt"), 4),
+    ];
+
+    let v1_files: Vec<_> = Torrent::read_from_file(&output_name)
+        .unwrap()
+        .content_files()
+        .into_iter()
+        .map(|file| (file.path.clone(), file.length))
+        .collect();
+    assert_eq!(v1_files, expected);
+
+    // the v1 and v2 parts of a hybrid torrent must agree
+    let v2_files: Vec<_> = v2::Torrent::read_from_file(&output_name)
+        .unwrap()
+        .files()
+        .into_iter()
+        .map(|(path, file)| (path, file.length))
+        .collect();
+    assert_eq!(v2_files, expected);
+}
+
+fn file_paths(torrent: &Torrent) -> Vec<PathBuf> {
+    torrent
+        .files
+        .as_ref()
+        .unwrap()
+        .iter()
+        .map(|file| file.path.clone())
+        .collect()
+}
+
+#[test]
+fn build_include_hidden() {
+    let root = create_dir_tree(&[(".config/settings", "s"), (".env", "e"), ("file", "f")]);
+
+    let torrent = TorrentBuilder::new(&root, PIECE_LENGTH)
+        .set_include_hidden(true)
+        .build()
+        .unwrap();
+    assert_eq!(
+        file_paths(&torrent),
+        vec![
+            PathBuf::from(".config/settings"),
+            PathBuf::from(".env"),
+            PathBuf::from("file"),
+        ]
+    );
+
+    let torrent = TorrentBuilder::new(&root, PIECE_LENGTH).build().unwrap();
+    assert_eq!(file_paths(&torrent), vec![PathBuf::from("file")]);
+}
+
+#[test]
+fn build_with_globs() {
+    let root = create_dir_tree(&[
+        ("a/Thumbs.db", "t"),
+        ("a/video.mkv", "v"),
+        ("a/video.srt", "s"),
+        ("b/video.mkv.part", "p"),
+        ("cache/video.mkv", "c"),
+        ("video.mkv", "v"),
+    ]);
+
+    let torrent = TorrentBuilder::new(&root, PIECE_LENGTH)
+        .add_exclude_glob("*.part".to_owned())
+        .add_exclude_glob("Thumbs.db".to_owned())
+        .add_exclude_glob("cache".to_owned())
+        .build()
+        .unwrap();
+    assert_eq!(
+        file_paths(&torrent),
+        vec![
+            PathBuf::from("a/video.mkv"),
+            PathBuf::from("a/video.srt"),
+            PathBuf::from("video.mkv"),
+        ]
+    );
+
+    let torrent = TorrentBuilder::new(&root, PIECE_LENGTH)
+        .add_include_glob("*.mkv".to_owned())
+        .add_exclude_glob("cache".to_owned())
+        .build()
+        .unwrap();
+    assert_eq!(
+        file_paths(&torrent),
+        vec![PathBuf::from("a/video.mkv"), PathBuf::from("video.mkv")]
+    );
+}
+
+#[test]
+fn build_with_globs_nothing_left() {
+    let root = create_dir_tree(&[("file.part", "p")]);
+
+    let result = TorrentBuilder::new(&root, PIECE_LENGTH)
+        .add_exclude_glob("*.part".to_owned())
+        .build();
+
+    match result {
+        Err(Error(ErrorKind::TorrentBuilderFailure(m), _)) => assert_eq!(
+            m,
+            "TorrentBuilder has `path` but it contains no file to include."
+        ),
+        _ => assert!(false),
+    }
+}
+
+#[test]
+fn build_symlink_policy() {
+    let root = create_dir_tree(&[("dir/file", "content"), ("file2", "content2")]);
+    unix::fs::symlink("dir/file", root.join("link")).unwrap();
+    unix::fs::symlink("dir", root.join("link_dir")).unwrap();
+
+    let torrent = TorrentBuilder::new(&root, PIECE_LENGTH).build().unwrap();
+    assert_eq!(
+        file_paths(&torrent),
+        vec![
+            PathBuf::from("dir/file"),
+            PathBuf::from("file2"),
+            PathBuf::from("link"),
+            PathBuf::from("link_dir/file"),
+        ]
+    );
+    assert_eq!(torrent.length, 29);
+
+    let torrent = TorrentBuilder::new(&root, PIECE_LENGTH)
+        .set_symlink_policy(SymlinkPolicy::Skip)
+        .build()
+        .unwrap();
+    assert_eq!(
+        file_paths(&torrent),
+        vec![PathBuf::from("dir/file"), PathBuf::from("file2")]
+    );
+    assert_eq!(torrent.length, 15);
+
+    for &is_hybrid in &[false, true] {
+        let output_name = rand_file_name() + ".torrent";
+        TorrentBuilder::new(&root, PIECE_LENGTH)
+            .set_symlink_policy(SymlinkPolicy::Record)
+            .set_hybrid(is_hybrid)
+            .build()
+            .unwrap()
+            .write_into_file(&output_name)
+            .unwrap();
+
+        let torrent = Torrent::read_from_file(&output_name).unwrap();
+        let files = torrent.content_files();
+        assert_eq!(files.len(), 4);
+        assert_eq!(files[2].path, PathBuf::from("link"));
+        assert_eq!(files[2].length, 0);
+        assert!(files[2].is_symlink());
+        assert_eq!(files[2].symlink_path, Some(PathBuf::from("dir/file")));
+        assert!(files[3].is_symlink());
+        assert_eq!(files[3].symlink_path, Some(PathBuf::from("dir")));
+    }
+}
+
+#[test]
+fn build_symlink_outside_root() {
+    let root = create_dir_tree(&[("file", "content")]);
+    unix::fs::symlink("/", root.join("link")).unwrap();
+
+    let result = TorrentBuilder::new(&root, PIECE_LENGTH)
+        .set_symlink_policy(SymlinkPolicy::Record)
+        .build();
+
+    match result {
+        Err(Error(ErrorKind::TorrentBuilderFailure(m), _)) => assert_eq!(
+            m,
+            format!(
+                "Symbolic link [{}] points outside of the root.",
+                root.join("link").display()
+            )
+        ),
+        _ => assert!(false),
+    }
+}
+
+#[test]
+fn build_hybrid_multi_file_ok() {
+    let output_name = rand_file_name() + ".torrent";
+
+    TorrentBuilder::new(
+        PathBuf::from("tests/files").canonicalize().unwrap(),
+        PIECE_LENGTH,
+    )
+    .set_announce(Some(
+        "udp://tracker.coppersurfer.tk:6969/announce".to_owned(),
+    ))
+    .add_extra_field("creation date".to_owned(), BencodeElem::Integer(1523607302))
+    .add_extra_field(
+        "encoding".to_owned(),
+        BencodeElem::String("UTF-8".to_owned()),
+    )
+    .add_extra_info_field("private".to_owned(), BencodeElem::Integer(0))
+    .set_hybrid(true)
+    .build()
+    .unwrap()
+    .write_into_file(&output_name)
+    .unwrap();
+
+    // compare against a sample generated by an earlier build (i.e. a regression
+    // fixture, not a cross-check against another client)
+    assert_eq!(
+        Torrent::read_from_file(&output_name).unwrap(),
+        Torrent::read_from_file("tests/samples/files-hybrid.torrent").unwrap(),
+    );
+    // the same file should be readable as a v2 torrent
+    assert_eq!(
+        v2::Torrent::read_from_file(&output_name).unwrap(),
+        v2::Torrent::read_from_file("tests/samples/files-hybrid.torrent").unwrap(),
+    );
+    assert_eq!(
+        v2::Torrent::read_from_file(&output_name)
+            .unwrap()
+            .info_hash(),
+        "3803bf5391d4058572dc3d0551ce2584ca357911ee731daa5f92cf264b566fdc".to_owned(),
+    );
+}
+
+#[test]
+fn build_hybrid_multi_file_matches_reference() {
+    // `files-hybrid-reference.torrent` is created by a standalone
+    // implementation of BEP 52 (see `make_hybrid_reference.py`),
+    // so it cross-checks the padding and the v1/v2 hashes
+    let built = TorrentBuilder::new(
+        PathBuf::from("tests/files").canonicalize().unwrap(),
+        PIECE_LENGTH,
+    )
+    .set_hybrid(true)
+    .build()
+    .unwrap()
+    .encode()
+    .unwrap();
+    let reference = fs::read("tests/samples/files-hybrid-reference.torrent").unwrap();
+
+    let (built_v1, reference_v1) = (
+        Torrent::read_from_bytes(&built).unwrap(),
+        Torrent::read_from_bytes(&reference).unwrap(),
+    );
+    assert_eq!(built_v1.files, reference_v1.files);
+    assert_eq!(built_v1.pieces, reference_v1.pieces);
+    assert_eq!(
+        built_v1.info_hash(),
+        "dc8d485d91be917fd112d485ea6f24dd94223063".to_owned()
+    );
+    assert_eq!(built_v1.info_hash(), reference_v1.info_hash());
+
+    let (built_v2, reference_v2) = (
+        v2::Torrent::read_from_bytes(&built).unwrap(),
+        v2::Torrent::read_from_bytes(&reference).unwrap(),
+    );
+    assert_eq!(built_v2.file_tree, reference_v2.file_tree);
+    assert_eq!(built_v2.piece_layers, reference_v2.piece_layers);
+    assert_eq!(
+        built_v2.info_hash(),
+        "43ca314c10e14d33be1cb2741daf5d8aea98759bd6500b1b5944c418b488e096".to_owned()
+    );
+    assert_eq!(built_v2.info_hash(), reference_v2.info_hash());
+}
+
+#[test]
+fn build_hybrid_multi_file_parallel() {
+    let path = PathBuf::from("tests/files").canonicalize().unwrap();
+    let sequential = TorrentBuilder::new(&path, PIECE_LENGTH)
+        .set_hybrid(true)
+        .build()
+        .unwrap();
+
+    for num_threads in 2..6 {
+        let mut last: Option<BuildProgress> = None;
+        let parallel = TorrentBuilder::new(&path, PIECE_LENGTH)
+            .set_hybrid(true)
+            .set_num_threads(num_threads)
+            .build_with_progress(|progress| last = Some(progress.clone()))
+            .unwrap();
+
+        // v1 pieces and v2 fields should be identical to those hashed sequentially
+        assert_eq!(parallel, sequential);
+        let last = last.unwrap();
+        assert_eq!(last.pieces_done, parallel.pieces.len());
+        assert_eq!(last.bytes_hashed, parallel.content_length() as u64);
+    }
+}
+
+#[test]
+fn build_padded_multi_file() {
+    let path = PathBuf::from("tests/files").canonicalize().unwrap();
+    let hybrid = TorrentBuilder::new(&path, PIECE_LENGTH)
+        .set_hybrid(true)
+        .build()
+        .unwrap();
+
+    for &num_threads in &[1, 4] {
+        let mut last: Option<BuildProgress> = None;
+        let torrent = TorrentBuilder::new(&path, PIECE_LENGTH)
+            .set_pad_files(true)
+            .set_num_threads(num_threads)
+            .build_with_progress(|progress| last = Some(progress.clone()))
+            .unwrap();
+
+        // every file starts at a piece boundary
+        let mut offset = 0;
+        for file in torrent.files.as_ref().unwrap() {
+            if !file.is_padding() {
+                assert_eq!(offset % PIECE_LENGTH, 0);
+            }
+            offset += file.length;
+        }
+        assert_eq!(offset, torrent.length);
+        assert_eq!(torrent.content_files().len(), 4);
+
+        let last = last.unwrap();
+        assert_eq!(last.pieces_done, torrent.pieces.len());
+        assert_eq!(last.bytes_hashed, torrent.content_length() as u64);
+
+        // the v1 part of a hybrid torrent is padded in the same way
+        assert_eq!(torrent.files, hybrid.files);
+        assert_eq!(torrent.pieces, hybrid.pieces);
+    }
+}
+
+#[test]
+fn build_hybrid_single_file_ok() {
+    let output_name = rand_file_name() + ".torrent";
+
+    TorrentBuilder::new(
+        PathBuf::from("tests/files/tails-amd64-3.6.1.torrent")
+            .canonicalize()
+            .unwrap(),
+        PIECE_LENGTH,
+    )
+    .set_hybrid(true)
+    .build()
+    .unwrap()
+    .write_into_file(&output_name)
+    .unwrap();
+
+    // no padding is needed, so the v1 part
+    // should be identical to a v1-only torrent
+    let built = Torrent::read_from_file(&output_name).unwrap();
+    let sample =
+        Torrent::read_from_file("tests/samples/tails-amd64-3.6.1.torrent.torrent").unwrap();
+    assert_eq!(built.files, None);
+    assert_eq!(built.length, sample.length);
+    assert_eq!(built.pieces, sample.pieces);
+
+    let built = v2::Torrent::read_from_file(&output_name).unwrap();
+    let files = built.files();
+    assert_eq!(files.len(), 1);
+    assert_eq!(files[0].0, PathBuf::from("tails-amd64-3.6.1.torrent"));
+    assert_eq!(files[0].1.length, sample.length);
+    assert_eq!(built.piece_layers.len(), 1);
+}
+
+#[test]
+fn build_hybrid_piece_length_too_small() {
+    let result = TorrentBuilder::new(PathBuf::from("tests/files").canonicalize().unwrap(), 8192)
+        .set_hybrid(true)
+        .build();
+
+    match result {
+        Err(Error(ErrorKind::TorrentBuilderFailure(m), _)) => {
+            assert_eq!(
+                m,
+                "TorrentBuilder is hybrid but has `piece_length` < 16384."
+            );
+        }
+        _ => assert!(false),
+    }
+}
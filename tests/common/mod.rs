@@ -0,0 +1,27 @@
+// Helpers shared by the integration tests that create content on disk.
+
+use rand::Rng;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+pub const OUTPUT_ROOT: &str = "tests/tmp/";
+
+pub fn rand_file_name() -> String {
+    OUTPUT_ROOT.to_owned() + &rand::thread_rng().gen::<u16>().to_string()
+}
+
+// create a directory tree containing `files` (relative path, content)
+// and return its canonicalized root
+pub fn create_dir_tree<P, C>(files: &[(P, C)]) -> PathBuf
+where
+    P: AsRef<Path>,
+    C: AsRef<[u8]>,
+{
+    let root = PathBuf::from(rand_file_name() + "-dir");
+    for (path, content) in files {
+        let path = root.join(path);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, content).unwrap();
+    }
+    root.canonicalize().unwrap()
+}
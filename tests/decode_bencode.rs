@@ -0,0 +1,42 @@
+extern crate lava_torrent;
+
+use lava_torrent::bencode::decode::{Decoded, Decoder};
+use lava_torrent::bencode::BencodeElem;
+use std::io::{BufReader, Read};
+
+#[test]
+fn decoder_feed_in_chunks() {
+    let mut bytes = Vec::new();
+    BufReader::new(
+        std::fs::File::open("tests/files/ubuntu-16.04.4-desktop-amd64.iso.torrent").unwrap(),
+    )
+    .read_to_end(&mut bytes)
+    .unwrap();
+
+    let mut decoder = Decoder::new();
+    let mut decoded = Vec::new();
+    for chunk in bytes.chunks(7) {
+        decoder.feed(chunk);
+        while let Decoded::Element(element) = decoder.decode().unwrap() {
+            decoded.push(element);
+        }
+    }
+
+    assert_eq!(decoded, BencodeElem::from_bytes(&bytes).unwrap());
+    assert_eq!(decoder.buffered_len(), 0);
+}
+
+#[test]
+fn decoder_read_from_file() {
+    let mut file = std::fs::File::open("tests/files/tails-amd64-3.6.1.torrent").unwrap();
+    let mut decoder = Decoder::new();
+
+    let element = decoder.read_from(&mut file).unwrap();
+    assert_eq!(
+        element,
+        BencodeElem::from_file("tests/files/tails-amd64-3.6.1.torrent")
+            .unwrap()
+            .pop()
+    );
+    assert_eq!(decoder.read_from(&mut file).unwrap(), None);
+}
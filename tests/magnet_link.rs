@@ -0,0 +1,50 @@
+extern crate lava_torrent;
+
+use lava_torrent::magnet::{MagnetLink, MagnetLinkOptions};
+use lava_torrent::torrent::v1::Torrent;
+use lava_torrent::torrent::v2;
+
+#[test]
+fn parse_generated_magnet_link() {
+    let torrent = Torrent::read_from_file("tests/files/tails-amd64-3.6.1.torrent").unwrap();
+    let magnet = MagnetLink::parse(&torrent.magnet_link()).unwrap();
+
+    assert_eq!(magnet.info_hash, Some(torrent.info_hash_bytes()));
+    assert_eq!(magnet.info_hash_hex(), Some(torrent.info_hash()));
+    assert_eq!(magnet.name, Some(torrent.name.clone()));
+    assert_eq!(
+        magnet.trackers,
+        torrent
+            .announce_list
+            .unwrap()
+            .into_iter()
+            .flatten()
+            .collect::<Vec<String>>()
+    );
+    // round trip
+    assert_eq!(MagnetLink::parse(&magnet.to_string()).unwrap(), magnet);
+}
+
+#[test]
+fn parse_generated_hybrid_magnet_link() {
+    let torrent = Torrent::read_from_file("tests/samples/files-hybrid.torrent").unwrap();
+    let magnet = MagnetLink::parse(
+        &torrent.magnet_link_with(
+            &MagnetLinkOptions::new()
+                .set_info_hash_v2(true)
+                .set_length(true),
+        ),
+    )
+    .unwrap();
+
+    assert_eq!(magnet.info_hash, Some(torrent.info_hash_bytes()));
+    assert_eq!(
+        magnet.info_hash_v2,
+        Some(
+            v2::Torrent::read_from_file("tests/samples/files-hybrid.torrent")
+                .unwrap()
+                .info_hash_bytes()
+        )
+    );
+    assert_eq!(magnet.length, Some(torrent.content_length()));
+}
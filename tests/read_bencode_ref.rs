@@ -0,0 +1,60 @@
+extern crate lava_torrent;
+
+use lava_torrent::bencode::{BencodeElem, BencodeRef};
+use std::io::{BufReader, Read};
+
+fn read_bytes(path: &str) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    BufReader::new(std::fs::File::open(path).unwrap())
+        .read_to_end(&mut bytes)
+        .unwrap();
+    bytes
+}
+
+#[test]
+fn bencode_ref_to_owned_matches_bencode_elem() {
+    for path in &[
+        "tests/files/ubuntu-16.04.4-desktop-amd64.iso.torrent",
+        "tests/files/tails-amd64-3.6.1.torrent",
+        "tests/samples/files-v2.torrent",
+    ] {
+        let bytes = read_bytes(path);
+        let borrowed = BencodeRef::from_bytes(&bytes).unwrap();
+        let owned = BencodeElem::from_bytes(&bytes).unwrap();
+
+        assert_eq!(
+            borrowed
+                .iter()
+                .map(BencodeRef::to_owned)
+                .collect::<Vec<_>>(),
+            owned
+        );
+    }
+}
+
+#[test]
+fn bencode_ref_borrows_pieces() {
+    let bytes = read_bytes("tests/files/ubuntu-16.04.4-desktop-amd64.iso.torrent");
+    let parsed = BencodeRef::from_bytes(&bytes).unwrap();
+
+    let info = match parsed[0] {
+        BencodeRef::Dictionary(ref entries) => {
+            &entries.iter().find(|(k, _)| k == b"info").unwrap().1
+        }
+        _ => panic!("torrent is not a dictionary"),
+    };
+    let pieces = match *info {
+        BencodeRef::Dictionary(ref entries) => {
+            match entries.iter().find(|(k, _)| k == b"pieces").unwrap().1 {
+                BencodeRef::Bytes(pieces) => pieces,
+                _ => panic!("pieces are not bytes"),
+            }
+        }
+        _ => panic!("info is not a dictionary"),
+    };
+
+    // the slice points into the input buffer
+    let start = pieces.as_ptr() as usize - bytes.as_ptr() as usize;
+    assert_eq!(&bytes[start..start + pieces.len()], pieces);
+    assert_eq!(pieces.len() % 20, 0);
+}
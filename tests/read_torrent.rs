@@ -2,11 +2,8 @@ extern crate conv;
 extern crate lava_torrent;
 
 use conv::ValueFrom;
-use lava_torrent::bencode::BencodeElem;
 use lava_torrent::torrent::v1::{File, Torrent};
-use std::collections::HashMap;
 use std::io::{BufReader, Read};
-use std::iter::FromIterator;
 use std::path::PathBuf;
 
 #[test]
@@ -41,24 +38,14 @@ fn read_from_bytes() {
                 / f64::value_from(parsed.piece_length).unwrap())
             .ceil())
         .abs()
-            < std::f64::EPSILON
+            < f64::EPSILON
     );
+    assert_eq!(parsed.creation_date, Some(1_519_934_077));
     assert_eq!(
-        parsed.extra_fields,
-        Some(HashMap::from_iter(
-            vec![
-                (
-                    "comment".to_owned(),
-                    BencodeElem::String("Ubuntu CD releases.ubuntu.com".to_owned()),
-                ),
-                (
-                    "creation date".to_owned(),
-                    BencodeElem::Integer(1_519_934_077),
-                ),
-            ]
-            .into_iter()
-        )),
+        parsed.comment,
+        Some("Ubuntu CD releases.ubuntu.com".to_owned())
     );
+    assert_eq!(parsed.extra_fields, None);
     assert_eq!(parsed.extra_info_fields, None);
     assert_eq!(
         parsed.info_hash(),
@@ -115,11 +102,17 @@ fn read_from_bytes_multiple_files() {
             File {
                 length: 1_225_568_256,
                 path: PathBuf::from("tails-amd64-3.6.1.iso"),
+                attr: None,
+                sha1: None,
+                symlink_path: None,
                 extra_fields: None,
             },
             File {
                 length: 228,
                 path: PathBuf::from("tails-amd64-3.6.1.iso.sig"),
+                attr: None,
+                sha1: None,
+                symlink_path: None,
                 extra_fields: None,
             },
         ])
@@ -136,24 +129,11 @@ fn read_from_bytes_multiple_files() {
                 / f64::value_from(parsed.piece_length).unwrap())
             .ceil())
         .abs()
-            < std::f64::EPSILON
-    );
-    assert_eq!(
-        parsed.extra_fields,
-        Some(HashMap::from_iter(
-            vec![
-                (
-                    "created by".to_owned(),
-                    BencodeElem::String("mktorrent 1.0".to_owned()),
-                ),
-                (
-                    "creation date".to_owned(),
-                    BencodeElem::Integer(1_521_245_346),
-                ),
-            ]
-            .into_iter()
-        )),
+            < f64::EPSILON
     );
+    assert_eq!(parsed.creation_date, Some(1_521_245_346));
+    assert_eq!(parsed.created_by, Some("mktorrent 1.0".to_owned()));
+    assert_eq!(parsed.extra_fields, None);
     assert_eq!(parsed.extra_info_fields, None);
     assert_eq!(
         parsed.info_hash(),
@@ -182,3 +162,83 @@ fn read_from_files_multiple_files() {
         Torrent::read_from_file("tests/files/tails-amd64-3.6.1.torrent").unwrap(),
     );
 }
+
+#[test]
+fn read_from_bytes_non_nfc_info_hash() {
+    use lava_torrent::torrent::InfoHashSource;
+
+    // "e\u{301}" is not in NFC form, so it would be changed
+    // when the `info` dict is re-encoded
+    let mut info = "d6:lengthi4e4:name3:e\u{301}12:piece lengthi2e6:pieces40:"
+        .as_bytes()
+        .to_vec();
+    info.extend_from_slice(&[0xff; 40]);
+    info.push(b'e');
+    let mut bytes = "d8:announce3:url4:info".as_bytes().to_vec();
+    bytes.extend_from_slice(&info);
+    bytes.push(b'e');
+
+    let parsed = Torrent::read_from_bytes(bytes).unwrap();
+    assert_eq!(parsed.raw_info, Some(info));
+    assert_eq!(
+        parsed.info_hash(),
+        "0c62348e1931f4ac5c41c5315e9a239b9c58df40".to_owned()
+    );
+    assert_eq!(
+        parsed.info_hash_from(InfoHashSource::Original),
+        "0c62348e1931f4ac5c41c5315e9a239b9c58df40".to_owned()
+    );
+    assert_eq!(
+        parsed.info_hash_from(InfoHashSource::Reencoded),
+        "905cf123e86d589a76933b649c79fab59f06be30".to_owned()
+    );
+}
+
+#[test]
+fn read_from_bytes_utf8_file_sha1() {
+    // "e\u{301}" is valid UTF8 but not in NFC form, so normalizing
+    // it would change (and shorten) the hash
+    let mut sha1 = "e\u{301}".as_bytes().to_vec();
+    sha1.resize(20, b'a');
+
+    let mut bytes = "d4:infod5:filesld6:lengthi4e4:pathl1:ae4:sha120:"
+        .as_bytes()
+        .to_vec();
+    bytes.extend_from_slice(&sha1);
+    bytes.extend_from_slice(b"ee4:name1:a12:piece lengthi4e6:pieces20:");
+    bytes.extend_from_slice(&[0xff; 20]);
+    bytes.extend_from_slice(b"ee");
+
+    let parsed = Torrent::read_from_bytes(bytes).unwrap();
+    assert_eq!(parsed.files.unwrap()[0].sha1, Some(sha1));
+}
+
+#[test]
+fn read_from_bytes_malformed_file_fields() {
+    use lava_torrent::bencode::BencodeElem;
+
+    // malformed optional fields are kept as extra fields
+    let mut bytes =
+        "d4:infod5:filesld4:attri1e6:lengthi4e4:pathl1:ae4:sha12:aa12:symlink path3:dire"
+            .as_bytes()
+            .to_vec();
+    bytes.extend_from_slice(b"e4:name1:a12:piece lengthi4e6:pieces20:");
+    bytes.extend_from_slice(&[0xff; 20]);
+    bytes.extend_from_slice(b"ee");
+
+    let files = Torrent::read_from_bytes(&bytes).unwrap().files.unwrap();
+    assert_eq!(files[0].attr, None);
+    assert_eq!(files[0].sha1, None);
+    assert_eq!(files[0].symlink_path, None);
+
+    let extra_fields = files[0].extra_fields.as_ref().unwrap();
+    assert_eq!(extra_fields.get("attr"), Some(&BencodeElem::Integer(1)));
+    assert_eq!(
+        extra_fields.get("sha1"),
+        Some(&BencodeElem::Bytes(b"aa".to_vec()))
+    );
+    assert_eq!(
+        extra_fields.get("symlink path"),
+        Some(&BencodeElem::String("dir".to_owned()))
+    );
+}
@@ -0,0 +1,93 @@
+extern crate lava_torrent;
+
+use lava_torrent::torrent::v2::Torrent;
+use std::io::{BufReader, Read};
+use std::path::PathBuf;
+
+#[test]
+fn read_from_bytes() {
+    let file = std::fs::File::open("tests/samples/files-v2.torrent").unwrap();
+    let mut bytes = Vec::new();
+    BufReader::new(file).read_to_end(&mut bytes).unwrap();
+
+    let parsed = Torrent::read_from_bytes(bytes).unwrap();
+    assert_eq!(
+        parsed.announce,
+        Some("udp://tracker.coppersurfer.tk:6969/announce".to_owned()),
+    );
+    assert_eq!(parsed.announce_list, None);
+    assert_eq!(parsed.length, 256 + 62300 + 94016 + 62300);
+    assert_eq!(parsed.name, "files".to_owned());
+    assert_eq!(parsed.piece_length, 16384);
+    assert_eq!(
+        parsed
+            .files()
+            .into_iter()
+            .map(|(path, file)| (path, file.length))
+            .collect::<Vec<(PathBuf, i64)>>(),
+        vec![
+            (PathBuf::from("byte_sequence"), 256),
+            (PathBuf::from("symlink"), 62300),
+            (PathBuf::from("tails-amd64-3.6.1.torrent"), 94016),
+            (
+                PathBuf::from("ubuntu-16.04.4-desktop-amd64.iso.torrent"),
+                62300
+            ),
+        ]
+    );
+    // `symlink` and `ubuntu-16.04.4-desktop-amd64.iso.torrent`
+    // have the same content, thus sharing the same piece layer
+    assert_eq!(parsed.piece_layers.len(), 2);
+    for (_, file) in parsed.files() {
+        if file.length > parsed.piece_length {
+            let root = file.pieces_root.as_ref().unwrap();
+            let expected = ((file.length - 1) / parsed.piece_length + 1) as usize;
+            assert_eq!(parsed.piece_layers[root].len(), expected);
+        }
+    }
+    assert_eq!(parsed.extra_info_fields, None);
+    assert_eq!(
+        parsed.info_hash(),
+        "1d931870066e47f5200a224df330a79b78053f9ba383250708cfab0f3753ebf1".to_owned()
+    );
+    assert!(!parsed.is_private());
+}
+
+#[test]
+fn read_from_file() {
+    let file = std::fs::File::open("tests/samples/files-v2.torrent").unwrap();
+    let mut bytes = Vec::new();
+    BufReader::new(file).read_to_end(&mut bytes).unwrap();
+
+    assert_eq!(
+        Torrent::read_from_bytes(bytes).unwrap(),
+        Torrent::read_from_file("tests/samples/files-v2.torrent").unwrap(),
+    );
+}
+
+#[test]
+fn read_from_bytes_v1_torrent() {
+    assert!(Torrent::read_from_file("tests/files/tails-amd64-3.6.1.torrent").is_err());
+}
+
+#[test]
+fn read_from_bytes_utf8_pieces_root() {
+    // "e\u{301}" is valid UTF8 but not in NFC form, so normalizing
+    // it would change (and shorten) the hash
+    let mut pieces_root = "e\u{301}".as_bytes().to_vec();
+    pieces_root.resize(32, b'a');
+
+    let mut bytes = "d4:infod9:file treed4:filed0:d6:lengthi4e11:pieces root32:"
+        .as_bytes()
+        .to_vec();
+    bytes.extend_from_slice(&pieces_root);
+    bytes.extend_from_slice(
+        b"eee12:meta versioni2e4:name1:a12:piece lengthi16384ee12:piece layersdee",
+    );
+
+    let parsed = Torrent::read_from_bytes(bytes).unwrap();
+    assert_eq!(
+        parsed.files()[0].1.pieces_root.as_ref().unwrap(),
+        &pieces_root
+    );
+}
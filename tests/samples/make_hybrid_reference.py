@@ -0,0 +1,120 @@
+#!/usr/bin/env python3
+"""Generate files-hybrid-reference.torrent from tests/files.
+
+This is a standalone implementation of hybrid (v1 + v2) torrent creation
+written from BEP 3, BEP 47 and BEP 52 (using only the Python standard
+library). It shares no code with `TorrentBuilder`, so the resulting sample
+can be used to cross-check the padding placement and the v1/v2 hashes that
+`TorrentBuilder` produces.
+
+Usage (from the repository root):
+
+    python3 tests/samples/make_hybrid_reference.py
+"""
+
+import hashlib
+import os
+
+ROOT = "tests/files"
+OUTPUT = "tests/samples/files-hybrid-reference.torrent"
+PIECE_LENGTH = 32 * 1024
+BLOCK_SIZE = 16 * 1024
+
+
+def bencode(value):
+    if isinstance(value, int):
+        return b"i%de" % value
+    if isinstance(value, str):
+        value = value.encode()
+    if isinstance(value, bytes):
+        return b"%d:%s" % (len(value), value)
+    if isinstance(value, list):
+        return b"l" + b"".join(bencode(v) for v in value) + b"e"
+    if isinstance(value, dict):
+        items = sorted((k.encode() if isinstance(k, str) else k, v) for k, v in value.items())
+        return b"d" + b"".join(bencode(k) + bencode(v) for k, v in items) + b"e"
+    raise TypeError(value)
+
+
+def merkle_layers(leaves):
+    # all layers of a merkle tree, from the leaves (padded with
+    # zero hashes to a power of 2) up to the root
+    width = 1
+    while width < len(leaves):
+        width *= 2
+    layer = leaves + [bytes(32)] * (width - len(leaves))
+    layers = [layer]
+    while len(layer) > 1:
+        layer = [
+            hashlib.sha256(layer[i] + layer[i + 1]).digest() for i in range(0, len(layer), 2)
+        ]
+        layers.append(layer)
+    return layers
+
+
+def v2_hashes(content):
+    # returns (pieces root, piece layer) of a non-empty file
+    leaves = [
+        hashlib.sha256(content[i : i + BLOCK_SIZE]).digest()
+        for i in range(0, len(content), BLOCK_SIZE)
+    ]
+    if len(content) <= PIECE_LENGTH:
+        return merkle_layers(leaves)[-1][0], None
+
+    # the piece layer is the layer where each node covers
+    # `PIECE_LENGTH` bytes, truncated to the actual # of pieces
+    blocks_per_piece = PIECE_LENGTH // BLOCK_SIZE
+    leaves += [bytes(32)] * (-len(leaves) % blocks_per_piece)
+    layers = merkle_layers(leaves)
+    height = blocks_per_piece.bit_length() - 1
+    n_pieces = (len(content) + PIECE_LENGTH - 1) // PIECE_LENGTH
+    return layers[-1][0], b"".join(layers[height][:n_pieces])
+
+
+def main():
+    # hidden files are excluded, symlinks are followed
+    names = sorted(name for name in os.listdir(ROOT) if not name.startswith("."))
+
+    files = []
+    file_tree = {}
+    piece_layers = {}
+    v1_content = b""
+    for i, name in enumerate(names):
+        with open(os.path.join(ROOT, name), "rb") as f:
+            content = f.read()
+
+        root, layer = v2_hashes(content)
+        file_tree[name] = {"": {"length": len(content), "pieces root": root}}
+        if layer is not None:
+            piece_layers[root] = layer
+
+        files.append({"length": len(content), "path": [name]})
+        v1_content += content
+        # BEP 52: "each file is padded with a padding file to the next
+        # piece boundary", except the last one
+        padding = -len(content) % PIECE_LENGTH
+        if padding and i != len(names) - 1:
+            files.append({"attr": "p", "length": padding, "path": [".pad", str(padding)]})
+            v1_content += bytes(padding)
+
+    pieces = b"".join(
+        hashlib.sha1(v1_content[i : i + PIECE_LENGTH]).digest()
+        for i in range(0, len(v1_content), PIECE_LENGTH)
+    )
+    info = {
+        "file tree": file_tree,
+        "files": files,
+        "meta version": 2,
+        "name": "files",
+        "piece length": PIECE_LENGTH,
+        "pieces": pieces,
+    }
+
+    with open(OUTPUT, "wb") as f:
+        f.write(bencode({"info": info, "piece layers": piece_layers}))
+    print("v1 info hash:", hashlib.sha1(bencode(info)).hexdigest())
+    print("v2 info hash:", hashlib.sha256(bencode(info)).hexdigest())
+
+
+if __name__ == "__main__":
+    main()
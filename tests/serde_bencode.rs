@@ -0,0 +1,153 @@
+#![cfg(feature = "serde")]
+
+extern crate lava_torrent;
+#[macro_use]
+extern crate serde;
+extern crate serde_bytes;
+
+use lava_torrent::bencode;
+use lava_torrent::bencode::BencodeElem;
+use std::collections::{BTreeMap, HashMap};
+
+#[derive(Debug, Deserialize, PartialEq, Serialize)]
+struct Info {
+    name: String,
+    #[serde(rename = "piece length")]
+    piece_length: i64,
+    #[serde(with = "serde_bytes")]
+    pieces: Vec<u8>,
+    private: Option<bool>,
+}
+
+#[derive(Debug, Deserialize, PartialEq, Serialize)]
+struct Metainfo {
+    announce: String,
+    #[serde(rename = "announce-list")]
+    announce_list: Option<Vec<Vec<String>>>,
+    info: Info,
+}
+
+#[derive(Debug, Deserialize, PartialEq, Serialize)]
+enum Event {
+    Started,
+    Progress(u32),
+    Moved { from: String, to: String },
+    Peers(String, u16),
+}
+
+#[test]
+fn serde_struct_round_trip() {
+    let metainfo = Metainfo {
+        announce: "url".to_owned(),
+        announce_list: None,
+        info: Info {
+            name: "sample".to_owned(),
+            piece_length: 2,
+            pieces: vec![0xff, 0xfe, 0xfd, 0xfc],
+            private: Some(true),
+        },
+    };
+
+    let bytes = bencode::to_bytes(&metainfo).unwrap();
+    // keys are sorted, `None` is omitted
+    assert_eq!(
+        bytes,
+        b"d8:announce3:url4:infod4:name6:sample12:piece lengthi2e\
+          6:pieces4:\xff\xfe\xfd\xfc7:privatei1eee"
+            .to_vec()
+    );
+    assert_eq!(bencode::from_bytes::<Metainfo>(&bytes).unwrap(), metainfo);
+}
+
+#[test]
+fn serde_from_real_torrent() {
+    let torrent = std::fs::read("tests/files/tails-amd64-3.6.1.torrent").unwrap();
+    let metainfo: Metainfo = bencode::from_bytes(&torrent).unwrap();
+
+    assert_eq!(
+        metainfo.announce,
+        "http://linuxtracker.org:2710/00000000000000000000000000000000/announce"
+    );
+    assert_eq!(metainfo.info.name, "tails-amd64-3.6.1");
+    assert_eq!(metainfo.info.piece_length, 262_144);
+    assert_eq!(metainfo.info.pieces.len() % 20, 0);
+    assert_eq!(metainfo.info.private, None);
+}
+
+#[test]
+fn serde_borrowed() {
+    #[derive(Deserialize)]
+    struct Borrowed<'a> {
+        name: &'a str,
+        #[serde(with = "serde_bytes")]
+        hash: &'a [u8],
+    }
+
+    let bytes = b"d4:hash2:\xff\xfe4:name4:spame";
+    let borrowed: Borrowed = bencode::from_bytes(bytes).unwrap();
+    assert_eq!(borrowed.name, "spam");
+    assert_eq!(borrowed.hash, &[0xff, 0xfe]);
+}
+
+#[test]
+fn serde_enum_round_trip() {
+    let events = vec![
+        Event::Started,
+        Event::Progress(50),
+        Event::Moved {
+            from: "a".to_owned(),
+            to: "b".to_owned(),
+        },
+        Event::Peers("host".to_owned(), 6881),
+    ];
+
+    let bytes = bencode::to_bytes(&events).unwrap();
+    assert_eq!(
+        bytes,
+        b"l7:Startedd8:Progressi50eed5:Movedd4:from1:a2:to1:beed5:Peersl4:hosti6881eeee".to_vec()
+    );
+    assert_eq!(bencode::from_bytes::<Vec<Event>>(&bytes).unwrap(), events);
+}
+
+#[test]
+fn serde_maps() {
+    let mut map = HashMap::new();
+    map.insert("zoo".to_owned(), 1);
+    map.insert("cow".to_owned(), 2);
+
+    let bytes = bencode::to_bytes(&map).unwrap();
+    assert_eq!(bytes, b"d3:cowi2e3:zooi1ee".to_vec());
+    assert_eq!(
+        bencode::from_bytes::<BTreeMap<String, i64>>(&bytes).unwrap(),
+        map.into_iter().collect()
+    );
+}
+
+#[test]
+fn serde_matches_bencode_elem() {
+    let bytes = bencode::to_bytes(&("spam", vec![1, 2], true)).unwrap();
+    assert_eq!(
+        BencodeElem::from_bytes(&bytes).unwrap(),
+        vec![BencodeElem::List(vec![
+            BencodeElem::String("spam".to_owned()),
+            BencodeElem::List(vec![BencodeElem::Integer(1), BencodeElem::Integer(2)]),
+            BencodeElem::Integer(1),
+        ])]
+    );
+}
+
+#[test]
+fn serde_float_not_supported() {
+    assert!(bencode::to_bytes(&1.5f64).is_err());
+}
+
+#[test]
+fn serde_type_mismatch() {
+    assert!(bencode::from_bytes::<Info>(b"d4:name6:samplee").is_err());
+    assert!(bencode::from_bytes::<u8>(b"i256e").is_err());
+}
+
+#[test]
+fn serde_trailing_bytes() {
+    assert!(bencode::from_bytes::<i64>(b"i1ei2e").is_err());
+}
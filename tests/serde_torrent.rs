@@ -0,0 +1,99 @@
+#![cfg(feature = "serde")]
+
+extern crate lava_torrent;
+extern crate serde_json;
+
+use lava_torrent::bencode;
+use lava_torrent::torrent::{v1, v2};
+use lava_torrent::tracker::{TrackerResponse, TrackerScrapeResponse};
+
+#[test]
+fn torrent_json_round_trip() {
+    let torrent =
+        v1::Torrent::read_from_file("tests/files/ubuntu-16.04.4-desktop-amd64.iso.torrent")
+            .unwrap();
+
+    let json = serde_json::to_string(&torrent).unwrap();
+    let duplicate: v1::Torrent = serde_json::from_str(&json).unwrap();
+    assert_eq!(duplicate, torrent);
+    assert_eq!(duplicate.info_hash(), torrent.info_hash());
+
+    // pieces are hex strings
+    let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+    assert_eq!(
+        value["pieces"][0],
+        serde_json::Value::String(
+            torrent.pieces[0]
+                .iter()
+                .map(|b| format!("{:02x}", b))
+                .collect()
+        )
+    );
+    // typed optional fields are plain values
+    assert_eq!(value["creation_date"], serde_json::json!(1_519_934_077));
+}
+
+#[test]
+fn torrent_json_multiple_files() {
+    let torrent = v1::Torrent::read_from_file("tests/samples/files-hybrid.torrent").unwrap();
+
+    let json = serde_json::to_string(&torrent).unwrap();
+    let duplicate: v1::Torrent = serde_json::from_str(&json).unwrap();
+    assert_eq!(duplicate, torrent);
+
+    let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+    assert!(value["files"][0]["path"].is_string());
+}
+
+#[test]
+fn torrent_bencode_round_trip() {
+    let torrent = v1::Torrent::read_from_file("tests/files/tails-amd64-3.6.1.torrent").unwrap();
+
+    let bytes = bencode::to_bytes(&torrent).unwrap();
+    let duplicate: v1::Torrent = bencode::from_bytes(&bytes).unwrap();
+    assert_eq!(duplicate, torrent);
+}
+
+#[test]
+fn torrent_v2_json_round_trip() {
+    let torrent = v2::Torrent::read_from_file("tests/samples/files-v2.torrent").unwrap();
+
+    let json = serde_json::to_string(&torrent).unwrap();
+    let duplicate: v2::Torrent = serde_json::from_str(&json).unwrap();
+    assert_eq!(duplicate, torrent);
+    assert_eq!(duplicate.info_hash(), torrent.info_hash());
+}
+
+#[test]
+fn tracker_response_json_round_trip() {
+    let response = TrackerResponse::from_bytes(
+        "d8:intervali900e5:peersld2:ip9:127.0.0.17:peer id20:-LT1000-abcdefghijkl\
+         4:porti6881eeee",
+    )
+    .unwrap();
+
+    let json = serde_json::to_string(&response).unwrap();
+    let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+    assert_eq!(value["peers"][0]["addr"], "127.0.0.1:6881");
+
+    let duplicate: TrackerResponse = serde_json::from_str(&json).unwrap();
+    assert_eq!(duplicate, response);
+}
+
+#[test]
+fn tracker_scrape_response_json_round_trip() {
+    let mut bytes = "d5:filesd20:".as_bytes().to_vec();
+    bytes.extend(vec![0xff; 20]);
+    bytes.extend("d8:completei5e10:downloadedi50e10:incompletei10eeee".as_bytes());
+    let response = TrackerScrapeResponse::from_bytes(bytes).unwrap();
+
+    let json = serde_json::to_string(&response).unwrap();
+    let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+    assert_eq!(
+        value["files"]["ffffffffffffffffffffffffffffffffffffffff"]["complete"],
+        5
+    );
+
+    let duplicate: TrackerScrapeResponse = serde_json::from_str(&json).unwrap();
+    assert_eq!(duplicate, response);
+}
@@ -0,0 +1,150 @@
+#![allow(clippy::assertions_on_constants)]
+
+extern crate lava_torrent;
+
+use lava_torrent::error::*;
+use lava_torrent::tracker::udp::*;
+use lava_torrent::tracker::{AnnounceEvent, Peer};
+use std::net::{SocketAddr, UdpSocket};
+use std::thread;
+use std::time::Duration;
+
+const CONNECTION_ID: u64 = 0x0123_4567_89ab_cdef;
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+fn bind() -> UdpSocket {
+    let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+    socket.set_read_timeout(Some(TIMEOUT)).unwrap();
+    socket
+}
+
+// a minimal tracker that answers `count` requests
+fn spawn_tracker(count: usize) -> (SocketAddr, thread::JoinHandle<()>) {
+    let socket = bind();
+    let addr = socket.local_addr().unwrap();
+
+    let handle = thread::spawn(move || {
+        let mut buf = [0; 2048];
+        for _ in 0..count {
+            let (len, src) = socket.recv_from(&mut buf).unwrap();
+            let response = match Request::from_bytes(&buf[..len]).unwrap() {
+                Request::Connect(request) => Response::Connect(ConnectResponse {
+                    transaction_id: request.transaction_id,
+                    connection_id: CONNECTION_ID,
+                }),
+                Request::Announce(ref request) if request.connection_id != CONNECTION_ID => {
+                    Response::Error(ErrorResponse {
+                        transaction_id: request.transaction_id,
+                        message: "unknown connection id".to_owned(),
+                    })
+                }
+                Request::Announce(request) => {
+                    assert_eq!(request.url_data, b"/announce?passkey=abc".to_vec());
+                    Response::Announce(AnnounceResponse {
+                        transaction_id: request.transaction_id,
+                        interval: 1800,
+                        leechers: 1,
+                        seeders: 0,
+                        // the client itself
+                        peers: vec![Peer {
+                            id: None,
+                            addr: SocketAddr::new(src.ip(), request.port),
+                            extra_fields: None,
+                        }],
+                    })
+                }
+                Request::Scrape(request) => Response::Scrape(ScrapeResponse {
+                    transaction_id: request.transaction_id,
+                    stats: request
+                        .info_hashes
+                        .iter()
+                        .map(|_| ScrapeStats {
+                            seeders: 0,
+                            completed: 3,
+                            leechers: 1,
+                        })
+                        .collect(),
+                }),
+            };
+            socket.send_to(&response.encode().unwrap(), src).unwrap();
+        }
+    });
+
+    (addr, handle)
+}
+
+fn send(socket: &UdpSocket, request: Request) -> Vec<u8> {
+    socket.send(&request.encode().unwrap()).unwrap();
+    let mut buf = [0; 2048];
+    let len = socket.recv(&mut buf).unwrap();
+    buf[..len].to_vec()
+}
+
+#[test]
+fn udp_tracker_exchange() {
+    let (addr, tracker) = spawn_tracker(4);
+    let url = TrackerUrl::parse(&format!("udp://{}/announce?passkey=abc", addr)).unwrap();
+    assert_eq!(url.host, "127.0.0.1");
+
+    let client = bind();
+    client.connect((url.host.as_str(), url.port)).unwrap();
+
+    // connect
+    let response = send(
+        &client,
+        Request::Connect(ConnectRequest { transaction_id: 1 }),
+    );
+    let response = ConnectResponse::from_bytes(response).unwrap();
+    assert_eq!(response.transaction_id, 1);
+    assert_eq!(response.connection_id, CONNECTION_ID);
+
+    // announce
+    let request = AnnounceRequest {
+        event: Some(AnnounceEvent::Started),
+        left: 1024,
+        url_data: url.url_data.clone(),
+        ..AnnounceRequest::new(CONNECTION_ID, 2, vec![0xab; 20], vec![b'p'; 20], 6881)
+    };
+    let response = send(&client, Request::Announce(request.clone()));
+    let response = AnnounceResponse::from_bytes(response, IpVersion::V4).unwrap();
+    assert_eq!(response.transaction_id, 2);
+    assert_eq!(response.interval, 1800);
+    assert_eq!(
+        response.peers[0].addr,
+        SocketAddr::from(([127, 0, 0, 1], 6881))
+    );
+
+    // scrape
+    let response = send(
+        &client,
+        Request::Scrape(ScrapeRequest {
+            connection_id: CONNECTION_ID,
+            transaction_id: 3,
+            info_hashes: vec![vec![0xab; 20], vec![0xcd; 20]],
+        }),
+    );
+    let response = ScrapeResponse::from_bytes(response).unwrap();
+    assert_eq!(response.transaction_id, 3);
+    assert_eq!(response.stats.len(), 2);
+    assert_eq!(response.stats[1].completed, 3);
+
+    // error
+    let request = AnnounceRequest {
+        connection_id: 0,
+        transaction_id: 4,
+        ..request
+    };
+    let response = send(&client, Request::Announce(request));
+    match AnnounceResponse::from_bytes(&response, IpVersion::V4) {
+        Err(Error(ErrorKind::TrackerErrorResponse(m), _)) => {
+            assert_eq!(m, "unknown connection id")
+        }
+        _ => assert!(false),
+    }
+    match Response::from_bytes(&response, IpVersion::V4).unwrap() {
+        Response::Error(response) => assert_eq!(response.transaction_id, 4),
+        _ => assert!(false),
+    }
+
+    tracker.join().unwrap();
+}
@@ -0,0 +1,186 @@
+#![allow(clippy::assertions_on_constants)]
+
+extern crate lava_torrent;
+extern crate rand;
+
+mod common;
+
+use common::create_dir_tree;
+use lava_torrent::error::*;
+use lava_torrent::torrent::v1::{Integer, Torrent, TorrentBuilder, VerificationStatus};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const PIECE_LENGTH: Integer = 16 * 1024;
+
+// create `base/root` containing `files` (relative path, length), filled with
+// non-zero bytes, and return the canonicalized `base` along with a torrent of it
+fn create_content(files: &[(&str, usize)], pad_files: bool) -> (PathBuf, Torrent) {
+    let base = create_dir_tree(
+        &files
+            .iter()
+            .enumerate()
+            .map(|(i, &(path, length))| (Path::new("root").join(path), vec![i as u8 + 1; length]))
+            .collect::<Vec<_>>(),
+    );
+    let torrent = TorrentBuilder::new(base.join("root"), PIECE_LENGTH)
+        .set_pad_files(pad_files)
+        .build()
+        .unwrap();
+    (base, torrent)
+}
+
+#[test]
+fn verify_multi_file_ok() {
+    let torrent = Torrent::read_from_file("tests/samples/files.torrent").unwrap();
+    let report = torrent
+        .verify(PathBuf::from("tests").canonicalize().unwrap())
+        .unwrap();
+
+    assert!(report.is_complete());
+    assert_eq!(report.pieces.len(), torrent.pieces.len());
+    assert_eq!(
+        report.files,
+        vec![VerificationStatus::Ok; torrent.files.unwrap().len()]
+    );
+}
+
+#[test]
+fn verify_single_file_ok() {
+    let torrent =
+        Torrent::read_from_file("tests/samples/tails-amd64-3.6.1.torrent.torrent").unwrap();
+    let report = torrent
+        .verify(PathBuf::from("tests/files").canonicalize().unwrap())
+        .unwrap();
+
+    assert!(report.is_complete());
+    assert_eq!(report.files, vec![VerificationStatus::Ok]);
+}
+
+#[test]
+fn verify_mismatch() {
+    let (base, torrent) = create_content(&[("a", 20000), ("b", 20000), ("c", 20000)], false);
+    let mut content = fs::read(base.join("root/b")).unwrap();
+    content[0] = 0;
+    fs::write(base.join("root/b"), content).unwrap();
+
+    let report = torrent.verify(&base).unwrap();
+    // b starts in the 2nd piece, which also covers a
+    assert_eq!(
+        report.pieces,
+        vec![
+            VerificationStatus::Ok,
+            VerificationStatus::Mismatch,
+            VerificationStatus::Ok,
+            VerificationStatus::Ok,
+        ]
+    );
+    // it can't be told whether a or b is corrupted
+    assert_eq!(
+        report.files,
+        vec![
+            VerificationStatus::Unknown,
+            VerificationStatus::Unknown,
+            VerificationStatus::Ok,
+        ]
+    );
+    assert!(!report.is_complete());
+
+    // the 1st piece covers only a
+    let mut content = fs::read(base.join("root/a")).unwrap();
+    content[0] = 0;
+    fs::write(base.join("root/a"), content).unwrap();
+
+    let report = torrent.verify(&base).unwrap();
+    assert_eq!(
+        report.files,
+        vec![
+            VerificationStatus::Mismatch,
+            VerificationStatus::Unknown,
+            VerificationStatus::Ok,
+        ]
+    );
+}
+
+#[test]
+fn verify_missing_and_short() {
+    let (base, torrent) = create_content(&[("a", 20000), ("b", 20000), ("dir/c", 20000)], false);
+    fs::remove_file(base.join("root/a")).unwrap();
+    fs::write(base.join("root/dir/c"), vec![3; 10000]).unwrap();
+
+    let report = torrent.verify(&base).unwrap();
+    assert_eq!(
+        report.pieces,
+        vec![
+            VerificationStatus::Missing,
+            VerificationStatus::Missing,
+            VerificationStatus::Ok,
+            VerificationStatus::Short,
+        ]
+    );
+    assert_eq!(
+        report.files,
+        vec![
+            VerificationStatus::Missing,
+            VerificationStatus::Unknown,
+            VerificationStatus::Short,
+        ]
+    );
+}
+
+#[test]
+fn verify_padded() {
+    let (base, torrent) = create_content(&[("a", 20000), ("b", 20000)], true);
+    assert!(torrent.files.as_ref().unwrap()[1].is_padding());
+
+    // padding files are not on disk
+    let report = torrent.verify(&base).unwrap();
+    assert!(report.is_complete());
+    assert_eq!(report.files, vec![VerificationStatus::Ok; 3]);
+
+    fs::remove_file(base.join("root/a")).unwrap();
+    let report = torrent.verify(&base).unwrap();
+    assert_eq!(
+        report.pieces,
+        vec![
+            VerificationStatus::Missing,
+            VerificationStatus::Missing,
+            VerificationStatus::Ok,
+            VerificationStatus::Ok,
+        ]
+    );
+    assert_eq!(
+        report.files,
+        vec![
+            VerificationStatus::Missing,
+            VerificationStatus::Ok,
+            VerificationStatus::Ok,
+        ]
+    );
+}
+
+#[test]
+fn verify_base_dir_not_absolute() {
+    let torrent = Torrent::read_from_file("tests/samples/files.torrent").unwrap();
+
+    match torrent.verify("tests") {
+        Err(Error(ErrorKind::InvalidArgument(m), _)) => {
+            assert_eq!(m, "Joined path is not absolute.")
+        }
+        _ => assert!(false),
+    }
+}
+
+#[test]
+fn verify_path_escapes_base_dir() {
+    let (base, mut torrent) = create_content(&[("a", 20000), ("b", 20000)], false);
+    torrent.files.as_mut().unwrap()[1].path = PathBuf::from("../../b");
+
+    match torrent.verify(&base) {
+        Err(Error(ErrorKind::InvalidArgument(m), _)) => assert_eq!(
+            m,
+            "File path is empty or contains components that are not normal."
+        ),
+        _ => assert!(false),
+    }
+}
@@ -61,13 +61,10 @@ fn bencode_elem_write_list_to_file_ok() {
 
 #[test]
 fn bencode_elem_write_dictionary_to_file_ok() {
-    let original = BencodeElem::Dictionary(HashMap::from_iter(
-        vec![
-            ("spam".to_owned(), BencodeElem::Integer(42)),
-            ("cow".to_owned(), BencodeElem::String("moo".to_owned())),
-        ]
-        .into_iter(),
-    ));
+    let original = BencodeElem::Dictionary(HashMap::from_iter(vec![
+        ("spam".to_owned(), BencodeElem::Integer(42)),
+        ("cow".to_owned(), BencodeElem::String("moo".to_owned())),
+    ]));
     let output = rand_file_name();
 
     original.write_into_file(&output).unwrap();
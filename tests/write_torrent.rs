@@ -59,3 +59,122 @@ fn write_torrent_to_file_multiple_files() {
     let duplicate = Torrent::read_from_file(&output).unwrap();
     assert_eq!(original, duplicate);
 }
+
+#[test]
+fn encode_torrent_v2() {
+    let file = File::open("tests/samples/files-v2.torrent").unwrap();
+    let mut bytes = Vec::new();
+    BufReader::new(file).read_to_end(&mut bytes).unwrap();
+
+    let parsed = lava_torrent::torrent::v2::Torrent::read_from_bytes(&bytes).unwrap();
+    let encoded = parsed.encode().unwrap();
+    assert_eq!(encoded, bytes);
+}
+
+#[test]
+fn write_torrent_to_file_v2() {
+    let output = rand_file_name();
+    let original =
+        lava_torrent::torrent::v2::Torrent::read_from_file("tests/samples/files-v2.torrent")
+            .unwrap();
+    original.clone().write_into_file(&output).unwrap();
+    let duplicate = lava_torrent::torrent::v2::Torrent::read_from_file(&output).unwrap();
+    assert_eq!(original, duplicate);
+}
+
+#[test]
+fn encode_torrent_non_nfc_info_hash() {
+    // "e\u{301}" is not in NFC form, so it would be changed
+    // if the `info` dict were re-encoded
+    let mut bytes =
+        "d8:announce3:url4:infod6:lengthi4e4:name3:e\u{301}12:piece lengthi2e6:pieces40:"
+            .as_bytes()
+            .to_vec();
+    bytes.extend_from_slice(&[0xff; 40]);
+    bytes.extend_from_slice(b"ee");
+
+    let original = Torrent::read_from_bytes(&bytes).unwrap();
+    let info_hash = original.info_hash();
+    let encoded = original.encode().unwrap();
+    assert_eq!(encoded, bytes);
+
+    let duplicate = Torrent::read_from_bytes(encoded).unwrap();
+    assert_eq!(duplicate.info_hash(), info_hash);
+}
+
+#[test]
+fn encode_torrent_v2_non_nfc_info_hash() {
+    // same as above, but with a non-NFC component in "file tree"
+    let mut bytes = "d4:infod9:file treed3:e\u{301}d0:d6:lengthi4e11:pieces root32:"
+        .as_bytes()
+        .to_vec();
+    bytes.extend_from_slice(&[0xff; 32]);
+    bytes.extend_from_slice(
+        b"eee12:meta versioni2e4:name1:a12:piece lengthi16384ee12:piece layersdee",
+    );
+
+    let original = lava_torrent::torrent::v2::Torrent::read_from_bytes(&bytes).unwrap();
+    let info_hash = original.info_hash();
+    let encoded = original.encode().unwrap();
+    assert_eq!(encoded, bytes);
+
+    let duplicate = lava_torrent::torrent::v2::Torrent::read_from_bytes(encoded).unwrap();
+    assert_eq!(duplicate.info_hash(), info_hash);
+}
+
+#[test]
+fn encode_torrent_modified() {
+    let mut torrent = Torrent::read_from_file("tests/files/tails-amd64-3.6.1.torrent").unwrap();
+    let original_info_hash = torrent.info_hash();
+    torrent.name = "renamed".to_owned();
+    let info_hash = torrent.info_hash();
+    assert_ne!(info_hash, original_info_hash);
+
+    // the modification is written (instead of the original `info`)
+    let duplicate = Torrent::read_from_bytes(torrent.clone().encode().unwrap()).unwrap();
+    assert_eq!(duplicate.name, "renamed".to_owned());
+    assert_eq!(duplicate.info_hash(), info_hash);
+    // `raw_info` differs, but it is not compared
+    assert_ne!(duplicate.raw_info, torrent.raw_info);
+    assert_eq!(duplicate, torrent);
+}
+
+#[test]
+fn encode_torrent_v2_modified() {
+    let mut torrent =
+        lava_torrent::torrent::v2::Torrent::read_from_file("tests/samples/files-v2.torrent")
+            .unwrap();
+    let original_info_hash = torrent.info_hash();
+    torrent.name = "renamed".to_owned();
+    let info_hash = torrent.info_hash();
+    assert_ne!(info_hash, original_info_hash);
+
+    let duplicate =
+        lava_torrent::torrent::v2::Torrent::read_from_bytes(torrent.clone().encode().unwrap())
+            .unwrap();
+    assert_eq!(duplicate.name, "renamed".to_owned());
+    assert_eq!(duplicate.info_hash(), info_hash);
+    assert_eq!(duplicate, torrent);
+}
+
+#[test]
+fn encode_torrent_single_string_web_seeds() {
+    // `url-list` and `httpseeds` are single strings (instead of lists)
+    let mut bytes = "d9:httpseeds2:hs4:infod6:lengthi4e4:name1:a12:piece lengthi4e6:pieces20:"
+        .as_bytes()
+        .to_vec();
+    bytes.extend_from_slice(&[0xff; 20]);
+    bytes.extend_from_slice(b"e8:url-list0:e");
+
+    let torrent = Torrent::read_from_bytes(&bytes).unwrap();
+    assert_eq!(torrent.url_list, Some(Vec::new()));
+    assert_eq!(torrent.http_seeds, Some(vec!["hs".to_owned()]));
+    assert_eq!(torrent.clone().encode().unwrap(), bytes);
+
+    // a list is needed for more than 1 url
+    let mut torrent = torrent;
+    torrent.http_seeds = Some(vec!["hs1".to_owned(), "hs2".to_owned()]);
+    let duplicate = Torrent::read_from_bytes(torrent.clone().encode().unwrap()).unwrap();
+    assert!(!duplicate.http_seeds_is_string);
+    assert_eq!(duplicate, torrent);
+}